@@ -0,0 +1,570 @@
+// An AgentX subagent (RFC 2741): the agent's registrations served through
+// a master agent such as net-snmp's snmpd, which owns port 161 and passes
+// on the requests for our subtrees. Only the default context is
+// registered, and access control is left to the master. PDUs go out
+// in network byte order; the master's are read in whichever order its
+// header flags say.
+
+use std::io::ErrorKind;
+use std::sync::atomic::Ordering;
+
+use anyhow::{Context, Result, anyhow, bail};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use super::{Agent, PendingSet, RequestError, end_of_mib, oid_string};
+use crate::snmp::pdu::{ErrorStatus, ObjectSyntax, VarBind, decode_opaque_float};
+
+/// Where net-snmp's master agent listens for subagents by default.
+pub const AGENTX_SOCKET: &str = "/var/agentx/master";
+/// The AgentX port, for masters listening on TCP.
+pub const AGENTX_PORT: u16 = 705;
+
+const VERSION: u8 = 1;
+const HEADER_LEN: usize = 20;
+// nothing we register or are asked comes near this
+const MAX_PAYLOAD: usize = 1 << 20;
+
+// h.flags
+const NON_DEFAULT_CONTEXT: u8 = 0x08;
+const NETWORK_BYTE_ORDER: u8 = 0x10;
+
+// h.type
+const OPEN: u8 = 1;
+const CLOSE: u8 = 2;
+const REGISTER: u8 = 3;
+const GET: u8 = 5;
+const GET_NEXT: u8 = 6;
+const GET_BULK: u8 = 7;
+const TEST_SET: u8 = 8;
+const COMMIT_SET: u8 = 9;
+const UNDO_SET: u8 = 10;
+const CLEANUP_SET: u8 = 11;
+const RESPONSE: u8 = 18;
+
+// n_subid caps OIDs; each sub-identifier is 32 bits
+const MAX_SUBIDS: usize = 128;
+
+const DEFAULT_PRIORITY: u8 = 127;
+const PARSE_ERROR: u16 = 266;
+const PROCESSING_ERROR: u16 = 268;
+
+struct Header {
+    kind: u8,
+    flags: u8,
+    session_id: u32,
+    transaction_id: u32,
+    packet_id: u32,
+}
+
+struct SearchRange {
+    start: Vec<u64>,
+    include: bool,
+    end: Vec<u64>,
+}
+
+impl Agent {
+    /// Serves the registered subtrees as an AgentX subagent over `stream`,
+    /// a connection to the master agent, e.g. a `UnixStream` to
+    /// [`AGENTX_SOCKET`]. Opens a session, registers every subtree
+    /// registered so far, then answers the master until it closes the
+    /// session or the connection. Subtrees registered later aren't passed
+    /// on to the master.
+    pub async fn serve_agentx<S>(&self, stream: S) -> Result<()>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let mut session = Session {
+            stream,
+            session_id: 0,
+            packet_id: 0,
+        };
+        // default timeout, no object identifying us
+        let mut open = vec![0; 4];
+        put_oid(&mut open, &[], false);
+        put_octets(&mut open, b"rusnmp");
+        session.session_id = session
+            .call(OPEN, open)
+            .await
+            .context("The master agent refused the session")?
+            .session_id;
+
+        let subtrees: Vec<Vec<u64>> = self
+            .registrations
+            .read()
+            .unwrap()
+            .iter()
+            .map(|registration| registration.subtree.clone())
+            .collect();
+        for subtree in subtrees {
+            if !fits_agentx(&subtree) {
+                bail!("{} can't be registered over AgentX", oid_string(&subtree));
+            }
+            let mut register = vec![0, DEFAULT_PRIORITY, 0, 0];
+            put_oid(&mut register, &subtree, false);
+            session.call(REGISTER, register).await.with_context(|| {
+                format!(
+                    "The master agent refused to register {}",
+                    oid_string(&subtree)
+                )
+            })?;
+        }
+
+        let mut pending = None;
+        while let Some((header, payload)) = session.receive().await? {
+            let answer = match header.kind {
+                CLOSE => break,
+                // the one PDU that isn't answered
+                CLEANUP_SET => {
+                    pending = None;
+                    continue;
+                }
+                _ => self.answer_agentx(&header, &payload, &mut pending),
+            };
+            // rather than send the master some other OID
+            let answer = answer.and_then(|varbinds| {
+                match varbinds.iter().position(|varbind| !encodable(varbind)) {
+                    Some(i) => Err((ErrorStatus::GenErr as u16, i as u16 + 1)),
+                    None => Ok(varbinds),
+                }
+            });
+            let (error, index, varbinds) = match answer {
+                Ok(varbinds) => (0, 0, varbinds),
+                Err((error, index)) => (error, index, Vec::new()),
+            };
+            let mut response = vec![0; 4];
+            put_u16(&mut response, error);
+            put_u16(&mut response, index);
+            for varbind in &varbinds {
+                put_varbind(&mut response, varbind);
+            }
+            session
+                .send(RESPONSE, header.transaction_id, header.packet_id, &response)
+                .await?;
+        }
+        Ok(())
+    }
+
+    // the varbinds to respond with, or res.error and res.index
+    fn answer_agentx(
+        &self,
+        header: &Header,
+        payload: &[u8],
+        pending: &mut Option<PendingSet>,
+    ) -> Result<Vec<VarBind>, (u16, u16)> {
+        let mut reader = Reader {
+            bytes: payload,
+            network_order: header.flags & NETWORK_BYTE_ORDER != 0,
+        };
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        let parse_error = |_| (PARSE_ERROR, 0);
+        let snmp_error = |(status, index): RequestError| (status as u16, index as u16 + 1);
+        if header.flags & NON_DEFAULT_CONTEXT != 0 {
+            reader.octets().map_err(parse_error)?;
+        }
+        match header.kind {
+            GET => {
+                let varbinds: Vec<VarBind> = reader
+                    .ranges()
+                    .map_err(parse_error)?
+                    .into_iter()
+                    .map(|range| VarBind {
+                        oid: range.start,
+                        value: ObjectSyntax::Null,
+                    })
+                    .collect();
+                self.get(&varbinds, false, None).map_err(snmp_error)
+            }
+            GET_NEXT => {
+                let ranges = reader.ranges().map_err(parse_error)?;
+                Ok(ranges.iter().map(|range| self.search(range)).collect())
+            }
+            GET_BULK => {
+                let non_repeaters = reader.u16().map_err(parse_error)?;
+                let max_repetitions = reader.u16().map_err(parse_error)?;
+                let ranges = reader.ranges().map_err(parse_error)?;
+                Ok(self.search_bulk(non_repeaters, max_repetitions, ranges))
+            }
+            TEST_SET => {
+                let varbinds = reader.varbinds().map_err(parse_error)?;
+                *pending = Some(self.test_set(&varbinds, None).map_err(snmp_error)?);
+                Ok(Vec::new())
+            }
+            COMMIT_SET => {
+                let pending = pending
+                    .as_mut()
+                    .ok_or((ErrorStatus::CommitFailed as u16, 0))?;
+                pending
+                    .commit()
+                    .map(|()| Vec::new())
+                    .map_err(|i| snmp_error((ErrorStatus::CommitFailed, i)))
+            }
+            UNDO_SET => {
+                if pending.as_mut().is_none_or(PendingSet::undo) {
+                    Ok(Vec::new())
+                } else {
+                    Err((ErrorStatus::UndoFailed as u16, 0))
+                }
+            }
+            _ => Err((PROCESSING_ERROR, 0)),
+        }
+    }
+
+    // the first instance in `range`, or endOfMibView past its end
+    fn search(&self, range: &SearchRange) -> VarBind {
+        if range.include {
+            let requested = VarBind {
+                oid: range.start.clone(),
+                value: ObjectSyntax::Null,
+            };
+            if let Ok(mut found) = self.get(&[requested], false, None)
+                && let Some(found) = found.pop()
+                && !matches!(
+                    found.value,
+                    ObjectSyntax::NoSuchObject | ObjectSyntax::NoSuchInstance
+                )
+            {
+                return found;
+            }
+        }
+        match self.next_after(&range.start, false, None) {
+            Some(next) if range.end.is_empty() || next.oid < range.end => next,
+            _ => end_of_mib(&range.start),
+        }
+    }
+
+    // the master has already fitted max-repetitions to its response
+    fn search_bulk(
+        &self,
+        non_repeaters: u16,
+        max_repetitions: u16,
+        ranges: Vec<SearchRange>,
+    ) -> Vec<VarBind> {
+        let mut ranges = ranges;
+        let repeaters = ranges.split_off(usize::from(non_repeaters).min(ranges.len()));
+        let mut varbinds: Vec<VarBind> = ranges.iter().map(|range| self.search(range)).collect();
+        let mut cursors = repeaters;
+        for _ in 0..max_repetitions {
+            if cursors.is_empty() {
+                break;
+            }
+            let mut all_ended = true;
+            for cursor in &mut cursors {
+                let next = self.search(cursor);
+                all_ended &= next.value == ObjectSyntax::EndOfMib;
+                cursor.start.clone_from(&next.oid);
+                cursor.include = false;
+                varbinds.push(next);
+            }
+            if all_ended {
+                break;
+            }
+        }
+        varbinds
+    }
+}
+
+struct Session<S> {
+    stream: S,
+    session_id: u32,
+    packet_id: u32,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> Session<S> {
+    // sends a request of ours and waits for the master's response
+    async fn call(&mut self, kind: u8, payload: Vec<u8>) -> Result<Header> {
+        self.packet_id += 1;
+        self.send(kind, 0, self.packet_id, &payload).await?;
+        let (header, payload) = self
+            .receive()
+            .await?
+            .ok_or_else(|| anyhow!("The master agent closed the connection"))?;
+        if header.kind != RESPONSE || header.packet_id != self.packet_id {
+            bail!(
+                "Expected the master agent's response, got a PDU of type {}",
+                header.kind
+            );
+        }
+        let mut reader = Reader {
+            bytes: &payload,
+            network_order: header.flags & NETWORK_BYTE_ORDER != 0,
+        };
+        reader.u32()?;
+        match reader.u16()? {
+            0 => Ok(header),
+            error => Err(anyhow!("The master agent answered {}", error_name(error))),
+        }
+    }
+
+    async fn send(
+        &mut self,
+        kind: u8,
+        transaction_id: u32,
+        packet_id: u32,
+        payload: &[u8],
+    ) -> Result<()> {
+        let mut pdu = vec![VERSION, kind, NETWORK_BYTE_ORDER, 0];
+        put_u32(&mut pdu, self.session_id);
+        put_u32(&mut pdu, transaction_id);
+        put_u32(&mut pdu, packet_id);
+        put_u32(&mut pdu, payload.len() as u32);
+        pdu.extend_from_slice(payload);
+        self.stream
+            .write_all(&pdu)
+            .await
+            .context("Failed to write to the master agent")
+    }
+
+    // the next PDU, or None once the master has closed the connection
+    async fn receive(&mut self) -> Result<Option<(Header, Vec<u8>)>> {
+        let mut bytes = [0; HEADER_LEN];
+        match self.stream.read_exact(&mut bytes).await {
+            Ok(_) => {}
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e).context("Failed to read from the master agent"),
+        }
+        if bytes[0] != VERSION {
+            bail!("Unsupported AgentX version {}", bytes[0]);
+        }
+        let mut reader = Reader {
+            bytes: &bytes[4..],
+            network_order: bytes[2] & NETWORK_BYTE_ORDER != 0,
+        };
+        let header = Header {
+            kind: bytes[1],
+            flags: bytes[2],
+            session_id: reader.u32()?,
+            transaction_id: reader.u32()?,
+            packet_id: reader.u32()?,
+        };
+        let len = reader.u32()? as usize;
+        if len > MAX_PAYLOAD {
+            bail!("AgentX PDU of {} bytes is too large", len);
+        }
+        let mut payload = vec![0; len];
+        self.stream
+            .read_exact(&mut payload)
+            .await
+            .context("Failed to read from the master agent")?;
+        Ok(Some((header, payload)))
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    network_order: bool,
+}
+
+impl Reader<'_> {
+    fn take<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let (taken, rest) = self
+            .bytes
+            .split_first_chunk()
+            .ok_or_else(|| anyhow!("Truncated AgentX PDU"))?;
+        self.bytes = rest;
+        Ok(*taken)
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        let bytes = self.take()?;
+        Ok(if self.network_order {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        })
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        let bytes = self.take()?;
+        Ok(if self.network_order {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        let bytes = self.take()?;
+        Ok(if self.network_order {
+            u64::from_be_bytes(bytes)
+        } else {
+            u64::from_le_bytes(bytes)
+        })
+    }
+
+    // the OID and its include flag
+    fn oid(&mut self) -> Result<(Vec<u64>, bool)> {
+        let [n_subid, prefix, include, _] = self.take()?;
+        let mut oid = match prefix {
+            0 => Vec::new(),
+            prefix => vec![1, 3, 6, 1, u64::from(prefix)],
+        };
+        for _ in 0..n_subid {
+            oid.push(u64::from(self.u32()?));
+        }
+        Ok((oid, include != 0))
+    }
+
+    fn octets(&mut self) -> Result<Vec<u8>> {
+        let len = self.u32()? as usize;
+        let padded = len.next_multiple_of(4);
+        if padded > self.bytes.len() {
+            bail!("Truncated AgentX PDU");
+        }
+        let (octets, rest) = self.bytes.split_at(padded);
+        self.bytes = rest;
+        Ok(octets[..len].to_vec())
+    }
+
+    fn ranges(&mut self) -> Result<Vec<SearchRange>> {
+        let mut ranges = Vec::new();
+        while !self.bytes.is_empty() {
+            let (start, include) = self.oid()?;
+            let (end, _) = self.oid()?;
+            ranges.push(SearchRange {
+                start,
+                include,
+                end,
+            });
+        }
+        Ok(ranges)
+    }
+
+    fn varbinds(&mut self) -> Result<Vec<VarBind>> {
+        let mut varbinds = Vec::new();
+        while !self.bytes.is_empty() {
+            let kind = self.u16()?;
+            self.u16()?;
+            let (oid, _) = self.oid()?;
+            let value = match kind {
+                2 => ObjectSyntax::Integer(self.u32()? as i32),
+                4 => ObjectSyntax::OctetString(self.octets()?),
+                5 => ObjectSyntax::Null,
+                6 => ObjectSyntax::ObjectIdentifier(self.oid()?.0),
+                64 => ObjectSyntax::IpAddress(self.octets()?),
+                65 => ObjectSyntax::Counter32(self.u32()?),
+                66 => ObjectSyntax::Gauge32(self.u32()?),
+                67 => ObjectSyntax::TimeTicks(self.u32()?),
+                68 => {
+                    let bytes = self.octets()?;
+                    decode_opaque_float(&bytes).unwrap_or(ObjectSyntax::Opaque(bytes))
+                }
+                70 => ObjectSyntax::Counter64(self.u64()?),
+                kind => bail!("Unknown AgentX value type {}", kind),
+            };
+            varbinds.push(VarBind { oid, value });
+        }
+        Ok(varbinds)
+    }
+}
+
+fn put_u16(buf: &mut Vec<u8>, value: u16) {
+    buf.extend_from_slice(&value.to_be_bytes());
+}
+
+fn put_u32(buf: &mut Vec<u8>, value: u32) {
+    buf.extend_from_slice(&value.to_be_bytes());
+}
+
+fn fits_agentx(oid: &[u64]) -> bool {
+    oid.len() <= MAX_SUBIDS && oid.iter().all(|&subid| u32::try_from(subid).is_ok())
+}
+
+fn encodable(varbind: &VarBind) -> bool {
+    fits_agentx(&varbind.oid)
+        && match &varbind.value {
+            ObjectSyntax::ObjectIdentifier(oid) => fits_agentx(oid),
+            _ => true,
+        }
+}
+
+// 1.3.6.1.x goes as a prefix, as RFC 2741 5.1 suggests. Callers check
+// fits_agentx() first
+fn put_oid(buf: &mut Vec<u8>, oid: &[u64], include: bool) {
+    let (prefix, subids) = match oid {
+        [1, 3, 6, 1, prefix @ 1..=255, rest @ ..] => (*prefix as u8, rest),
+        _ => (0, oid),
+    };
+    buf.extend_from_slice(&[subids.len() as u8, prefix, include.into(), 0]);
+    for &subid in subids {
+        put_u32(buf, subid as u32);
+    }
+}
+
+fn put_octets(buf: &mut Vec<u8>, octets: &[u8]) {
+    put_u32(buf, octets.len() as u32);
+    buf.extend_from_slice(octets);
+    buf.resize(buf.len().next_multiple_of(4), 0);
+}
+
+fn put_varbind(buf: &mut Vec<u8>, varbind: &VarBind) {
+    let at = buf.len();
+    buf.extend_from_slice(&[0; 4]);
+    put_oid(buf, &varbind.oid, false);
+    let kind: u16 = match &varbind.value {
+        ObjectSyntax::Integer(value) => {
+            put_u32(buf, *value as u32);
+            2
+        }
+        ObjectSyntax::OctetString(bytes) => {
+            put_octets(buf, bytes);
+            4
+        }
+        ObjectSyntax::Null => 5,
+        ObjectSyntax::ObjectIdentifier(oid) => {
+            put_oid(buf, oid, false);
+            6
+        }
+        ObjectSyntax::IpAddress(bytes) => {
+            put_octets(buf, bytes);
+            64
+        }
+        ObjectSyntax::Counter32(value) => {
+            put_u32(buf, *value);
+            65
+        }
+        ObjectSyntax::Gauge32(value) => {
+            put_u32(buf, *value);
+            66
+        }
+        ObjectSyntax::TimeTicks(value) => {
+            put_u32(buf, *value);
+            67
+        }
+        ObjectSyntax::Opaque(bytes) => {
+            put_octets(buf, bytes);
+            68
+        }
+        // in the Opaque wrapping the BER encoding gives them
+        ObjectSyntax::Float(_) | ObjectSyntax::Double(_) => {
+            let mut ber = Vec::new();
+            varbind.value.write_to_buf(&mut ber);
+            put_octets(buf, &ber[2..]);
+            68
+        }
+        ObjectSyntax::Counter64(value) => {
+            buf.extend_from_slice(&value.to_be_bytes());
+            70
+        }
+        ObjectSyntax::NoSuchObject => 128,
+        // AgentX has no way to carry a value under another tag
+        ObjectSyntax::NoSuchInstance | ObjectSyntax::Tagged { .. } => 129,
+        ObjectSyntax::EndOfMib => 130,
+    };
+    buf[at..at + 2].copy_from_slice(&kind.to_be_bytes());
+}
+
+// RFC 2741 6.2.16, for the errors only a master agent sends
+fn error_name(error: u16) -> String {
+    let name = match error {
+        256 => "openFailed",
+        257 => "notOpen",
+        262 => "unsupportedContext",
+        263 => "duplicateRegistration",
+        264 => "unknownRegistration",
+        266 => "parseError",
+        267 => "requestDenied",
+        268 => "processingError",
+        error => return format!("error {}", error),
+    };
+    name.to_string()
+}
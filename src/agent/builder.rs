@@ -0,0 +1,173 @@
+// Settings an agent is built with. Handlers are registered on the built
+// agent, since applications add and remove them while it runs.
+
+#[cfg(feature = "v3")]
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "v3")]
+use anyhow::Result;
+
+use super::notify::NotifySink;
+use super::{Agent, Vacm};
+use crate::manager::Manager;
+#[cfg(feature = "v3")]
+use crate::snmp::engine_id::{EngineId, NET_SNMP_ENTERPRISE};
+#[cfg(feature = "v3")]
+use crate::snmp::usm::UsmUser;
+
+// an answer that fits an Ethernet frame without IP fragmentation
+const DEFAULT_MAX_MESSAGE_SIZE: usize = 1472;
+
+/// Builds an [`Agent`]. Start from [`Agent::builder`].
+#[derive(Debug, Clone)]
+pub struct AgentBuilder {
+    community: Vec<u8>,
+    max_message_size: usize,
+    vacm: Option<Vacm>,
+    sinks: Vec<NotifySink>,
+    inform_timeout: Option<Duration>,
+    #[cfg(feature = "v3")]
+    engine_id: EngineId,
+    #[cfg(feature = "v3")]
+    engine_boots: u32,
+    #[cfg(feature = "v3")]
+    users: Vec<UsmUser>,
+}
+
+impl Default for AgentBuilder {
+    fn default() -> Self {
+        Self {
+            community: b"public".to_vec(),
+            max_message_size: DEFAULT_MAX_MESSAGE_SIZE,
+            vacm: None,
+            sinks: Vec::new(),
+            inform_timeout: None,
+            #[cfg(feature = "v3")]
+            engine_id: EngineId::random(NET_SNMP_ENTERPRISE)
+                .expect("a random engine ID is always valid"),
+            #[cfg(feature = "v3")]
+            engine_boots: 1,
+            #[cfg(feature = "v3")]
+            users: Vec::new(),
+        }
+    }
+}
+
+impl AgentBuilder {
+    /// The community v1 and v2c requests must carry; others are dropped
+    /// unanswered. Defaults to "public".
+    pub fn community(mut self, community: impl Into<Vec<u8>>) -> Self {
+        self.community = community.into();
+        self
+    }
+
+    /// The largest response to send. GetBulk answers are cut short to
+    /// fit, other requests get a tooBig error. Defaults to 1472 bytes.
+    pub fn max_message_size(mut self, bytes: usize) -> Self {
+        self.max_message_size = bytes;
+        self
+    }
+
+    /// Checks requests against `vacm` instead of letting the community
+    /// read everything. The communities accepted are then the security
+    /// names of its v1 and v2c groups, and [`AgentBuilder::community`] is
+    /// unused.
+    pub fn vacm(mut self, vacm: Vacm) -> Self {
+        self.vacm = Some(vacm);
+        self
+    }
+
+    /// Sends [`Agent::notify`]'s notifications to `target`, port 162
+    /// unless it names one, as SNMPv2c traps with `community`.
+    pub fn trap_sink(mut self, target: impl Into<String>, community: impl Into<String>) -> Self {
+        self.sinks.push(NotifySink {
+            target: target.into(),
+            community: community.into(),
+            inform_retries: None,
+        });
+        self
+    }
+
+    /// Sends [`Agent::notify`]'s notifications to `target` as SNMPv2c
+    /// informs with `community`, sending each up to `retries` more times
+    /// until the receiver acknowledges it.
+    pub fn inform_sink(
+        mut self,
+        target: impl Into<String>,
+        community: impl Into<String>,
+        retries: u32,
+    ) -> Self {
+        self.sinks.push(NotifySink {
+            target: target.into(),
+            community: community.into(),
+            inform_retries: Some(retries),
+        });
+        self
+    }
+
+    /// How long to wait for an inform sink's acknowledgement before
+    /// sending again. Defaults to 5 seconds.
+    pub fn inform_timeout(mut self, timeout: Duration) -> Self {
+        self.inform_timeout = Some(timeout);
+        self
+    }
+
+    /// The snmpEngineID v3 requests are addressed to. Defaults to a
+    /// random one, which changes every start and invalidates the keys
+    /// managers localized to the last one; see
+    /// [`AgentBuilder::engine_file`].
+    #[cfg(feature = "v3")]
+    pub fn engine_id(mut self, engine_id: EngineId) -> Self {
+        self.engine_id = engine_id;
+        self
+    }
+
+    /// snmpEngineBoots, which has to grow with every start for the time
+    /// window to keep replayed requests out. Defaults to 1.
+    #[cfg(feature = "v3")]
+    pub fn engine_boots(mut self, boots: u32) -> Self {
+        self.engine_boots = boots;
+        self
+    }
+
+    /// Keeps the engine ID and boot count in `path`, as the `oldEngineID`
+    /// and `engineBoots` lines of net-snmp's persistent snmpd.conf: reads
+    /// them back, counts one more boot and saves them. A missing file is
+    /// created with the engine ID set so far.
+    #[cfg(feature = "v3")]
+    pub fn engine_file(mut self, path: impl AsRef<Path>) -> Result<Self> {
+        let (engine_id, boots) = super::usm::persist_engine(path.as_ref(), self.engine_id)?;
+        self.engine_id = engine_id;
+        self.engine_boots = boots;
+        Ok(self)
+    }
+
+    /// Accepts v3 requests from `user`. Without a [`Vacm`] the user has
+    /// full access, and only at the security level it is configured for.
+    #[cfg(feature = "v3")]
+    pub fn usm_user(mut self, user: UsmUser) -> Self {
+        self.users.push(user);
+        self
+    }
+
+    pub fn build(self) -> Agent {
+        let mut notifier = Manager::builder();
+        if let Some(timeout) = self.inform_timeout {
+            notifier = notifier.timeout(timeout);
+        }
+        Agent {
+            community: self.community,
+            max_message_size: self.max_message_size,
+            vacm: self.vacm,
+            #[cfg(feature = "v3")]
+            engine: super::usm::Engine::new(self.engine_id, self.engine_boots, &self.users),
+            started: Instant::now(),
+            sinks: self.sinks,
+            notifier: notifier.build(),
+            requests: Arc::default(),
+            registrations: RwLock::new(Vec::new()),
+        }
+    }
+}
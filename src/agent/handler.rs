@@ -0,0 +1,45 @@
+// What an application implements to answer for its part of the OID tree.
+// The agent works out which registration a request falls under and does
+// the protocol; a handler only looks up values and, if it is writable,
+// checks and applies SETs one varbind at a time.
+
+use crate::snmp::pdu::{ErrorStatus, ObjectSyntax, VarBind};
+
+/// Answers requests under the subtree it was registered for with
+/// [`Agent::register`](super::Agent::register). Called from the agent's
+/// receive loop, so lookups should be quick.
+pub trait SubtreeHandler: Send + Sync {
+    /// The value of the instance `oid`, or `None` if there is none.
+    fn get(&self, oid: &[u64]) -> Option<ObjectSyntax>;
+
+    /// The first instance after `oid` in the subtree, with its value, or
+    /// `None` past the last one. `oid` may come before the subtree, in
+    /// which case the first instance is wanted.
+    fn get_next(&self, oid: &[u64]) -> Option<VarBind>;
+
+    /// Checks that `value` may be written to `oid`, without writing it:
+    /// the first phase of a SET, run for every varbind before any is
+    /// committed. The error is the status to answer with, e.g. wrongType
+    /// or wrongValue. Read-only by default.
+    fn test_set(&self, oid: &[u64], value: &ObjectSyntax) -> Result<(), ErrorStatus> {
+        let _ = (oid, value);
+        Err(ErrorStatus::NotWritable)
+    }
+
+    /// Writes a value that passed [`SubtreeHandler::test_set`]. Returns
+    /// false if that failed after all, which undoes the varbinds
+    /// committed before it and answers commitFailed.
+    fn commit_set(&self, oid: &[u64], value: &ObjectSyntax) -> bool {
+        let _ = (oid, value);
+        false
+    }
+
+    /// Takes back a committed write after a later varbind in the same
+    /// request failed to commit. `previous` is what [`SubtreeHandler::get`]
+    /// returned before the commit. Returns false if it couldn't, which
+    /// answers undoFailed.
+    fn undo_set(&self, oid: &[u64], previous: Option<&ObjectSyntax>) -> bool {
+        let _ = (oid, previous);
+        false
+    }
+}
@@ -0,0 +1,528 @@
+// An SNMP agent for applications to embed, exposing their own objects.
+// Handlers answer for the subtrees they were registered under; the agent
+// does the protocol around them: GetNext ordering across registrations,
+// GetBulk, SETs applied all or nothing, access control, the v1/v2c
+// differences in error reporting, and fitting the response into one
+// message.
+
+#[cfg(feature = "agentx")]
+mod agentx;
+mod builder;
+mod handler;
+mod notify;
+mod table;
+#[cfg(feature = "v3")]
+mod usm;
+mod vacm;
+
+#[cfg(feature = "agentx")]
+pub use agentx::{AGENTX_PORT, AGENTX_SOCKET};
+pub use builder::AgentBuilder;
+pub use handler::SubtreeHandler;
+pub use table::{TableProvider, TableRow};
+pub use vacm::{AccessKind, SecurityLevel, SecurityModel, Vacm, VacmError, View};
+
+use std::io::ErrorKind;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Instant;
+
+use anyhow::{Context, Result, anyhow};
+use tokio::net::UdpSocket;
+
+use crate::ber::{Asn1Tag, encoder};
+use crate::manager::{Manager, parse_oid_string};
+#[cfg(feature = "v3")]
+use crate::snmp::message::{SNMP_VERSION_3, peek_version};
+use crate::snmp::message::{SnmpMessage, parse_message};
+use crate::snmp::pdu::{ErrorStatus, ObjectSyntax, Pdu, PduData, VarBind};
+use notify::NotifySink;
+use table::TableHandler;
+use vacm::AccessKind::{Read, Write};
+
+const SNMP_V1: i32 = 0;
+const SNMP_V2C: i32 = 1;
+
+// the largest UDP payload
+const MAX_DATAGRAM: usize = 65535;
+
+// an error-status and the index of the varbind it is about, from 0
+type RequestError = (ErrorStatus, usize);
+
+struct Registration {
+    subtree: Vec<u64>,
+    handler: Arc<dyn SubtreeHandler>,
+}
+
+/// An SNMP agent answering v1, v2c and, with the v3 feature, USM
+/// requests from registered [`SubtreeHandler`]s, and sending the
+/// application's notifications. Requests are handled one at a time, so
+/// the varbinds of a SetRequest are applied together or not at all.
+pub struct Agent {
+    community: Vec<u8>,
+    max_message_size: usize,
+    vacm: Option<Vacm>,
+    #[cfg(feature = "v3")]
+    engine: usm::Engine,
+    // for sysUpTime in notifications
+    started: Instant,
+    sinks: Vec<NotifySink>,
+    // sends the notifications
+    notifier: Manager,
+    // bumped per request, for handlers that snapshot per request
+    requests: Arc<AtomicU64>,
+    // in OID order, none inside another
+    registrations: RwLock<Vec<Registration>>,
+}
+
+impl Agent {
+    /// An agent answering requests that carry `community`.
+    pub fn new(community: impl Into<Vec<u8>>) -> Self {
+        Self::builder().community(community).build()
+    }
+
+    /// Starts an [`AgentBuilder`] for settings beyond the community.
+    pub fn builder() -> AgentBuilder {
+        AgentBuilder::default()
+    }
+
+    /// Answers requests under `subtree`, e.g. "1.3.6.1.4.1.99999", from
+    /// `handler`. Subtrees can't overlap: registering one inside or around
+    /// another fails.
+    pub fn register(&self, subtree: &str, handler: impl SubtreeHandler + 'static) -> Result<()> {
+        let subtree = parse_oid_string(subtree)?;
+        if subtree.is_empty() {
+            return Err(anyhow!("Can't register the empty OID"));
+        }
+        let mut registrations = self.registrations.write().unwrap();
+        if let Some(existing) = registrations.iter().find(|registration| {
+            registration.subtree.starts_with(&subtree) || subtree.starts_with(&registration.subtree)
+        }) {
+            return Err(anyhow!(
+                "{} overlaps the registered subtree {}",
+                oid_string(&subtree),
+                oid_string(&existing.subtree)
+            ));
+        }
+        let at = registrations.partition_point(|registration| registration.subtree < subtree);
+        registrations.insert(
+            at,
+            Registration {
+                subtree,
+                handler: Arc::new(handler),
+            },
+        );
+        Ok(())
+    }
+
+    /// Answers for the table at `table` (the table, not its entry) from
+    /// `provider`'s rows, walking them in column and index order.
+    pub fn register_table(
+        &self,
+        table: &str,
+        provider: impl TableProvider + 'static,
+    ) -> Result<()> {
+        let handler = TableHandler::new(parse_oid_string(table)?, provider, self.requests.clone());
+        self.register(table, handler)
+    }
+
+    /// Stops answering for `subtree`. False if it wasn't registered.
+    pub fn unregister(&self, subtree: &str) -> Result<bool> {
+        let subtree = parse_oid_string(subtree)?;
+        let mut registrations = self.registrations.write().unwrap();
+        let before = registrations.len();
+        registrations.retain(|registration| registration.subtree != subtree);
+        Ok(registrations.len() < before)
+    }
+
+    /// Answers requests arriving on `socket` until receiving fails. Serve
+    /// from an `Arc<Agent>` to keep registering handlers while it runs.
+    pub async fn serve(&self, socket: UdpSocket) -> Result<()> {
+        let mut buf = vec![0; MAX_DATAGRAM];
+        loop {
+            let (len, from) = match socket.recv_from(&mut buf).await {
+                Ok(received) => received,
+                // Windows reports an earlier ICMP unreachable here
+                Err(e) if e.kind() == ErrorKind::ConnectionReset => continue,
+                Err(e) => return Err(e).context("Failed to receive a request"),
+            };
+            if let Some(response) = self.handle(&buf[..len]) {
+                // a requester that went away is no reason to stop
+                let _ = socket.send_to(&response, from).await;
+            }
+        }
+    }
+
+    /// The response to one request packet, or `None` when nothing should
+    /// go back: the packet didn't parse, carried a community the agent
+    /// doesn't know or wasn't a request.
+    pub fn handle(&self, packet: &[u8]) -> Option<Vec<u8>> {
+        #[cfg(feature = "v3")]
+        if peek_version(packet) == Ok(SNMP_VERSION_3) {
+            return self.handle_v3(packet);
+        }
+        let request = parse_message(packet).ok()?;
+        let model = match request.version {
+            SNMP_V1 => SecurityModel::V1,
+            SNMP_V2C => SecurityModel::V2c,
+            _ => return None,
+        };
+        let known = match &self.vacm {
+            Some(vacm) => vacm.has_group(model, &request.community),
+            None => request.community == self.community,
+        };
+        if !known {
+            return None;
+        }
+        let envelope = encoder::tlv_len(encoder::integer_len(request.version))
+            + encoder::tlv_len(request.community.len());
+        let fits = |pdu_len| encoder::tlv_len(envelope + pdu_len) <= self.max_message_size;
+        let pdu = self.answer(
+            &request.pdu,
+            model,
+            &request.community,
+            SecurityLevel::NoAuthNoPriv,
+            fits,
+        )?;
+        let response = SnmpMessage {
+            version: request.version,
+            community: request.community,
+            pdu,
+        };
+        Some(response.to_bytes())
+    }
+
+    // the response to a request PDU from `security_name`, cut to what
+    // `fits` a message; None for PDUs that aren't requests
+    fn answer(
+        &self,
+        pdu: &Pdu,
+        model: SecurityModel,
+        security_name: &[u8],
+        level: SecurityLevel,
+        fits: impl Fn(usize) -> bool,
+    ) -> Option<Pdu> {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        let v1 = model == SecurityModel::V1;
+        let view = |kind| self.view(model, security_name, level, kind);
+        let result = match pdu.tag {
+            Asn1Tag::GetRequest => view(Read).and_then(|view| self.get(&pdu.varbinds, v1, view)),
+            Asn1Tag::GetNextRequest => {
+                view(Read).and_then(|view| self.get_next(&pdu.varbinds, v1, view))
+            }
+            Asn1Tag::GetBulkRequest if !v1 => view(Read).map(|view| self.get_bulk(pdu, view)),
+            Asn1Tag::SetRequest => view(Write)
+                .and_then(|view| self.set(&pdu.varbinds, view))
+                .map(|()| pdu.varbinds.clone()),
+            _ => return None,
+        };
+        let result = match result {
+            Err((status, index)) if v1 => Err((v1_error_status(status), index)),
+            result => result,
+        };
+
+        let mut response = response_pdu(pdu, result);
+        if !fits(response.encoded_len()) {
+            if pdu.tag == Asn1Tag::GetBulkRequest {
+                // RFC 3416 4.2.3: send as many varbinds as fit
+                while !fits(response.encoded_len()) && response.varbinds.pop().is_some() {}
+            } else {
+                response.data = PduData::Basic {
+                    error_status: ErrorStatus::TooBig,
+                    error_index: 0,
+                };
+                // v1 echoes the request's varbinds, v2c sends none
+                response.varbinds = if v1 { pdu.varbinds.clone() } else { Vec::new() };
+            }
+        }
+        Some(response)
+    }
+
+    // the view a request reads or writes through, None without VACM
+    fn view(
+        &self,
+        model: SecurityModel,
+        security_name: &[u8],
+        level: SecurityLevel,
+        kind: AccessKind,
+    ) -> Result<Option<&View>, RequestError> {
+        let Some(vacm) = &self.vacm else {
+            return Ok(None);
+        };
+        vacm.view(model, security_name, level, kind)
+            .map(Some)
+            .map_err(|_| (ErrorStatus::AuthorizationError, 0))
+    }
+
+    fn get(
+        &self,
+        varbinds: &[VarBind],
+        v1: bool,
+        view: Option<&View>,
+    ) -> Result<Vec<VarBind>, RequestError> {
+        let registrations = self.registrations.read().unwrap();
+        varbinds
+            .iter()
+            .enumerate()
+            .map(|(i, varbind)| {
+                let value = match registrations
+                    .iter()
+                    .find(|registration| varbind.oid.starts_with(&registration.subtree))
+                {
+                    _ if !visible(view, &varbind.oid) => ObjectSyntax::NoSuchObject,
+                    Some(registration) => registration
+                        .handler
+                        .get(&varbind.oid)
+                        .unwrap_or(ObjectSyntax::NoSuchInstance),
+                    None => ObjectSyntax::NoSuchObject,
+                };
+                // v1 has no exception values, and no Counter64 (RFC 3584)
+                if v1
+                    && matches!(
+                        value,
+                        ObjectSyntax::NoSuchObject
+                            | ObjectSyntax::NoSuchInstance
+                            | ObjectSyntax::Counter64(_)
+                    )
+                {
+                    return Err((ErrorStatus::NoSuchName, i));
+                }
+                Ok(VarBind {
+                    oid: varbind.oid.clone(),
+                    value,
+                })
+            })
+            .collect()
+    }
+
+    // RFC 3416 4.2.5: every varbind is tested before any is committed,
+    // and a failed commit undoes the ones before it
+    fn set(&self, varbinds: &[VarBind], view: Option<&View>) -> Result<(), RequestError> {
+        let mut pending = self.test_set(varbinds, view)?;
+        pending.commit().map_err(|i| {
+            let status = if pending.undo() {
+                ErrorStatus::CommitFailed
+            } else {
+                ErrorStatus::UndoFailed
+            };
+            (status, i)
+        })
+    }
+
+    fn test_set(
+        &self,
+        varbinds: &[VarBind],
+        view: Option<&View>,
+    ) -> Result<PendingSet, RequestError> {
+        let registrations = self.registrations.read().unwrap();
+        let handlers = varbinds
+            .iter()
+            .enumerate()
+            .map(|(i, varbind)| {
+                if !visible(view, &varbind.oid) {
+                    return Err((ErrorStatus::NoAccess, i));
+                }
+                registrations
+                    .iter()
+                    .find(|registration| varbind.oid.starts_with(&registration.subtree))
+                    .map(|registration| registration.handler.clone())
+                    .ok_or((ErrorStatus::NotWritable, i))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for (i, (handler, varbind)) in handlers.iter().zip(varbinds).enumerate() {
+            handler
+                .test_set(&varbind.oid, &varbind.value)
+                .map_err(|status| (status, i))?;
+        }
+        let previous = handlers
+            .iter()
+            .zip(varbinds)
+            .map(|(handler, varbind)| handler.get(&varbind.oid))
+            .collect();
+        Ok(PendingSet {
+            handlers,
+            varbinds: varbinds.to_vec(),
+            previous,
+            committed: 0,
+        })
+    }
+
+    fn get_next(
+        &self,
+        varbinds: &[VarBind],
+        v1: bool,
+        view: Option<&View>,
+    ) -> Result<Vec<VarBind>, RequestError> {
+        varbinds
+            .iter()
+            .enumerate()
+            .map(
+                |(i, varbind)| match self.next_after(&varbind.oid, v1, view) {
+                    Some(next) => Ok(next),
+                    None if v1 => Err((ErrorStatus::NoSuchName, i)),
+                    None => Ok(end_of_mib(&varbind.oid)),
+                },
+            )
+            .collect()
+    }
+
+    fn get_bulk(&self, pdu: &Pdu, view: Option<&View>) -> Vec<VarBind> {
+        let PduData::Bulk {
+            non_repeaters,
+            max_repititions,
+        } = pdu.data
+        else {
+            return Vec::new();
+        };
+        let non_repeaters = usize::try_from(non_repeaters)
+            .unwrap_or(0)
+            .min(pdu.varbinds.len());
+        let (singles, repeaters) = pdu.varbinds.split_at(non_repeaters);
+
+        let mut varbinds: Vec<VarBind> = singles
+            .iter()
+            .map(|varbind| {
+                self.next_after(&varbind.oid, false, view)
+                    .unwrap_or_else(|| end_of_mib(&varbind.oid))
+            })
+            .collect();
+        // stop once the answer can't fit any more; handle() trims the rest
+        let mut size: usize = varbinds.iter().map(VarBind::encoded_len).sum();
+        let mut cursors: Vec<Vec<u64>> = repeaters.iter().map(|v| v.oid.clone()).collect();
+        'rounds: for _ in 0..max_repititions.max(0) {
+            if cursors.is_empty() {
+                break;
+            }
+            let mut all_ended = true;
+            for cursor in &mut cursors {
+                let next = self
+                    .next_after(cursor, false, view)
+                    .unwrap_or_else(|| end_of_mib(cursor));
+                all_ended &= next.value == ObjectSyntax::EndOfMib;
+                size += next.encoded_len();
+                cursor.clone_from(&next.oid);
+                varbinds.push(next);
+                if size > self.max_message_size {
+                    break 'rounds;
+                }
+            }
+            if all_ended {
+                break;
+            }
+        }
+        varbinds
+    }
+
+    // the first instance after `oid` in any registration and the view; v1
+    // skips Counter64 values, which it can't carry
+    fn next_after(&self, oid: &[u64], v1: bool, view: Option<&View>) -> Option<VarBind> {
+        let registrations = self.registrations.read().unwrap();
+        let mut cursor = oid.to_vec();
+        let candidates = registrations.iter().skip_while(|registration| {
+            registration.subtree.as_slice() < oid && !oid.starts_with(&registration.subtree)
+        });
+        for registration in candidates {
+            // a handler answering out of order or outside its subtree is
+            // taken to have nothing more
+            while let Some(next) = registration.handler.get_next(&cursor)
+                && next.oid > cursor
+                && next.oid.starts_with(&registration.subtree)
+            {
+                if !visible(view, &next.oid)
+                    || v1 && matches!(next.value, ObjectSyntax::Counter64(_))
+                {
+                    cursor = next.oid;
+                    continue;
+                }
+                return Some(next);
+            }
+        }
+        None
+    }
+}
+
+// the varbinds of a SET that passed testing, with the values they replace
+struct PendingSet {
+    handlers: Vec<Arc<dyn SubtreeHandler>>,
+    varbinds: Vec<VarBind>,
+    previous: Vec<Option<ObjectSyntax>>,
+    committed: usize,
+}
+
+impl PendingSet {
+    // commits in order, stopping at the index of the first that fails
+    fn commit(&mut self) -> Result<(), usize> {
+        for i in self.committed..self.varbinds.len() {
+            if !self.handlers[i].commit_set(&self.varbinds[i].oid, &self.varbinds[i].value) {
+                return Err(i);
+            }
+            self.committed = i + 1;
+        }
+        Ok(())
+    }
+
+    // newest first, and every one of them even if one fails
+    fn undo(&mut self) -> bool {
+        let mut undone = true;
+        for i in (0..self.committed).rev() {
+            undone &= self.handlers[i].undo_set(&self.varbinds[i].oid, self.previous[i].as_ref());
+        }
+        self.committed = 0;
+        undone
+    }
+}
+
+fn response_pdu(request: &Pdu, result: Result<Vec<VarBind>, RequestError>) -> Pdu {
+    let (error_status, error_index, varbinds) = match result {
+        Ok(varbinds) => (ErrorStatus::NoError, 0, varbinds),
+        Err((status, index)) => (status, index as i32 + 1, request.varbinds.clone()),
+    };
+    Pdu {
+        tag: Asn1Tag::GetResponse,
+        request_id: request.request_id,
+        data: PduData::Basic {
+            error_status,
+            error_index,
+        },
+        varbinds,
+    }
+}
+
+// RFC 3584 section 4.4: the v2 error-status values v1 doesn't have
+fn v1_error_status(status: ErrorStatus) -> ErrorStatus {
+    match status {
+        ErrorStatus::WrongValue
+        | ErrorStatus::WrongEncoding
+        | ErrorStatus::WrongType
+        | ErrorStatus::WrongLength
+        | ErrorStatus::InconsistentValue => ErrorStatus::BadValue,
+        ErrorStatus::NoAccess
+        | ErrorStatus::NotWritable
+        | ErrorStatus::NoCreation
+        | ErrorStatus::InconsistentName
+        | ErrorStatus::AuthorizationError => ErrorStatus::NoSuchName,
+        ErrorStatus::ResourceUnavailable | ErrorStatus::CommitFailed | ErrorStatus::UndoFailed => {
+            ErrorStatus::GenErr
+        }
+        status => status,
+    }
+}
+
+fn visible(view: Option<&View>, oid: &[u64]) -> bool {
+    view.is_none_or(|view| view.contains(oid))
+}
+
+fn end_of_mib(oid: &[u64]) -> VarBind {
+    VarBind {
+        oid: oid.to_vec(),
+        value: ObjectSyntax::EndOfMib,
+    }
+}
+
+fn oid_string(oid: &[u64]) -> String {
+    oid.iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(".")
+}
@@ -0,0 +1,86 @@
+// Notifications the agent raises itself: SNMPv2c traps or informs to the
+// sinks it was built with, sent through a Manager's notification
+// originator with sysUpTime counted from the agent's start.
+
+use anyhow::{Context, Result};
+use futures::future::join_all;
+
+use super::Agent;
+use crate::manager::network::{TRAP_PORT, split_port};
+use crate::manager::{Credentials, notification_varbinds, parse_oid_string};
+use crate::snmp::pdu::VarBind;
+
+/// Where [`Agent::notify`] sends notifications, and how.
+#[derive(Debug, Clone)]
+pub(super) struct NotifySink {
+    pub(super) target: String,
+    pub(super) community: String,
+    // Some for informs, with how many more times to send one that went
+    // unacknowledged
+    pub(super) inform_retries: Option<u32>,
+}
+
+impl Agent {
+    /// Sends the notification `trap_oid`, e.g. "1.3.6.1.6.3.1.1.5.3" for
+    /// linkDown, with `varbinds` after sysUpTime.0 and snmpTrapOID.0, to
+    /// every configured sink at once. Fails if any sink failed, after all
+    /// were tried: a trap that couldn't be sent or an inform that was
+    /// never acknowledged.
+    pub async fn notify(&self, trap_oid: &str, varbinds: Vec<VarBind>) -> Result<()> {
+        // checked once here rather than by every sink
+        parse_oid_string(trap_oid)?;
+        // hundredths of a second, wrapping like sysUpTime does
+        let uptime = (self.started.elapsed().as_millis() / 10) as u32;
+        let sends = self
+            .sinks
+            .iter()
+            .map(|sink| self.notify_sink(sink, uptime, trap_oid, &varbinds));
+        let failures: Vec<_> = join_all(sends)
+            .await
+            .into_iter()
+            .filter_map(Result::err)
+            .collect();
+        let count = failures.len();
+        match failures.into_iter().next() {
+            None => Ok(()),
+            Some(first) => Err(first.context(format!(
+                "{} of {} notification sinks failed",
+                count,
+                self.sinks.len()
+            ))),
+        }
+    }
+
+    async fn notify_sink(
+        &self,
+        sink: &NotifySink,
+        uptime: u32,
+        trap_oid: &str,
+        varbinds: &[VarBind],
+    ) -> Result<()> {
+        let credentials = Credentials::v2c(sink.community.as_str());
+        let result = match sink.inform_retries {
+            Some(retries) => {
+                let varbinds =
+                    notification_varbinds(uptime, parse_oid_string(trap_oid)?, varbinds.to_vec());
+                self.notifier
+                    .inform(&sink.target, &credentials, varbinds, retries)
+                    .await
+            }
+            None => {
+                let (host, port) = split_port(&sink.target)?;
+                self.notifier
+                    .send_trap(
+                        host,
+                        port.unwrap_or(TRAP_PORT),
+                        &credentials,
+                        uptime,
+                        trap_oid,
+                        varbinds.to_vec(),
+                    )
+                    .await
+            }
+        };
+        result.with_context(|| format!("Failed to notify {}", sink.target))
+    }
+}
@@ -0,0 +1,103 @@
+// Tables are the awkward part of answering GetNext: cells go column by
+// column, rows in index order, with the index spelled out in arcs. A
+// TableProvider only lists its rows and the agent does the rest.
+
+use std::collections::BTreeMap;
+use std::ops::Bound;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use super::SubtreeHandler;
+use crate::manager::{IndexSyntax, encode_index};
+use crate::snmp::pdu::{ObjectSyntax, VarBind};
+
+/// One row of a [`TableProvider`]'s table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableRow {
+    /// The values of the INDEX objects, in order.
+    pub index: Vec<ObjectSyntax>,
+    /// The row's cells by column number.
+    pub cells: BTreeMap<u64, ObjectSyntax>,
+}
+
+/// Supplies the rows of a table registered with
+/// [`Agent::register_table`](super::Agent::register_table).
+pub trait TableProvider: Send + Sync {
+    /// The syntax of the table's INDEX objects, in order.
+    fn index(&self) -> Vec<IndexSyntax>;
+
+    /// The current rows, in any order. Rows whose index doesn't fit
+    /// [`TableProvider::index`] are left out; columns a row lacks are
+    /// skipped by GetNext and noSuchInstance to Get.
+    fn rows(&self) -> Vec<TableRow>;
+}
+
+type Cells = BTreeMap<Vec<u64>, ObjectSyntax>;
+
+// serves a TableProvider as the subtree of the table it was registered at
+pub(super) struct TableHandler<P> {
+    table: Vec<u64>,
+    provider: P,
+    // the agent's request count, and the cells built for the request it
+    // was at, so a walk or a GetBulk lists the rows once per request
+    requests: Arc<AtomicU64>,
+    snapshot: Mutex<Option<(u64, Arc<Cells>)>>,
+}
+
+impl<P: TableProvider> TableHandler<P> {
+    pub(super) fn new(table: Vec<u64>, provider: P, requests: Arc<AtomicU64>) -> Self {
+        TableHandler {
+            table,
+            provider,
+            requests,
+            snapshot: Mutex::new(None),
+        }
+    }
+
+    // the cells as of the current request
+    fn cells(&self) -> Arc<Cells> {
+        let request = self.requests.load(Ordering::Relaxed);
+        let mut snapshot = self.snapshot.lock().unwrap();
+        match &*snapshot {
+            Some((taken_at, cells)) if *taken_at == request => cells.clone(),
+            _ => {
+                let cells = Arc::new(self.build_cells());
+                *snapshot = Some((request, cells.clone()));
+                cells
+            }
+        }
+    }
+
+    // every cell by its OID, table.1.column.index, which sorts them into
+    // GetNext order
+    fn build_cells(&self) -> Cells {
+        let index = self.provider.index();
+        let mut cells = BTreeMap::new();
+        for row in self.provider.rows() {
+            let Some(arcs) = encode_index(&row.index, &index) else {
+                continue;
+            };
+            for (column, value) in row.cells {
+                cells.insert([&self.table[..], &[1, column], &arcs].concat(), value);
+            }
+        }
+        cells
+    }
+}
+
+impl<P: TableProvider> SubtreeHandler for TableHandler<P> {
+    fn get(&self, oid: &[u64]) -> Option<ObjectSyntax> {
+        self.cells().get(oid).cloned()
+    }
+
+    fn get_next(&self, oid: &[u64]) -> Option<VarBind> {
+        let cells = self.cells();
+        let (oid, value) = cells
+            .range::<[u64], _>((Bound::Excluded(oid), Bound::Unbounded))
+            .next()?;
+        Some(VarBind {
+            oid: oid.clone(),
+            value: value.clone(),
+        })
+    }
+}
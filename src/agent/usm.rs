@@ -0,0 +1,319 @@
+// SNMPv3 for the agent, as the authoritative engine of the User-based
+// Security Model (RFC 3414). A request is checked against the engine ID,
+// the user database, the user's digest and the time window, and decrypted,
+// in that order; the first check to fail counts in usmStats and answers
+// with a Report of that counter, if the request asked for reports.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::time::Instant;
+
+use anyhow::{Context, Result, anyhow};
+
+use super::{Agent, SecurityLevel, SecurityModel};
+use crate::ber::Asn1Tag;
+use crate::manager::initial_salt;
+use crate::snmp::engine_id::EngineId;
+use crate::snmp::message::{
+    FLAG_AUTH, FLAG_PRIV, FLAG_REPORTABLE, HeaderData, SECURITY_MODEL_USM, ScopedPdu,
+    ScopedPduData, SnmpV3Message, parse_v3_message,
+};
+use crate::snmp::pdu::{ErrorStatus, ObjectSyntax, Pdu, PduData, VarBind};
+use crate::snmp::usm::{self, AuthProtocol, PrivProtocol, UsmSecurityParameters, UsmUser};
+
+// usmStats (RFC 3414 section 5), by their last arc
+const USM_STATS: [u64; 9] = [1, 3, 6, 1, 6, 3, 15, 1, 1];
+const UNSUPPORTED_SEC_LEVELS: usize = 1;
+const NOT_IN_TIME_WINDOWS: usize = 2;
+const UNKNOWN_USER_NAMES: usize = 3;
+const UNKNOWN_ENGINE_IDS: usize = 4;
+const WRONG_DIGESTS: usize = 5;
+const DECRYPTION_ERRORS: usize = 6;
+// snmpUnknownContexts.0 (RFC 3413)
+const UNKNOWN_CONTEXTS: [u64; 9] = [1, 3, 6, 1, 6, 3, 12, 1, 5];
+
+// RFC 3414 section 2.2.3: how far a request's time may be from ours
+const TIME_WINDOW: i32 = 150;
+
+// a user's keys, localized to the agent's engine
+struct LocalUser {
+    auth: Option<(AuthProtocol, Vec<u8>)>,
+    privacy: Option<(PrivProtocol, Vec<u8>)>,
+}
+
+impl LocalUser {
+    fn level(&self) -> SecurityLevel {
+        match (&self.auth, &self.privacy) {
+            (Some(_), Some(_)) => SecurityLevel::AuthPriv,
+            (Some(_), None) => SecurityLevel::AuthNoPriv,
+            _ => SecurityLevel::NoAuthNoPriv,
+        }
+    }
+}
+
+pub(super) struct Engine {
+    id: EngineId,
+    boots: i32,
+    started: Instant,
+    users: HashMap<Vec<u8>, LocalUser>,
+    // usmStats counters by their last arc, snmpUnknownContexts at 0
+    stats: [AtomicU32; 7],
+    salt: AtomicU64,
+}
+
+impl Engine {
+    pub(super) fn new(id: EngineId, boots: u32, users: &[UsmUser]) -> Self {
+        let users = users
+            .iter()
+            .map(|user| {
+                let local = LocalUser {
+                    auth: user.localized_auth(id.as_bytes()),
+                    privacy: user.localized_priv(id.as_bytes()),
+                };
+                (user.name.clone(), local)
+            })
+            .collect();
+        Engine {
+            id,
+            boots: boots.min(i32::MAX as u32) as i32,
+            started: Instant::now(),
+            users,
+            stats: Default::default(),
+            salt: AtomicU64::new(initial_salt()),
+        }
+    }
+
+    // snmpEngineTime: seconds since the engine last booted
+    fn time(&self) -> i32 {
+        self.started.elapsed().as_secs().min(i32::MAX as u64) as i32
+    }
+}
+
+/// Loads the engine ID and boot count an agent saved in `path` before,
+/// counting this start as one more boot, and saves them back. A missing
+/// file is created with `engine_id` and a first boot. The lines are those
+/// of net-snmp's persistent snmpd.conf, which keeps the ID it generated
+/// as `oldEngineID`.
+pub(super) fn persist_engine(path: &Path, engine_id: EngineId) -> Result<(EngineId, u32)> {
+    let (engine_id, boots) = match fs::read_to_string(path) {
+        Ok(saved) => {
+            let mut saved_id = None;
+            let mut saved_boots = None;
+            for line in saved.lines() {
+                match line.split_once(' ') {
+                    Some(("oldEngineID", hex)) => saved_id = Some(EngineId::from_hex(hex.trim())?),
+                    Some(("engineBoots", boots)) => saved_boots = Some(boots.trim().parse()?),
+                    _ => {}
+                }
+            }
+            let saved_id =
+                saved_id.ok_or_else(|| anyhow!("No oldEngineID in {}", path.display()))?;
+            (saved_id, saved_boots.unwrap_or(0u32).saturating_add(1))
+        }
+        Err(e) if e.kind() == ErrorKind::NotFound => (engine_id, 1),
+        Err(e) => {
+            return Err(e).with_context(|| format!("Failed to read {}", path.display()));
+        }
+    };
+    let hex: String = engine_id
+        .as_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    // written aside and renamed over, so a crash mid-write leaves the old
+    // file whole
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(".tmp");
+    let partial = PathBuf::from(partial);
+    fs::write(
+        &partial,
+        format!("engineBoots {}\noldEngineID 0x{}\n", boots, hex),
+    )
+    .and_then(|()| fs::rename(&partial, path))
+    .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok((engine_id, boots))
+}
+
+impl Agent {
+    /// The agent's snmpEngineID, which managers discover and localize
+    /// their users' keys to.
+    pub fn engine_id(&self) -> &EngineId {
+        &self.engine.id
+    }
+
+    /// snmpEngineBoots: how many times the agent has started, as far as
+    /// its saved engine state knows.
+    pub fn engine_boots(&self) -> u32 {
+        self.engine.boots as u32
+    }
+
+    pub(super) fn handle_v3(&self, packet: &[u8]) -> Option<Vec<u8>> {
+        let engine = &self.engine;
+        let mut request = parse_v3_message(packet).ok()?;
+        let level = match request.header.flags & (FLAG_AUTH | FLAG_PRIV) {
+            0 => SecurityLevel::NoAuthNoPriv,
+            FLAG_AUTH => SecurityLevel::AuthNoPriv,
+            // privacy without authentication is no valid message
+            FLAG_PRIV => return None,
+            _ => SecurityLevel::AuthPriv,
+        };
+        let params = &request.security_params;
+
+        if params.authoritative_engine_id != engine.id.as_bytes() {
+            // also how managers discover the engine ID
+            return self.report(&request, &USM_STATS, UNKNOWN_ENGINE_IDS, None);
+        }
+        let Some(user) = engine.users.get(&params.user_name) else {
+            return self.report(&request, &USM_STATS, UNKNOWN_USER_NAMES, None);
+        };
+        // without VACM to say otherwise, a user is only trusted at the
+        // level it is configured for
+        if level > user.level() || self.vacm.is_none() && level < user.level() {
+            return self.report(&request, &USM_STATS, UNSUPPORTED_SEC_LEVELS, None);
+        }
+        let auth = user
+            .auth
+            .as_ref()
+            .filter(|_| level >= SecurityLevel::AuthNoPriv);
+        if let Some((protocol, key)) = auth {
+            if usm::verify_message(packet, *protocol, key).is_err() {
+                return self.report(&request, &USM_STATS, WRONG_DIGESTS, None);
+            }
+            let out_of_window = params.engine_boots != engine.boots
+                || engine.boots == i32::MAX
+                || params.engine_time.abs_diff(engine.time()) > TIME_WINDOW as u32;
+            if out_of_window {
+                // authenticated, so the manager can trust our boots and time
+                return self.report(&request, &USM_STATS, NOT_IN_TIME_WINDOWS, auth);
+            }
+        }
+        let privacy = user
+            .privacy
+            .as_ref()
+            .filter(|_| level == SecurityLevel::AuthPriv);
+        if let Some((protocol, key)) = privacy
+            && usm::decrypt_scoped_pdu(&mut request, *protocol, key).is_err()
+        {
+            return self.report(&request, &USM_STATS, DECRYPTION_ERRORS, None);
+        }
+        let ScopedPduData::Plaintext(scoped) = &request.data else {
+            return None;
+        };
+        if !scoped.context_name.is_empty() {
+            return self.report(&request, &UNKNOWN_CONTEXTS, 0, None);
+        }
+
+        let mut response = self.v3_message(&request, empty_response(&scoped.pdu));
+        let max_size = self
+            .max_message_size
+            .min(usize::try_from(request.header.max_size).unwrap_or(0));
+        // what wraps the PDU, and a margin for padding and longer lengths
+        let overhead = self.secure(response.clone(), auth, privacy).len()
+            - empty_response(&scoped.pdu).encoded_len()
+            + 16;
+        let fits = |pdu_len| pdu_len + overhead <= max_size;
+        let security_name = &request.security_params.user_name;
+        let pdu = self.answer(&scoped.pdu, SecurityModel::Usm, security_name, level, fits)?;
+        response.data = ScopedPduData::Plaintext(ScopedPdu {
+            context_engine_id: engine.id.as_bytes().to_vec(),
+            context_name: Vec::new(),
+            pdu,
+        });
+        Some(self.secure(response, auth, privacy))
+    }
+
+    // counts the failure and answers with the counter, if the request
+    // wants to hear about it
+    fn report(
+        &self,
+        request: &SnmpV3Message,
+        counter: &[u64],
+        stat: usize,
+        auth: Option<&(AuthProtocol, Vec<u8>)>,
+    ) -> Option<Vec<u8>> {
+        let count = self.engine.stats[stat].fetch_add(1, Ordering::Relaxed) + 1;
+        if request.header.flags & FLAG_REPORTABLE == 0 {
+            return None;
+        }
+        let oid = match stat {
+            0 => [counter, &[0]].concat(),
+            stat => [counter, &[stat as u64, 0]].concat(),
+        };
+        let request_id = request
+            .scoped_pdu()
+            .map_or(0, |scoped| scoped.pdu.request_id);
+        let report = Pdu {
+            tag: Asn1Tag::Report,
+            request_id,
+            data: PduData::Basic {
+                error_status: ErrorStatus::NoError,
+                error_index: 0,
+            },
+            varbinds: vec![VarBind {
+                oid,
+                value: ObjectSyntax::Counter32(count),
+            }],
+        };
+        let mut message = self.v3_message(request, report);
+        message.header.flags = 0;
+        Some(self.secure(message, auth, None))
+    }
+
+    // an answer to `request` from this engine, in the clear for now
+    fn v3_message(&self, request: &SnmpV3Message, pdu: Pdu) -> SnmpV3Message {
+        SnmpV3Message {
+            header: HeaderData {
+                msg_id: request.header.msg_id,
+                max_size: self.max_message_size.min(i32::MAX as usize) as i32,
+                flags: request.header.flags & (FLAG_AUTH | FLAG_PRIV),
+                security_model: SECURITY_MODEL_USM,
+            },
+            security_params: UsmSecurityParameters {
+                authoritative_engine_id: self.engine.id.as_bytes().to_vec(),
+                engine_boots: self.engine.boots,
+                engine_time: self.engine.time(),
+                user_name: request.security_params.user_name.clone(),
+                ..Default::default()
+            },
+            data: ScopedPduData::Plaintext(ScopedPdu {
+                context_engine_id: self.engine.id.as_bytes().to_vec(),
+                context_name: Vec::new(),
+                pdu,
+            }),
+        }
+    }
+
+    fn secure(
+        &self,
+        mut message: SnmpV3Message,
+        auth: Option<&(AuthProtocol, Vec<u8>)>,
+        privacy: Option<&(PrivProtocol, Vec<u8>)>,
+    ) -> Vec<u8> {
+        if let Some((protocol, key)) = privacy {
+            let salt = self.engine.salt.fetch_add(1, Ordering::Relaxed);
+            usm::encrypt_scoped_pdu(&mut message, *protocol, key, salt);
+        }
+        match auth {
+            Some((protocol, key)) => {
+                message.header.flags |= FLAG_AUTH;
+                usm::authenticate_message(&mut message, *protocol, key)
+            }
+            None => message.to_bytes(),
+        }
+    }
+}
+
+fn empty_response(request: &Pdu) -> Pdu {
+    Pdu {
+        tag: Asn1Tag::GetResponse,
+        request_id: request.request_id,
+        data: PduData::Basic {
+            error_status: ErrorStatus::NoError,
+            error_index: 0,
+        },
+        varbinds: Vec::new(),
+    }
+}
@@ -0,0 +1,221 @@
+// The View-based Access Control Model (RFC 3415): which subtrees each
+// community or user may read and write. Principals are put in groups,
+// groups get a read and a write view at a security level, and views are
+// built from included and excluded subtrees. There are no contexts; all
+// access is to the default one.
+
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+use thiserror::Error;
+
+/// The security model a request arrived under.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SecurityModel {
+    V1 = 1,
+    V2c = 2,
+    Usm = 3,
+}
+
+/// How well a request was protected. Ordered, so access granted at
+/// authNoPriv also covers authPriv requests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SecurityLevel {
+    NoAuthNoPriv = 1,
+    AuthNoPriv = 2,
+    AuthPriv = 3,
+}
+
+/// Whether an object is being read or written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessKind {
+    Read,
+    Write,
+}
+
+/// Why access was refused, as isAccessAllowed (RFC 3415 3.2) reports it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum VacmError {
+    #[error("The security name is in no group")]
+    NoSuchGroupName,
+    #[error("The group has no access at this security level")]
+    NoAccessEntry,
+    #[error("The group has no view for this kind of access")]
+    NoSuchView,
+    #[error("The object is not in the view")]
+    NotInView,
+}
+
+#[derive(Debug, Clone)]
+struct ViewFamily {
+    subtree: Vec<u64>,
+    // per arc, whether it has to match; false for a `*`
+    mask: Vec<bool>,
+    included: bool,
+}
+
+impl ViewFamily {
+    fn matches(&self, oid: &[u64]) -> bool {
+        oid.len() >= self.subtree.len()
+            && self
+                .subtree
+                .iter()
+                .zip(&self.mask)
+                .zip(oid)
+                .all(|((arc, must_match), oid_arc)| !must_match || arc == oid_arc)
+    }
+}
+
+/// The subtrees a group may read or write through.
+#[derive(Debug, Clone, Default)]
+pub struct View(Vec<ViewFamily>);
+
+impl View {
+    /// RFC 3415 5.2: the most specific family covering `oid` decides, and
+    /// an OID no family covers is out of the view.
+    pub fn contains(&self, oid: &[u64]) -> bool {
+        self.0
+            .iter()
+            .filter(|family| family.matches(oid))
+            .max_by(|a, b| (a.subtree.len(), &a.subtree).cmp(&(b.subtree.len(), &b.subtree)))
+            .is_some_and(|family| family.included)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Access {
+    group: String,
+    level: SecurityLevel,
+    read_view: String,
+    write_view: String,
+}
+
+/// The groups, views and access entries an [`Agent`](super::Agent)
+/// checks requests against, built up with chained calls.
+#[derive(Debug, Clone, Default)]
+pub struct Vacm {
+    groups: HashMap<(SecurityModel, Vec<u8>), String>,
+    views: HashMap<String, View>,
+    access: Vec<Access>,
+}
+
+impl Vacm {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Puts `security_name`, a community for v1 and v2c or a user name
+    /// for USM, in `group` for requests under `model`.
+    pub fn group(
+        mut self,
+        model: SecurityModel,
+        security_name: impl Into<Vec<u8>>,
+        group: &str,
+    ) -> Self {
+        self.groups
+            .insert((model, security_name.into()), group.to_string());
+        self
+    }
+
+    /// Adds the subtree under `subtree` to `view`. An arc of `*` matches
+    /// any arc, as a 0 bit in a family mask does, e.g.
+    /// "1.3.6.1.2.1.2.2.1.*.3" for every column of interface 3.
+    pub fn include(self, view: &str, subtree: &str) -> Result<Self> {
+        self.family(view, subtree, true)
+    }
+
+    /// Takes the subtree under `subtree` out of `view`, even where a
+    /// shorter included subtree covers it.
+    pub fn exclude(self, view: &str, subtree: &str) -> Result<Self> {
+        self.family(view, subtree, false)
+    }
+
+    /// Lets `group` read through `read_view` and write through
+    /// `write_view` in requests at `level` or better. An empty view name
+    /// grants nothing.
+    pub fn access(
+        mut self,
+        group: &str,
+        level: SecurityLevel,
+        read_view: &str,
+        write_view: &str,
+    ) -> Self {
+        self.access.push(Access {
+            group: group.to_string(),
+            level,
+            read_view: read_view.to_string(),
+            write_view: write_view.to_string(),
+        });
+        self
+    }
+
+    /// Whether `security_name` may read or write `oid` in a request under
+    /// `model` at `level`: isAccessAllowed for the default context.
+    pub fn is_access_allowed(
+        &self,
+        model: SecurityModel,
+        security_name: &[u8],
+        level: SecurityLevel,
+        kind: AccessKind,
+        oid: &[u64],
+    ) -> Result<(), VacmError> {
+        if self.view(model, security_name, level, kind)?.contains(oid) {
+            Ok(())
+        } else {
+            Err(VacmError::NotInView)
+        }
+    }
+
+    pub(super) fn has_group(&self, model: SecurityModel, security_name: &[u8]) -> bool {
+        self.groups.contains_key(&(model, security_name.to_vec()))
+    }
+
+    pub(super) fn view(
+        &self,
+        model: SecurityModel,
+        security_name: &[u8],
+        level: SecurityLevel,
+        kind: AccessKind,
+    ) -> Result<&View, VacmError> {
+        let group = self
+            .groups
+            .get(&(model, security_name.to_vec()))
+            .ok_or(VacmError::NoSuchGroupName)?;
+        // the entry for the highest level the request meets
+        let access = self
+            .access
+            .iter()
+            .filter(|access| &access.group == group && access.level <= level)
+            .max_by_key(|access| access.level)
+            .ok_or(VacmError::NoAccessEntry)?;
+        let view = match kind {
+            AccessKind::Read => &access.read_view,
+            AccessKind::Write => &access.write_view,
+        };
+        self.views.get(view).ok_or(VacmError::NoSuchView)
+    }
+
+    fn family(mut self, view: &str, subtree: &str, included: bool) -> Result<Self> {
+        let (subtree, mask) = subtree
+            .split('.')
+            .filter(|arc| !arc.is_empty())
+            .map(|arc| match arc {
+                "*" => Ok((0, false)),
+                arc => arc
+                    .parse::<u64>()
+                    .map(|arc| (arc, true))
+                    .with_context(|| format!("Invalid OID component: '{}'", arc)),
+            })
+            .collect::<Result<(Vec<u64>, Vec<bool>)>>()?;
+        self.views
+            .entry(view.to_string())
+            .or_default()
+            .0
+            .push(ViewFamily {
+                subtree,
+                mask,
+                included,
+            });
+        Ok(self)
+    }
+}
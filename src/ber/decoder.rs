@@ -1,4 +1,4 @@
-use crate::ber::{BerError, BerResult};
+use crate::ber::{Asn1Tag, BerError, BerResult, parse_length, parse_tag_number};
 
 pub fn decode_integer(input: &[u8]) -> BerResult<i32> {
     if input.is_empty() {
@@ -11,11 +11,9 @@ pub fn decode_integer(input: &[u8]) -> BerResult<i32> {
     if input.len() > 4 {
         // 5 bytes valid if first byte is padding
         if input.len() == 5 {
-            if input[0] == 0x00 && (input[1] & 0x80) != 0 {
-                // valid continue
-            } else if input[0] == 0xFF && (input[1] & 0x80) == 0 {
-                // valid continue
-            } else {
+            let positive_padding = input[0] == 0x00 && (input[1] & 0x80) != 0;
+            let negative_padding = input[0] == 0xFF && (input[1] & 0x80) == 0;
+            if !positive_padding && !negative_padding {
                 return Err(BerError::IntegerOverflow);
             }
         } else {
@@ -73,3 +71,100 @@ pub fn decode_unsigned_integer64(input: &[u8]) -> BerResult<u64> {
     }
     Ok(value)
 }
+
+// deeper than any SNMP message gets
+const MAX_DER_DEPTH: usize = 32;
+
+/// Strict mode: checks that `input` is exactly one TLV in canonical DER
+/// form (X.690 section 10). That means definite, minimal lengths and tags,
+/// minimal integers and sub-identifiers, and SET OF members in ascending
+/// order. The parsers stay lenient; call this on a message first when
+/// only canonical encodings are acceptable.
+pub fn validate_der(input: &[u8]) -> BerResult<()> {
+    let rest = validate_der_element(input, 0)?;
+    if !rest.is_empty() {
+        return Err(BerError::TrailingData);
+    }
+    Ok(())
+}
+
+fn validate_der_element(input: &[u8], depth: usize) -> BerResult<&[u8]> {
+    let tag_byte = *input.first().ok_or(BerError::IncompleteData)?;
+    let (number, after_tag) = parse_tag_number(input)?;
+    if tag_byte & 0x1F == 0x1F && number < 0x1F {
+        return Err(BerError::NotDer("long-form tag for a tag number below 31"));
+    }
+
+    let (value, rest) = der_value(after_tag)?;
+
+    if tag_byte & 0x20 != 0 {
+        if depth >= MAX_DER_DEPTH {
+            return Err(BerError::NotDer("nested too deeply"));
+        }
+        let mut members = Vec::new();
+        let mut content = value;
+        while !content.is_empty() {
+            let after = validate_der_element(content, depth + 1)?;
+            members.push(&content[..content.len() - after.len()]);
+            content = after;
+        }
+        // universal SET, constructed
+        if tag_byte == 0x31 && !members.is_sorted() {
+            return Err(BerError::NotDer("SET OF members out of order"));
+        }
+        return Ok(rest);
+    }
+
+    match Asn1Tag::from_u8(tag_byte) {
+        Ok(
+            Asn1Tag::Integer
+            | Asn1Tag::Counter32
+            | Asn1Tag::Gauge32
+            | Asn1Tag::TimeTicks
+            | Asn1Tag::Counter64,
+        ) => {
+            if let [first, second, ..] = value
+                && ((*first == 0x00 && second & 0x80 == 0)
+                    || (*first == 0xFF && second & 0x80 != 0))
+            {
+                return Err(BerError::NotDer("integer with redundant leading octet"));
+            }
+        }
+        Ok(Asn1Tag::ObjectIdentifier) => {
+            // a sub-identifier starts at the first octet and after each
+            // octet without the continuation bit
+            let mut at_start = true;
+            for byte in value {
+                if at_start && *byte == 0x80 {
+                    return Err(BerError::NotDer("sub-identifier with leading 0x80"));
+                }
+                at_start = byte & 0x80 == 0;
+            }
+        }
+        Ok(Asn1Tag::Null) if !value.is_empty() => {
+            return Err(BerError::NotDer("NULL with contents"));
+        }
+        _ => {}
+    }
+    Ok(rest)
+}
+
+fn der_value(input: &[u8]) -> BerResult<(&[u8], &[u8])> {
+    let len_byte = *input.first().ok_or(BerError::IncompleteData)?;
+    if len_byte == 0x80 {
+        return Err(BerError::NotDer("indefinite length"));
+    }
+    let (value_len, after_length) = parse_length(input)?;
+    if len_byte > 0x80 {
+        if input.get(1) == Some(&0x00) {
+            return Err(BerError::NotDer("length with leading zero octets"));
+        }
+        if value_len < 0x80 {
+            return Err(BerError::NotDer("long-form length under 128"));
+        }
+    }
+    if after_length.len() < value_len {
+        return Err(BerError::IncompleteData);
+    }
+    Ok(after_length.split_at(value_len))
+}
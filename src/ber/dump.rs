@@ -0,0 +1,141 @@
+// Renders raw BER as an indented tree of TLVs, for looking at packets the
+// parser rejects. It never fails: whatever can't be parsed is shown as an
+// error line with the bytes that were left.
+
+use std::fmt::Write;
+
+use super::decoder::decode_integer;
+use super::{TagClass, decode_oid, parse_raw_ber_object};
+
+// nesting costs two bytes a level, so hostile input could go very deep
+const MAX_DEPTH: usize = 32;
+const PREVIEW_LEN: usize = 16;
+
+/// Dumps every TLV in `input`, one per line, children indented under
+/// their parent. Each line has the TLV's offset, tag, length and a preview
+/// of primitive contents.
+pub fn dump(input: &[u8]) -> String {
+    let mut out = String::new();
+    dump_level(&mut out, input, 0, 0);
+    out
+}
+
+fn dump_level(out: &mut String, input: &[u8], offset: usize, depth: usize) {
+    let indent = "  ".repeat(depth);
+    let mut rest = input;
+
+    while !rest.is_empty() {
+        let position = offset + (input.len() - rest.len());
+        let (obj, after) = match parse_raw_ber_object(rest) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                let _ = writeln!(out, "{:5}: {}error: {}: {}", position, indent, e, hex(rest));
+                return;
+            }
+        };
+        let header_len = obj.value.as_ptr() as usize - rest.as_ptr() as usize;
+
+        let _ = write!(out, "{:5}: {}", position, indent);
+        if let Some(name) = name(obj.tag_byte) {
+            let _ = write!(out, "{} ", name);
+        }
+        let _ = write!(
+            out,
+            "[{} {}{}] len {}",
+            class_name(obj.class()),
+            obj.number,
+            if obj.is_constructed() {
+                ", constructed"
+            } else {
+                ""
+            },
+            obj.value.len()
+        );
+
+        if obj.value.is_empty() {
+            out.push('\n');
+        } else if !obj.is_constructed() {
+            let _ = writeln!(out, ": {}", preview(obj.tag_byte, obj.value));
+        } else if depth + 1 >= MAX_DEPTH {
+            let _ = writeln!(out, ": nested too deep to show");
+        } else {
+            out.push('\n');
+            dump_level(out, obj.value, position + header_len, depth + 1);
+        }
+
+        rest = after;
+    }
+}
+
+fn name(tag_byte: u8) -> Option<&'static str> {
+    let name = match tag_byte {
+        0x02 => "INTEGER",
+        0x04 => "OCTET STRING",
+        0x05 => "NULL",
+        0x06 => "OBJECT IDENTIFIER",
+        0x30 => "SEQUENCE",
+        0x40 => "IpAddress",
+        0x41 => "Counter32",
+        0x42 => "Gauge32",
+        0x43 => "TimeTicks",
+        0x44 => "Opaque",
+        0x46 => "Counter64",
+        0xA0 => "GetRequest",
+        0xA1 => "GetNextRequest",
+        0xA2 => "Response",
+        0xA3 => "SetRequest",
+        0xA4 => "Trap",
+        0xA5 => "GetBulkRequest",
+        0xA6 => "InformRequest",
+        0xA7 => "SNMPv2-Trap",
+        0xA8 => "Report",
+        0x80 => "noSuchObject",
+        0x81 => "noSuchInstance",
+        0x82 => "endOfMibView",
+        _ => return None,
+    };
+    Some(name)
+}
+
+fn class_name(class: TagClass) -> &'static str {
+    match class {
+        TagClass::Universal => "universal",
+        TagClass::Application => "application",
+        TagClass::Context => "context",
+        TagClass::Private => "private",
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    let mut text = bytes
+        .iter()
+        .take(PREVIEW_LEN)
+        .map(|b| format!("{:02x}", b))
+        .collect::<Vec<_>>()
+        .join(" ");
+    if bytes.len() > PREVIEW_LEN {
+        text.push_str(" ...");
+    }
+    text
+}
+
+// the hex, plus the value where it is easy to read off
+fn preview(tag_byte: u8, value: &[u8]) -> String {
+    let decoded = match tag_byte {
+        0x02 => decode_integer(value).ok().map(|v| v.to_string()),
+        0x06 => decode_oid(value).ok().map(|oid| {
+            oid.iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(".")
+        }),
+        0x04 if value.iter().all(|b| b.is_ascii_graphic() || *b == b' ') => {
+            Some(format!("{:?}", String::from_utf8_lossy(value)))
+        }
+        _ => None,
+    };
+    match decoded {
+        Some(decoded) => format!("{} ({})", hex(value), decoded),
+        None => hex(value),
+    }
+}
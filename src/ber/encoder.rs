@@ -1,45 +1,50 @@
 // this file does the reverse. takes rust types and then converts them into BER encoded bytes
 // Just TLV , TLV ...
 
-use crate::ber::Asn1Tag;
+use crate::ber::{Asn1Tag, TagClass};
 
-pub fn encode_integer(buf: &mut Vec<u8>, value: i32) {
-    let bytes = value.to_be_bytes(); // get in Big endian order
-
-    let mut start_index = 0;
+/// Content octets of the minimal two's complement encoding of `value`.
+pub fn integer_len(value: i32) -> usize {
+    let redundant = if value < 0 {
+        value.leading_ones()
+    } else {
+        value.leading_zeros()
+    };
+    // one sign bit has to stay
+    4 - ((redundant - 1) / 8) as usize
+}
 
-    if value > 0 {
-        // if first significant byte has it highbit set , then prepend 0x00 to signigy its positive
-        while start_index < 3 && bytes[start_index] == 0x00 {
-            start_index += 1;
-        }
+pub fn encode_integer(buf: &mut Vec<u8>, value: i32) {
+    let len = integer_len(value);
 
-        if (bytes[start_index] & 0x80) != 0 {
-            start_index -= 1; // here it says the current number seems to be - ,and we need to
-            // include one leading 0x00
-        }
-    } else if value < 0 {
-        while start_index < 3 && bytes[start_index] == 0xFF {
-            start_index += 1;
-        }
+    // TLV
+    buf.push(Asn1Tag::Integer as u8);
+    encode_length(buf, len);
+    buf.extend_from_slice(&value.to_be_bytes()[4 - len..]);
+}
 
-        if (bytes[start_index] & 0x80) == 0 {
-            start_index -= 1; // here it says the current number seems to be - ,and we need to
-            // include one leading 0xFF
-        }
+/// Octets taken by the length field for `len` content octets.
+pub fn length_len(len: usize) -> usize {
+    if len < 128 {
+        1
     } else {
-        start_index = 3;
+        1 + (usize::BITS - len.leading_zeros()).div_ceil(8) as usize
     }
+}
 
-    let value_bytes = &bytes[start_index..];
-    let len = value_bytes.len();
+/// Total size of a TLV with a one-octet tag and `content_len` content octets.
+pub fn tlv_len(content_len: usize) -> usize {
+    1 + length_len(content_len) + content_len
+}
 
-    // TLV
-    buf.push(Asn1Tag::Integer as u8);
-    encode_length(buf, len);
-    buf.extend_from_slice(value_bytes);
+/// Writes the tag and length of a TLV whose content the caller writes
+/// straight after, so nested structures need no buffer of their own.
+pub fn encode_header(buf: &mut Vec<u8>, tag: Asn1Tag, content_len: usize) {
+    buf.push(tag as u8);
+    encode_length(buf, content_len);
 }
-fn encode_length(buf: &mut Vec<u8>, len: usize) {
+
+pub fn encode_length(buf: &mut Vec<u8>, len: usize) {
     if len < 128 {
         buf.push(len as u8);
     } else {
@@ -69,65 +74,87 @@ pub fn encode_null(buf: &mut Vec<u8>) {
     buf.push(0x00);
 }
 
-fn encode_oid_sub_id(buf: &mut Vec<u8>, mut sub_id: u32) {
+fn oid_sub_id_len(sub_id: u64) -> usize {
+    ((u64::BITS - sub_id.leading_zeros()).div_ceil(7) as usize).max(1)
+}
+
+fn encode_oid_sub_id(buf: &mut Vec<u8>, mut sub_id: u64) {
     if sub_id == 0 {
         buf.push(0x00);
         return;
     }
 
-    let mut bytes = [0u8; 5];
-    let mut i = 4;
+    // ten 7-bit groups cover a u64
+    let mut bytes = [0u8; 10];
+    let mut i = 9;
 
     bytes[i] = (sub_id & 0x7F) as u8;
     sub_id >>= 7;
-    i -= 1;
 
     while sub_id > 0 {
-        bytes[i] = ((sub_id * 0x7F) | 0x80) as u8;
-        sub_id >>= 7;
         i -= 1;
+        bytes[i] = ((sub_id & 0x7F) | 0x80) as u8;
+        sub_id >>= 7;
     }
 
-    buf.extend_from_slice(&bytes[(i + 1)..]);
+    buf.extend_from_slice(&bytes[i..]);
 }
 
-pub fn encode_oid(buf: &mut Vec<u8>, oid: &[u32]) {
-    let mut oid_value_buf = Vec::new();
-
-    let b1 = (oid[0] * 40) + oid[1];
-    oid_value_buf.push(b1 as u8);
-
-    for sub_id in &oid[2..] {
-        encode_oid_sub_id(&mut oid_value_buf, *sub_id);
+/// Octets taken by a tag with this number.
+pub fn tag_len(number: u32) -> usize {
+    if number < 0x1F {
+        1
+    } else {
+        1 + oid_sub_id_len(number as u64)
     }
+}
 
-    let len = oid_value_buf.len();
+/// Writes a tag's identifier octets, in long form for tag numbers of 31
+/// and up.
+pub fn encode_tag(buf: &mut Vec<u8>, class: TagClass, constructed: bool, number: u32) {
+    let first = class.bits() | if constructed { 0x20 } else { 0 };
+    if number < 0x1F {
+        buf.push(first | number as u8);
+    } else {
+        buf.push(first | 0x1F);
+        encode_oid_sub_id(buf, number as u64);
+    }
+}
 
-    buf.push(Asn1Tag::ObjectIdentifier as u8);
-    encode_length(buf, len);
-    buf.extend_from_slice(&oid_value_buf);
+/// Content octets of an encoded OID.
+pub fn oid_len(oid: &[u64]) -> usize {
+    oid_sub_id_len((oid[0] * 40) + oid[1])
+        + oid[2..].iter().map(|id| oid_sub_id_len(*id)).sum::<usize>()
 }
 
-pub fn encode_unsigned_integer_helper(buf: &mut Vec<u8>, tag: Asn1Tag, value: u32) {
-    let bytes = value.to_be_bytes();
+pub fn encode_oid(buf: &mut Vec<u8>, oid: &[u64]) {
+    encode_header(buf, Asn1Tag::ObjectIdentifier, oid_len(oid));
 
-    let mut start_index = 0;
+    // under the 2 arc the combined value can need several bytes
+    encode_oid_sub_id(buf, (oid[0] * 40) + oid[1]);
 
-    while start_index < 3 && bytes[start_index] == 0x00 {
-        start_index += 1;
+    for sub_id in &oid[2..] {
+        encode_oid_sub_id(buf, *sub_id);
     }
+}
 
-    if (bytes[start_index] & 0x80) != 0 {
-        start_index -= 1;
-    }
+/// Content octets of an unsigned value, with the 0x00 that keeps a set
+/// high bit from reading as negative.
+pub fn unsigned_len(value: u64) -> usize {
+    (u64::BITS - value.leading_zeros()) as usize / 8 + 1
+}
 
-    let value_bytes = &bytes[start_index..];
-    let len = value_bytes.len();
+fn encode_unsigned(buf: &mut Vec<u8>, tag: Asn1Tag, value: u64) {
+    let len = unsigned_len(value);
+    encode_header(buf, tag, len);
+    if len > 8 {
+        buf.push(0x00);
+    }
+    buf.extend_from_slice(&value.to_be_bytes()[8 - len.min(8)..]);
+}
 
-    // TLV
-    buf.push(tag as u8);
-    encode_length(buf, len);
-    buf.extend_from_slice(value_bytes);
+pub fn encode_unsigned_integer_helper(buf: &mut Vec<u8>, tag: Asn1Tag, value: u32) {
+    encode_unsigned(buf, tag, value as u64);
 }
 
 pub fn encode_counter32(buf: &mut Vec<u8>, value: u32) {
@@ -142,29 +169,12 @@ pub fn encode_timeticks(buf: &mut Vec<u8>, value: u32) {
     encode_unsigned_integer_helper(buf, Asn1Tag::TimeTicks, value);
 }
 
-fn encode_unsigned_integer64_helper(buf: &mut Vec<u8>, tag: Asn1Tag, value: u64) {
-    let bytes = value.to_be_bytes();
-    let mut start_index = 0;
-    while start_index < 7 && bytes[start_index] == 0x00 {
-        start_index += 1;
-    }
-    if (bytes[start_index] & 0x80) != 0 {
-        start_index -= 1;
-    }
-    let value_bytes = &bytes[start_index..];
-
-    buf.push(tag as u8);
-    encode_length(buf, value_bytes.len());
-    buf.extend_from_slice(value_bytes);
-}
-
 pub fn encode_counter64(buf: &mut Vec<u8>, value: u64) {
-    encode_unsigned_integer64_helper(buf, Asn1Tag::Counter64, value);
+    encode_unsigned(buf, Asn1Tag::Counter64, value);
 }
 
 fn encode_bytes_with_tag(buf: &mut Vec<u8>, tag: Asn1Tag, value: &[u8]) {
-    buf.push(tag as u8);
-    encode_length(buf, value.len());
+    encode_header(buf, tag, value.len());
     buf.extend_from_slice(value);
 }
 
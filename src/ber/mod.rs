@@ -1,7 +1,11 @@
 use thiserror::Error;
 
 pub mod decoder;
+mod dump;
 pub mod encoder;
+pub mod stream;
+
+pub use dump::dump;
 
 pub type BerResult<T> = Result<T, BerError>;
 
@@ -33,6 +37,16 @@ pub enum BerError {
 
     #[error("Invalid value for enum: {0}")]
     InvalidEnumValue(i32),
+
+    #[error("Indefinite-length encodings nested too deeply")]
+    IndefiniteLengthTooDeep,
+
+    #[error("Message of {0} bytes exceeds the frame limit")]
+    FrameTooLarge(usize),
+
+    /// Valid BER, but not the canonical DER encoding.
+    #[error("Not DER: {0}")]
+    NotDer(&'static str),
 }
 
 /// ┌─────────────────────────────────────────────┐
@@ -56,6 +70,7 @@ pub enum BerError {
 /// │    31 means "long form" (multi-byte)        │
 /// └─────────────────────────────────────────────┘
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum Asn1Tag {
     // --- Universal tags
@@ -84,6 +99,7 @@ pub enum Asn1Tag {
     GetBulkRequest = 0xA5, // [CONTEXT 5]
     InformRequest = 0xA6,  // [CONTEXT 6]
     SnmpV2Trap = 0xA7,     // [CONTEXT 7]
+    Report = 0xA8,         // [CONTEXT 8]
 
     // exception types
     NoSuchObject = 0x80,
@@ -108,6 +124,7 @@ impl Asn1Tag {
             Asn1Tag::GetNextRequest => "Context, Constructed, Tag 1 (GetNext)",
             Asn1Tag::GetResponse => "Context, Constructed, Tag 2 (Response)",
             Asn1Tag::GetBulkRequest => "Context, Constructed, Tag 5 (GetBulk)",
+            Asn1Tag::Report => "Context, Constructed, Tag 8 (Report)",
             _ => "Other",
         }
     }
@@ -136,6 +153,7 @@ impl Asn1Tag {
             0xA5 => Ok(Asn1Tag::GetBulkRequest),
             0xA6 => Ok(Asn1Tag::InformRequest),
             0xA7 => Ok(Asn1Tag::SnmpV2Trap),
+            0xA8 => Ok(Asn1Tag::Report),
             0x80 => Ok(Asn1Tag::NoSuchObject),
             0x81 => Ok(Asn1Tag::NoSuchInstance),
             0x82 => Ok(Asn1Tag::EndOfMib),
@@ -145,6 +163,95 @@ impl Asn1Tag {
     }
 }
 
+/// Class bits (8-7) of a tag byte.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TagClass {
+    Universal,
+    Application,
+    Context,
+    Private,
+}
+
+impl TagClass {
+    pub fn of(tag_byte: u8) -> Self {
+        match tag_byte >> 6 {
+            0 => TagClass::Universal,
+            1 => TagClass::Application,
+            2 => TagClass::Context,
+            _ => TagClass::Private,
+        }
+    }
+
+    pub fn bits(&self) -> u8 {
+        match self {
+            TagClass::Universal => 0x00,
+            TagClass::Application => 0x40,
+            TagClass::Context => 0x80,
+            TagClass::Private => 0xC0,
+        }
+    }
+}
+
+/// A TLV whose tag byte is kept as-is, for tags [`Asn1Tag`] doesn't know.
+/// For long-form tags `tag_byte` is the first identifier octet and `number`
+/// the tag number from the octets after it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RawBerObject<'a> {
+    pub tag_byte: u8,
+    pub number: u32,
+    pub value: &'a [u8],
+}
+
+impl RawBerObject<'_> {
+    pub fn class(&self) -> TagClass {
+        TagClass::of(self.tag_byte)
+    }
+
+    pub fn is_constructed(&self) -> bool {
+        self.tag_byte & 0x20 != 0
+    }
+}
+
+pub fn parse_raw_ber_object(input: &[u8]) -> BerResult<(RawBerObject<'_>, &[u8])> {
+    let tag_byte = *input.first().ok_or(BerError::IncompleteData)?;
+    let (number, after_tag) = parse_tag_number(input)?;
+    let (value, rest) = split_value(tag_byte, after_tag, 0)?;
+    Ok((
+        RawBerObject {
+            tag_byte,
+            number,
+            value,
+        },
+        rest,
+    ))
+}
+
+// Tag numbers of 31 and up follow the first octet as base-128 digits, high
+// bit set on all but the last (X.690 section 8.1.2.4).
+pub(crate) fn parse_tag_number(input: &[u8]) -> BerResult<(u32, &[u8])> {
+    let tag_byte = *input.first().ok_or(BerError::IncompleteData)?;
+    if tag_byte & 0x1F != 0x1F {
+        return Ok(((tag_byte & 0x1F) as u32, &input[1..]));
+    }
+
+    let mut number: u32 = 0;
+    for (i, byte) in input[1..].iter().enumerate() {
+        // a leading 0x80 would be a non-minimal encoding
+        if i == 0 && *byte == 0x80 {
+            return Err(BerError::MalformedTag);
+        }
+        if number > (u32::MAX >> 7) {
+            return Err(BerError::MalformedTag);
+        }
+        number = (number << 7) | (byte & 0x7F) as u32;
+        if byte & 0x80 == 0 {
+            return Ok((number, &input[i + 2..]));
+        }
+    }
+    Err(BerError::IncompleteData)
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct BerObject<'a> {
     pub tag: Asn1Tag,
@@ -155,20 +262,14 @@ pub struct BerObject<'a> {
 
 pub fn parse_ber_object(input: &[u8]) -> BerResult<(BerObject<'_>, &[u8])> {
     let (tag, after_tag) = parse_tag(input)?;
-    let (value_len, after_length) = parse_length(after_tag)?;
-
-    let total_header_len = (after_length.as_ptr() as usize) - (input.as_ptr() as usize);
+    let (value, rest) = split_value(tag as u8, after_tag, 0)?;
 
-    if after_length.len() < value_len {
-        return Err(BerError::IncompleteData);
-    }
-
-    let (value, rest) = after_length.split_at(value_len);
+    let total_header_len = (value.as_ptr() as usize) - (input.as_ptr() as usize);
 
     let object = BerObject {
         tag,
         header_len: total_header_len,
-        value_len,
+        value_len: value.len(),
         value,
     };
 
@@ -181,7 +282,48 @@ fn parse_tag(input: &[u8]) -> BerResult<(Asn1Tag, &[u8])> {
     Ok((tag, &input[1..]))
 }
 
-fn parse_length(input: &[u8]) -> BerResult<(usize, &[u8])> {
+// bounds the recursion on hostile input, real agents nest a few levels at most
+const MAX_INDEFINITE_DEPTH: usize = 16;
+
+// Splits the value octets from whatever follows the TLV. `input` starts at
+// the length octets.
+pub(crate) fn split_value(tag_byte: u8, input: &[u8], depth: usize) -> BerResult<(&[u8], &[u8])> {
+    if input.first() == Some(&0x80) {
+        return split_indefinite(tag_byte, &input[1..], depth);
+    }
+
+    let (value_len, after_length) = parse_length(input)?;
+    if after_length.len() < value_len {
+        return Err(BerError::IncompleteData);
+    }
+    Ok(after_length.split_at(value_len))
+}
+
+// Indefinite length (X.690 section 8.1.3.6): the contents run until a 00 00
+// end-of-contents marker, which can only be found by stepping over every
+// element inside. Only constructed encodings may use it.
+fn split_indefinite(tag_byte: u8, content: &[u8], depth: usize) -> BerResult<(&[u8], &[u8])> {
+    if tag_byte & 0x20 == 0 {
+        return Err(BerError::MalformedLength);
+    }
+    if depth >= MAX_INDEFINITE_DEPTH {
+        return Err(BerError::IndefiniteLengthTooDeep);
+    }
+
+    let mut rest = content;
+    loop {
+        if rest.starts_with(&[0x00, 0x00]) {
+            let value_len = content.len() - rest.len();
+            return Ok((&content[..value_len], &rest[2..]));
+        }
+        let inner_tag = *rest.first().ok_or(BerError::IncompleteData)?;
+        let (_, after_tag) = parse_tag_number(rest)?;
+        let (_, after_value) = split_value(inner_tag, after_tag, depth + 1)?;
+        rest = after_value;
+    }
+}
+
+pub(crate) fn parse_length(input: &[u8]) -> BerResult<(usize, &[u8])> {
     let len_byte = input.first().ok_or(BerError::IncompleteData)?;
 
     match *len_byte {
@@ -207,34 +349,40 @@ fn parse_length(input: &[u8]) -> BerResult<(usize, &[u8])> {
             }
             Ok((value_len, rest))
         }
+        // indefinite, only valid where split_value handles it
         0x80 => Err(BerError::MalformedLength),
         0xFF => Err(BerError::MalformedLength),
     }
 }
 
-/// First Two Numbers: The first two numbers (e.g., 1 and 3) are compressed into a single byte using the formula (X * 40) + Y.
+/// First Two Numbers: The first two numbers (e.g., 1 and 3) are compressed into one sub-identifier
+/// using the formula (X * 40) + Y, a single byte except deep under the 2 arc.
 /// Example: For .1.3.6.1, the first byte is (1 * 40) + 3 = 43, which is 0x2B in hex.
 /// All Other Numbers: Every number after the first two is encoded in a variable-length format.
 /// A number is broken into 7-bit chunks.
 /// The 8th bit (the most significant bit) of a byte is a "continuation" flag.
 /// If the bit is 1, it means "this number continues in the next byte."
 /// If the bit is 0, it means "this is the last byte for this number."
-pub fn decode_oid(input: &[u8]) -> BerResult<Vec<u32>> {
+pub fn decode_oid(input: &[u8]) -> BerResult<Vec<u64>> {
     if input.is_empty() {
         return Err(BerError::IncompleteData);
     }
 
     let mut oid = Vec::with_capacity(10);
 
-    // --- first byte
-    let b1 = input[0];
-    let x = (b1 / 40) as u32;
-    let y = (b1 % 40) as u32;
+    // --- first sub-identifier holds the first two arcs, (X * 40) + Y. X is
+    // 0, 1 or 2 and only under 2 can Y go past 39, so the value may take
+    // more than one byte (X.690 section 8.19.4)
+    let (first, mut current) = decode_oid_sub_id(input)?;
+    let (x, y) = match first {
+        0..40 => (0, first),
+        40..80 => (1, first - 40),
+        _ => (2, first - 80),
+    };
     oid.push(x);
     oid.push(y);
 
     // ---2 . Rest of the bytes
-    let mut current = &input[1..];
     while !current.is_empty() {
         let (sub_id, rest) = decode_oid_sub_id(current)?;
         oid.push(sub_id);
@@ -243,18 +391,19 @@ pub fn decode_oid(input: &[u8]) -> BerResult<Vec<u32>> {
     Ok(oid)
 }
 
-fn decode_oid_sub_id(input: &[u8]) -> BerResult<(u32, &[u8])> {
-    let mut sub_id = 0u32;
-    let mut bytes_read = 0;
+/// Sub-identifiers are decoded as u64, some vendor MIBs go past 32 bits.
+fn decode_oid_sub_id(input: &[u8]) -> BerResult<(u64, &[u8])> {
+    let mut sub_id = 0u64;
 
     for (i, &bytes) in input.iter().enumerate() {
-        bytes_read = i + 1;
+        let bytes_read = i + 1;
 
-        if bytes_read > 5 {
+        // another 7 bits would not fit
+        if sub_id > (u64::MAX >> 7) {
             return Err(BerError::IntegerOverflow);
         }
 
-        let values_bits = (bytes & 0x7F) as u32;
+        let values_bits = (bytes & 0x7F) as u64;
 
         sub_id = (sub_id << 7) | values_bits;
 
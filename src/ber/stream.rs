@@ -0,0 +1,105 @@
+// Framing for stream transports (RFC 3430, SNMP over TCP), where a message
+// can arrive split across reads or several can arrive in one.
+
+use crate::ber::{BerError, BerResult, parse_length, parse_tag_number, split_value};
+
+/// How much of a buffer the first TLV takes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frame {
+    /// The first TLV is complete and this many bytes long.
+    Complete(usize),
+    /// At least this many more bytes are needed. Exact once the length
+    /// octets are in, a lower bound before that and for indefinite lengths.
+    NeedMore(usize),
+}
+
+/// Works out whether `input` starts with a complete TLV.
+pub fn frame_len(input: &[u8]) -> BerResult<Frame> {
+    let Some(&tag_byte) = input.first() else {
+        return Ok(Frame::NeedMore(1));
+    };
+    let after_tag = match parse_tag_number(input) {
+        Ok((_, after_tag)) => after_tag,
+        Err(BerError::IncompleteData) => return Ok(Frame::NeedMore(1)),
+        Err(e) => return Err(e),
+    };
+    let Some(&len_byte) = after_tag.first() else {
+        return Ok(Frame::NeedMore(1));
+    };
+
+    if len_byte == 0x80 {
+        // no way around scanning for the end-of-contents
+        return match split_value(tag_byte, after_tag, 0) {
+            Ok((_, rest)) => Ok(Frame::Complete(input.len() - rest.len())),
+            Err(BerError::IncompleteData) => Ok(Frame::NeedMore(1)),
+            Err(e) => Err(e),
+        };
+    }
+    if len_byte > 0x80 {
+        let len_octets = 1 + (len_byte & 0x7F) as usize;
+        if after_tag.len() < len_octets {
+            return Ok(Frame::NeedMore(len_octets - after_tag.len()));
+        }
+    }
+
+    let (value_len, after_length) = parse_length(after_tag)?;
+    let header_len = input.len() - after_length.len();
+    let total = header_len
+        .checked_add(value_len)
+        .ok_or(BerError::MalformedLength)?;
+    if total > input.len() {
+        Ok(Frame::NeedMore(total - input.len()))
+    } else {
+        Ok(Frame::Complete(total))
+    }
+}
+
+/// Buffers bytes read from a stream and splits them into whole messages,
+/// ready for [`parse_message`](crate::snmp::message::parse_message) or
+/// [`parse_v3_message`](crate::snmp::message::parse_v3_message).
+#[derive(Debug)]
+pub struct StreamDecoder {
+    buf: Vec<u8>,
+    max_frame_len: usize,
+}
+
+impl StreamDecoder {
+    /// Messages longer than `max_frame_len` are rejected instead of
+    /// buffered; a peer could otherwise claim any length.
+    pub fn new(max_frame_len: usize) -> Self {
+        Self {
+            buf: Vec::new(),
+            max_frame_len,
+        }
+    }
+
+    pub fn extend(&mut self, bytes: &[u8]) {
+        self.buf.extend_from_slice(bytes);
+    }
+
+    /// Takes the next whole message out of the buffer, or `None` if more
+    /// bytes are needed. A framing error leaves the stream unusable.
+    pub fn next_frame(&mut self) -> BerResult<Option<Vec<u8>>> {
+        match frame_len(&self.buf)? {
+            Frame::Complete(len) if len > self.max_frame_len => Err(BerError::FrameTooLarge(len)),
+            Frame::Complete(len) => Ok(Some(self.buf.drain(..len).collect())),
+            Frame::NeedMore(more) if self.buf.len() + more > self.max_frame_len => {
+                Err(BerError::FrameTooLarge(self.buf.len() + more))
+            }
+            Frame::NeedMore(_) => Ok(None),
+        }
+    }
+
+    /// Minimum number of bytes to read before the next frame can complete.
+    pub fn needed(&self) -> BerResult<usize> {
+        Ok(match frame_len(&self.buf)? {
+            Frame::Complete(_) => 0,
+            Frame::NeedMore(more) => more,
+        })
+    }
+
+    /// Bytes buffered but not yet returned as a frame.
+    pub fn buffered(&self) -> usize {
+        self.buf.len()
+    }
+}
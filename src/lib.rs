@@ -1,3 +1,4 @@
+pub mod agent;
 pub mod ber;
 pub mod manager;
 pub mod snmp;
@@ -1,13 +1,28 @@
+use std::io;
+use std::net::Ipv4Addr;
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 
-use anyhow::Result;
-use clap::Parser;
-use futures::future::join_all;
+use anyhow::{Context, Result, anyhow};
+use clap::{Args, CommandFactory, Parser};
+use clap_complete::Shell;
+use encoding_rs::{Encoding, UTF_8};
+use futures::stream::{FuturesUnordered, StreamExt};
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+#[cfg(feature = "precheck")]
+use rusnmp::manager::ProbeMethod;
 use rusnmp::{
-    manager::Manager,
-    snmp::pdu::{ObjectSyntax, VarBind},
+    ber,
+    manager::{BULK_WALK_MAX_REPETITIONS, Credentials, ErrorClass, Manager, SnmpError},
+    snmp::display_hint::DisplayHint,
+    snmp::engine_id::EngineId,
+    snmp::pdu::{ErrorStatus, ObjectSyntax, VarBind},
+    snmp::snmprec,
+    snmp::usm::{AuthProtocol, PrivProtocol, UsmUser},
 };
+use serde_json::{Value, json};
+use tokio::task::{AbortHandle, JoinError, JoinHandle};
 
 #[derive(Parser, Debug)]
 struct Cli {
@@ -15,21 +30,282 @@ struct Cli {
     command: Command,
 }
 
+/// SNMPv3 security options. Giving a user switches the request to v3.
+#[derive(Args, Debug, Clone)]
+struct V3Args {
+    #[clap(short = 'u', long)]
+    user: Option<String>,
+
+    #[clap(short = 'a', long, value_parser = parse_auth_protocol, requires = "auth_password")]
+    auth_protocol: Option<AuthProtocol>,
+
+    #[clap(short = 'A', long, requires = "auth_protocol")]
+    auth_password: Option<String>,
+
+    /// DES, AES, AES192, AES256, or AES192C/AES256C for the Cisco key extension
+    #[clap(short = 'x', long, value_parser = parse_priv_protocol, requires_all = ["priv_password", "auth_protocol"])]
+    priv_protocol: Option<PrivProtocol>,
+
+    #[clap(short = 'X', long, requires = "priv_protocol")]
+    priv_password: Option<String>,
+
+    #[clap(short = 'n', long, requires = "user")]
+    context_name: Option<String>,
+
+    /// contextEngineID in hex, defaults to the agent's engine ID
+    #[clap(short = 'E', long, value_parser = EngineId::from_hex, requires = "user")]
+    context_engine_id: Option<EngineId>,
+}
+
+/// How multi-target results are reported.
+#[derive(Args, Debug, Clone)]
+struct OutputArgs {
+    /// Print results as JSON, with a classified error per failed target
+    #[clap(long)]
+    json: bool,
+
+    /// Abort the remaining targets as soon as one fails, and exit non-zero
+    #[clap(long)]
+    fail_fast: bool,
+
+    /// Character encoding of string values, e.g. latin1, gb18030 or shift_jis
+    #[clap(long, value_parser = parse_encoding, default_value = "utf-8")]
+    encoding: &'static Encoding,
+
+    /// Encoding for one target's strings, overriding --encoding
+    #[clap(long = "target-encoding", value_name = "TARGET=ENCODING", value_parser = parse_target_encoding)]
+    target_encodings: Vec<(String, &'static Encoding)>,
+
+    #[clap(flatten)]
+    format: FormatArgs,
+}
+
+// (value, name) pairs of an enumerated INTEGER
+type EnumLabels = Vec<(i32, String)>;
+
+/// How values of particular objects are shown, in place of a MIB.
+#[derive(Args, Debug, Clone)]
+struct FormatArgs {
+    /// Format values under OID with a DISPLAY-HINT, e.g. 1.3.6.1.2.1.2.2.1.6=1x:
+    #[clap(long = "display-hint", value_name = "OID=HINT", value_parser = parse_display_hint)]
+    display_hints: Vec<(Vec<u64>, DisplayHint)>,
+
+    /// Name the values of an enumerated INTEGER under OID, e.g.
+    /// 1.3.6.1.2.1.2.2.1.8=up(1),down(2). SET accepts the names too
+    #[clap(long = "enum", value_name = "OID=LABELS", value_parser = parse_enum_labels)]
+    enums: Vec<(Vec<u64>, EnumLabels)>,
+}
+
+impl FormatArgs {
+    // what was given for the longest prefix of `oid`
+    fn longest<'a, T>(options: &'a [(Vec<u64>, T)], oid: &[u64]) -> Option<&'a T> {
+        options
+            .iter()
+            .filter(|(prefix, _)| oid.starts_with(prefix))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, option)| option)
+    }
+
+    fn enum_labels(&self, oid: &[u64]) -> Option<Vec<(i32, &str)>> {
+        let labels = Self::longest(&self.enums, oid)?;
+        Some(
+            labels
+                .iter()
+                .map(|(value, name)| (*value, name.as_str()))
+                .collect(),
+        )
+    }
+
+    /// The value as labelled or hinted for its object, if it is of the
+    /// type that was given.
+    fn display(&self, varbind: &VarBind) -> Option<String> {
+        self.enum_labels(&varbind.oid)
+            .and_then(|labels| varbind.value.enum_label(&labels))
+            .or_else(|| Self::longest(&self.display_hints, &varbind.oid)?.format(&varbind.value))
+    }
+}
+
+impl OutputArgs {
+    fn encoding_for(&self, target: &str) -> &'static Encoding {
+        self.target_encodings
+            .iter()
+            .rev()
+            .find(|(name, _)| name == target)
+            .map_or(self.encoding, |(_, encoding)| encoding)
+    }
+}
+
+impl V3Args {
+    /// The user's v3 credentials, or v2c with `community` when no user was
+    /// given.
+    fn credentials(&self, community: Option<String>) -> Result<Credentials> {
+        let Some(name) = &self.user else {
+            return community
+                .map(Credentials::v2c)
+                .ok_or_else(|| anyhow!("Either a community or a user is required"));
+        };
+        let user = UsmUser::new(name.as_bytes());
+        let user = match (self.auth_protocol, &self.auth_password) {
+            (Some(protocol), Some(password)) => user.with_auth(protocol, password.as_bytes())?,
+            _ => user,
+        };
+        let user = match (self.priv_protocol, &self.priv_password) {
+            (Some(protocol), Some(password)) => user.with_privacy(protocol, password.as_bytes())?,
+            _ => user,
+        };
+        let user = match &self.context_name {
+            Some(context_name) => user.with_context_name(context_name.as_bytes()),
+            None => user,
+        };
+        let user = match &self.context_engine_id {
+            Some(engine_id) => user.with_context_engine_id(engine_id.as_bytes()),
+            None => user,
+        };
+        Ok(user.into())
+    }
+}
+
+fn parse_auth_protocol(s: &str) -> Result<AuthProtocol> {
+    match s.to_ascii_uppercase().as_str() {
+        "MD5" => Ok(AuthProtocol::Md5),
+        "SHA" | "SHA1" => Ok(AuthProtocol::Sha1),
+        "SHA224" | "SHA-224" => Ok(AuthProtocol::Sha224),
+        "SHA256" | "SHA-256" => Ok(AuthProtocol::Sha256),
+        "SHA384" | "SHA-384" => Ok(AuthProtocol::Sha384),
+        "SHA512" | "SHA-512" => Ok(AuthProtocol::Sha512),
+        other => Err(anyhow!("Unknown auth protocol '{}'", other)),
+    }
+}
+
+fn parse_priv_protocol(s: &str) -> Result<PrivProtocol> {
+    match s.to_ascii_uppercase().as_str() {
+        "DES" => Ok(PrivProtocol::Des),
+        "AES" | "AES128" => Ok(PrivProtocol::Aes128),
+        "AES192" | "AES-192" => Ok(PrivProtocol::Aes192),
+        "AES256" | "AES-256" => Ok(PrivProtocol::Aes256),
+        "AES192C" | "AES-192-C" => Ok(PrivProtocol::Aes192Reeder),
+        "AES256C" | "AES-256-C" => Ok(PrivProtocol::Aes256Reeder),
+        other => Err(anyhow!("Unknown privacy protocol '{}'", other)),
+    }
+}
+
+// takes any WHATWG label; note latin1 means windows-1252 there, a superset
+fn parse_encoding(s: &str) -> Result<&'static Encoding> {
+    Encoding::for_label(s.trim().as_bytes()).ok_or_else(|| anyhow!("Unknown encoding '{}'", s))
+}
+
+fn parse_target_encoding(s: &str) -> Result<(String, &'static Encoding)> {
+    let (target, label) = s
+        .rsplit_once('=')
+        .ok_or_else(|| anyhow!("Expected TARGET=ENCODING, got '{}'", s))?;
+    Ok((target.to_string(), parse_encoding(label)?))
+}
+
+fn parse_oid(oid: &str) -> Result<Vec<u64>> {
+    oid.trim_start_matches('.')
+        .split('.')
+        .map(str::parse)
+        .collect::<Result<_, _>>()
+        .with_context(|| format!("Invalid OID '{}'", oid))
+}
+
+fn parse_display_hint(s: &str) -> Result<(Vec<u64>, DisplayHint)> {
+    let (oid, hint) = s
+        .split_once('=')
+        .ok_or_else(|| anyhow!("Expected OID=HINT, got '{}'", s))?;
+    Ok((parse_oid(oid)?, hint.parse()?))
+}
+
+// up(1),down(2), as in the INTEGER { ... } definition
+fn parse_enum_labels(s: &str) -> Result<(Vec<u64>, EnumLabels)> {
+    let (oid, labels) = s
+        .split_once('=')
+        .ok_or_else(|| anyhow!("Expected OID=LABELS, got '{}'", s))?;
+    let labels = labels
+        .split(',')
+        .map(|label| {
+            let (name, value) = label
+                .trim()
+                .strip_suffix(')')
+                .and_then(|label| label.split_once('('))
+                .ok_or_else(|| anyhow!("Expected name(value), got '{}'", label))?;
+            Ok((value.trim().parse()?, name.trim().to_string()))
+        })
+        .collect::<Result<_>>()?;
+    Ok((parse_oid(oid)?, labels))
+}
+
+#[cfg(feature = "precheck")]
+fn parse_probe_method(s: &str) -> Result<ProbeMethod> {
+    match s.to_ascii_lowercase().as_str() {
+        "icmp" => Ok(ProbeMethod::Icmp),
+        "udp" => Ok(ProbeMethod::Udp),
+        other => Err(anyhow!("Unknown probe method '{}'", other)),
+    }
+}
+
+#[cfg(feature = "precheck")]
+const PRECHECK_WAIT: Duration = Duration::from_secs(1);
+
+#[cfg(feature = "precheck")]
+async fn precheck_target(
+    manager: &Manager,
+    target: &str,
+    method: Option<ProbeMethod>,
+) -> Result<()> {
+    if let Some(method) = method
+        && !manager.is_reachable(target, method, PRECHECK_WAIT).await?
+    {
+        return Err(anyhow!(
+            "Unreachable: no answer to {:?} probe within {}s",
+            method,
+            PRECHECK_WAIT.as_secs()
+        ));
+    }
+    Ok(())
+}
+
 #[derive(Parser, Debug)]
 enum Command {
     Get {
-        #[clap(short, long, required = true)]
-        community: String,
+        #[clap(short, long, required_unless_present = "user")]
+        community: Option<String>,
         #[clap(short, long, required = true)]
         oid: String,
+        #[clap(flatten)]
+        v3: V3Args,
+        /// Retry with .0 appended when the OID names a scalar object
+        /// rather than its instance
+        #[clap(long)]
+        auto_instance: bool,
+        /// Resolve and discover all targets first, for at most this many seconds
+        #[clap(long, value_name = "SECS")]
+        warm_up: Option<u64>,
+        #[clap(flatten)]
+        output: OutputArgs,
+        /// Skip targets that don't answer an ICMP or UDP probe
+        #[cfg(feature = "precheck")]
+        #[clap(long, value_parser = parse_probe_method)]
+        precheck: Option<ProbeMethod>,
         #[clap( required = true , num_args = 1..)]
         targets: Vec<String>,
     },
     Walk {
-        #[clap(short, long, required = true)]
-        community: String,
+        #[clap(short, long, required_unless_present = "user")]
+        community: Option<String>,
         #[clap(short, long, required = true)]
         oid: String,
+        #[clap(flatten)]
+        v3: V3Args,
+        /// Resolve and discover all targets first, for at most this many seconds
+        #[clap(long, value_name = "SECS")]
+        warm_up: Option<u64>,
+        #[clap(flatten)]
+        output: OutputArgs,
+        /// Skip targets that don't answer an ICMP or UDP probe
+        #[cfg(feature = "precheck")]
+        #[clap(long, value_parser = parse_probe_method)]
+        precheck: Option<ProbeMethod>,
         #[clap( required = true , num_args = 1..)]
         targets: Vec<String>,
     },
@@ -48,6 +324,9 @@ enum Command {
 
         #[clap(required = true , num_args = 1..)]
         oids: Vec<String>,
+
+        #[clap(flatten)]
+        format: FormatArgs,
     },
     BulkWalk {
         #[clap(short, long, required = true)]
@@ -56,18 +335,132 @@ enum Command {
         #[clap(short, long, required = true)]
         target: String,
 
-        #[clap(short, long, default_value_t = 20)]
+        /// Where the walk starts; it adapts down to what the agent takes
+        #[clap(short, long, default_value_t = BULK_WALK_MAX_REPETITIONS)]
         max_repetitions: i32,
 
         #[clap(short, long, required = true)]
         oid: String,
+
+        #[clap(flatten)]
+        format: FormatArgs,
+    },
+    /// Walk a device and save it as an snmprec file for an SNMP simulator.
+    Record {
+        #[clap(short, long, required_unless_present = "user")]
+        community: Option<String>,
+
+        #[clap(flatten)]
+        v3: V3Args,
+
+        #[clap(short, long, default_value = "1.3")]
+        oid: String,
+
+        #[clap(long, required = true)]
+        out: PathBuf,
+
+        target: String,
+    },
+    /// Set objects in one atomic request, given as OID TYPE VALUE triples.
+    /// TYPE is i (INTEGER, or a label given with --enum), u (Gauge32), c (Counter32), t (TimeTicks),
+    /// a (IpAddress), o (OID), s (string) or x (hex string).
+    Set {
+        #[clap(short, long, required_unless_present = "user")]
+        community: Option<String>,
+
+        #[clap(flatten)]
+        v3: V3Args,
+
+        #[clap(short, long, required = true)]
+        target: String,
+
+        #[clap(required = true, num_args = 3.., value_names = ["OID", "TYPE", "VALUE"])]
+        assignments: Vec<String>,
+
+        #[clap(flatten)]
+        format: FormatArgs,
+    },
+    /// Show the TLV structure of a BER-encoded packet, given as hex or read
+    /// raw from a file.
+    Dump {
+        #[clap(long, conflicts_with = "hex")]
+        file: Option<PathBuf>,
+
+        #[clap(required_unless_present = "file", num_args = 1..)]
+        hex: Vec<String>,
     },
+    /// Print the completion script for a shell.
+    Completions { shell: Shell },
+    /// Print the man page in roff format.
+    Manpage,
+}
+
+// separators such as spaces or colons are skipped
+fn parse_hex(value: &str) -> Result<Vec<u8>> {
+    let digits: String = value.chars().filter(|c| c.is_ascii_hexdigit()).collect();
+    if !digits.len().is_multiple_of(2) {
+        return Err(anyhow!("Odd number of hex digits in '{}'", value));
+    }
+    Ok((0..digits.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&digits[i..i + 2], 16))
+        .collect::<Result<_, _>>()?)
+}
+
+// `labels` are the enum labels given for the object, if any
+fn parse_set_value(
+    kind: &str,
+    value: &str,
+    labels: Option<&[(i32, &str)]>,
+) -> Result<ObjectSyntax> {
+    let syntax = match (kind, labels) {
+        ("i", Some(labels)) => ObjectSyntax::from_enum_label(value, labels)
+            .ok_or_else(|| anyhow!("'{}' is not one of the labels given with --enum", value))?,
+        ("i", None) => ObjectSyntax::Integer(value.parse()?),
+        ("u", _) => ObjectSyntax::Gauge32(value.parse()?),
+        ("c", _) => ObjectSyntax::Counter32(value.parse()?),
+        ("t", _) => ObjectSyntax::TimeTicks(value.parse()?),
+        ("a", _) => ObjectSyntax::IpAddress(value.parse::<Ipv4Addr>()?.octets().to_vec()),
+        ("o", _) => ObjectSyntax::ObjectIdentifier(
+            value
+                .split('.')
+                .filter(|s| !s.is_empty())
+                .map(str::parse)
+                .collect::<Result<_, _>>()?,
+        ),
+        ("s", _) => ObjectSyntax::OctetString(value.as_bytes().to_vec()),
+        ("x", _) => ObjectSyntax::OctetString(parse_hex(value)?),
+        (other, _) => return Err(anyhow!("Unknown value type '{}'", other)),
+    };
+    Ok(syntax)
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
 
+    // these only describe the CLI, so nothing else needs setting up
+    match cli.command {
+        Command::Completions { shell } => {
+            clap_complete::generate(shell, &mut Cli::command(), "rusnmp", &mut io::stdout());
+            return Ok(());
+        }
+        Command::Manpage => {
+            clap_mangen::Man::new(Cli::command()).render(&mut io::stdout())?;
+            return Ok(());
+        }
+        Command::Dump { file, hex } => {
+            let bytes = match file {
+                Some(path) => std::fs::read(&path)
+                    .with_context(|| format!("Failed to read {}", path.display()))?,
+                None => parse_hex(&hex.join(" "))?,
+            };
+            print!("{}", ber::dump(&bytes));
+            return Ok(());
+        }
+        _ => {}
+    }
+
     let manager = Arc::new(Manager::new());
     let multi_progress = MultiProgress::new();
     let main_pb = multi_progress.add(ProgressBar::new(0)); // Main progress bar
@@ -75,12 +468,23 @@ async fn main() -> Result<()> {
         "{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} ({percent}%)",
     )?);
 
-    let (results, targets) = match cli.command {
+    let (results, targets, output) = match cli.command {
         Command::Get {
             community,
             oid,
+            v3,
+            auto_instance,
+            warm_up,
+            output,
+            #[cfg(feature = "precheck")]
+            precheck,
             targets,
         } => {
+            let credentials = v3.credentials(community)?;
+            if let Some(secs) = warm_up {
+                let discover_engines = matches!(credentials, Credentials::UsmV3(_));
+                run_warm_up(&manager, &targets, discover_engines, secs, &multi_progress).await?;
+            }
             main_pb.set_length(targets.len() as u64);
             main_pb.set_message("Running GET");
             let mut tasks = Vec::new();
@@ -95,30 +499,57 @@ async fn main() -> Result<()> {
                 task_pb.set_message(format!("GET: {}", target));
 
                 let manager = Arc::clone(&manager);
-                let community = community.clone();
+                let credentials = credentials.clone();
                 let oid = oid.clone();
                 let target = target.clone();
                 let main_pb = main_pb.clone();
 
                 tasks.push(tokio::spawn(async move {
                     task_pb.enable_steady_tick(std::time::Duration::from_millis(100));
-                    let result = manager
-                        .get(&target, &community, &oid)
-                        .await
-                        .map(|vb| vec![vb]);
+                    let result = async {
+                        #[cfg(feature = "precheck")]
+                        precheck_target(&manager, &target, precheck).await?;
+                        let result = manager.get(&target, &credentials, &oid).await;
+                        let retry = match &result {
+                            Ok(varbind) => lacks_instance(varbind),
+                            Err(e) => lacks_instance_v1(&oid, e),
+                        };
+                        if auto_instance && retry {
+                            let scalar_oid = format!("{}.0", oid.trim_end_matches('.'));
+                            manager.get(&target, &credentials, &scalar_oid).await
+                        } else {
+                            result
+                        }
+                        .map(|vb| vec![vb])
+                    }
+                    .await;
                     task_pb.finish_with_message(format!("GET: {}", target));
                     main_pb.inc(1);
                     result
                 }));
             }
-            (join_all(tasks).await, targets)
+            (
+                collect_results(tasks, output.fail_fast).await,
+                targets,
+                output,
+            )
         }
 
         Command::Walk {
             community,
             oid,
+            v3,
+            warm_up,
+            output,
+            #[cfg(feature = "precheck")]
+            precheck,
             targets,
         } => {
+            let credentials = v3.credentials(community)?;
+            if let Some(secs) = warm_up {
+                let discover_engines = matches!(credentials, Credentials::UsmV3(_));
+                run_warm_up(&manager, &targets, discover_engines, secs, &multi_progress).await?;
+            }
             main_pb.set_length(targets.len() as u64);
             main_pb.set_message("Running WALK");
             let mut tasks = Vec::new();
@@ -134,7 +565,7 @@ async fn main() -> Result<()> {
                 task_pb.set_message(format!("WALK: {}", target));
 
                 let manager = Arc::clone(&manager);
-                let community = community.clone();
+                let credentials = credentials.clone();
                 let oid = oid.clone();
                 let target = target.clone();
                 let main_pb = main_pb.clone();
@@ -142,13 +573,22 @@ async fn main() -> Result<()> {
                 // --- NEW: Spawn a true tokio task ---
                 tasks.push(tokio::spawn(async move {
                     task_pb.enable_steady_tick(std::time::Duration::from_millis(100));
-                    let result = manager.walk(&target, &community, &oid).await;
+                    let result = async {
+                        #[cfg(feature = "precheck")]
+                        precheck_target(&manager, &target, precheck).await?;
+                        manager.walk(&target, &credentials, &oid).await
+                    }
+                    .await;
                     task_pb.finish_with_message(format!("WALK: {}", target));
                     main_pb.inc(1);
                     result
                 }));
             }
-            (join_all(tasks).await, targets)
+            (
+                collect_results(tasks, output.fail_fast).await,
+                targets,
+                output,
+            )
         }
 
         // --- Other commands (Bulk, BulkWalk) ---
@@ -160,12 +600,13 @@ async fn main() -> Result<()> {
             non_repeaters,
             max_repititions,
             oids,
+            format,
         } => {
             let oid_strs: Vec<&str> = oids.iter().map(AsRef::as_ref).collect();
             let varbinds = manager
                 .get_bulk(
                     &target,
-                    &community,
+                    &Credentials::v2c(community),
                     non_repeaters,
                     max_repititions,
                     &oid_strs,
@@ -173,7 +614,7 @@ async fn main() -> Result<()> {
                 .await?;
             println!("\n--- Success! (Found {} results) ---", varbinds.len());
             for varbind in varbinds {
-                print_varbind(&varbind);
+                print_varbind(&varbind, UTF_8, &format);
             }
             return Ok(()); // Exit early
         }
@@ -182,49 +623,344 @@ async fn main() -> Result<()> {
             target,
             max_repetitions,
             oid,
+            format,
         } => {
             let varbinds = manager
-                .bulk_walk(&target, &community, &oid, max_repetitions)
+                .bulk_walk(&target, &Credentials::v2c(community), &oid, max_repetitions)
                 .await?;
             println!("\n--- Success! (Found {} results) ---", varbinds.len());
             for varbind in varbinds {
-                print_varbind(&varbind);
+                print_varbind(&varbind, UTF_8, &format);
             }
             return Ok(()); // Exit early
         }
+        Command::Record {
+            community,
+            v3,
+            oid,
+            out,
+            target,
+        } => {
+            let credentials = v3.credentials(community)?;
+            let varbinds = manager.walk(&target, &credentials, &oid).await?;
+            let mut contents = String::new();
+            for varbind in &varbinds {
+                contents.push_str(&snmprec::format_line(varbind));
+                contents.push('\n');
+            }
+            std::fs::write(&out, contents)
+                .with_context(|| format!("Failed to write {}", out.display()))?;
+            println!("Recorded {} objects to {}", varbinds.len(), out.display());
+            return Ok(());
+        }
+        Command::Set {
+            community,
+            v3,
+            target,
+            assignments,
+            format,
+        } => {
+            if !assignments.len().is_multiple_of(3) {
+                return Err(anyhow!("Expected OID TYPE VALUE triples"));
+            }
+            let values = assignments
+                .chunks(3)
+                .map(|triple| {
+                    let labels = parse_oid(&triple[0])
+                        .ok()
+                        .and_then(|oid| format.enum_labels(&oid));
+                    parse_set_value(&triple[1], &triple[2], labels.as_deref())
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let bindings: Vec<(&str, ObjectSyntax)> = assignments
+                .chunks(3)
+                .map(|triple| triple[0].as_str())
+                .zip(values)
+                .collect();
+
+            let credentials = v3.credentials(community)?;
+            let varbinds = manager.set_multi(&target, &credentials, &bindings).await?;
+            for varbind in varbinds {
+                print_varbind(&varbind, UTF_8, &format);
+            }
+            return Ok(());
+        }
+        Command::Completions { .. } | Command::Manpage | Command::Dump { .. } => {
+            unreachable!("handled above")
+        }
     };
 
     // --- INDICATIF: Clean up ---
     main_pb.finish_with_message("All tasks complete!");
 
-    // 4. Print results
-    println!("\n--- === All Results === ---");
-    for (target, result) in targets.iter().zip(results) {
-        println!("\n--- Result for {} ---", target);
-        // The result from tokio::spawn is itself a Result
-        match result {
-            Ok(Ok(varbinds)) => {
-                // Task succeeded, manager succeeded
-                println!("Success! (Found {} results)", varbinds.len());
-                for varbind in varbinds {
-                    print_varbind(&varbind);
+    if output.json {
+        print_json_results(&manager, &targets, &results, &output)?;
+    } else {
+        // 4. Print results
+        println!("\n--- === All Results === ---");
+        for (target, result) in targets.iter().zip(&results) {
+            println!("\n--- Result for {} ---", target);
+            // The result from tokio::spawn is itself a Result
+            match result {
+                Ok(Ok(varbinds)) => {
+                    // Task succeeded, manager succeeded
+                    println!("Success! (Found {} results)", varbinds.len());
+                    let encoding = output.encoding_for(target);
+                    for varbind in varbinds {
+                        print_varbind(varbind, encoding, &output.format);
+                        if lacks_instance(varbind) {
+                            println!(
+                                "hint: scalar objects need an instance suffix, try {}.0 or pass --auto-instance",
+                                format_oid(&varbind.oid)
+                            );
+                        }
+                    }
+                }
+                Ok(Err(e)) => {
+                    // Task succeeded, manager returned an error
+                    println!("Error ({}): {:#}", ErrorClass::of(e), e);
+                }
+                Err(e) if e.is_cancelled() => println!("Aborted (--fail-fast)"),
+                Err(e) => {
+                    // Task itself panicked
+                    println!("Task Panicked: {}", e);
                 }
-            }
-            Ok(Err(e)) => {
-                // Task succeeded, manager returned an error
-                println!("Error: {}", e);
-            }
-            Err(e) => {
-                // Task itself panicked
-                println!("Task Panicked: {}", e);
             }
         }
+        print_transport_summary(&manager);
     }
 
+    if output.fail_fast && results.iter().any(|result| !matches!(result, Ok(Ok(_)))) {
+        std::process::exit(1);
+    }
     Ok(())
 }
 
-fn print_varbind(varbind: &VarBind) {
+// 1.3.6.1.2.1.1.1 names sysDescr itself; only sysDescr.0 holds a value.
+// Agents differ in which exception they answer that with.
+fn lacks_instance(varbind: &VarBind) -> bool {
+    matches!(
+        varbind.value,
+        ObjectSyntax::NoSuchObject | ObjectSyntax::NoSuchInstance
+    ) && varbind.oid.last() != Some(&0)
+}
+
+// the same, from an agent still answering the v1 way with noSuchName
+fn lacks_instance_v1(oid: &str, error: &anyhow::Error) -> bool {
+    error
+        .downcast_ref::<SnmpError>()
+        .is_some_and(|error| error.status == ErrorStatus::NoSuchName)
+        && !oid.trim_end_matches('.').ends_with(".0")
+}
+
+type TaskResult = Result<Result<Vec<VarBind>>, JoinError>;
+
+/// Waits for every task, in target order. With `fail_fast` the first
+/// failure aborts whatever is still running.
+async fn collect_results(
+    tasks: Vec<JoinHandle<Result<Vec<VarBind>>>>,
+    fail_fast: bool,
+) -> Vec<TaskResult> {
+    let abort_handles: Vec<_> = tasks.iter().map(JoinHandle::abort_handle).collect();
+    let mut pending: FuturesUnordered<_> = tasks
+        .into_iter()
+        .enumerate()
+        .map(|(i, task)| async move { (i, task.await) })
+        .collect();
+
+    let mut results: Vec<Option<TaskResult>> = abort_handles.iter().map(|_| None).collect();
+    while let Some((i, result)) = pending.next().await {
+        if fail_fast && !matches!(result, Ok(Ok(_))) {
+            abort_handles.iter().for_each(AbortHandle::abort);
+        }
+        results[i] = Some(result);
+    }
+    results.into_iter().flatten().collect()
+}
+
+// how many of the slowest targets the summary lists
+const SLOWEST_SHOWN: usize = 5;
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+fn print_transport_summary(manager: &Manager) {
+    let total = manager.total_stats();
+    println!("\n--- Transport ---");
+    println!(
+        "Packets: {} sent, {} received ({} bytes sent, {} received)",
+        total.packets_sent, total.packets_received, total.bytes_sent, total.bytes_received
+    );
+    if let Some(rtt) = total.average_rtt() {
+        println!("Average RTT: {:.1} ms", millis(rtt));
+    }
+    println!(
+        "Retransmits: {:.1}%, timeouts: {}, failures: {}, decode errors: {}",
+        total.retransmit_percent(),
+        total.timeouts,
+        total.failures,
+        total.decode_errors
+    );
+    let slowest = manager.slowest_targets(SLOWEST_SHOWN);
+    if !slowest.is_empty() {
+        println!("Slowest targets:");
+        for (target, rtt) in slowest {
+            println!("  {}: {:.1} ms", target, millis(rtt));
+        }
+    }
+}
+
+fn transport_summary_json(manager: &Manager) -> Value {
+    let total = manager.total_stats();
+    json!({
+        "packets_sent": total.packets_sent,
+        "packets_received": total.packets_received,
+        "bytes_sent": total.bytes_sent,
+        "bytes_received": total.bytes_received,
+        "average_rtt_ms": total.average_rtt().map(millis),
+        "retransmit_percent": total.retransmit_percent(),
+        "timeouts": total.timeouts,
+        "failures": total.failures,
+        "decode_errors": total.decode_errors,
+        "slowest_targets": manager
+            .slowest_targets(SLOWEST_SHOWN)
+            .into_iter()
+            .map(|(target, rtt)| json!({ "target": target, "average_rtt_ms": millis(rtt) }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+fn print_json_results(
+    manager: &Manager,
+    targets: &[String],
+    results: &[TaskResult],
+    output: &OutputArgs,
+) -> Result<()> {
+    let entries: Vec<Value> = targets
+        .iter()
+        .zip(results)
+        .map(|(target, result)| match result {
+            Ok(Ok(varbinds)) => json!({
+                "target": target,
+                "ok": true,
+                "varbinds": varbinds
+                    .iter()
+                    .map(|varbind| {
+                        varbind_json(varbind, output.encoding_for(target), &output.format)
+                    })
+                    .collect::<Vec<_>>(),
+            }),
+            Ok(Err(e)) => json!({
+                "target": target,
+                "ok": false,
+                "error": { "class": ErrorClass::of(e).as_str(), "message": format!("{:#}", e) },
+            }),
+            Err(e) => json!({
+                "target": target,
+                "ok": false,
+                "error": {
+                    "class": if e.is_cancelled() { "aborted" } else { "panic" },
+                    "message": e.to_string(),
+                },
+            }),
+        })
+        .collect();
+    let document = json!({
+        "results": entries,
+        "summary": transport_summary_json(manager),
+    });
+    println!("{}", serde_json::to_string_pretty(&document)?);
+    Ok(())
+}
+
+fn format_oid(oid: &[u64]) -> String {
+    oid.iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+// undecodable bytes become U+FFFD, as from_utf8_lossy does for UTF-8
+fn decode_string<'a>(bytes: &'a [u8], encoding: &'static Encoding) -> std::borrow::Cow<'a, str> {
+    encoding.decode_without_bom_handling(bytes).0
+}
+
+fn varbind_json(varbind: &VarBind, encoding: &'static Encoding, format: &FormatArgs) -> Value {
+    let hex = |bytes: &[u8]| {
+        bytes
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<String>()
+    };
+    let (kind, value) = match &varbind.value {
+        ObjectSyntax::Integer(val) => ("integer", json!(val)),
+        ObjectSyntax::OctetString(val) => ("octet-string", json!(decode_string(val, encoding))),
+        ObjectSyntax::Null => ("null", Value::Null),
+        ObjectSyntax::ObjectIdentifier(val) => ("oid", json!(format_oid(val))),
+        ObjectSyntax::IpAddress(val) => (
+            "ip-address",
+            json!(
+                val.iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(".")
+            ),
+        ),
+        ObjectSyntax::Counter32(val) => ("counter32", json!(val)),
+        ObjectSyntax::Gauge32(val) => ("gauge32", json!(val)),
+        ObjectSyntax::TimeTicks(val) => ("timeticks", json!(val)),
+        ObjectSyntax::Opaque(val) => ("opaque", json!(hex(val))),
+        ObjectSyntax::Counter64(val) => ("counter64", json!(val)),
+        ObjectSyntax::Float(val) => ("float", json!(val)),
+        ObjectSyntax::Double(val) => ("double", json!(val)),
+        ObjectSyntax::NoSuchObject => ("no-such-object", Value::Null),
+        ObjectSyntax::NoSuchInstance => ("no-such-instance", Value::Null),
+        ObjectSyntax::EndOfMib => ("end-of-mib-view", Value::Null),
+        ObjectSyntax::Tagged { bytes, .. } => ("tagged", json!(hex(bytes))),
+    };
+    let mut json = json!({ "oid": format_oid(&varbind.oid), "type": kind, "value": value });
+    if let Some(display) = format.display(varbind) {
+        json["display"] = json!(display);
+    }
+    json
+}
+
+async fn run_warm_up(
+    manager: &Manager,
+    targets: &[String],
+    discover_engines: bool,
+    secs: u64,
+    multi_progress: &MultiProgress,
+) -> Result<()> {
+    let warm_up_pb = multi_progress.add(ProgressBar::new(targets.len() as u64));
+    warm_up_pb.set_style(
+        ProgressStyle::default_bar().template("Warming up [{bar:40.yellow/blue}] {pos}/{len}")?,
+    );
+
+    let report = manager
+        .warm_up(
+            targets,
+            discover_engines,
+            Duration::from_secs(secs),
+            |_, _| warm_up_pb.inc(1),
+        )
+        .await;
+    warm_up_pb.finish_and_clear();
+
+    eprintln!(
+        "Warm-up: {} ready, {} failed, {} timed out",
+        report.ready.len(),
+        report.failed.len(),
+        report.timed_out.len()
+    );
+    for (target, e) in &report.failed {
+        eprintln!("  {}: {:#}", target, e);
+    }
+    Ok(())
+}
+
+fn print_varbind(varbind: &VarBind, encoding: &'static Encoding, format: &FormatArgs) {
     let oid_str = varbind
         .oid
         .iter()
@@ -234,15 +970,22 @@ fn print_varbind(varbind: &VarBind) {
 
     print!("OID: {} | Value: ", oid_str);
 
+    if let Some(display) = format.display(varbind) {
+        println!("{}", display);
+        return;
+    }
+
     match &varbind.value {
         ObjectSyntax::OctetString(val) => {
-            println!("{}", String::from_utf8_lossy(val));
+            println!("{}", decode_string(val, encoding));
         }
         ObjectSyntax::Integer(val) => println!("{}", val),
         ObjectSyntax::Counter32(val) => println!("{}", val),
         ObjectSyntax::Gauge32(val) => println!("{}", val),
         ObjectSyntax::TimeTicks(val) => println!("{}", val),
         ObjectSyntax::Counter64(val) => println!("{}", val),
+        ObjectSyntax::Float(val) => println!("{}", val),
+        ObjectSyntax::Double(val) => println!("{}", val),
         other => println!("{:?}", other),
     }
 }
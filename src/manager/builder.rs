@@ -0,0 +1,168 @@
+// Transport settings that differ between environments: a lab answers in
+// milliseconds, a satellite link needs seconds and a retry or two.
+
+use std::fmt;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Result, anyhow};
+
+use super::network::{DEFAULT_TIMEOUT, MAX_RESPONSE_SIZE, SNMP_PORT, SourceBinding};
+use super::pool::DEFAULT_MAX_IDLE_SOCKETS;
+use super::rate_limit::TokenBucket;
+use super::{Credentials, Manager, Session, Transport, UdpTransport};
+
+/// Builds a [`Manager`] with non-default transport settings. Start from
+/// [`Manager::builder`].
+#[derive(Clone)]
+pub struct ManagerBuilder {
+    timeout: Duration,
+    retries: u32,
+    max_message_size: usize,
+    port: u16,
+    credentials: Option<Credentials>,
+    max_idle_sockets: usize,
+    rate_limit: Option<f64>,
+    source: SourceBinding,
+    transport: Option<Arc<dyn Transport>>,
+}
+
+impl fmt::Debug for ManagerBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ManagerBuilder")
+            .field("timeout", &self.timeout)
+            .field("retries", &self.retries)
+            .field("max_message_size", &self.max_message_size)
+            .field("port", &self.port)
+            .field("credentials", &self.credentials)
+            .field("max_idle_sockets", &self.max_idle_sockets)
+            .field("rate_limit", &self.rate_limit)
+            .field("source", &self.source)
+            .field("custom_transport", &self.transport.is_some())
+            .finish()
+    }
+}
+
+impl Default for ManagerBuilder {
+    fn default() -> Self {
+        Self {
+            timeout: DEFAULT_TIMEOUT,
+            retries: 0,
+            max_message_size: MAX_RESPONSE_SIZE,
+            port: SNMP_PORT,
+            credentials: None,
+            max_idle_sockets: DEFAULT_MAX_IDLE_SOCKETS,
+            rate_limit: None,
+            source: SourceBinding::default(),
+            transport: None,
+        }
+    }
+}
+
+impl ManagerBuilder {
+    /// How long to wait for each response. Defaults to 5 seconds.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// How many more times to send a request that timed out. Defaults to 0.
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// The largest response to accept, and the msgMaxSize advertised to
+    /// v3 agents. Longer responses fail with a
+    /// [`MessageTooLargeError`](super::MessageTooLargeError). Defaults to
+    /// 4096 bytes; a UDP datagram holds at most 65507.
+    pub fn max_message_size(mut self, bytes: usize) -> Self {
+        self.max_message_size = bytes;
+        self
+    }
+
+    /// The agent port for targets that don't name one. Defaults to 161.
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = port;
+        self
+    }
+
+    /// How many connected sockets to keep for reuse once their request is
+    /// done, over all targets. Defaults to 256; 0 binds a fresh socket for
+    /// every request.
+    pub fn max_idle_sockets(mut self, max: usize) -> Self {
+        self.max_idle_sockets = max;
+        self
+    }
+
+    /// At most this many packets a second over all targets, with up to a
+    /// second's worth sent in a burst. Unlimited by default; panics unless
+    /// positive. [`Target::rate_limit`](super::Target::rate_limit) caps
+    /// single targets.
+    pub fn rate_limit(mut self, packets_per_second: f64) -> Self {
+        assert!(packets_per_second > 0.0, "rate limit must be positive");
+        self.rate_limit = Some(packets_per_second);
+        self
+    }
+
+    /// Sends from this local address, for hosts with several. Targets of
+    /// the other address family can't be reached then.
+    pub fn source_address(mut self, address: IpAddr) -> Self {
+        self.source.address = Some(address);
+        self
+    }
+
+    /// Sends through this network device, such as a management VRF, with
+    /// SO_BINDTODEVICE. Linux only; requests fail elsewhere, and without
+    /// CAP_NET_RAW.
+    pub fn bind_device(mut self, device: impl Into<String>) -> Self {
+        self.source.device = Some(device.into());
+        self
+    }
+
+    /// Sends requests through `transport` instead of UDP, e.g. an
+    /// in-memory agent for tests or a wrapper that injects faults.
+    /// [`ManagerBuilder::max_idle_sockets`] and the source settings then
+    /// have no effect.
+    pub fn transport(mut self, transport: impl Transport + 'static) -> Self {
+        self.transport = Some(Arc::new(transport));
+        self
+    }
+
+    /// Shorthand for v2c [`ManagerBuilder::credentials`].
+    pub fn community(self, community: impl Into<String>) -> Self {
+        self.credentials(Credentials::v2c(community))
+    }
+
+    /// Credentials callers can fetch back with
+    /// [`Manager::default_credentials`] instead of carrying their own.
+    pub fn credentials(mut self, credentials: impl Into<Credentials>) -> Self {
+        self.credentials = Some(credentials.into());
+        self
+    }
+
+    /// Opens a [`Session`] to `target` with these settings and the
+    /// builder's credentials.
+    pub async fn session(self, target: impl Into<String>) -> Result<Session> {
+        let credentials = self.credentials.clone().ok_or_else(|| {
+            anyhow!("A session needs credentials, set with community() or credentials()")
+        })?;
+        let pin = self.transport.is_none().then(|| self.source.clone());
+        Session::open(self.build(), target.into(), credentials, pin).await
+    }
+
+    pub fn build(self) -> Manager {
+        let mut manager = Manager::new();
+        manager.timeout = self.timeout;
+        manager.retries = self.retries;
+        manager.max_message_size = self.max_message_size;
+        manager.port = self.port;
+        manager.default_credentials = self.credentials;
+        manager.transport = self.transport.unwrap_or_else(|| {
+            Arc::new(UdpTransport::new(self.max_idle_sockets).with_source(self.source))
+        });
+        manager.rate_limit = self.rate_limit.map(TokenBucket::new);
+        manager
+    }
+}
@@ -0,0 +1,95 @@
+// Long walks can outlive the reason they were started. These variants take
+// a `stop` future, such as a tokio-util `CancellationToken::cancelled()` or
+// `tokio::time::sleep_until(deadline)`, and give up as soon as it finishes,
+// keeping whatever had been collected.
+
+use std::future::Future;
+use std::pin::pin;
+
+use anyhow::Result;
+use futures::future::{Either, select};
+
+use super::{CancelledError, Credentials, Manager};
+use crate::snmp::pdu::VarBind;
+
+impl Manager {
+    /// [`Manager::get`], failing with [`CancelledError`] if `stop`
+    /// finishes first.
+    pub async fn get_until(
+        &self,
+        target: &str,
+        credentials: &Credentials,
+        oid_str: &str,
+        stop: impl Future<Output = ()>,
+    ) -> Result<VarBind> {
+        until(self.get(target, credentials, oid_str), stop)
+            .await
+            .unwrap_or_else(|| {
+                Err(CancelledError {
+                    collected: Vec::new(),
+                }
+                .into())
+            })
+    }
+
+    /// [`Manager::walk`], stopping between requests or mid-request once
+    /// `stop` finishes. The [`CancelledError`] then holds the varbinds
+    /// walked so far.
+    pub async fn walk_until(
+        &self,
+        target: &str,
+        credentials: &Credentials,
+        root_oid_str: &str,
+        stop: impl Future<Output = ()>,
+    ) -> Result<Vec<VarBind>> {
+        let mut collected = Vec::new();
+        let walked = until(
+            self.walk_into(target, credentials, root_oid_str, &mut collected),
+            stop,
+        )
+        .await;
+        finish(walked, collected)
+    }
+
+    /// [`Manager::bulk_walk`], stopping like [`Manager::walk_until`].
+    pub async fn bulk_walk_until(
+        &self,
+        target: &str,
+        credentials: &Credentials,
+        root_oid_str: &str,
+        max_repititions: i32,
+        stop: impl Future<Output = ()>,
+    ) -> Result<Vec<VarBind>> {
+        let mut collected = Vec::new();
+        let walked = until(
+            self.bulk_walk_into(
+                target,
+                credentials,
+                root_oid_str,
+                max_repititions,
+                &mut collected,
+            ),
+            stop,
+        )
+        .await;
+        finish(walked, collected)
+    }
+}
+
+// None if `stop` won; dropping the operation abandons its request
+async fn until<T>(
+    operation: impl Future<Output = Result<T>>,
+    stop: impl Future<Output = ()>,
+) -> Option<Result<T>> {
+    match select(pin!(operation), pin!(stop)).await {
+        Either::Left((result, _)) => Some(result),
+        Either::Right(_) => None,
+    }
+}
+
+fn finish(walked: Option<Result<()>>, collected: Vec<VarBind>) -> Result<Vec<VarBind>> {
+    match walked {
+        Some(result) => result.map(|()| collected),
+        None => Err(CancelledError { collected }.into()),
+    }
+}
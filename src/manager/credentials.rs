@@ -0,0 +1,63 @@
+// Which SNMP version and security model a request goes out with. Taking
+// this instead of a community string lets one Manager talk to a fleet with
+// mixed versions, and leaves room for new security models.
+
+use std::fmt;
+
+#[cfg(feature = "v3")]
+use crate::snmp::usm::UsmUser;
+
+#[derive(Clone)]
+#[non_exhaustive]
+pub enum Credentials {
+    /// SNMPv1 community. GETBULK doesn't exist in v1.
+    CommunityV1(String),
+    CommunityV2c(String),
+    #[cfg(feature = "v3")]
+    UsmV3(UsmUser),
+}
+
+impl Credentials {
+    pub fn v1(community: impl Into<String>) -> Self {
+        Credentials::CommunityV1(community.into())
+    }
+
+    pub fn v2c(community: impl Into<String>) -> Self {
+        Credentials::CommunityV2c(community.into())
+    }
+
+    // the same version and community, or the same user at the same level
+    pub(super) fn matches(&self, other: &Credentials) -> bool {
+        match (self, other) {
+            (Credentials::CommunityV1(a), Credentials::CommunityV1(b))
+            | (Credentials::CommunityV2c(a), Credentials::CommunityV2c(b)) => a == b,
+            #[cfg(feature = "v3")]
+            (Credentials::UsmV3(a), Credentials::UsmV3(b)) => {
+                a.name == b.name && a.security_flags() == b.security_flags()
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(feature = "v3")]
+impl From<UsmUser> for Credentials {
+    fn from(user: UsmUser) -> Self {
+        Credentials::UsmV3(user)
+    }
+}
+
+// communities are secrets too
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Credentials::CommunityV1(_) => f.write_str("CommunityV1(..)"),
+            Credentials::CommunityV2c(_) => f.write_str("CommunityV2c(..)"),
+            #[cfg(feature = "v3")]
+            Credentials::UsmV3(user) => f
+                .debug_tuple("UsmV3")
+                .field(&String::from_utf8_lossy(&user.name))
+                .finish(),
+        }
+    }
+}
@@ -0,0 +1,192 @@
+// Finding the agents on a subnet: ask every address for its identity and
+// keep whoever answers. Addresses that stay silent cost a timeout each, so
+// they are asked concurrently, within the Manager's rate limits. On a LAN
+// one broadcast GET is cheaper still, if the agents answer broadcasts.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result, anyhow};
+use futures::stream::{self, StreamExt};
+
+use super::system::text;
+use super::{Credentials, ErrorClass, Expected, Manager, basic_request, network, parse_oid_string};
+use crate::ber::Asn1Tag;
+use crate::snmp::message::{SnmpMessage, parse_message};
+use crate::snmp::pdu::{ObjectSyntax, VarBind};
+
+const SYS_DESCR: &str = "1.3.6.1.2.1.1.1.0";
+const SYS_OBJECT_ID: &str = "1.3.6.1.2.1.1.2.0";
+
+// a /16, or a /112 of IPv6
+const MAX_SCAN_ADDRESSES: u128 = 1 << 16;
+
+/// An address that answered a discovery scan.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscoveredAgent {
+    pub address: IpAddr,
+    /// sysDescr, `None` if the agent answered but didn't give it, e.g.
+    /// with an error or a v3 Report.
+    pub description: Option<String>,
+    pub object_id: Option<Vec<u64>>,
+    pub rtt: Duration,
+}
+
+impl Manager {
+    /// Asks every address in `cidr` (e.g. "10.0.0.0/24", or a bare
+    /// address) for sysDescr.0 and sysObjectID.0, with up to
+    /// `max_concurrent` requests in flight, and returns the agents that
+    /// answered in address order. An error status or a v3 Report still
+    /// counts as an answer. The network and broadcast addresses of IPv4
+    /// ranges wider than /31 are skipped. Ranges over 65536 addresses are
+    /// refused.
+    pub async fn discover(
+        &self,
+        cidr: &str,
+        credentials: &Credentials,
+        max_concurrent: usize,
+    ) -> Result<Vec<DiscoveredAgent>> {
+        let addresses = scan_addresses(cidr)?;
+        let mut found: Vec<DiscoveredAgent> = stream::iter(addresses)
+            .map(|address| async move {
+                let target = address.to_string();
+                let started = Instant::now();
+                let result = self
+                    .get_multi(&target, credentials, &[SYS_DESCR, SYS_OBJECT_ID])
+                    .await;
+                let rtt = started.elapsed();
+                match result {
+                    Ok(varbinds) => Some(DiscoveredAgent {
+                        address,
+                        description: varbinds.first().and_then(|v| text(&v.value)),
+                        object_id: varbinds
+                            .get(1)
+                            .and_then(|v| v.value.as_oid())
+                            .map(<[u64]>::to_vec),
+                        rtt,
+                    }),
+                    Err(e)
+                        if matches!(
+                            ErrorClass::of(&e),
+                            ErrorClass::SnmpError | ErrorClass::Report
+                        ) =>
+                    {
+                        Some(DiscoveredAgent {
+                            address,
+                            description: None,
+                            object_id: None,
+                            rtt,
+                        })
+                    }
+                    Err(_) => None,
+                }
+            })
+            .buffer_unordered(max_concurrent.max(1))
+            .filter_map(|agent| async move { agent })
+            .collect()
+            .await;
+        found.sort_by_key(|agent| agent.address);
+        Ok(found)
+    }
+
+    /// Sends one GetRequest for `oid_strs` to a broadcast, directed
+    /// broadcast or multicast `target`, e.g. "192.168.1.255", and returns
+    /// every response that arrives within `window`, in arrival order, with
+    /// the address it came from. The varbinds are as each agent sent them;
+    /// error-status isn't checked. v1 and v2c only, since v3 needs each
+    /// agent's engine first. The request goes out on a UDP socket of its
+    /// own, whatever the Manager's [`Transport`](super::Transport).
+    pub async fn broadcast_get(
+        &self,
+        target: &str,
+        credentials: &Credentials,
+        oid_strs: &[&str],
+        window: Duration,
+    ) -> Result<Vec<(SocketAddr, Vec<VarBind>)>> {
+        let (version, community) = match credentials {
+            Credentials::CommunityV1(community) => (0, community),
+            Credentials::CommunityV2c(community) => (1, community),
+            #[cfg(feature = "v3")]
+            Credentials::UsmV3(_) => return Err(anyhow!("Broadcast GET needs v1 or v2c")),
+        };
+        if oid_strs.is_empty() {
+            return Err(anyhow!("GetRequest needs at least one oid"));
+        }
+        let varbinds = oid_strs
+            .iter()
+            .map(|oid_str| {
+                Ok(VarBind {
+                    oid: parse_oid_string(oid_str)?,
+                    value: ObjectSyntax::Null,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut pdu = basic_request(Asn1Tag::GetRequest, varbinds);
+        pdu.request_id = self.next_request_id();
+        let expected = Expected::community(version, community, pdu.request_id);
+        let message = SnmpMessage {
+            version,
+            community: community.as_bytes().to_vec(),
+            pdu,
+        };
+
+        let address = self.resolve(target).await?;
+        self.throttle(target).await;
+        let replies = network::broadcast(address, &message.to_bytes(), window, |reply| {
+            expected.matches(reply)
+        })
+        .await?;
+        // undecodable replies can't be told apart from noise here
+        Ok(replies
+            .into_iter()
+            .filter_map(|(from, reply)| Some((from, parse_message(&reply).ok()?.pdu.varbinds)))
+            .collect())
+    }
+}
+
+fn scan_addresses(cidr: &str) -> Result<Vec<IpAddr>> {
+    let (address, prefix) = match cidr.split_once('/') {
+        Some((address, prefix)) => (address, Some(prefix)),
+        None => (cidr, None),
+    };
+    let address: IpAddr = address
+        .parse()
+        .with_context(|| format!("Invalid address in {:?}", cidr))?;
+    let bits = if address.is_ipv4() { 32 } else { 128 };
+    let prefix: u32 = match prefix {
+        Some(prefix) => prefix
+            .parse()
+            .ok()
+            .filter(|prefix| *prefix <= bits)
+            .ok_or_else(|| anyhow!("Invalid prefix length in {:?}", cidr))?,
+        None => bits,
+    };
+
+    let count = 1u128 << (bits - prefix);
+    if count > MAX_SCAN_ADDRESSES {
+        return Err(anyhow!(
+            "{} holds {} addresses, more than the {} a scan may cover",
+            cidr,
+            count,
+            MAX_SCAN_ADDRESSES
+        ));
+    }
+    let first = match address {
+        IpAddr::V4(v4) => u128::from(u32::from(v4)),
+        IpAddr::V6(v6) => u128::from(v6),
+    } & !(count - 1);
+
+    // no hosts at the network and broadcast addresses
+    let hosts = if address.is_ipv4() && count > 2 {
+        1..count - 1
+    } else {
+        0..count
+    };
+    Ok(hosts
+        .map(|offset| match address {
+            IpAddr::V4(_) => Ipv4Addr::from((first + offset) as u32).into(),
+            IpAddr::V6(_) => Ipv6Addr::from(first + offset).into(),
+        })
+        .collect())
+}
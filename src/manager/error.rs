@@ -0,0 +1,143 @@
+// Typed errors the manager puts inside anyhow::Error, and a coarse
+// classification of any manager error for callers that need to branch on
+// the kind of failure rather than parse the message.
+
+use std::fmt;
+use std::io;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use thiserror::Error;
+
+use crate::ber::BerError;
+use crate::snmp::pdu::{ErrorStatus, VarBind};
+use crate::snmp::report::ReportError;
+#[cfg(feature = "v3")]
+use crate::snmp::usm::UsmError;
+
+/// The agent did not answer in time.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("Timeout: No response from {address} after {}s", after.as_secs())]
+pub struct TimeoutError {
+    pub address: SocketAddr,
+    pub after: Duration,
+}
+
+/// A response was longer than the largest message we accept, set with
+/// `max_message_size`. Only its start arrived, so it can't be decoded.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("Response from {address} exceeded the max message size of {max_size} bytes")]
+pub struct MessageTooLargeError {
+    pub address: SocketAddr,
+    pub max_size: usize,
+}
+
+/// The agent answered with a non-zero error-status.
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[error("SNMP Error: {status} (Index: {index})")]
+pub struct SnmpError {
+    pub status: ErrorStatus,
+    pub index: i32,
+}
+
+/// The Manager's [`SetPolicy`](super::SetPolicy) refused a binding; nothing
+/// was sent.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("SET of {oid} is not permitted by the set policy")]
+pub struct SetDeniedError {
+    pub oid: String,
+}
+
+/// A walk got back an OID that does not come after the one it asked
+/// about. Following it would loop forever.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error(
+    "OID not increasing: asked after {}, agent returned {}",
+    dotted(previous),
+    dotted(returned)
+)]
+pub struct OidNotIncreasingError {
+    pub previous: Vec<u64>,
+    pub returned: Vec<u64>,
+}
+
+/// The stop future passed to one of the `*_until` calls finished before
+/// the operation did. `collected` holds what had arrived by then.
+#[derive(Error, Debug, Clone, PartialEq)]
+#[error("Cancelled after collecting {} varbinds", collected.len())]
+pub struct CancelledError {
+    pub collected: Vec<VarBind>,
+}
+
+fn dotted(oid: &[u64]) -> String {
+    oid.iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorClass {
+    Timeout,
+    /// ICMP port unreachable, nothing listening on the SNMP port.
+    Refused,
+    SnmpError,
+    /// A Report PDU, usually a v3 security problem.
+    Report,
+    /// A response we could not decode or authenticate.
+    Parse,
+    /// The caller stopped the operation.
+    Cancelled,
+    Other,
+}
+
+impl ErrorClass {
+    /// Walks the error's cause chain for the first error we recognise.
+    pub fn of(error: &anyhow::Error) -> Self {
+        for cause in error.chain() {
+            if cause.is::<TimeoutError>() {
+                return ErrorClass::Timeout;
+            }
+            if cause.is::<SnmpError>() {
+                return ErrorClass::SnmpError;
+            }
+            if cause.is::<ReportError>() {
+                return ErrorClass::Report;
+            }
+            if cause.is::<CancelledError>() {
+                return ErrorClass::Cancelled;
+            }
+            if cause.is::<BerError>() || cause.is::<MessageTooLargeError>() {
+                return ErrorClass::Parse;
+            }
+            #[cfg(feature = "v3")]
+            if cause.is::<UsmError>() {
+                return ErrorClass::Parse;
+            }
+            if let Some(io_error) = cause.downcast_ref::<io::Error>()
+                && io_error.kind() == io::ErrorKind::ConnectionRefused
+            {
+                return ErrorClass::Refused;
+            }
+        }
+        ErrorClass::Other
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorClass::Timeout => "timeout",
+            ErrorClass::Refused => "refused",
+            ErrorClass::SnmpError => "snmp-error",
+            ErrorClass::Report => "report",
+            ErrorClass::Parse => "parse",
+            ErrorClass::Cancelled => "cancelled",
+            ErrorClass::Other => "other",
+        }
+    }
+}
+
+impl fmt::Display for ErrorClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
@@ -0,0 +1,67 @@
+// The same request against many targets at once. Requests run as futures
+// on the caller's task rather than spawned tasks, and at most
+// `max_concurrent` are in flight, which also bounds the sockets in use.
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use futures::future::join_all;
+use futures::stream::{self, StreamExt};
+use tokio::sync::Semaphore;
+
+use super::{Credentials, Manager};
+use crate::snmp::pdu::VarBind;
+
+impl Manager {
+    /// Gets `oid_strs` from every target with [`Manager::get_multi`],
+    /// running up to `max_concurrent` requests at a time. Each target's
+    /// result is keyed by the target as given; duplicates are asked once.
+    pub async fn get_many(
+        &self,
+        targets: &[&str],
+        credentials: &Credentials,
+        oid_strs: &[&str],
+        max_concurrent: usize,
+    ) -> HashMap<String, Result<Vec<VarBind>>> {
+        let mut unique = targets.to_vec();
+        unique.sort_unstable();
+        unique.dedup();
+        stream::iter(unique)
+            .map(|target| async move {
+                let result = self.get_multi(target, credentials, oid_strs).await;
+                (target.to_string(), result)
+            })
+            .buffer_unordered(max_concurrent.max(1))
+            .collect()
+            .await
+    }
+
+    /// Walks `root_oid_str` on every target, keyed by target like
+    /// [`Manager::get_many`]. A semaphore lets at most `max_concurrent`
+    /// walks talk to agents at once; the rest wait for a permit.
+    pub async fn walk_many(
+        &self,
+        targets: &[&str],
+        credentials: &Credentials,
+        root_oid_str: &str,
+        max_concurrent: usize,
+    ) -> HashMap<String, Result<Vec<VarBind>>> {
+        let permits = Semaphore::new(max_concurrent.max(1));
+        let mut unique = targets.to_vec();
+        unique.sort_unstable();
+        unique.dedup();
+
+        let walks = unique.into_iter().map(|target| {
+            let permits = &permits;
+            async move {
+                let result = async {
+                    let _permit = permits.acquire().await?;
+                    self.walk(target, credentials, root_oid_str).await
+                }
+                .await;
+                (target.to_string(), result)
+            }
+        });
+        join_all(walks).await.into_iter().collect()
+    }
+}
@@ -0,0 +1,170 @@
+// IF-MIB (RFC 2863) is what most people poll SNMP for. ifTable has the
+// original 32-bit counters, which wrap in seconds on fast links; ifXTable
+// adds names, aliases and 64-bit counters. An Interface takes each value
+// from the better table that has it.
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+
+use super::system::text;
+use super::{Credentials, Manager};
+use crate::snmp::pdu::ObjectSyntax;
+
+const IF_TABLE: &str = "1.3.6.1.2.1.2.2";
+const IF_DESCR: u64 = 2;
+const IF_SPEED: u64 = 5;
+const IF_ADMIN_STATUS: u64 = 7;
+const IF_OPER_STATUS: u64 = 8;
+const IF_IN_OCTETS: u64 = 10;
+const IF_IN_UCAST_PKTS: u64 = 11;
+const IF_OUT_OCTETS: u64 = 16;
+const IF_OUT_UCAST_PKTS: u64 = 17;
+
+const IF_X_TABLE: &str = "1.3.6.1.2.1.31.1.1";
+const IF_NAME: u64 = 1;
+const IF_HC_IN_OCTETS: u64 = 6;
+const IF_HC_IN_UCAST_PKTS: u64 = 7;
+const IF_HC_OUT_OCTETS: u64 = 10;
+const IF_HC_OUT_UCAST_PKTS: u64 = 11;
+const IF_HIGH_SPEED: u64 = 15;
+const IF_ALIAS: u64 = 18;
+
+/// ifAdminStatus and ifOperStatus. Admin status is only ever `Up`, `Down`
+/// or `Testing`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IfStatus {
+    Up,
+    Down,
+    Testing,
+    Unknown,
+    Dormant,
+    NotPresent,
+    LowerLayerDown,
+}
+
+impl IfStatus {
+    fn from_value(value: &ObjectSyntax) -> Option<Self> {
+        let status = match value.as_i64()? {
+            1 => IfStatus::Up,
+            2 => IfStatus::Down,
+            3 => IfStatus::Testing,
+            4 => IfStatus::Unknown,
+            5 => IfStatus::Dormant,
+            6 => IfStatus::NotPresent,
+            7 => IfStatus::LowerLayerDown,
+            _ => return None,
+        };
+        Some(status)
+    }
+}
+
+/// One row of ifTable, merged with its ifXTable row. Values the agent
+/// doesn't have are `None`. Counters and speed come from ifXTable where
+/// it has them, and from ifTable otherwise.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Interface {
+    pub index: u32,
+    /// ifName, e.g. "eth0".
+    pub name: Option<String>,
+    /// ifDescr, often the product name of the interface.
+    pub description: Option<String>,
+    /// ifAlias, the label an operator gave it.
+    pub alias: Option<String>,
+    /// Bits per second.
+    pub speed: Option<u64>,
+    pub admin_status: Option<IfStatus>,
+    pub oper_status: Option<IfStatus>,
+    pub in_octets: Option<u64>,
+    pub out_octets: Option<u64>,
+    /// Unicast packets received.
+    pub in_packets: Option<u64>,
+    /// Unicast packets sent.
+    pub out_packets: Option<u64>,
+}
+
+impl Manager {
+    /// Fetches ifTable and ifXTable and merges them by ifIndex, in ifIndex
+    /// order. Agents without ifXTable still get every interface, with
+    /// 32-bit counters and no names or aliases.
+    pub async fn get_interfaces(
+        &self,
+        target: &str,
+        credentials: &Credentials,
+    ) -> Result<Vec<Interface>> {
+        let if_table = self
+            .get_table_columns(
+                target,
+                credentials,
+                IF_TABLE,
+                &[
+                    IF_DESCR,
+                    IF_SPEED,
+                    IF_ADMIN_STATUS,
+                    IF_OPER_STATUS,
+                    IF_IN_OCTETS,
+                    IF_IN_UCAST_PKTS,
+                    IF_OUT_OCTETS,
+                    IF_OUT_UCAST_PKTS,
+                ],
+            )
+            .await?;
+        let if_x_table = self
+            .get_table_columns(
+                target,
+                credentials,
+                IF_X_TABLE,
+                &[
+                    IF_NAME,
+                    IF_HC_IN_OCTETS,
+                    IF_HC_IN_UCAST_PKTS,
+                    IF_HC_OUT_OCTETS,
+                    IF_HC_OUT_UCAST_PKTS,
+                    IF_HIGH_SPEED,
+                    IF_ALIAS,
+                ],
+            )
+            .await?;
+
+        let empty = BTreeMap::new();
+        let interfaces = if_table
+            .iter()
+            .filter_map(|(index, row)| {
+                let [index] = index[..] else { return None };
+                let x_row = if_x_table.get(&vec![index]).unwrap_or(&empty);
+                let counter = |hc: u64, low: u64| {
+                    x_row
+                        .get(&hc)
+                        .or_else(|| row.get(&low))
+                        .and_then(ObjectSyntax::as_u64)
+                };
+                Some(Interface {
+                    index: index.try_into().ok()?,
+                    name: x_row.get(&IF_NAME).and_then(text),
+                    description: row.get(&IF_DESCR).and_then(text),
+                    alias: x_row.get(&IF_ALIAS).and_then(text),
+                    speed: speed(row.get(&IF_SPEED), x_row.get(&IF_HIGH_SPEED)),
+                    admin_status: row.get(&IF_ADMIN_STATUS).and_then(IfStatus::from_value),
+                    oper_status: row.get(&IF_OPER_STATUS).and_then(IfStatus::from_value),
+                    in_octets: counter(IF_HC_IN_OCTETS, IF_IN_OCTETS),
+                    out_octets: counter(IF_HC_OUT_OCTETS, IF_OUT_OCTETS),
+                    in_packets: counter(IF_HC_IN_UCAST_PKTS, IF_IN_UCAST_PKTS),
+                    out_packets: counter(IF_HC_OUT_UCAST_PKTS, IF_OUT_UCAST_PKTS),
+                })
+            })
+            .collect();
+        Ok(interfaces)
+    }
+}
+
+// ifSpeed tops out at 4294967295 for anything faster; ifHighSpeed is in
+// millions of bits per second
+fn speed(if_speed: Option<&ObjectSyntax>, if_high_speed: Option<&ObjectSyntax>) -> Option<u64> {
+    let low = if_speed.and_then(ObjectSyntax::as_u64);
+    let high = if_high_speed.and_then(ObjectSyntax::as_u64);
+    match (low, high) {
+        (Some(low), _) if low < u64::from(u32::MAX) => Some(low),
+        (_, Some(high)) => Some(high * 1_000_000),
+        (low, None) => low,
+    }
+}
@@ -0,0 +1,234 @@
+// IP-MIB and IP-FORWARD-MIB tables, whose rows are indexed by addresses.
+// The old tables (ipAddrTable, ipRouteTable) are IPv4 only with the
+// address as four arcs; their replacements (ipAddressTable,
+// inetCidrRouteTable) use a type arc and a length-prefixed address, so one
+// index can hold either family. Agents may have either or both, so the
+// new tables are tried first.
+
+use std::net::{IpAddr, Ipv4Addr};
+
+use anyhow::Result;
+
+use super::{Credentials, Manager, TableRows};
+use crate::snmp::pdu::ObjectSyntax;
+
+const IP_ADDRESS_TABLE: &str = "1.3.6.1.2.1.4.34";
+const IP_ADDRESS_IF_INDEX: u64 = 3;
+const IP_ADDRESS_PREFIX: u64 = 5;
+
+const IP_ADDR_TABLE: &str = "1.3.6.1.2.1.4.20";
+const IP_AD_ENT_IF_INDEX: u64 = 2;
+const IP_AD_ENT_NET_MASK: u64 = 3;
+
+const INET_CIDR_ROUTE_TABLE: &str = "1.3.6.1.2.1.4.24.7";
+const INET_CIDR_ROUTE_IF_INDEX: u64 = 7;
+const INET_CIDR_ROUTE_METRIC1: u64 = 12;
+
+const IP_ROUTE_TABLE: &str = "1.3.6.1.2.1.4.21";
+const IP_ROUTE_IF_INDEX: u64 = 2;
+const IP_ROUTE_METRIC1: u64 = 3;
+const IP_ROUTE_NEXT_HOP: u64 = 7;
+const IP_ROUTE_MASK: u64 = 11;
+
+/// An address assigned to an interface.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IpAddressEntry {
+    pub address: IpAddr,
+    pub prefix_len: Option<u8>,
+    pub if_index: Option<u32>,
+}
+
+/// A route from the agent's forwarding table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteEntry {
+    pub destination: IpAddr,
+    pub prefix_len: u8,
+    /// `None` for directly connected routes.
+    pub next_hop: Option<IpAddr>,
+    pub if_index: Option<u32>,
+    pub metric: Option<u32>,
+}
+
+impl Manager {
+    /// The addresses on the agent's interfaces, from ipAddressTable, or
+    /// from the IPv4-only ipAddrTable if the agent lacks it.
+    pub async fn get_ip_addresses(
+        &self,
+        target: &str,
+        credentials: &Credentials,
+    ) -> Result<Vec<IpAddressEntry>> {
+        let rows = self
+            .get_table_columns(
+                target,
+                credentials,
+                IP_ADDRESS_TABLE,
+                &[IP_ADDRESS_IF_INDEX, IP_ADDRESS_PREFIX],
+            )
+            .await?;
+        if !rows.is_empty() {
+            return Ok(ip_address_entries(&rows));
+        }
+
+        let rows = self
+            .get_table_columns(
+                target,
+                credentials,
+                IP_ADDR_TABLE,
+                &[IP_AD_ENT_IF_INDEX, IP_AD_ENT_NET_MASK],
+            )
+            .await?;
+        Ok(rows
+            .iter()
+            .filter_map(|(index, row)| {
+                Some(IpAddressEntry {
+                    address: ipv4_from_arcs(index)?.into(),
+                    prefix_len: row.get(&IP_AD_ENT_NET_MASK).and_then(mask_prefix_len),
+                    if_index: row.get(&IP_AD_ENT_IF_INDEX).and_then(unsigned),
+                })
+            })
+            .collect())
+    }
+
+    /// The agent's routes, from inetCidrRouteTable, or from the IPv4-only
+    /// ipRouteTable if the agent lacks it.
+    pub async fn get_routes(
+        &self,
+        target: &str,
+        credentials: &Credentials,
+    ) -> Result<Vec<RouteEntry>> {
+        let rows = self
+            .get_table_columns(
+                target,
+                credentials,
+                INET_CIDR_ROUTE_TABLE,
+                &[INET_CIDR_ROUTE_IF_INDEX, INET_CIDR_ROUTE_METRIC1],
+            )
+            .await?;
+        if !rows.is_empty() {
+            return Ok(inet_cidr_route_entries(&rows));
+        }
+
+        let rows = self
+            .get_table_columns(
+                target,
+                credentials,
+                IP_ROUTE_TABLE,
+                &[
+                    IP_ROUTE_IF_INDEX,
+                    IP_ROUTE_METRIC1,
+                    IP_ROUTE_NEXT_HOP,
+                    IP_ROUTE_MASK,
+                ],
+            )
+            .await?;
+        Ok(rows
+            .iter()
+            .filter_map(|(index, row)| {
+                Some(RouteEntry {
+                    destination: ipv4_from_arcs(index)?.into(),
+                    prefix_len: row.get(&IP_ROUTE_MASK).and_then(mask_prefix_len)?,
+                    next_hop: row
+                        .get(&IP_ROUTE_NEXT_HOP)
+                        .and_then(ObjectSyntax::as_ipaddr)
+                        .filter(|hop| !hop.is_unspecified()),
+                    if_index: row.get(&IP_ROUTE_IF_INDEX).and_then(unsigned),
+                    metric: row.get(&IP_ROUTE_METRIC1).and_then(unsigned),
+                })
+            })
+            .collect())
+    }
+}
+
+// INDEX { ipAddressAddrType, ipAddressAddr }
+fn ip_address_entries(rows: &TableRows) -> Vec<IpAddressEntry> {
+    rows.iter()
+        .filter_map(|(index, row)| {
+            let (address, _) = split_inet_address(index)?;
+            // ipAddressPrefix points at the ipAddressPrefixTable row,
+            // whose last index arc is the prefix length; 0.0 if unknown
+            let prefix_len = row
+                .get(&IP_ADDRESS_PREFIX)
+                .and_then(ObjectSyntax::as_oid)
+                .filter(|prefix| prefix.len() > 2)
+                .and_then(|prefix| u8::try_from(*prefix.last()?).ok());
+            Some(IpAddressEntry {
+                address: address?,
+                prefix_len,
+                if_index: row.get(&IP_ADDRESS_IF_INDEX).and_then(unsigned),
+            })
+        })
+        .collect()
+}
+
+// INDEX { inetCidrRouteDestType, inetCidrRouteDest, inetCidrRoutePfxLen,
+//         inetCidrRoutePolicy, inetCidrRouteNextHopType,
+//         inetCidrRouteNextHop }
+fn inet_cidr_route_entries(rows: &TableRows) -> Vec<RouteEntry> {
+    rows.iter()
+        .filter_map(|(index, row)| {
+            let (destination, rest) = split_inet_address(index)?;
+            let [prefix_len, policy_len, rest @ ..] = rest else {
+                return None;
+            };
+            let rest = rest.get(usize::try_from(*policy_len).ok()?..)?;
+            let (next_hop, _) = split_inet_address(rest)?;
+            Some(RouteEntry {
+                destination: destination?,
+                prefix_len: u8::try_from(*prefix_len).ok()?,
+                next_hop: next_hop.filter(|hop| !hop.is_unspecified()),
+                if_index: row.get(&INET_CIDR_ROUTE_IF_INDEX).and_then(unsigned),
+                metric: row.get(&INET_CIDR_ROUTE_METRIC1).and_then(unsigned),
+            })
+        })
+        .collect()
+}
+
+// An InetAddressType arc and a length-prefixed InetAddress off the front
+// of an index, and what follows them. The address is None for types other
+// than ipv4, ipv6 and their zoned forms, whose zone is dropped.
+fn split_inet_address(index: &[u64]) -> Option<(Option<IpAddr>, &[u64])> {
+    let [kind, len, rest @ ..] = index else {
+        return None;
+    };
+    let len = usize::try_from(*len).ok()?;
+    if rest.len() < len {
+        return None;
+    }
+    let (arcs, rest) = rest.split_at(len);
+    let bytes = arcs
+        .iter()
+        .map(|&arc| u8::try_from(arc).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    let address = match (kind, len) {
+        (1, 4) | (3, 8) => Some(IpAddr::from(<[u8; 4]>::try_from(&bytes[..4]).ok()?)),
+        (2, 16) | (4, 20) => Some(IpAddr::from(<[u8; 16]>::try_from(&bytes[..16]).ok()?)),
+        _ => None,
+    };
+    Some((address, rest))
+}
+
+fn ipv4_from_arcs(arcs: &[u64]) -> Option<Ipv4Addr> {
+    let [a, b, c, d] = arcs else {
+        return None;
+    };
+    Some(Ipv4Addr::new(
+        u8::try_from(*a).ok()?,
+        u8::try_from(*b).ok()?,
+        u8::try_from(*c).ok()?,
+        u8::try_from(*d).ok()?,
+    ))
+}
+
+// None for masks that aren't a run of ones followed by zeros
+fn mask_prefix_len(mask: &ObjectSyntax) -> Option<u8> {
+    let IpAddr::V4(mask) = mask.as_ipaddr()? else {
+        return None;
+    };
+    let mask = u32::from(mask);
+    (mask.leading_ones() + mask.trailing_zeros() == 32).then_some(mask.leading_ones() as u8)
+}
+
+// None for negative values, like the -1 of an unused metric
+fn unsigned(value: &ObjectSyntax) -> Option<u32> {
+    value.as_u64()?.try_into().ok()
+}
@@ -0,0 +1,106 @@
+// Background keepalive for long-lived targets: a periodic GET of
+// sysUpTime.0 proves the agent is reachable, the credentials still work
+// and, for v3, our engine boots/time estimate is in sync (a stale
+// estimate is corrected on the way). Failures mark the target degraded
+// so callers can see trouble before their next real poll runs into it.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{Result, bail};
+use tokio::task::JoinHandle;
+
+use super::{Credentials, ErrorClass, Manager, TimeoutError};
+
+const SYS_UP_TIME: &str = "1.3.6.1.2.1.1.3.0";
+const PROBE_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TargetHealth {
+    Healthy {
+        checked_at: Instant,
+    },
+    Degraded {
+        /// When the first failing check in the current run happened.
+        since: Instant,
+        class: ErrorClass,
+        reason: String,
+    },
+}
+
+impl TargetHealth {
+    pub fn is_healthy(&self) -> bool {
+        matches!(self, TargetHealth::Healthy { .. })
+    }
+}
+
+impl Manager {
+    /// The verdict of the last keepalive check of `target`, if one ran.
+    pub fn health(&self, target: &str) -> Option<TargetHealth> {
+        self.health.lock().unwrap().get(target).cloned()
+    }
+
+    /// One sysUpTime.0 GET that gives up after a second, retries
+    /// included, for a quick check that the agent is up and takes
+    /// `credentials` before starting anything expensive. Returns the
+    /// round-trip time, which for a v3 target not yet discovered includes
+    /// discovery.
+    pub async fn probe(&self, target: &str, credentials: &Credentials) -> Result<Duration> {
+        let address = self.resolve(target).await?;
+        let started = Instant::now();
+        match tokio::time::timeout(PROBE_TIMEOUT, self.get(target, credentials, SYS_UP_TIME)).await
+        {
+            Ok(result) => result.map(|_| started.elapsed()),
+            Err(_) => Err(TimeoutError {
+                address,
+                after: PROBE_TIMEOUT,
+            }
+            .into()),
+        }
+    }
+
+    /// Checks `target` now and then every `interval` until the returned
+    /// handle is aborted. Fails if `interval` is zero.
+    pub fn spawn_keepalive(
+        self: &Arc<Self>,
+        target: &str,
+        credentials: Credentials,
+        interval: Duration,
+    ) -> Result<JoinHandle<()>> {
+        if interval.is_zero() {
+            bail!("Keepalive interval must be non-zero");
+        }
+        let manager = Arc::clone(self);
+        let target = target.to_string();
+        Ok(tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                manager.check_health(&target, &credentials).await;
+            }
+        }))
+    }
+
+    async fn check_health(&self, target: &str, credentials: &Credentials) {
+        let result = self.get(target, credentials, SYS_UP_TIME).await;
+
+        let mut health = self.health.lock().unwrap();
+        let verdict = match result {
+            Ok(_) => TargetHealth::Healthy {
+                checked_at: Instant::now(),
+            },
+            Err(e) => {
+                let since = match health.get(target) {
+                    Some(TargetHealth::Degraded { since, .. }) => *since,
+                    _ => Instant::now(),
+                };
+                TargetHealth::Degraded {
+                    since,
+                    class: ErrorClass::of(&e),
+                    reason: format!("{:#}", e),
+                }
+            }
+        };
+        health.insert(target.to_string(), verdict);
+    }
+}
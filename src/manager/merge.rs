@@ -0,0 +1,37 @@
+// Collecting several subtrees concurrently finishes them in whatever order
+// the network allows. Merging by OID makes the combined output independent
+// of that, so diffs against a baseline only show real changes.
+
+use anyhow::Result;
+use futures::future::try_join_all;
+
+use super::{Credentials, Manager};
+use crate::snmp::pdu::VarBind;
+
+/// Merges varbind lists into one in ascending OID order. An OID present in
+/// more than one list is kept once, from the earliest list holding it, so
+/// the output depends only on the contents and order of `lists`, never on
+/// when each was collected. Lists need not be sorted beforehand.
+pub fn merge_ordered(lists: Vec<Vec<VarBind>>) -> Vec<VarBind> {
+    let mut merged: Vec<VarBind> = lists.into_iter().flatten().collect();
+    // stable, so the first occurrence of a duplicate stays in front
+    merged.sort_by(|a, b| a.oid.cmp(&b.oid));
+    merged.dedup_by(|later, earlier| later.oid == earlier.oid);
+    merged
+}
+
+impl Manager {
+    /// Walks every root concurrently and returns the results merged with
+    /// [`merge_ordered`]. Roots may overlap. Fails if any walk fails.
+    pub async fn walk_subtrees(
+        &self,
+        target: &str,
+        credentials: &Credentials,
+        roots: &[&str],
+    ) -> Result<Vec<VarBind>> {
+        let walks = roots
+            .iter()
+            .map(|root| self.walk(target, credentials, root));
+        Ok(merge_ordered(try_join_all(walks).await?))
+    }
+}
@@ -1,34 +1,312 @@
 use crate::ber::Asn1Tag;
-use crate::snmp::message::{SnmpMessage, parse_message};
+use crate::snmp::message::{SnmpMessage, parse_message, peek_header};
 use crate::snmp::pdu::{ErrorStatus, ObjectSyntax, Pdu, PduData, VarBind};
 use anyhow::{Ok, anyhow};
 
 use anyhow::Context;
+use futures::stream::{self, Stream, StreamExt, TryStreamExt};
+mod builder;
+mod cancel;
+mod credentials;
+mod discovery;
+mod error;
+mod fan_out;
+mod interfaces;
+mod ip;
+mod keepalive;
+mod merge;
 pub mod network;
+mod notify;
+mod poller;
+mod pool;
+#[cfg(feature = "precheck")]
+mod precheck;
+mod rate_limit;
+mod session;
+mod set_policy;
+mod shared_socket;
+mod stats;
+mod system;
+mod table;
+mod target;
+#[cfg(feature = "tls")]
+mod tls;
+mod transport;
+#[cfg(feature = "v3")]
+mod v3;
+mod warm_up;
 use anyhow::Result;
-use tokio::net::lookup_host;
+pub use builder::ManagerBuilder;
+pub use credentials::Credentials;
+pub use discovery::DiscoveredAgent;
+pub use error::{
+    CancelledError, ErrorClass, MessageTooLargeError, OidNotIncreasingError, SetDeniedError,
+    SnmpError, TimeoutError,
+};
+pub use interfaces::{IfStatus, Interface};
+pub use ip::{IpAddressEntry, RouteEntry};
+pub use keepalive::TargetHealth;
+pub use merge::merge_ordered;
+pub use network::{AddressFamilyPolicy, SourceBinding};
+pub use notify::notification_varbinds;
+pub use poller::{JobId, PollJob, PollRequest, PollResult, Poller};
+pub use pool::DEFAULT_MAX_IDLE_SOCKETS;
+#[cfg(feature = "precheck")]
+pub use precheck::ProbeMethod;
+pub use session::Session;
+pub use set_policy::SetPolicy;
+pub use shared_socket::SharedSocketTransport;
+pub use stats::TransportStats;
+use std::collections::hash_map::RandomState;
+use std::collections::{HashMap, HashSet};
+use std::hash::{BuildHasher, Hasher};
+use std::net::SocketAddr;
+use std::pin::pin;
+#[cfg(feature = "v3")]
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+pub use system::SystemInfo;
+pub use table::{IndexSyntax, IndexedRows, TableRows, decode_index, encode_index};
+pub use target::Target;
+#[cfg(feature = "tls")]
+pub use tls::{
+    Fingerprint, FingerprintAlgorithm, TLS_PORT, TLS_TRAP_PORT, TlsTcpBuilder, TlsTcpTransport,
+};
+pub use transport::{Transport, UdpTransport};
+#[cfg(feature = "v3")]
+pub(crate) use v3::initial_salt;
+pub use warm_up::WarmUpReport;
 
-fn parse_oid_string(oid_str: &str) -> Result<Vec<u32>> {
+pub(crate) fn parse_oid_string(oid_str: &str) -> Result<Vec<u64>> {
     oid_str
         .split('.')
         .filter(|s| !s.is_empty()) // Filter out the empty string before the first dot
         .map(|s| {
-            s.parse::<u32>()
+            s.parse::<u64>()
                 .with_context(|| format!("Invalid OID component: '{}'", s))
         })
-        .collect::<Result<Vec<u32>, _>>()
+        .collect::<Result<Vec<u64>, _>>()
 }
 
-fn is_in_subtree(root: &[u32], child: &[u32]) -> bool {
+/// A max-repetitions for [`Manager::bulk_walk`] to start from and adapt
+/// down, for when nothing is known about the agent.
+pub const BULK_WALK_MAX_REPETITIONS: i32 = 100;
+
+// how long a resolved target address is used before asking DNS again
+const ADDRESS_TTL: Duration = Duration::from_secs(300);
+
+fn is_in_subtree(root: &[u64], child: &[u64]) -> bool {
     if child.len() < root.len() {
         return false;
     }
     child.starts_with(root)
 }
 
+fn check_error_status(pdu: &Pdu) -> Result<()> {
+    if let PduData::Basic {
+        error_status,
+        error_index,
+    } = pdu.data
+        && error_status != ErrorStatus::NoError
+    {
+        return Err(SnmpError {
+            status: error_status,
+            index: error_index,
+        }
+        .into());
+    }
+    Ok(())
+}
+
+// the request-id is filled in when the PDU is sent
+fn basic_request(tag: Asn1Tag, varbinds: Vec<VarBind>) -> Pdu {
+    Pdu {
+        tag,
+        request_id: 0,
+        data: PduData::Basic {
+            error_status: ErrorStatus::NoError,
+            error_index: 0,
+        },
+        varbinds,
+    }
+}
+
+// names the binding the agent blamed, keeping the SnmpError as the cause
+fn check_set_response(pdu: &Pdu, bindings: &[(&str, ObjectSyntax)]) -> Result<()> {
+    if let PduData::Basic {
+        error_status,
+        error_index,
+    } = pdu.data
+        && error_status != ErrorStatus::NoError
+    {
+        let error = anyhow::Error::from(SnmpError {
+            status: error_status,
+            index: error_index,
+        });
+        let failed = usize::try_from(error_index)
+            .ok()
+            .and_then(|i| i.checked_sub(1))
+            .and_then(|i| bindings.get(i));
+        return Err(match failed {
+            Some((oid_str, _)) => error.context(format!("SET of {} failed", oid_str)),
+            None => error.context("SET failed"),
+        });
+    }
+    Ok(())
+}
+
+fn check_increasing(previous: &[u64], returned: &[u64]) -> Result<()> {
+    if returned <= previous {
+        return Err(OidNotIncreasingError {
+            previous: previous.to_vec(),
+            returned: returned.to_vec(),
+        }
+        .into());
+    }
+    Ok(())
+}
+
+fn is_too_big(pdu: &Pdu) -> bool {
+    matches!(
+        pdu.data,
+        PduData::Basic {
+            error_status: ErrorStatus::TooBig,
+            ..
+        }
+    )
+}
+
+// Whether a failed GETBULK might work asking for fewer varbinds. get_bulk
+// already does that for tooBig; this is for answers that were cut short.
+// An answer too big for the path can also just never arrive, but a timeout
+// only counts once the agent has answered this walk before.
+fn asked_too_much(error: &anyhow::Error, answered_before: bool) -> bool {
+    match ErrorClass::of(error) {
+        ErrorClass::Parse => true,
+        ErrorClass::Timeout => answered_before,
+        _ => false,
+    }
+}
+
+// Agents without real GETBULK support have been seen answering with the
+// request OID echoed back or OIDs going backwards, which would loop forever.
+// With `skip` those varbinds are dropped instead.
+fn check_bulk_batch(start: &[u64], batch: Vec<VarBind>, skip: bool) -> Result<Vec<VarBind>> {
+    let mut checked: Vec<VarBind> = Vec::with_capacity(batch.len());
+    for varbind in batch {
+        if matches!(
+            varbind.value,
+            ObjectSyntax::EndOfMib | ObjectSyntax::NoSuchObject | ObjectSyntax::NoSuchInstance
+        ) {
+            checked.push(varbind);
+            break;
+        }
+        let previous = checked.last().map_or(start, |last| &last.oid);
+        match check_increasing(previous, &varbind.oid) {
+            Err(_) if skip => continue,
+            result => result?,
+        }
+        checked.push(varbind);
+    }
+    Ok(checked)
+}
+
+// What the answer to a request looks like. Anything else arriving on the
+// socket, like a late answer to an earlier request or a stray packet from
+// another agent, is skipped and we keep waiting.
+#[derive(Debug, Clone, Copy)]
+struct Expected<'a> {
+    version: i32,
+    request_id: i32,
+    // v1/v2c only; v3 answers are checked by USM instead
+    community: Option<&'a [u8]>,
+}
+
+impl<'a> Expected<'a> {
+    fn community(version: i32, community: &'a str, request_id: i32) -> Self {
+        Self {
+            version,
+            request_id,
+            community: Some(community.as_bytes()),
+        }
+    }
+
+    #[cfg(feature = "v3")]
+    fn v3(msg_id: i32) -> Self {
+        Self {
+            version: 3,
+            request_id: msg_id,
+            community: None,
+        }
+    }
+
+    // datagrams we can't read are let through so their parse error shows
+    fn matches(&self, response: &[u8]) -> bool {
+        peek_header(response).ok().is_none_or(|header| {
+            header.version == self.version
+                && header.request_id == self.request_id
+                && header.community == self.community
+                && header.pdu_tag.is_none_or(|tag| tag == Asn1Tag::GetResponse)
+        })
+    }
+}
+
+// random, so a restarted manager doesn't take late answers meant for the
+// one before it as its own
+fn initial_request_id() -> i32 {
+    RandomState::new().build_hasher().finish() as i32
+}
+
 /// The main SNMP Manager struct.
 /// This will be the entry point for all operations.
-pub struct Manager {}
+pub struct Manager {
+    family_policy: AddressFamilyPolicy,
+    // per-target exceptions to family_policy
+    target_family_policies: HashMap<String, AddressFamilyPolicy>,
+    // resolved target addresses and when, so DNS is only asked once per
+    // target every ADDRESS_TTL
+    addresses: Mutex<HashMap<String, (SocketAddr, Instant)>>,
+    // discovered v3 engines, keyed by target
+    #[cfg(feature = "v3")]
+    engines: Mutex<HashMap<String, v3::EngineState>>,
+    // next privacy salt
+    #[cfg(feature = "v3")]
+    salt: AtomicU64,
+    // last keepalive verdict per target
+    health: Mutex<HashMap<String, TargetHealth>>,
+    // targets that mishandled GETBULK, walked with GetNext instead
+    no_bulk: Mutex<HashSet<String>>,
+    // max-repetitions each target's last bulk walk ended up with
+    bulk_repetitions: Mutex<HashMap<String, i32>>,
+    // GETBULK responses at least this big are parsed on the blocking pool
+    offload_parse_at: Option<usize>,
+    set_policy: SetPolicy,
+    // drop non-increasing OIDs during walks instead of failing
+    skip_non_increasing: bool,
+    // transport counters per target
+    stats: Mutex<HashMap<String, TransportStats>>,
+    timeout: Duration,
+    retries: u32,
+    // largest response accepted, for targets without their own
+    max_message_size: usize,
+    // agent port for targets without one
+    port: u16,
+    default_credentials: Option<Credentials>,
+    // next request-id, or msgID for v3
+    request_ids: AtomicI32,
+    // a Session's socket to its target
+    pinned: Option<session::PinnedSocket>,
+    // what carries packets to agents
+    transport: Arc<dyn Transport>,
+    // per-target settings, keyed by target name
+    targets: HashMap<String, Target>,
+    // packets per second over all targets
+    rate_limit: Option<rate_limit::TokenBucket>,
+    // and for targets registered with their own limit
+    target_rate_limits: HashMap<String, rate_limit::TokenBucket>,
+}
 
 // just cause rust analyzer wouldnt leave me
 impl Default for Manager {
@@ -40,151 +318,543 @@ impl Default for Manager {
 impl Manager {
     /// Creates a new Manager.
     pub fn new() -> Self {
-        Self {}
+        Self {
+            family_policy: AddressFamilyPolicy::default(),
+            target_family_policies: HashMap::new(),
+            addresses: Mutex::new(HashMap::new()),
+            #[cfg(feature = "v3")]
+            engines: Mutex::new(HashMap::new()),
+            #[cfg(feature = "v3")]
+            salt: AtomicU64::new(v3::initial_salt()),
+            health: Mutex::new(HashMap::new()),
+            no_bulk: Mutex::new(HashSet::new()),
+            bulk_repetitions: Mutex::new(HashMap::new()),
+            offload_parse_at: None,
+            set_policy: SetPolicy::default(),
+            skip_non_increasing: false,
+            stats: Mutex::new(HashMap::new()),
+            timeout: network::DEFAULT_TIMEOUT,
+            retries: 0,
+            max_message_size: network::MAX_RESPONSE_SIZE,
+            port: network::SNMP_PORT,
+            default_credentials: None,
+            request_ids: AtomicI32::new(initial_request_id()),
+            pinned: None,
+            transport: Arc::new(UdpTransport::default()),
+            targets: HashMap::new(),
+            rate_limit: None,
+            target_rate_limits: HashMap::new(),
+        }
     }
 
-    /// Performs a single, asynchronous SNMP GET operation.
-    pub async fn get(&self, target: &str, community: &str, oid_str: &str) -> Result<VarBind> {
-        let oid = parse_oid_string(oid_str)?;
+    /// Starts a [`ManagerBuilder`] for tuning timeouts, retries and ports.
+    pub fn builder() -> ManagerBuilder {
+        ManagerBuilder::default()
+    }
+
+    /// The credentials given to the builder, if any.
+    pub fn default_credentials(&self) -> Option<&Credentials> {
+        self.default_credentials.as_ref()
+    }
+
+    /// Sets which address family to use for host names that resolve to
+    /// both IPv4 and IPv6.
+    pub fn with_family_policy(mut self, policy: AddressFamilyPolicy) -> Self {
+        self.family_policy = policy;
+        self
+    }
+
+    /// Overrides the family policy for one target.
+    pub fn with_target_family_policy(
+        mut self,
+        target: impl Into<String>,
+        policy: AddressFamilyPolicy,
+    ) -> Self {
+        self.target_family_policies.insert(target.into(), policy);
+        self
+    }
+
+    /// Limits which objects SETs may write to.
+    pub fn with_set_policy(mut self, policy: SetPolicy) -> Self {
+        self.set_policy = policy;
+        self
+    }
+
+    /// What walks do when the agent returns an OID that does not come
+    /// after the one asked about. By default the walk fails with
+    /// [`OidNotIncreasingError`]; with `skip` such varbinds are dropped,
+    /// and a walk that can no longer make progress ends with what it has.
+    pub fn with_skip_non_increasing(mut self, skip: bool) -> Self {
+        self.skip_non_increasing = skip;
+        self
+    }
+
+    /// Parses GETBULK responses of at least `bytes` on tokio's blocking
+    /// thread pool, so big responses don't hold up the runtime's workers.
+    /// Off by default; `None` turns it back off.
+    pub fn with_offloaded_parsing(mut self, bytes: Option<usize>) -> Self {
+        self.offload_parse_at = bytes;
+        self
+    }
 
-        // Build the GetRequest packet from scratch.
+    async fn parse_response(&self, response_bytes: Vec<u8>, offload: bool) -> Result<SnmpMessage> {
+        let parsed = match self.offload_parse_at {
+            Some(threshold) if offload && response_bytes.len() >= threshold => {
+                tokio::task::spawn_blocking(move || parse_message(&response_bytes)).await?
+            }
+            _ => parse_message(&response_bytes),
+        };
+        parsed.map_err(|e| anyhow!(e).context("Failed to parse response"))
+    }
+
+    // checked before anything goes on the wire
+    fn set_varbinds(&self, bindings: &[(&str, ObjectSyntax)]) -> Result<Vec<VarBind>> {
+        if bindings.is_empty() {
+            return Err(anyhow!("SetRequest needs at least one binding"));
+        }
+        bindings
+            .iter()
+            .map(|(oid_str, value)| {
+                let oid = parse_oid_string(oid_str)?;
+                if !self.set_policy.permits(&oid) {
+                    return Err(SetDeniedError {
+                        oid: oid_str.to_string(),
+                    }
+                    .into());
+                }
+                Ok(VarBind {
+                    oid,
+                    value: value.clone(),
+                })
+            })
+            .collect()
+    }
+
+    async fn resolve(&self, target: &str) -> Result<SocketAddr> {
+        if let Some((address, resolved)) = self.addresses.lock().unwrap().get(target)
+            && resolved.elapsed() < ADDRESS_TTL
+        {
+            return Ok(*address);
+        }
+        let policy = self
+            .target_family_policies
+            .get(target)
+            .copied()
+            .unwrap_or(self.family_policy);
+        let address = network::resolve(target, self.target_port(target), policy).await?;
+        self.addresses
+            .lock()
+            .unwrap()
+            .insert(target.to_string(), (address, Instant::now()));
+        Ok(address)
+    }
+
+    // kept non-negative, some agents mishandle negative request-ids
+    fn next_request_id(&self) -> i32 {
+        self.request_ids.fetch_add(1, Ordering::Relaxed) & i32::MAX
+    }
+
+    // retries timeouts only; a refusal or an answer won't change on resend
+    async fn send(&self, target: &str, packet: &[u8], expected: Expected<'_>) -> Result<Vec<u8>> {
+        let address = self.resolve(target).await?;
+        let retries = self.settings(target).retries;
+        let mut attempt = 0;
+        loop {
+            match self.exchange(target, address, packet, expected).await {
+                Err(e) if attempt < retries && ErrorClass::of(&e) == ErrorClass::Timeout => {
+                    attempt += 1;
+                    self.note_retransmit(target);
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!(target = %target, attempt, "no answer, retransmitting");
+                }
+                result => return result,
+            }
+        }
+    }
+
+    // same host, different service, e.g. a trap sink, unless the target
+    // names a port; callers do their own retries
+    async fn send_to_port(
+        &self,
+        target: &str,
+        port: u16,
+        packet: &[u8],
+        expected: Expected<'_>,
+    ) -> Result<Vec<u8>> {
+        let mut address = self.resolve(target).await?;
+        if network::split_port(target)?.1.is_none() {
+            address.set_port(port);
+        }
+        self.exchange(target, address, packet, expected).await
+    }
+
+    /// Sends `pdu` as `credentials` and returns the agent's response PDU.
+    /// v3 Reports come back as [`ReportError`](crate::snmp::report::ReportError).
+    /// Failures are counted in the target's [`TransportStats`].
+    async fn request(&self, target: &str, credentials: &Credentials, pdu: Pdu) -> Result<Pdu> {
+        let result = self.request_once(target, credentials, pdu).await;
+        if let Err(e) = &result {
+            self.note_failure(target, e);
+            #[cfg(feature = "tracing")]
+            tracing::debug!(target = %target, error = %format!("{:#}", e), "request failed");
+        }
+        result
+    }
+
+    async fn request_once(
+        &self,
+        target: &str,
+        credentials: &Credentials,
+        mut pdu: Pdu,
+    ) -> Result<Pdu> {
+        self.check_credentials(target, credentials)?;
+        pdu.request_id = self.next_request_id();
+        #[cfg(feature = "tracing")]
+        tracing::trace!(request_id = pdu.request_id, pdu = ?pdu.tag, "sending request");
+        let (version, community) = match credentials {
+            Credentials::CommunityV1(community) => (0, community),
+            Credentials::CommunityV2c(community) => (1, community),
+            #[cfg(feature = "v3")]
+            Credentials::UsmV3(user) => return self.request_v3(target, user, pdu).await,
+        };
+
+        let offload = pdu.tag == Asn1Tag::GetBulkRequest;
+        let expected = Expected::community(version, community, pdu.request_id);
         let message = SnmpMessage {
-            version: 1, // 1 = v2c
+            version,
             community: community.as_bytes().to_vec(),
-            pdu: Pdu {
-                tag: Asn1Tag::GetRequest,
-                request_id: 1, // Simple request ID
-                data: PduData::Basic {
-                    error_status: ErrorStatus::NoError,
-                    error_index: 0,
-                },
-                varbinds: vec![VarBind {
-                    oid,
-                    value: ObjectSyntax::Null, // Value is Null for a GetRequest
-                }],
-            },
+            pdu,
         };
         let packet_bytes = message.to_bytes();
 
         // Send and receive the raw bytes, handling timeouts.
-        let response_bytes = network::send_and_receive(target, &packet_bytes).await?;
+        let response_bytes = self.send(target, &packet_bytes, expected).await?;
+        Ok(self.parse_response(response_bytes, offload).await?.pdu)
+    }
 
-        // Parse the raw response bytes into our structs.
-        let response_message = parse_message(&response_bytes)
-            .map_err(|e| anyhow!("Failed to parse response: {}", e))?;
+    /// Performs a single, asynchronous SNMP GET operation. With v3
+    /// credentials the agent's engine is discovered on first contact and
+    /// cached for later requests.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(target = %target, oid = %oid_str))
+    )]
+    pub async fn get(
+        &self,
+        target: &str,
+        credentials: &Credentials,
+        oid_str: &str,
+    ) -> Result<VarBind> {
+        let oid = parse_oid_string(oid_str)?;
+        let request = basic_request(
+            Asn1Tag::GetRequest,
+            vec![VarBind {
+                oid,
+                value: ObjectSyntax::Null, // Value is Null for a GetRequest
+            }],
+        );
 
-        if let PduData::Basic {
-            error_status,
-            error_index,
-        } = response_message.pdu.data
-        {
-            if error_status != ErrorStatus::NoError {
-                return Err(anyhow!(
-                    "SNMP Error: {:?} (Index: {})",
-                    error_status,
-                    error_index
-                ));
-            }
-        }
+        let response = self.request(target, credentials, request).await?;
+        check_error_status(&response)?;
 
-        response_message
-            .pdu
+        response
             .varbinds
             .into_iter()
             .next()
             .ok_or_else(|| anyhow!("No VarBinds in response"))
     }
 
-    pub async fn walk(
+    /// Gets all `oid_strs` in one GetRequest, returning the values in
+    /// request order. If the answer would be tooBig for the agent, the
+    /// OIDs are split over several requests.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(target = %target, oids = ?oid_strs))
+    )]
+    pub async fn get_multi(
         &self,
         target: &str,
-        community: &str,
-        root_id_str: &str,
+        credentials: &Credentials,
+        oid_strs: &[&str],
     ) -> Result<Vec<VarBind>> {
-        let mut results = Vec::new();
-        let root_id = parse_oid_string(root_id_str)?;
-        let mut current_oid = root_id.clone();
+        if oid_strs.is_empty() {
+            return Err(anyhow!("GetRequest needs at least one oid"));
+        }
+        let varbinds = oid_strs
+            .iter()
+            .map(|oid_str| {
+                Ok(VarBind {
+                    oid: parse_oid_string(oid_str)?,
+                    value: ObjectSyntax::Null,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
 
-        loop {
-            let message = SnmpMessage {
-                version: 1,
-                community: community.as_bytes().to_vec(),
-                pdu: Pdu {
-                    tag: Asn1Tag::GetNextRequest,
-                    request_id: 1,
-                    data: PduData::Basic {
-                        error_status: ErrorStatus::NoError,
-                        error_index: 0,
-                    },
-                    varbinds: vec![VarBind {
-                        oid: current_oid.clone(),
-                        value: ObjectSyntax::Null,
-                    }],
-                },
-            };
+        self.request_split(target, credentials, Asn1Tag::GetRequest, varbinds)
+            .await
+    }
 
-            let packet_bytes = message.to_bytes();
+    /// Sets a single object, returning the value the agent echoed back.
+    pub async fn set(
+        &self,
+        target: &str,
+        credentials: &Credentials,
+        oid_str: &str,
+        value: ObjectSyntax,
+    ) -> Result<VarBind> {
+        self.set_multi(target, credentials, &[(oid_str, value)])
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("No VarBinds in response"))
+    }
 
-            let response_bytes = network::send_and_receive(target, &packet_bytes).await?;
+    /// Sends all bindings in one SetRequest, which the agent applies as a
+    /// whole or not at all (RFC 3416 section 4.2.5). On failure the
+    /// [`SnmpError`] index is the 1-based position in `bindings` of the
+    /// binding the agent rejected.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(target = %target, count = bindings.len()))
+    )]
+    pub async fn set_multi(
+        &self,
+        target: &str,
+        credentials: &Credentials,
+        bindings: &[(&str, ObjectSyntax)],
+    ) -> Result<Vec<VarBind>> {
+        let request = basic_request(Asn1Tag::SetRequest, self.set_varbinds(bindings)?);
 
-            let response_message = parse_message(&response_bytes)
-                .map_err(|e| anyhow!("Failed to parse response: {}", e))?;
+        let response = self.request(target, credentials, request).await?;
+        check_set_response(&response, bindings)?;
+        Ok(response.varbinds)
+    }
 
-            // check for errors in the response
-            if let PduData::Basic {
-                error_status,
-                error_index,
-            } = response_message.pdu.data
-            {
-                if error_status != ErrorStatus::NoError {
-                    if error_status == ErrorStatus::NoSuchName {
-                        break;
-                    }
+    /// Sends one GetNextRequest for all `oid_strs` and returns the successor
+    /// of each, in request order. Past the end of the MIB the agent answers
+    /// with an `EndOfMib` value for that varbind. Split up like
+    /// [`Manager::get_multi`] on tooBig.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(target = %target, oids = ?oid_strs))
+    )]
+    pub async fn get_next(
+        &self,
+        target: &str,
+        credentials: &Credentials,
+        oid_strs: &[&str],
+    ) -> Result<Vec<VarBind>> {
+        let mut request_varbinds = Vec::new();
+        for s in oid_strs {
+            request_varbinds.push(VarBind {
+                oid: parse_oid_string(s)?,
+                value: ObjectSyntax::Null,
+            });
+        }
 
-                    return Err(anyhow!(
-                        "
-                SNMP Error: {:?} (Index : {}) ,
-                        ",
-                        error_status,
-                        error_index
-                    ));
-                }
-            }
+        if request_varbinds.is_empty() {
+            return Err(anyhow!("GetNextRequest needs at least one oid"));
+        }
 
-            let response_varbind = response_message
-                .pdu
-                .varbinds
-                .into_iter()
-                .next()
-                .ok_or_else(|| anyhow!("No Varbinds in getnext response"))?;
-
-            match response_varbind.value {
-                ObjectSyntax::NoSuchObject
-                | ObjectSyntax::NoSuchInstance
-                | ObjectSyntax::EndOfMib => {
-                    break;
-                }
-                _ => {}
+        let varbinds = self
+            .request_split(
+                target,
+                credentials,
+                Asn1Tag::GetNextRequest,
+                request_varbinds,
+            )
+            .await?;
+
+        if varbinds.len() != oid_strs.len() {
+            return Err(anyhow!(
+                "Asked for {} successors, agent returned {}",
+                oid_strs.len(),
+                varbinds.len()
+            ));
+        }
+
+        Ok(varbinds)
+    }
+
+    // sends `varbinds` in one request, or in as many smaller ones as it
+    // takes for the answers not to be tooBig; error indexes still count
+    // from the start of `varbinds`
+    async fn request_split(
+        &self,
+        target: &str,
+        credentials: &Credentials,
+        tag: Asn1Tag,
+        varbinds: Vec<VarBind>,
+    ) -> Result<Vec<VarBind>> {
+        let mut results = Vec::with_capacity(varbinds.len());
+        let mut chunk = varbinds.len();
+        let mut done = 0;
+        while done < varbinds.len() {
+            let size = chunk.min(varbinds.len() - done);
+            let request = basic_request(tag, varbinds[done..done + size].to_vec());
+            let mut response = self.request(target, credentials, request).await?;
+            if size > 1 && is_too_big(&response) {
+                chunk = size.div_ceil(2);
+                continue;
             }
+            if let PduData::Basic { error_index, .. } = &mut response.data
+                && *error_index > 0
+            {
+                *error_index += done as i32;
+            }
+            check_error_status(&response)?;
+            results.append(&mut response.varbinds);
+            done += size;
+        }
+        Ok(results)
+    }
 
-            if !is_in_subtree(&root_id, &response_varbind.oid) {
-                break;
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(target = %target, root = %root_id_str))
+    )]
+    pub async fn walk(
+        &self,
+        target: &str,
+        credentials: &Credentials,
+        root_id_str: &str,
+    ) -> Result<Vec<VarBind>> {
+        self.walk_stream(target, credentials, root_id_str)
+            .try_collect()
+            .await
+    }
+
+    /// Like [`Manager::walk`], but yields each varbind as soon as its
+    /// GetNext is answered, so callers can stop early or apply their own
+    /// limits. Dropping the stream stops the walk. An error ends the
+    /// stream. Pin it, e.g. with `std::pin::pin!`, to call `next` on it.
+    pub fn walk_stream<'a>(
+        &'a self,
+        target: &'a str,
+        credentials: &'a Credentials,
+        root_id_str: &str,
+    ) -> impl Stream<Item = Result<VarBind>> + use<'a> {
+        let root = parse_oid_string(root_id_str);
+        // (root, where we are); None once the walk is over
+        let start = root.as_ref().ok().map(|root| (root.clone(), root.clone()));
+        let failed = root.err().map(Err);
+        stream::iter(failed).chain(stream::unfold(start, move |state| async move {
+            let (root, current) = state?;
+            let step = self.walk_step(target, credentials, &root, &current).await;
+            let next = step
+                .as_ref()
+                .ok()
+                .and_then(Option::as_ref)
+                .map(|varbind| (root, varbind.oid.clone()));
+            step.transpose().map(|item| (item, next))
+        }))
+    }
+
+    // walk, appending to `results` as varbinds arrive
+    async fn walk_into(
+        &self,
+        target: &str,
+        credentials: &Credentials,
+        root_id_str: &str,
+        results: &mut Vec<VarBind>,
+    ) -> Result<()> {
+        let mut walk = pin!(self.walk_stream(target, credentials, root_id_str));
+        while let Some(varbind) = walk.try_next().await? {
+            results.push(varbind);
+        }
+        Ok(())
+    }
+
+    // the varbind after `current`, or None once the walk has left `root`
+    async fn walk_step(
+        &self,
+        target: &str,
+        credentials: &Credentials,
+        root: &[u64],
+        current: &[u64],
+    ) -> Result<Option<VarBind>> {
+        let request = basic_request(
+            Asn1Tag::GetNextRequest,
+            vec![VarBind {
+                oid: current.to_vec(),
+                value: ObjectSyntax::Null,
+            }],
+        );
+
+        let response = self.request(target, credentials, request).await?;
+
+        // check for errors in the response; v1 agents signal the end
+        // of the MIB with noSuchName
+        if let PduData::Basic { error_status, .. } = response.data
+            && error_status == ErrorStatus::NoSuchName
+        {
+            return Ok(None);
+        }
+        check_error_status(&response)?;
+
+        let response_varbind = response
+            .varbinds
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("No Varbinds in getnext response"))?;
+
+        match response_varbind.value {
+            ObjectSyntax::NoSuchObject | ObjectSyntax::NoSuchInstance | ObjectSyntax::EndOfMib => {
+                return Ok(None);
             }
+            _ => {}
+        }
 
-            current_oid = response_varbind.oid.clone();
-            results.push(response_varbind);
+        if !is_in_subtree(root, &response_varbind.oid) {
+            return Ok(None);
         }
-        Ok(results)
+        // asking again from the same place would get the same answer
+        match check_increasing(current, &response_varbind.oid) {
+            Err(_) if self.skip_non_increasing => return Ok(None),
+            result => result?,
+        }
+
+        Ok(Some(response_varbind))
     }
 
+    /// Sends one GetBulkRequest. An agent answering tooBig is asked again
+    /// with half the max-repetitions, down to one.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(target = %target, oids = ?oid_strs, max_repititions))
+    )]
     pub async fn get_bulk(
         &self,
         target: &str,
-        community: &str,
+        credentials: &Credentials,
         non_repeaters: i32,
         max_repititions: i32,
         oid_strs: &[&str],
     ) -> Result<Vec<VarBind>> {
+        self.get_bulk_fitting(
+            target,
+            credentials,
+            non_repeaters,
+            max_repititions,
+            oid_strs,
+        )
+        .await
+        .map(|(varbinds, _)| varbinds)
+    }
+
+    // get_bulk, also returning the max-repetitions that was answered
+    async fn get_bulk_fitting(
+        &self,
+        target: &str,
+        credentials: &Credentials,
+        non_repeaters: i32,
+        max_repititions: i32,
+        oid_strs: &[&str],
+    ) -> Result<(Vec<VarBind>, i32)> {
+        if let Credentials::CommunityV1(_) = credentials {
+            return Err(anyhow!("GetBulkRequest needs SNMPv2c or v3"));
+        }
+
         let mut request_varbinds = Vec::new();
         for s in oid_strs {
             let oid = parse_oid_string(s)?;
@@ -198,108 +868,193 @@ impl Manager {
             return Err(anyhow!("GetBulkRequest needs atlaeat one oid"));
         }
 
-        // encode
-        let message = SnmpMessage {
-            version: 1,
-            community: community.as_bytes().to_vec(),
-            pdu: Pdu {
+        // small-buffer agents: ask for fewer repetitions until it fits
+        let mut max_repititions = max_repititions;
+        let response = loop {
+            let request = Pdu {
                 tag: Asn1Tag::GetBulkRequest,
-                request_id: 1,
-                data: crate::snmp::pdu::PduData::Bulk {
+                request_id: 0,
+                data: PduData::Bulk {
                     non_repeaters,
                     max_repititions,
                 },
-                varbinds: request_varbinds,
-            },
+                varbinds: request_varbinds.clone(),
+            };
+            let response = self.request(target, credentials, request).await?;
+            if max_repititions > 1 && is_too_big(&response) {
+                max_repititions /= 2;
+                continue;
+            }
+            break response;
         };
 
-        let packet_bytes = message.to_bytes();
-        let response_bytes = network::send_and_receive(target, &packet_bytes).await?;
-
-        let response_message = parse_message(&response_bytes)
-            .map_err(|e| anyhow!("Faield to parse response: {}", e))?;
-
-        if response_message.pdu.tag != Asn1Tag::GetResponse {
-            return Err(anyhow!(
-                "Expewcted GetBulkRequest, got {:?}",
-                response_message.pdu.tag
-            ));
+        if response.tag != Asn1Tag::GetResponse {
+            return Err(anyhow!("Expewcted GetBulkRequest, got {:?}", response.tag));
         }
 
-        match response_message.pdu.data {
-            PduData::Basic {
-                error_status,
-                error_index,
-            } => {
-                if error_status != ErrorStatus::NoError {
-                    return Err(anyhow!(
-                        "
-                    SNMP Error : {:?} (Index : {})
-                            ",
-                        error_status,
-                        error_index
-                    ));
-                }
-            }
+        match response.data {
+            PduData::Basic { .. } => check_error_status(&response)?,
             PduData::Bulk { .. } => {
                 return Err(anyhow!("received unexpected GetBulk PDU in response"));
             }
+            PduData::Trap { .. } => {
+                return Err(anyhow!("received unexpected Trap PDU in response"));
+            }
         }
 
-        Ok(response_message.pdu.varbinds)
+        Ok((response.varbinds, max_repititions))
     }
 
+    /// Walks `root_oid_str` with GetBulkRequests. Agents that reject or
+    /// mangle GETBULK on the first request are walked with GetNext instead,
+    /// and remembered so later bulk walks of that target skip straight to
+    /// GetNext. v1 credentials always walk with GetNext.
+    ///
+    /// `max_repititions` is an upper bound and where the walk starts, so
+    /// pass a large one, e.g. [`BULK_WALK_MAX_REPETITIONS`]. A request
+    /// answered with tooBig, or whose answer doesn't arrive whole, is
+    /// asked again for half as many, and the walk stays at or below that.
+    /// An agent sending fewer than asked is asked for that many next;
+    /// full answers double it again. Where a walk ended up is where the
+    /// next walk of the target starts.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(target = %target, root = %root_oid_str, max_repititions))
+    )]
     pub async fn bulk_walk(
         &self,
         target: &str,
-        community: &str,
+        credentials: &Credentials,
         root_oid_str: &str,
         max_repititions: i32,
     ) -> Result<Vec<VarBind>> {
         let mut results = Vec::new();
+        self.bulk_walk_into(
+            target,
+            credentials,
+            root_oid_str,
+            max_repititions,
+            &mut results,
+        )
+        .await?;
+        Ok(results)
+    }
+
+    // bulk_walk, appending to `results` as batches arrive so a cancelled
+    // walk keeps what it got
+    async fn bulk_walk_into(
+        &self,
+        target: &str,
+        credentials: &Credentials,
+        root_oid_str: &str,
+        max_repititions: i32,
+        results: &mut Vec<VarBind>,
+    ) -> Result<()> {
+        if !self.supports_bulk(target) || matches!(credentials, Credentials::CommunityV1(_)) {
+            return self
+                .walk_into(target, credentials, root_oid_str, results)
+                .await;
+        }
+
         let root_oid = parse_oid_string(root_oid_str)?;
-        let mut current_oid_str = root_oid_str.to_string();
+        let mut current_oid = root_oid.clone();
+        let mut first_request = true;
+        let max_repititions = max_repititions.max(1);
+        let mut repetitions = self
+            .bulk_repetitions
+            .lock()
+            .unwrap()
+            .get(target)
+            .map_or(max_repititions, |&learned| learned.min(max_repititions));
+        // how far this walk may grow it again
+        let mut ceiling = max_repititions;
 
-        loop {
-            // call existing get_bulk function
-            let varbind_batch = self
-                .get_bulk(target, community, 0, max_repititions, &[&current_oid_str])
-                .await?;
+        'walk: loop {
+            let current_oid_str = current_oid
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(".");
+            let batch = self
+                .get_bulk_fitting(target, credentials, 0, repetitions, &[&current_oid_str])
+                .await
+                .and_then(|(batch, fitted)| {
+                    check_bulk_batch(&current_oid, batch, self.skip_non_increasing)
+                        .map(|batch| (batch, fitted))
+                });
+
+            if let Err(e) = &batch
+                && repetitions > 1
+                && asked_too_much(e, !first_request)
+            {
+                repetitions /= 2;
+                ceiling = repetitions;
+                continue;
+            }
+
+            // fall back on the first request, unless nothing is listening at all
+            if let Err(e) = &batch
+                && first_request
+                && ErrorClass::of(e) != ErrorClass::Refused
+            {
+                self.walk_into(target, credentials, root_oid_str, results)
+                    .await
+                    .map_err(|walk_error| {
+                        walk_error.context(format!("GETBULK failed too: {:#}", e))
+                    })?;
+                self.no_bulk.lock().unwrap().insert(target.to_string());
+                return Ok(());
+            }
+            let (varbind_batch, fitted) = batch?;
+            first_request = false;
+            if fitted < repetitions {
+                // get_bulk got tooBig asking for more
+                repetitions = fitted;
+                ceiling = fitted;
+            }
 
             if varbind_batch.is_empty() {
                 break;
             }
 
-            let mut last_oid_in_batch = None;
+            // a full batch may mean there is room for more; a short one
+            // that didn't reach the end is as much as the agent would send
+            // this time, which needn't stay so
+            let returned = varbind_batch.len() as i32;
             for varbind in varbind_batch {
                 match varbind.value {
                     ObjectSyntax::EndOfMib
                     | ObjectSyntax::NoSuchObject
                     | ObjectSyntax::NoSuchInstance => {
-                        return Ok(results);
+                        break 'walk;
                     }
                     _ => {}
                 }
 
                 if !is_in_subtree(&root_oid, &varbind.oid) {
-                    return Ok(results);
+                    break 'walk;
                 }
 
-                last_oid_in_batch = Some(varbind.oid.clone());
+                current_oid = varbind.oid.clone();
                 results.push(varbind);
             }
-
-            if let Some(last_oid) = last_oid_in_batch {
-                current_oid_str = last_oid
-                    .iter()
-                    .map(ToString::to_string)
-                    .collect::<Vec<_>>()
-                    .join(".");
+            if returned >= repetitions {
+                repetitions = repetitions.saturating_mul(2).min(ceiling);
             } else {
-                // if we get a batch then this should not be reached... safe exit
-                break;
+                repetitions = returned.max(1);
             }
         }
-        Ok(results)
+
+        self.bulk_repetitions
+            .lock()
+            .unwrap()
+            .insert(target.to_string(), repetitions);
+        Ok(())
+    }
+
+    /// Whether [`Manager::bulk_walk`] still uses GETBULK for `target`.
+    /// False once the target was seen rejecting it.
+    pub fn supports_bulk(&self, target: &str) -> bool {
+        !self.no_bulk.lock().unwrap().contains(target)
     }
 }
@@ -1,39 +1,309 @@
 use anyhow::Context;
 use anyhow::Result;
 use anyhow::anyhow;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 use std::time::Duration;
-use tokio::net::UdpSocket;
-use tokio::time::timeout;
+use tokio::io::Interest;
+use tokio::net::{UdpSocket, lookup_host};
 
-const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+use super::error::{MessageTooLargeError, TimeoutError};
+use tokio::time::{Instant, timeout, timeout_at};
 
-pub async fn send_and_receive(target_ip: &str, packet: &[u8]) -> Result<Vec<u8>> {
-    let socket = UdpSocket::bind("0.0.0.0:0")
+pub(super) const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub(super) const SNMP_PORT: u16 = 161;
+
+/// Where notification receivers listen (RFC 3417).
+pub const TRAP_PORT: u16 = 162;
+
+/// The largest response accepted unless configured otherwise.
+pub const MAX_RESPONSE_SIZE: usize = 4096;
+
+/// Which address to use when a host name resolves to both IPv4 and IPv6.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AddressFamilyPolicy {
+    /// Whatever the resolver lists first.
+    #[default]
+    Any,
+    PreferIpv4,
+    PreferIpv6,
+    /// Fail rather than fall back to IPv4.
+    RequireIpv6,
+}
+
+impl AddressFamilyPolicy {
+    /// Picks an address out of the resolver's answer, keeping its order
+    /// within a family.
+    pub fn select(&self, addresses: impl IntoIterator<Item = SocketAddr>) -> Option<SocketAddr> {
+        let addresses: Vec<SocketAddr> = addresses.into_iter().collect();
+        let first_v4 = addresses.iter().find(|a| a.is_ipv4()).copied();
+        let first_v6 = addresses.iter().find(|a| a.is_ipv6()).copied();
+        match self {
+            AddressFamilyPolicy::Any => addresses.first().copied(),
+            AddressFamilyPolicy::PreferIpv4 => first_v4.or(first_v6),
+            AddressFamilyPolicy::PreferIpv6 => first_v6.or(first_v4),
+            AddressFamilyPolicy::RequireIpv6 => first_v6,
+        }
+    }
+}
+
+/// Where requests leave from. The default lets the OS pick the address
+/// and interface from its routes; multi-homed pollers and management VRFs
+/// can pin either.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceBinding {
+    /// The local address to send from. Only targets of the same address
+    /// family can be reached.
+    pub address: Option<IpAddr>,
+    /// The network device, e.g. a VRF, to send through
+    /// (SO_BINDTODEVICE). Linux only, and needs CAP_NET_RAW.
+    pub device: Option<String>,
+}
+
+impl SourceBinding {
+    // a fresh socket for talking to `target_address`
+    pub(super) async fn bind(&self, target_address: SocketAddr) -> Result<UdpSocket> {
+        let local = match self.address {
+            Some(address) if address.is_ipv6() != target_address.is_ipv6() => {
+                return Err(anyhow!(
+                    "Source address {} can't reach {}",
+                    address,
+                    target_address
+                ));
+            }
+            Some(address) => SocketAddr::new(address, 0),
+            None if target_address.is_ipv6() => SocketAddr::new(Ipv6Addr::UNSPECIFIED.into(), 0),
+            None => SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), 0),
+        };
+        let socket = UdpSocket::bind(local)
+            .await
+            .with_context(|| format!("Failed to bind to local address {}", local))?;
+        if let Some(device) = &self.device {
+            bind_device(&socket, device)?;
+        }
+        Ok(socket)
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn bind_device(socket: &UdpSocket, device: &str) -> Result<()> {
+    socket
+        .bind_device(Some(device.as_bytes()))
+        .with_context(|| format!("Failed to bind to device {}", device))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn bind_device(_socket: &UdpSocket, device: &str) -> Result<()> {
+    Err(anyhow!(
+        "Can't bind to device {}: only supported on Linux",
+        device
+    ))
+}
+
+/// Splits a target into host and port. A port is given as `host:port`;
+/// IPv6 addresses need brackets for that, `[2001:db8::1]:1161`, since
+/// without them every colon belongs to the address.
+pub fn split_port(target: &str) -> Result<(&str, Option<u16>)> {
+    let parse_port = |port: &str| {
+        port.parse::<u16>()
+            .with_context(|| format!("Invalid port in target '{}'", target))
+    };
+    if let Some(bracketed) = target.strip_prefix('[') {
+        let (host, rest) = bracketed
+            .split_once(']')
+            .ok_or_else(|| anyhow!("Missing ']' in target '{}'", target))?;
+        return match rest {
+            "" => Ok((host, None)),
+            _ => match rest.strip_prefix(':') {
+                Some(port) => Ok((host, Some(parse_port(port)?))),
+                None => Err(anyhow!(
+                    "Unexpected '{}' after ']' in target '{}'",
+                    rest,
+                    target
+                )),
+            },
+        };
+    }
+    match target.split_once(':') {
+        Some((host, port)) if !port.contains(':') => Ok((host, Some(parse_port(port)?))),
+        _ => Ok((target, None)),
+    }
+}
+
+/// Resolves a target to an address, on `default_port` unless the target
+/// names its own.
+pub async fn resolve(
+    target: &str,
+    default_port: u16,
+    policy: AddressFamilyPolicy,
+) -> Result<SocketAddr> {
+    let (host, port) = split_port(target)?;
+    let addresses = lookup_host((host, port.unwrap_or(default_port)))
+        .await
+        .with_context(|| format!("Failed to resolve {}", target))?;
+    policy
+        .select(addresses)
+        .ok_or_else(|| anyhow!("{} did not resolve to any {:?} address", target, policy))
+}
+
+/// Sends a datagram that expects no reply, such as a trap.
+pub async fn send_only(target_address: SocketAddr, packet: &[u8]) -> Result<()> {
+    send_only_from(target_address, packet, &SourceBinding::default()).await
+}
+
+/// [`send_only`] from the given source.
+pub async fn send_only_from(
+    target_address: SocketAddr,
+    packet: &[u8],
+    source: &SourceBinding,
+) -> Result<()> {
+    let socket = source.bind(target_address).await?;
+    socket
+        .send_to(packet, target_address)
         .await
-        .context("Failed to bind to local sockert")?;
+        .with_context(|| format!("Failed to send packet to {}", target_address))?;
+    Ok(())
+}
 
-    let target_address = format!("{}:161", target_ip);
+/// Sends `packet` and waits up to `wait` for the reply.
+pub async fn send_and_receive(
+    target_address: SocketAddr,
+    packet: &[u8],
+    wait: Duration,
+) -> Result<Vec<u8>> {
+    send_and_receive_matching(target_address, packet, wait, |_| true).await
+}
 
-    let _ = socket
-        .connect(&target_address)
+/// Like [`send_and_receive`], but skips datagrams `accept` turns down,
+/// such as late answers to earlier requests, until one is accepted or
+/// `wait` is up.
+pub async fn send_and_receive_matching(
+    target_address: SocketAddr,
+    packet: &[u8],
+    wait: Duration,
+    accept: impl Fn(&[u8]) -> bool,
+) -> Result<Vec<u8>> {
+    let socket = connect(target_address).await?;
+    send_and_receive_on(&socket, packet, wait, MAX_RESPONSE_SIZE, accept).await
+}
+
+/// Sends `packet` to a broadcast or multicast address and collects every
+/// reply `accept` takes until `window` is up, with where each came from,
+/// in arrival order.
+pub async fn broadcast(
+    target_address: SocketAddr,
+    packet: &[u8],
+    window: Duration,
+    accept: impl Fn(&[u8]) -> bool,
+) -> Result<Vec<(SocketAddr, Vec<u8>)>> {
+    let socket = SourceBinding::default().bind(target_address).await?;
+    socket
+        .set_broadcast(true)
+        .context("Failed to enable broadcast")?;
+    socket
+        .send_to(packet, target_address)
         .await
-        .with_context(|| format!("Failed to connect to {} address", target_address));
+        .with_context(|| format!("Failed to send packet to {}", target_address))?;
+
+    let deadline = Instant::now() + window;
+    let mut replies = Vec::new();
+    let mut buf = vec![0; MAX_RESPONSE_SIZE];
+    while let Ok(received) = timeout_at(deadline, socket.recv_from(&mut buf)).await {
+        let (len, from) = received.context("Failed to receive data")?;
+        if accept(&buf[..len]) {
+            replies.push((from, buf[..len].to_vec()));
+        }
+    }
+    Ok(replies)
+}
 
+/// Binds a local socket and connects it to `target_address`, so it only
+/// hears from that agent.
+pub async fn connect(target_address: SocketAddr) -> Result<UdpSocket> {
+    connect_from(target_address, &SourceBinding::default()).await
+}
+
+/// [`connect`] from the given source.
+pub async fn connect_from(target_address: SocketAddr, source: &SourceBinding) -> Result<UdpSocket> {
+    let socket = source.bind(target_address).await?;
+    socket
+        .connect(target_address)
+        .await
+        .with_context(|| format!("Failed to connect to {} address", target_address))?;
+    Ok(socket)
+}
+
+/// [`send_and_receive_matching`] on a socket from [`connect`], which can
+/// be reused for any number of requests. A response longer than
+/// `max_size` is a [`MessageTooLargeError`].
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(level = "trace", skip_all, fields(peer = ?socket.peer_addr().ok(), bytes = packet.len()))
+)]
+pub async fn send_and_receive_on(
+    socket: &UdpSocket,
+    packet: &[u8],
+    wait: Duration,
+    max_size: usize,
+    accept: impl Fn(&[u8]) -> bool,
+) -> Result<Vec<u8>> {
+    let target_address = socket.peer_addr().context("Socket is not connected")?;
     socket.send(packet).await.context("Failed to send packet")?;
 
-    let mut response_buf = vec![0; 4096];
-    let result = timeout(DEFAULT_TIMEOUT, socket.recv(&mut response_buf)).await;
+    // one byte spare, so datagrams the kernel cut short can be told apart
+    let mut response_buf = vec![0; max_size + 1];
+    let receive = async {
+        loop {
+            let len = recv_connected(socket, &mut response_buf).await?;
+            if accept(&response_buf[..len.min(max_size)]) {
+                return io::Result::Ok(len);
+            }
+        }
+    };
+    let result = timeout(wait, receive).await;
 
     match result {
+        Ok(Ok(len)) if len > max_size => Err(MessageTooLargeError {
+            address: target_address,
+            max_size,
+        }
+        .into()),
         Ok(Ok(len)) => {
             response_buf.truncate(len);
             Ok(response_buf)
         }
         Ok(Err(e)) => Err(anyhow!(e).context("Failed to receive data")),
-        Err(_) => Err(anyhow!(
-            "Timeout: No response from {} after {}s",
-            target_address,
-            DEFAULT_TIMEOUT.as_secs()
-        )),
+        Err(_) => Err(TimeoutError {
+            address: target_address,
+            after: wait,
+        }
+        .into()),
+    }
+}
+
+/// Receives on a connected socket, also returning a pending socket error
+/// such as ICMP port unreachable; plain recv() is never woken for those.
+pub(crate) async fn recv_connected(socket: &UdpSocket, buf: &mut [u8]) -> io::Result<usize> {
+    loop {
+        let ready = socket.ready(Interest::READABLE | Interest::ERROR).await?;
+        if ready.is_error() {
+            // try_recv() only clears READABLE; a WouldBlock here clears
+            // ERROR too, or one refusal would poison the socket for good
+            let pending: io::Result<()> =
+                socket.try_io(Interest::ERROR, || match socket.take_error()? {
+                    Some(e) => Err(e),
+                    None => Err(io::ErrorKind::WouldBlock.into()),
+                });
+            if let Err(e) = pending
+                && e.kind() != io::ErrorKind::WouldBlock
+            {
+                return Err(e);
+            }
+        }
+        match socket.try_recv(buf) {
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => continue,
+            result => return result,
+        }
     }
 }
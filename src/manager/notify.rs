@@ -0,0 +1,163 @@
+// Notification originator: fire-and-forget SNMPv2-Traps and confirmed
+// InformRequests.
+
+use anyhow::{Result, anyhow};
+
+use super::{Credentials, ErrorClass, Expected, Manager, SnmpError, network, parse_oid_string};
+use crate::ber::Asn1Tag;
+use crate::snmp::message::{SnmpMessage, parse_message};
+use crate::snmp::pdu::{ErrorStatus, ObjectSyntax, Pdu, PduData, VarBind};
+
+// sysUpTime.0
+const SYS_UP_TIME: [u64; 9] = [1, 3, 6, 1, 2, 1, 1, 3, 0];
+// snmpTrapOID.0
+const SNMP_TRAP_OID: [u64; 11] = [1, 3, 6, 1, 6, 3, 1, 1, 4, 1, 0];
+
+// notifications are only sent as v2c so far
+fn v2c_community(credentials: &Credentials) -> Result<&str> {
+    match credentials {
+        Credentials::CommunityV2c(community) => Ok(community),
+        other => Err(anyhow!(
+            "Notifications need v2c credentials, got {:?}",
+            other
+        )),
+    }
+}
+
+/// The varbind list of an SNMPv2 notification (RFC 3416 section 4.2.6):
+/// sysUpTime.0 and snmpTrapOID.0 followed by the payload.
+pub fn notification_varbinds(
+    uptime: u32,
+    trap_oid: Vec<u64>,
+    payload: Vec<VarBind>,
+) -> Vec<VarBind> {
+    let mut varbinds = vec![
+        VarBind {
+            oid: SYS_UP_TIME.to_vec(),
+            value: ObjectSyntax::TimeTicks(uptime),
+        },
+        VarBind {
+            oid: SNMP_TRAP_OID.to_vec(),
+            value: ObjectSyntax::ObjectIdentifier(trap_oid),
+        },
+    ];
+    varbinds.extend(payload);
+    varbinds
+}
+
+impl Manager {
+    /// Sends an SNMPv2c trap to the notification receiver at `sink:port`.
+    /// `uptime` is in hundredths of a second. Traps are unconfirmed; use
+    /// [`Manager::inform`] when delivery matters.
+    pub async fn send_trap(
+        &self,
+        sink: &str,
+        port: u16,
+        credentials: &Credentials,
+        uptime: u32,
+        trap_oid_str: &str,
+        payload: Vec<VarBind>,
+    ) -> Result<()> {
+        let community = v2c_community(credentials)?;
+        let trap_oid = parse_oid_string(trap_oid_str)?;
+        let message = SnmpMessage {
+            version: 1,
+            community: community.as_bytes().to_vec(),
+            pdu: Pdu {
+                tag: Asn1Tag::SnmpV2Trap,
+                request_id: self.next_request_id(),
+                data: PduData::Basic {
+                    error_status: ErrorStatus::NoError,
+                    error_index: 0,
+                },
+                varbinds: notification_varbinds(uptime, trap_oid, payload),
+            },
+        };
+
+        let mut address = self.resolve(sink).await?;
+        address.set_port(port);
+        self.throttle(sink).await;
+        let wait = self.settings(sink).timeout;
+        self.transport
+            .send(address, &message.to_bytes(), wait)
+            .await
+    }
+
+    /// Sends an SNMPv2c InformRequest to the notification receiver on
+    /// `target`, port 162 unless the target names one, and waits for the
+    /// receiver to confirm it with a Response. Only a timeout resends it,
+    /// up to `retries` more times. The varbinds go out as given; by
+    /// convention they start with sysUpTime.0 and snmpTrapOID.0.
+    pub async fn inform(
+        &self,
+        target: &str,
+        credentials: &Credentials,
+        varbinds: Vec<VarBind>,
+        retries: u32,
+    ) -> Result<()> {
+        let community = v2c_community(credentials)?;
+        let message = SnmpMessage {
+            version: 1,
+            community: community.as_bytes().to_vec(),
+            pdu: Pdu {
+                tag: Asn1Tag::InformRequest,
+                request_id: self.next_request_id(),
+                data: PduData::Basic {
+                    error_status: ErrorStatus::NoError,
+                    error_index: 0,
+                },
+                varbinds,
+            },
+        };
+        let packet_bytes = message.to_bytes();
+
+        let mut last_error = None;
+        for attempt in 0..=retries {
+            if attempt > 0 {
+                self.note_retransmit(target);
+            }
+            let response_bytes = match self
+                .send_to_port(
+                    target,
+                    network::TRAP_PORT,
+                    &packet_bytes,
+                    Expected::community(1, community, message.pdu.request_id),
+                )
+                .await
+            {
+                Ok(bytes) => bytes,
+                // a refusal or a failed send won't change on resend
+                Err(e) if ErrorClass::of(&e) == ErrorClass::Timeout => {
+                    last_error = Some(e);
+                    continue;
+                }
+                Err(e) => return Err(e.context(format!("Inform to {} failed", target))),
+            };
+
+            let response = parse_message(&response_bytes)
+                .map_err(|e| anyhow!(e).context("Failed to parse inform response"))?;
+
+            if let PduData::Basic {
+                error_status,
+                error_index,
+            } = response.pdu.data
+                && error_status != ErrorStatus::NoError
+            {
+                return Err(SnmpError {
+                    status: error_status,
+                    index: error_index,
+                }
+                .into());
+            }
+            return Ok(());
+        }
+
+        Err(last_error
+            .unwrap_or_else(|| anyhow!("Inform was not acknowledged"))
+            .context(format!(
+                "Inform to {} not acknowledged after {} attempts",
+                target,
+                retries + 1
+            )))
+    }
+}
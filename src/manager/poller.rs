@@ -0,0 +1,199 @@
+// Periodic polling for collectors: each job runs on its own timer through
+// a shared Manager, and every result, failures included, is handed over
+// as it comes in. Jobs are spawned tasks, so a slow target never delays
+// the others.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use anyhow::{Result, bail};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
+
+use super::{BULK_WALK_MAX_REPETITIONS, Credentials, Manager};
+use crate::snmp::pdu::VarBind;
+
+/// Names a job added to a [`Poller`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct JobId(u64);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PollRequest {
+    /// The given OIDs in one GET.
+    Get(Vec<String>),
+    /// A subtree or table, walked with [`Manager::bulk_walk`].
+    Walk(String),
+}
+
+/// What to poll, where, and how often.
+#[derive(Debug, Clone)]
+pub struct PollJob {
+    target: String,
+    credentials: Credentials,
+    request: PollRequest,
+    interval: Duration,
+}
+
+impl PollJob {
+    /// Fails if `interval` is zero.
+    pub fn new(
+        target: impl Into<String>,
+        credentials: impl Into<Credentials>,
+        request: PollRequest,
+        interval: Duration,
+    ) -> Result<Self> {
+        if interval.is_zero() {
+            bail!("Poll interval must be non-zero");
+        }
+        Ok(Self {
+            target: target.into(),
+            credentials: credentials.into(),
+            request,
+            interval,
+        })
+    }
+
+    async fn run(&self, manager: &Manager) -> Result<Vec<VarBind>> {
+        match &self.request {
+            PollRequest::Get(oids) => {
+                let oids: Vec<&str> = oids.iter().map(String::as_str).collect();
+                manager
+                    .get_multi(&self.target, &self.credentials, &oids)
+                    .await
+            }
+            PollRequest::Walk(root) => {
+                manager
+                    .bulk_walk(
+                        &self.target,
+                        &self.credentials,
+                        root,
+                        BULK_WALK_MAX_REPETITIONS,
+                    )
+                    .await
+            }
+        }
+    }
+}
+
+/// One run of a job.
+#[derive(Debug)]
+pub struct PollResult {
+    pub job: JobId,
+    pub target: String,
+    /// When the run started.
+    pub at: SystemTime,
+    pub result: Result<Vec<VarBind>>,
+}
+
+#[derive(Clone)]
+enum Delivery {
+    Channel(mpsc::Sender<PollResult>),
+    Callback(Arc<dyn Fn(PollResult) + Send + Sync>),
+}
+
+/// Runs [`PollJob`]s on their intervals until they are removed or the
+/// poller is dropped. Jobs are spawned on the current tokio runtime.
+pub struct Poller {
+    manager: Arc<Manager>,
+    delivery: Delivery,
+    jobs: HashMap<JobId, JoinHandle<()>>,
+    next_id: u64,
+}
+
+impl fmt::Debug for Poller {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Poller")
+            .field("jobs", &self.jobs.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Poller {
+    /// A poller sending results into a channel that holds up to `buffer`
+    /// of them. While the channel is full, jobs wait to deliver instead of
+    /// polling again; once the receiver is dropped they stop.
+    pub fn new(manager: Arc<Manager>, buffer: usize) -> (Self, mpsc::Receiver<PollResult>) {
+        let (sender, receiver) = mpsc::channel(buffer.max(1));
+        (
+            Self::with_delivery(manager, Delivery::Channel(sender)),
+            receiver,
+        )
+    }
+
+    /// A poller calling `callback` with each result, on the job's task.
+    pub fn with_callback(
+        manager: Arc<Manager>,
+        callback: impl Fn(PollResult) + Send + Sync + 'static,
+    ) -> Self {
+        Self::with_delivery(manager, Delivery::Callback(Arc::new(callback)))
+    }
+
+    fn with_delivery(manager: Arc<Manager>, delivery: Delivery) -> Self {
+        Self {
+            manager,
+            delivery,
+            jobs: HashMap::new(),
+            next_id: 0,
+        }
+    }
+
+    /// Starts `job`, running it straight away and then every interval. A
+    /// run that overruns the interval delays the next rather than
+    /// bunching them up.
+    pub fn add(&mut self, job: PollJob) -> JobId {
+        let id = JobId(self.next_id);
+        self.next_id += 1;
+
+        let manager = Arc::clone(&self.manager);
+        let delivery = self.delivery.clone();
+        let task = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(job.interval);
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                let result = PollResult {
+                    job: id,
+                    target: job.target.clone(),
+                    at: SystemTime::now(),
+                    result: job.run(&manager).await,
+                };
+                match &delivery {
+                    Delivery::Channel(sender) => {
+                        if sender.send(result).await.is_err() {
+                            return;
+                        }
+                    }
+                    Delivery::Callback(callback) => callback(result),
+                }
+            }
+        });
+        self.jobs.insert(id, task);
+        id
+    }
+
+    /// Stops a job. False if it wasn't running.
+    pub fn remove(&mut self, id: JobId) -> bool {
+        match self.jobs.remove(&id) {
+            Some(task) => {
+                task.abort();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn jobs(&self) -> impl Iterator<Item = JobId> + '_ {
+        self.jobs.keys().copied()
+    }
+}
+
+impl Drop for Poller {
+    fn drop(&mut self) {
+        for task in self.jobs.values() {
+            task.abort();
+        }
+    }
+}
@@ -0,0 +1,85 @@
+// Binding a socket for every PDU costs syscalls and churns through
+// ephemeral ports when polling thousands of devices. Sockets connected to
+// an agent are kept after use and handed to the next request for it.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use anyhow::Result;
+use tokio::net::UdpSocket;
+
+use super::ErrorClass;
+use super::network::{self, SourceBinding};
+
+/// How many idle sockets a Manager keeps by default, over all targets.
+pub const DEFAULT_MAX_IDLE_SOCKETS: usize = 256;
+
+#[derive(Default)]
+struct Idle {
+    by_address: HashMap<SocketAddr, Vec<UdpSocket>>,
+    count: usize,
+}
+
+// Each socket serves one request at a time; concurrent requests to the
+// same agent take separate sockets.
+pub(super) struct SocketPool {
+    idle: Mutex<Idle>,
+    max_idle: usize,
+    // where new sockets are bound
+    pub(super) source: SourceBinding,
+}
+
+impl SocketPool {
+    pub(super) fn new(max_idle: usize, source: SourceBinding) -> Self {
+        Self {
+            idle: Mutex::new(Idle::default()),
+            max_idle,
+            source,
+        }
+    }
+
+    async fn take(&self, address: SocketAddr) -> Result<UdpSocket> {
+        let reused = {
+            let mut idle = self.idle.lock().unwrap();
+            let socket = idle.by_address.get_mut(&address).and_then(Vec::pop);
+            if socket.is_some() {
+                idle.count -= 1;
+            }
+            socket
+        };
+        match reused {
+            Some(socket) => Ok(socket),
+            None => network::connect_from(address, &self.source).await,
+        }
+    }
+
+    fn put(&self, address: SocketAddr, socket: UdpSocket) {
+        let mut idle = self.idle.lock().unwrap();
+        if idle.count < self.max_idle {
+            idle.by_address.entry(address).or_default().push(socket);
+            idle.count += 1;
+        }
+    }
+
+    // sends on an idle socket for `address`, keeping it for reuse after
+    pub(super) async fn round_trip(
+        &self,
+        address: SocketAddr,
+        packet: &[u8],
+        wait: Duration,
+        max_size: usize,
+        accept: impl Fn(&[u8]) -> bool,
+    ) -> Result<Vec<u8>> {
+        let socket = self.take(address).await?;
+        let result = network::send_and_receive_on(&socket, packet, wait, max_size, accept).await;
+        // a late answer still queued on a timed-out socket is skipped by
+        // request-id; after other errors the socket may be in a bad state
+        match &result {
+            Err(e) if ErrorClass::of(e) != ErrorClass::Timeout => {}
+            _ => self.put(address, socket),
+        }
+        result
+    }
+}
@@ -0,0 +1,143 @@
+// Reachability pre-check, so dead hosts in a multi-target run are marked in
+// well under a second instead of each costing a full SNMP timeout.
+
+use std::io::ErrorKind;
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+use anyhow::{Context, Result, anyhow};
+use socket2::{Domain, Protocol, Socket, Type};
+use tokio::net::UdpSocket;
+use tokio::time::timeout;
+
+use super::{Manager, network};
+
+// traceroute's first port; nothing should be listening there
+const UDP_PROBE_PORT: u16 = 33434;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProbeMethod {
+    /// ICMP echo over an unprivileged ping socket. On Linux the user's group
+    /// must be in `net.ipv4.ping_group_range`.
+    Icmp,
+    /// A datagram to a closed port; the ICMP port unreachable it provokes
+    /// proves the host is up. Hosts behind a dropping firewall look down.
+    Udp,
+}
+
+impl Manager {
+    /// Checks whether `target` answers at all, waiting at most `wait`.
+    /// `Ok(false)` means no answer; errors are reserved for probes that
+    /// could not be sent, e.g. a missing ping socket permission.
+    pub async fn is_reachable(
+        &self,
+        target: &str,
+        method: ProbeMethod,
+        wait: Duration,
+    ) -> Result<bool> {
+        let address = self.resolve(target).await?;
+        let probe = async {
+            match method {
+                ProbeMethod::Icmp => icmp_probe(address.ip()).await,
+                ProbeMethod::Udp => udp_probe(address.ip()).await,
+            }
+        };
+        timeout(wait, probe).await.unwrap_or(Ok(false))
+    }
+}
+
+async fn icmp_probe(ip: IpAddr) -> Result<bool> {
+    let (domain, protocol, request_type, reply_type) = match ip {
+        IpAddr::V4(_) => (Domain::IPV4, Protocol::ICMPV4, 8, 0),
+        IpAddr::V6(_) => (Domain::IPV6, Protocol::ICMPV6, 128, 129),
+    };
+    let socket = Socket::new(domain, Type::DGRAM, Some(protocol))
+        .context("Failed to open ICMP socket (are ping sockets allowed?)")?;
+    socket.set_nonblocking(true)?;
+    let socket = UdpSocket::from_std(socket.into())?;
+    socket.connect(SocketAddr::new(ip, 0)).await?;
+
+    // the kernel fills in the identifier for ping sockets
+    let mut request = vec![request_type, 0, 0, 0, 0, 0, 0, 1];
+    request.extend_from_slice(b"rusnmp");
+    let checksum = icmp_checksum(&request);
+    request[2..4].copy_from_slice(&checksum.to_be_bytes());
+    socket.send(&request).await?;
+
+    let mut buf = [0u8; 64];
+    loop {
+        let len = socket.recv(&mut buf).await?;
+        if len > 0 && buf[0] == reply_type {
+            return Ok(true);
+        }
+    }
+}
+
+async fn udp_probe(ip: IpAddr) -> Result<bool> {
+    let local = if ip.is_ipv6() { "[::]:0" } else { "0.0.0.0:0" };
+    let socket = UdpSocket::bind(local).await?;
+    socket.connect(SocketAddr::new(ip, UDP_PROBE_PORT)).await?;
+    socket.send(&[0]).await?;
+
+    let mut buf = [0u8; 16];
+    match network::recv_connected(&socket, &mut buf).await {
+        Ok(_) => Ok(true),
+        Err(e) => classify_udp_error(e),
+    }
+}
+
+fn classify_udp_error(e: std::io::Error) -> Result<bool> {
+    match e.kind() {
+        ErrorKind::ConnectionRefused => Ok(true),
+        ErrorKind::HostUnreachable | ErrorKind::NetworkUnreachable => Ok(false),
+        _ => Err(anyhow!(e).context("UDP probe failed")),
+    }
+}
+
+// RFC 1071 internet checksum
+fn icmp_checksum(data: &[u8]) -> u16 {
+    let mut sum: u32 = data
+        .chunks(2)
+        .map(|pair| match pair {
+            [hi, lo] => u16::from_be_bytes([*hi, *lo]) as u32,
+            [hi] => (*hi as u32) << 8,
+            _ => 0,
+        })
+        .sum();
+    while sum > 0xFFFF {
+        sum = (sum & 0xFFFF) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Error, ErrorKind};
+
+    use super::{classify_udp_error, icmp_checksum};
+
+    #[test]
+    fn test_icmp_checksum() {
+        // the example in RFC 1071, section 3
+        let data = [0x00, 0x01, 0xf2, 0x03, 0xf4, 0xf5, 0xf6, 0xf7];
+        assert_eq!(icmp_checksum(&data), !0xddf2);
+        // an odd byte is padded with zero
+        assert_eq!(icmp_checksum(&[0x01]), !0x0100);
+
+        // and a packet carrying its checksum sums to zero
+        let mut request = vec![8, 0, 0, 0, 0, 0, 0, 1];
+        request.extend_from_slice(b"rusnmp");
+        let checksum = icmp_checksum(&request);
+        request[2..4].copy_from_slice(&checksum.to_be_bytes());
+        assert_eq!(icmp_checksum(&request), 0);
+    }
+
+    #[test]
+    fn test_classify_udp_error() {
+        let classify = |kind| classify_udp_error(Error::from(kind));
+        assert!(classify(ErrorKind::ConnectionRefused).unwrap());
+        assert!(!classify(ErrorKind::HostUnreachable).unwrap());
+        assert!(!classify(ErrorKind::NetworkUnreachable).unwrap());
+        assert!(classify(ErrorKind::PermissionDenied).is_err());
+    }
+}
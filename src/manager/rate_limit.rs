@@ -0,0 +1,64 @@
+// Token buckets pacing what the Manager puts on the wire, so mass polling
+// doesn't flood low-end agents or trip control-plane policers. Each packet
+// takes a token and tokens come back at the configured rate, up to one
+// second's worth.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use super::Manager;
+
+#[derive(Debug)]
+pub(super) struct TokenBucket {
+    per_second: f64,
+    state: Mutex<BucketState>,
+}
+
+#[derive(Debug)]
+struct BucketState {
+    // negative when packets are queued up waiting for tokens
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl TokenBucket {
+    pub(super) fn new(per_second: f64) -> Self {
+        Self {
+            per_second,
+            state: Mutex::new(BucketState {
+                tokens: per_second.max(1.0),
+                refilled: Instant::now(),
+            }),
+        }
+    }
+
+    // takes a token, returning how long to wait before it may be used
+    fn reserve(&self) -> Duration {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+        let refill = now.duration_since(state.refilled).as_secs_f64() * self.per_second;
+        state.tokens = (state.tokens + refill).min(self.per_second.max(1.0));
+        state.refilled = now;
+        state.tokens -= 1.0;
+        if state.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-state.tokens / self.per_second)
+        }
+    }
+}
+
+impl Manager {
+    // waits for a token from the Manager's bucket and the target's
+    pub(super) async fn throttle(&self, target: &str) {
+        let global = self.rate_limit.as_ref().map(TokenBucket::reserve);
+        let own = self
+            .target_rate_limits
+            .get(target)
+            .map(TokenBucket::reserve);
+        let wait = global.max(own).unwrap_or_default();
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
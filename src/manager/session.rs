@@ -0,0 +1,176 @@
+// Walks issue hundreds of requests to one agent. Binding a socket for each
+// of them costs a syscall round and a fresh ephemeral port every time, so a
+// session keeps one connected socket for its target.
+
+use std::net::SocketAddr;
+
+use anyhow::Result;
+use tokio::net::UdpSocket;
+use tokio::sync::Mutex;
+
+use super::network::{self, SourceBinding};
+use super::target::Settings;
+use super::{Credentials, Manager, TransportStats};
+use crate::snmp::pdu::{ObjectSyntax, VarBind};
+
+pub(super) struct PinnedSocket {
+    address: SocketAddr,
+    socket: Mutex<UdpSocket>,
+}
+
+/// A [`Manager`] tied to one target: every request goes out on the same
+/// socket with the same credentials and request-id counter. Requests on
+/// a session are sent one at a time; use separate sessions, or a plain
+/// `Manager`, to talk to a target in parallel.
+pub struct Session {
+    manager: Manager,
+    target: String,
+    credentials: Credentials,
+}
+
+impl Session {
+    /// Opens a session with the default timeout, retries and port.
+    pub async fn connect(target: impl Into<String>, credentials: Credentials) -> Result<Self> {
+        Self::open(
+            Manager::new(),
+            target.into(),
+            credentials,
+            Some(SourceBinding::default()),
+        )
+        .await
+    }
+
+    // the session's socket is bound to `pin`; None for Managers with
+    // their own Transport, which must carry the session's requests too
+    pub(super) async fn open(
+        mut manager: Manager,
+        target: String,
+        credentials: Credentials,
+        pin: Option<SourceBinding>,
+    ) -> Result<Self> {
+        if let Some(source) = pin {
+            let address = manager.resolve(&target).await?;
+            manager.pinned = Some(PinnedSocket {
+                address,
+                socket: Mutex::new(network::connect_from(address, &source).await?),
+            });
+        }
+        Ok(Self {
+            manager,
+            target,
+            credentials,
+        })
+    }
+
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+
+    pub fn credentials(&self) -> &Credentials {
+        &self.credentials
+    }
+
+    /// Transport counters for everything sent on this session.
+    pub fn stats(&self) -> TransportStats {
+        self.manager.stats(&self.target).unwrap_or_default()
+    }
+
+    pub async fn get(&self, oid_str: &str) -> Result<VarBind> {
+        self.manager
+            .get(&self.target, &self.credentials, oid_str)
+            .await
+    }
+
+    pub async fn get_next(&self, oid_strs: &[&str]) -> Result<Vec<VarBind>> {
+        self.manager
+            .get_next(&self.target, &self.credentials, oid_strs)
+            .await
+    }
+
+    pub async fn set(&self, oid_str: &str, value: ObjectSyntax) -> Result<VarBind> {
+        self.manager
+            .set(&self.target, &self.credentials, oid_str, value)
+            .await
+    }
+
+    pub async fn set_multi(&self, bindings: &[(&str, ObjectSyntax)]) -> Result<Vec<VarBind>> {
+        self.manager
+            .set_multi(&self.target, &self.credentials, bindings)
+            .await
+    }
+
+    pub async fn walk(&self, root_oid_str: &str) -> Result<Vec<VarBind>> {
+        self.manager
+            .walk(&self.target, &self.credentials, root_oid_str)
+            .await
+    }
+
+    pub async fn get_bulk(
+        &self,
+        non_repeaters: i32,
+        max_repetitions: i32,
+        oid_strs: &[&str],
+    ) -> Result<Vec<VarBind>> {
+        self.manager
+            .get_bulk(
+                &self.target,
+                &self.credentials,
+                non_repeaters,
+                max_repetitions,
+                oid_strs,
+            )
+            .await
+    }
+
+    pub async fn bulk_walk(
+        &self,
+        root_oid_str: &str,
+        max_repetitions: i32,
+    ) -> Result<Vec<VarBind>> {
+        self.manager
+            .bulk_walk(
+                &self.target,
+                &self.credentials,
+                root_oid_str,
+                max_repetitions,
+            )
+            .await
+    }
+}
+
+impl Manager {
+    // a session's own socket for its target, the transport for anything else
+    pub(super) async fn round_trip(
+        &self,
+        address: SocketAddr,
+        packet: &[u8],
+        settings: Settings,
+        accept: impl Fn(&[u8]) -> bool + Send + Sync,
+    ) -> Result<Vec<u8>> {
+        match &self.pinned {
+            Some(pinned) if pinned.address == address => {
+                // concurrent requests would read each other's answers
+                let socket = pinned.socket.lock().await;
+                network::send_and_receive_on(
+                    &socket,
+                    packet,
+                    settings.timeout,
+                    settings.max_message_size,
+                    accept,
+                )
+                .await
+            }
+            _ => {
+                self.transport
+                    .send_recv(
+                        address,
+                        packet,
+                        settings.timeout,
+                        settings.max_message_size,
+                        &accept,
+                    )
+                    .await
+            }
+        }
+    }
+}
@@ -0,0 +1,42 @@
+// Guards against writing to objects automation should never touch, e.g.
+// reboot triggers or config-copy tables.
+
+use anyhow::Result;
+
+use super::parse_oid_string;
+
+/// Which OID subtrees the Manager may send SETs to. A binding is refused if
+/// it falls under a denied prefix, or if allowed prefixes are given and it
+/// falls under none of them. The default allows everything.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SetPolicy {
+    allowed: Vec<Vec<u64>>,
+    denied: Vec<Vec<u64>>,
+}
+
+impl SetPolicy {
+    pub fn allow_all() -> Self {
+        Self::default()
+    }
+
+    /// Restricts SETs to the subtree under `prefix`, in addition to any
+    /// subtrees allowed before.
+    pub fn allow(mut self, prefix: &str) -> Result<Self> {
+        self.allowed.push(parse_oid_string(prefix)?);
+        Ok(self)
+    }
+
+    /// Refuses SETs to the subtree under `prefix`, even inside an allowed
+    /// subtree.
+    pub fn deny(mut self, prefix: &str) -> Result<Self> {
+        self.denied.push(parse_oid_string(prefix)?);
+        Ok(self)
+    }
+
+    pub fn permits(&self, oid: &[u64]) -> bool {
+        if self.denied.iter().any(|prefix| oid.starts_with(prefix)) {
+            return false;
+        }
+        self.allowed.is_empty() || self.allowed.iter().any(|prefix| oid.starts_with(prefix))
+    }
+}
@@ -0,0 +1,214 @@
+// Polling a fleet with a socket per request in flight runs into the file
+// descriptor limit long before the network is busy. Here every request
+// goes out on one unconnected socket per address family, and a dispatcher
+// task hands each datagram to the request waiting for its agent and
+// request-id.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+
+use anyhow::{Context, Result, anyhow};
+use futures::FutureExt;
+use futures::future::BoxFuture;
+use tokio::net::UdpSocket;
+use tokio::sync::{OnceCell, mpsc};
+use tokio::task::AbortHandle;
+use tokio::time::timeout;
+
+use super::error::{MessageTooLargeError, TimeoutError};
+use super::network::SourceBinding;
+use super::transport::Transport;
+use crate::snmp::message::peek_request_id;
+
+// the largest UDP payload, so the kernel never cuts a datagram short
+const MAX_DATAGRAM: usize = 65536;
+
+// requests waiting for an answer, by agent and request-id (msgID for v3)
+type Pending = Mutex<HashMap<(SocketAddr, i32), mpsc::UnboundedSender<Vec<u8>>>>;
+
+/// A [`Transport`] sending every request on one shared UDP socket per
+/// address family, however many are in flight. Responses are routed to
+/// their request by source address and request-id, so the Manager's
+/// request-ids must not be reused while a request is waiting.
+///
+/// An unconnected socket isn't told about ICMP port unreachable, so
+/// agents that refuse show up as timeouts.
+pub struct SharedSocketTransport {
+    source: SourceBinding,
+    v4: OnceCell<Arc<UdpSocket>>,
+    v6: OnceCell<Arc<UdpSocket>>,
+    pending: Arc<Pending>,
+    dispatchers: Mutex<Vec<AbortHandle>>,
+}
+
+impl Default for SharedSocketTransport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SharedSocketTransport {
+    pub fn new() -> Self {
+        Self {
+            source: SourceBinding::default(),
+            v4: OnceCell::new(),
+            v6: OnceCell::new(),
+            pending: Arc::new(Mutex::new(HashMap::new())),
+            dispatchers: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Binds the shared sockets to `source` instead of letting the OS
+    /// choose.
+    pub fn with_source(mut self, source: SourceBinding) -> Self {
+        self.source = source;
+        self
+    }
+
+    /// How many requests are waiting for their response.
+    pub fn in_flight(&self) -> usize {
+        self.pending.lock().unwrap().len()
+    }
+
+    // bound, with its dispatcher running, on first use
+    async fn socket(&self, address: SocketAddr) -> Result<&Arc<UdpSocket>> {
+        let cell = if address.is_ipv6() {
+            &self.v6
+        } else {
+            &self.v4
+        };
+        cell.get_or_try_init(|| async {
+            let socket = Arc::new(self.source.bind(address).await?);
+            let dispatcher = tokio::spawn(dispatch(socket.clone(), Arc::downgrade(&self.pending)));
+            self.dispatchers
+                .lock()
+                .unwrap()
+                .push(dispatcher.abort_handle());
+            Ok(socket)
+        })
+        .await
+    }
+}
+
+impl Drop for SharedSocketTransport {
+    fn drop(&mut self) {
+        for dispatcher in self.dispatchers.lock().unwrap().drain(..) {
+            dispatcher.abort();
+        }
+    }
+}
+
+// takes the request out of the pending map however its future ends
+struct Registration<'a> {
+    pending: &'a Pending,
+    key: (SocketAddr, i32),
+}
+
+impl Drop for Registration<'_> {
+    fn drop(&mut self) {
+        self.pending.lock().unwrap().remove(&self.key);
+    }
+}
+
+impl Transport for SharedSocketTransport {
+    fn send_recv<'a>(
+        &'a self,
+        address: SocketAddr,
+        packet: &'a [u8],
+        wait: Duration,
+        max_size: usize,
+        accept: &'a (dyn Fn(&[u8]) -> bool + Send + Sync),
+    ) -> BoxFuture<'a, Result<Vec<u8>>> {
+        async move {
+            let request_id = peek_request_id(packet)
+                .map_err(|e| anyhow!(e).context("Can't route a request without a request-id"))?;
+            let socket = self.socket(address).await?;
+
+            let key = (address, request_id);
+            let (sender, mut responses) = mpsc::unbounded_channel();
+            let _registration = {
+                let mut pending = self.pending.lock().unwrap();
+                if pending.contains_key(&key) {
+                    return Err(anyhow!(
+                        "Request-id {} to {} is already in flight",
+                        request_id,
+                        address
+                    ));
+                }
+                pending.insert(key, sender);
+                Registration {
+                    pending: &self.pending,
+                    key,
+                }
+            };
+
+            socket
+                .send_to(packet, address)
+                .await
+                .with_context(|| format!("Failed to send packet to {}", address))?;
+            let receive = async {
+                // the sender stays in the map until the registration drops
+                while let Some(response) = responses.recv().await {
+                    if !accept(&response[..response.len().min(max_size)]) {
+                        continue;
+                    }
+                    if response.len() > max_size {
+                        return Err(MessageTooLargeError { address, max_size }.into());
+                    }
+                    return Ok(response);
+                }
+                Err(anyhow!("Dispatcher for {} stopped", address))
+            };
+            match timeout(wait, receive).await {
+                Ok(result) => result,
+                Err(_) => Err(TimeoutError {
+                    address,
+                    after: wait,
+                }
+                .into()),
+            }
+        }
+        .boxed()
+    }
+
+    fn send<'a>(
+        &'a self,
+        address: SocketAddr,
+        packet: &'a [u8],
+        _wait: Duration,
+    ) -> BoxFuture<'a, Result<()>> {
+        async move {
+            self.socket(address)
+                .await?
+                .send_to(packet, address)
+                .await
+                .with_context(|| format!("Failed to send packet to {}", address))?;
+            Ok(())
+        }
+        .boxed()
+    }
+}
+
+// runs until the transport is dropped; datagrams nobody waits for, and
+// ones without a readable request-id, are dropped
+async fn dispatch(socket: Arc<UdpSocket>, pending: Weak<Pending>) {
+    let mut buf = vec![0; MAX_DATAGRAM];
+    loop {
+        // errors here belong to no request in particular, e.g. Windows
+        // reporting an earlier ICMP unreachable
+        let Ok((len, from)) = socket.recv_from(&mut buf).await else {
+            continue;
+        };
+        let Some(pending) = pending.upgrade() else {
+            return;
+        };
+        let Ok(request_id) = peek_request_id(&buf[..len]) else {
+            continue;
+        };
+        if let Some(waiting) = pending.lock().unwrap().get(&(from, request_id)) {
+            let _ = waiting.send(buf[..len].to_vec());
+        }
+    }
+}
@@ -0,0 +1,153 @@
+// Per-target transport counters, updated on every request/response
+// exchange. They show how a run actually went on the wire: how much was
+// sent, how long agents took to answer and how often we had to ask twice.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+
+use super::{ErrorClass, Expected, Manager};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TransportStats {
+    pub packets_sent: u64,
+    pub packets_received: u64,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    /// Requests that were sent again after an unanswered attempt.
+    pub retransmits: u64,
+    pub timeouts: u64,
+    /// Requests that got no usable response, after any retries: timed
+    /// out, refused, undecodable or rejected by v3 security. Agents
+    /// answering with an error-status don't count.
+    pub failures: u64,
+    /// Responses that could not be decoded or authenticated.
+    pub decode_errors: u64,
+    /// Summed round-trip time of the answered requests.
+    pub total_rtt: Duration,
+}
+
+impl TransportStats {
+    pub fn average_rtt(&self) -> Option<Duration> {
+        (self.packets_received > 0).then(|| self.total_rtt / self.packets_received as u32)
+    }
+
+    /// Share of sent packets that were retransmits, 0 to 100.
+    pub fn retransmit_percent(&self) -> f64 {
+        if self.packets_sent == 0 {
+            return 0.0;
+        }
+        self.retransmits as f64 * 100.0 / self.packets_sent as f64
+    }
+
+    fn add(&mut self, other: &TransportStats) {
+        self.packets_sent += other.packets_sent;
+        self.packets_received += other.packets_received;
+        self.bytes_sent += other.bytes_sent;
+        self.bytes_received += other.bytes_received;
+        self.retransmits += other.retransmits;
+        self.timeouts += other.timeouts;
+        self.failures += other.failures;
+        self.decode_errors += other.decode_errors;
+        self.total_rtt += other.total_rtt;
+    }
+}
+
+impl Manager {
+    /// Counters for one target, if anything was sent to it.
+    pub fn stats(&self, target: &str) -> Option<TransportStats> {
+        self.stats.lock().unwrap().get(target).cloned()
+    }
+
+    /// A copy of every target's counters, keyed by target.
+    pub fn stats_snapshot(&self) -> HashMap<String, TransportStats> {
+        self.stats.lock().unwrap().clone()
+    }
+
+    /// Counters summed over every target.
+    pub fn total_stats(&self) -> TransportStats {
+        let mut total = TransportStats::default();
+        for stats in self.stats.lock().unwrap().values() {
+            total.add(stats);
+        }
+        total
+    }
+
+    /// Up to `count` targets by average round-trip time, slowest first.
+    /// Targets that never answered are left out.
+    pub fn slowest_targets(&self, count: usize) -> Vec<(String, Duration)> {
+        let mut targets: Vec<(String, Duration)> = self
+            .stats
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|(target, stats)| Some((target.clone(), stats.average_rtt()?)))
+            .collect();
+        targets.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        targets.truncate(count);
+        targets
+    }
+
+    pub(super) fn note_failure(&self, target: &str, error: &anyhow::Error) {
+        let mut stats = self.stats.lock().unwrap();
+        let stats = stats.entry(target.to_string()).or_default();
+        stats.failures += 1;
+        if ErrorClass::of(error) == ErrorClass::Parse {
+            stats.decode_errors += 1;
+        }
+    }
+
+    pub(super) fn note_retransmit(&self, target: &str) {
+        let mut stats = self.stats.lock().unwrap();
+        stats.entry(target.to_string()).or_default().retransmits += 1;
+    }
+
+    // sends and waits for the expected answer, counting both
+    pub(super) async fn exchange(
+        &self,
+        target: &str,
+        address: SocketAddr,
+        packet: &[u8],
+        expected: Expected<'_>,
+    ) -> Result<Vec<u8>> {
+        self.throttle(target).await;
+        let answers = move |response: &[u8]| expected.matches(response);
+        let started = Instant::now();
+        let settings = self.settings(target);
+        let result = self.round_trip(address, packet, settings, answers).await;
+        let elapsed = started.elapsed();
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            target = %target,
+            %address,
+            bytes = packet.len(),
+            rtt = ?elapsed,
+            answered = result.is_ok(),
+            "round trip"
+        );
+
+        // the target may have moved; resolve it afresh next time
+        if let Err(e) = &result
+            && matches!(ErrorClass::of(e), ErrorClass::Timeout | ErrorClass::Refused)
+        {
+            self.addresses.lock().unwrap().remove(target);
+        }
+
+        let mut stats = self.stats.lock().unwrap();
+        let stats = stats.entry(target.to_string()).or_default();
+        stats.packets_sent += 1;
+        stats.bytes_sent += packet.len() as u64;
+        match &result {
+            Ok(response) => {
+                stats.packets_received += 1;
+                stats.bytes_received += response.len() as u64;
+                stats.total_rtt += elapsed;
+            }
+            Err(e) if ErrorClass::of(e) == ErrorClass::Timeout => stats.timeouts += 1,
+            Err(_) => {}
+        }
+        result
+    }
+}
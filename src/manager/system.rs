@@ -0,0 +1,66 @@
+// The system group (RFC 3418) is the first thing anyone asks a new device
+// for: what it is, how long it has been up and who looks after it.
+
+use std::time::Duration;
+
+use anyhow::Result;
+
+use super::{Credentials, Manager};
+use crate::snmp::pdu::ObjectSyntax;
+
+const SYSTEM_OIDS: [&str; 6] = [
+    "1.3.6.1.2.1.1.1.0", // sysDescr
+    "1.3.6.1.2.1.1.2.0", // sysObjectID
+    "1.3.6.1.2.1.1.3.0", // sysUpTime
+    "1.3.6.1.2.1.1.4.0", // sysContact
+    "1.3.6.1.2.1.1.5.0", // sysName
+    "1.3.6.1.2.1.1.6.0", // sysLocation
+];
+
+/// The scalars of the system group. Each is `None` if the agent doesn't
+/// have it or answered with an unexpected type. Strings that aren't
+/// UTF-8 are converted lossily.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SystemInfo {
+    pub description: Option<String>,
+    pub object_id: Option<Vec<u64>>,
+    pub uptime: Option<Duration>,
+    pub contact: Option<String>,
+    pub name: Option<String>,
+    pub location: Option<String>,
+}
+
+impl Manager {
+    /// Fetches the system group in one GET.
+    pub async fn get_system_info(
+        &self,
+        target: &str,
+        credentials: &Credentials,
+    ) -> Result<SystemInfo> {
+        let varbinds = self.get_multi(target, credentials, &SYSTEM_OIDS).await?;
+        let mut values = varbinds.into_iter().map(|varbind| varbind.value);
+        let mut next = || values.next().unwrap_or(ObjectSyntax::Null);
+
+        Ok(SystemInfo {
+            description: text(&next()),
+            object_id: next().as_oid().map(<[u64]>::to_vec),
+            uptime: match next() {
+                ObjectSyntax::TimeTicks(ticks) => {
+                    Some(Duration::from_millis(u64::from(ticks) * 10))
+                }
+                _ => None,
+            },
+            contact: text(&next()),
+            name: text(&next()),
+            location: text(&next()),
+        })
+    }
+}
+
+// an OCTET STRING as text, lossily if it isn't UTF-8
+pub(super) fn text(value: &ObjectSyntax) -> Option<String> {
+    match value {
+        ObjectSyntax::OctetString(bytes) => Some(String::from_utf8_lossy(bytes).into_owned()),
+        _ => None,
+    }
+}
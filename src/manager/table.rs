@@ -0,0 +1,318 @@
+// Tables come back from a walk column by column, one varbind per cell.
+// Pivoting them into rows is what nearly every caller wants, so it lives
+// here once rather than in each of them.
+
+use std::collections::BTreeMap;
+
+use anyhow::{Result, anyhow};
+
+use super::{
+    BULK_WALK_MAX_REPETITIONS, Credentials, ErrorClass, Manager, check_increasing, is_in_subtree,
+    parse_oid_string,
+};
+use crate::snmp::pdu::{ObjectSyntax, VarBind};
+
+/// Rows of a table keyed by their index, each holding its cells keyed by
+/// column number.
+pub type TableRows = BTreeMap<Vec<u64>, BTreeMap<u64, ObjectSyntax>>;
+
+/// Rows of a table with their index decoded by [`decode_index`], in index
+/// order.
+pub type IndexedRows = Vec<(Vec<ObjectSyntax>, BTreeMap<u64, ObjectSyntax>)>;
+
+/// The syntax of one object in a table's INDEX clause, which says how its
+/// value is spelled out in a row's instance arcs (RFC 2578 section 7.7).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexSyntax {
+    /// INTEGER or Integer32: one arc.
+    Integer,
+    /// Unsigned32 or Gauge32: one arc.
+    Unsigned,
+    /// A length arc, then an arc per octet.
+    OctetString,
+    /// An OCTET STRING of `SIZE (n)`: an arc per octet, no length.
+    FixedString(usize),
+    /// Four arcs.
+    IpAddress,
+    /// A length arc, then the OID's own arcs.
+    ObjectIdentifier,
+    /// A string last in an INDEX marked IMPLIED: no length, it runs to the
+    /// end.
+    ImpliedOctetString,
+    /// An OID last in an INDEX marked IMPLIED.
+    ImpliedObjectIdentifier,
+}
+
+// per column, for the fixed-size GetBulks of get_table_columns
+const TABLE_MAX_REPETITIONS: i32 = 10;
+
+impl Manager {
+    /// Walks the table at `table_oid_str` (the table itself, not its entry)
+    /// and pivots the cells into rows. Rows missing a column simply have no
+    /// cell for it. Anything under the table that isn't an entry cell is
+    /// left out.
+    pub async fn get_table(
+        &self,
+        target: &str,
+        credentials: &Credentials,
+        table_oid_str: &str,
+    ) -> Result<TableRows> {
+        let table = parse_oid_string(table_oid_str)?;
+        let cells = self
+            .bulk_walk(
+                target,
+                credentials,
+                table_oid_str,
+                BULK_WALK_MAX_REPETITIONS,
+            )
+            .await?;
+
+        let mut rows = TableRows::new();
+        for cell in cells {
+            insert_cell(&mut rows, table.len(), cell);
+        }
+        Ok(rows)
+    }
+
+    /// Like [`Manager::get_table`], but decodes each row's index into the
+    /// values of its INDEX objects, whose syntax `index` gives in order.
+    /// Fails on a row whose index doesn't fit.
+    pub async fn get_table_indexed(
+        &self,
+        target: &str,
+        credentials: &Credentials,
+        table_oid_str: &str,
+        index: &[IndexSyntax],
+    ) -> Result<IndexedRows> {
+        let rows = self.get_table(target, credentials, table_oid_str).await?;
+        rows.into_iter()
+            .map(|(arcs, row)| {
+                let values = decode_index(&arcs, index).ok_or_else(|| {
+                    anyhow!(
+                        "Row index {} of {} doesn't match the INDEX {:?}",
+                        oid_string(&arcs),
+                        table_oid_str,
+                        index
+                    )
+                })?;
+                Ok((values, row))
+            })
+            .collect()
+    }
+
+    /// Like [`Manager::get_table`], but fetches only the given column
+    /// numbers. Each GetBulk asks for the next cells of every unfinished
+    /// column at once, and the cells are stitched into rows by index.
+    /// v1 credentials, and targets that can't do GETBULK, walk each column
+    /// on its own instead.
+    pub async fn get_table_columns(
+        &self,
+        target: &str,
+        credentials: &Credentials,
+        table_oid_str: &str,
+        columns: &[u64],
+    ) -> Result<TableRows> {
+        let table = parse_oid_string(table_oid_str)?;
+        let roots: Vec<Vec<u64>> = columns
+            .iter()
+            .map(|column| [&table[..], &[1, *column]].concat())
+            .collect();
+        if !self.supports_bulk(target) || matches!(credentials, Credentials::CommunityV1(_)) {
+            return self
+                .walk_columns(target, credentials, table.len(), &roots)
+                .await;
+        }
+
+        let mut rows = TableRows::new();
+        // (column root, last cell seen) for the columns still going
+        let mut pending: Vec<(&[u64], Vec<u64>)> =
+            roots.iter().map(|root| (&root[..], root.clone())).collect();
+        let mut first_request = true;
+
+        while !pending.is_empty() {
+            let oid_strs: Vec<String> = pending
+                .iter()
+                .map(|(_, current)| oid_string(current))
+                .collect();
+            let oid_strs: Vec<&str> = oid_strs.iter().map(String::as_str).collect();
+            let batch = self
+                .get_bulk(target, credentials, 0, TABLE_MAX_REPETITIONS, &oid_strs)
+                .await;
+
+            // same fallback as bulk_walk
+            if let Err(e) = &batch
+                && first_request
+                && ErrorClass::of(e) != ErrorClass::Refused
+            {
+                let rows = self
+                    .walk_columns(target, credentials, table.len(), &roots)
+                    .await
+                    .map_err(|walk_error| {
+                        walk_error.context(format!("GETBULK failed too: {:#}", e))
+                    })?;
+                self.no_bulk.lock().unwrap().insert(target.to_string());
+                return Ok(rows);
+            }
+            let batch = batch?;
+            first_request = false;
+
+            if batch.is_empty() {
+                break;
+            }
+
+            // the answer repeats the requested columns in order, a row at a
+            // time, so varbind i belongs to column i % pending.len()
+            let mut finished = vec![false; pending.len()];
+            for (i, varbind) in batch.into_iter().enumerate() {
+                let column = i % pending.len();
+                if finished[column] {
+                    continue;
+                }
+                let (root, current) = &mut pending[column];
+                if matches!(
+                    varbind.value,
+                    ObjectSyntax::EndOfMib
+                        | ObjectSyntax::NoSuchObject
+                        | ObjectSyntax::NoSuchInstance
+                ) || !is_in_subtree(root, &varbind.oid)
+                {
+                    finished[column] = true;
+                    continue;
+                }
+                if let Err(e) = check_increasing(current, &varbind.oid) {
+                    if self.skip_non_increasing {
+                        finished[column] = true;
+                        continue;
+                    }
+                    return Err(e);
+                }
+                current.clone_from(&varbind.oid);
+                insert_cell(&mut rows, table.len(), varbind);
+            }
+            let mut finished = finished.into_iter();
+            pending.retain(|_| !finished.next().unwrap_or(false));
+        }
+        Ok(rows)
+    }
+
+    async fn walk_columns(
+        &self,
+        target: &str,
+        credentials: &Credentials,
+        table_len: usize,
+        roots: &[Vec<u64>],
+    ) -> Result<TableRows> {
+        let mut rows = TableRows::new();
+        for root in roots {
+            for cell in self.walk(target, credentials, &oid_string(root)).await? {
+                insert_cell(&mut rows, table_len, cell);
+            }
+        }
+        Ok(rows)
+    }
+}
+
+fn insert_cell(rows: &mut TableRows, table_len: usize, cell: VarBind) {
+    // table.1.column.index...
+    if let Some([1, column, index @ ..]) = cell.oid.get(table_len..)
+        && !index.is_empty()
+    {
+        rows.entry(index.to_vec())
+            .or_default()
+            .insert(*column, cell.value);
+    }
+}
+
+/// Decodes a row's index arcs into a value per INDEX object, given their
+/// syntax in order: Integer, Gauge32, OctetString, IpAddress or
+/// ObjectIdentifier values. `None` if the arcs don't fit, or some are left
+/// over.
+pub fn decode_index(index: &[u64], syntax: &[IndexSyntax]) -> Option<Vec<ObjectSyntax>> {
+    let mut rest = index;
+    let mut values = Vec::with_capacity(syntax.len());
+    for part in syntax {
+        let (arcs, tail) = match part {
+            IndexSyntax::Integer | IndexSyntax::Unsigned => split(rest, 1)?,
+            IndexSyntax::FixedString(len) => split(rest, *len)?,
+            IndexSyntax::IpAddress => split(rest, 4)?,
+            IndexSyntax::OctetString | IndexSyntax::ObjectIdentifier => {
+                let (len, rest) = rest.split_first()?;
+                split(rest, usize::try_from(*len).ok()?)?
+            }
+            IndexSyntax::ImpliedOctetString | IndexSyntax::ImpliedObjectIdentifier => {
+                (rest, &[][..])
+            }
+        };
+        let value = match part {
+            IndexSyntax::Integer => ObjectSyntax::Integer(i32::try_from(arcs[0]).ok()?),
+            IndexSyntax::Unsigned => ObjectSyntax::Gauge32(u32::try_from(arcs[0]).ok()?),
+            IndexSyntax::IpAddress => ObjectSyntax::IpAddress(octets(arcs)?),
+            IndexSyntax::OctetString
+            | IndexSyntax::FixedString(_)
+            | IndexSyntax::ImpliedOctetString => ObjectSyntax::OctetString(octets(arcs)?),
+            IndexSyntax::ObjectIdentifier | IndexSyntax::ImpliedObjectIdentifier => {
+                ObjectSyntax::ObjectIdentifier(arcs.to_vec())
+            }
+        };
+        values.push(value);
+        rest = tail;
+    }
+    rest.is_empty().then_some(values)
+}
+
+/// The reverse of [`decode_index`]: a row's index arcs from the values of
+/// its INDEX objects. `None` if a value isn't of its syntax, or a fixed
+/// string has the wrong length.
+pub fn encode_index(values: &[ObjectSyntax], syntax: &[IndexSyntax]) -> Option<Vec<u64>> {
+    if values.len() != syntax.len() {
+        return None;
+    }
+    let mut arcs = Vec::new();
+    for (value, part) in values.iter().zip(syntax) {
+        match (part, value) {
+            (IndexSyntax::Integer, ObjectSyntax::Integer(value)) => {
+                arcs.push(u64::try_from(*value).ok()?);
+            }
+            (IndexSyntax::Unsigned, value) => arcs.push(value.as_u64()?),
+            (IndexSyntax::IpAddress, ObjectSyntax::IpAddress(bytes)) if bytes.len() == 4 => {
+                arcs.extend(bytes.iter().map(|&b| u64::from(b)));
+            }
+            (IndexSyntax::FixedString(len), ObjectSyntax::OctetString(bytes))
+                if bytes.len() == *len =>
+            {
+                arcs.extend(bytes.iter().map(|&b| u64::from(b)));
+            }
+            (IndexSyntax::OctetString, ObjectSyntax::OctetString(bytes)) => {
+                arcs.push(bytes.len() as u64);
+                arcs.extend(bytes.iter().map(|&b| u64::from(b)));
+            }
+            (IndexSyntax::ImpliedOctetString, ObjectSyntax::OctetString(bytes)) => {
+                arcs.extend(bytes.iter().map(|&b| u64::from(b)));
+            }
+            (IndexSyntax::ObjectIdentifier, ObjectSyntax::ObjectIdentifier(oid)) => {
+                arcs.push(oid.len() as u64);
+                arcs.extend(oid);
+            }
+            (IndexSyntax::ImpliedObjectIdentifier, ObjectSyntax::ObjectIdentifier(oid)) => {
+                arcs.extend(oid);
+            }
+            _ => return None,
+        }
+    }
+    Some(arcs)
+}
+
+fn split(arcs: &[u64], len: usize) -> Option<(&[u64], &[u64])> {
+    (len <= arcs.len()).then(|| arcs.split_at(len))
+}
+
+fn octets(arcs: &[u64]) -> Option<Vec<u8>> {
+    arcs.iter().map(|&arc| u8::try_from(arc).ok()).collect()
+}
+
+fn oid_string(oid: &[u64]) -> String {
+    oid.iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(".")
+}
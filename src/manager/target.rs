@@ -0,0 +1,152 @@
+// One Manager polling a mixed fleet: the old switch that needs v1 and a
+// long timeout, the NAT-forwarded box on another port, the agent that
+// can't answer more than 1500 bytes.
+
+use std::time::Duration;
+
+use anyhow::{Result, bail};
+
+use super::rate_limit::TokenBucket;
+use super::{Credentials, Manager};
+
+/// Settings for one device. Register it with [`Manager::with_target`];
+/// requests naming the target then use its settings instead of the
+/// Manager's, and fail unless they pass the target's credentials.
+#[derive(Debug, Clone)]
+pub struct Target {
+    name: String,
+    credentials: Credentials,
+    port: Option<u16>,
+    timeout: Option<Duration>,
+    retries: Option<u32>,
+    max_message_size: Option<usize>,
+    rate_limit: Option<f64>,
+}
+
+impl Target {
+    /// `name` is what requests pass as their target: a host name or
+    /// address, optionally with a port.
+    pub fn new(name: impl Into<String>, credentials: impl Into<Credentials>) -> Self {
+        Self {
+            name: name.into(),
+            credentials: credentials.into(),
+            port: None,
+            timeout: None,
+            retries: None,
+            max_message_size: None,
+            rate_limit: None,
+        }
+    }
+
+    /// The agent port, unless the name carries one.
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = Some(port);
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = Some(retries);
+        self
+    }
+
+    /// The largest response the agent may send, and the msgMaxSize
+    /// advertised to v3 agents. Defaults to the Manager's.
+    pub fn max_message_size(mut self, bytes: usize) -> Self {
+        self.max_message_size = Some(bytes);
+        self
+    }
+
+    /// At most this many packets a second to the target, on top of any
+    /// limit the whole Manager has. Panics unless positive.
+    pub fn rate_limit(mut self, packets_per_second: f64) -> Self {
+        assert!(packets_per_second > 0.0, "rate limit must be positive");
+        self.rate_limit = Some(packets_per_second);
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The SNMP version and security to use, for passing to requests.
+    pub fn credentials(&self) -> &Credentials {
+        &self.credentials
+    }
+}
+
+// the settings one request goes out with
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Settings {
+    pub(super) timeout: Duration,
+    pub(super) retries: u32,
+    pub(super) max_message_size: usize,
+}
+
+impl Manager {
+    /// Registers per-target settings, replacing any for the same name.
+    pub fn with_target(mut self, target: Target) -> Self {
+        match target.rate_limit {
+            Some(rate) => {
+                let bucket = TokenBucket::new(rate);
+                self.target_rate_limits.insert(target.name.clone(), bucket);
+            }
+            None => {
+                self.target_rate_limits.remove(&target.name);
+            }
+        }
+        self.targets.insert(target.name.clone(), target);
+        self
+    }
+
+    /// The registered target's credentials, or else the Manager's
+    /// default credentials.
+    pub fn credentials_for(&self, target: &str) -> Option<&Credentials> {
+        self.targets
+            .get(target)
+            .map(Target::credentials)
+            .or(self.default_credentials.as_ref())
+    }
+
+    // a request with other credentials than the target was registered
+    // with would quietly go out as another version or user
+    pub(super) fn check_credentials(&self, target: &str, credentials: &Credentials) -> Result<()> {
+        if let Some(registered) = self.targets.get(target)
+            && !registered.credentials.matches(credentials)
+        {
+            bail!(
+                "{} is registered with {:?} credentials, not {:?}",
+                target,
+                registered.credentials,
+                credentials
+            );
+        }
+        Ok(())
+    }
+
+    pub(super) fn target_port(&self, target: &str) -> u16 {
+        self.targets
+            .get(target)
+            .and_then(|settings| settings.port)
+            .unwrap_or(self.port)
+    }
+
+    pub(super) fn settings(&self, target: &str) -> Settings {
+        let settings = self.targets.get(target);
+        Settings {
+            timeout: settings
+                .and_then(|settings| settings.timeout)
+                .unwrap_or(self.timeout),
+            retries: settings
+                .and_then(|settings| settings.retries)
+                .unwrap_or(self.retries),
+            max_message_size: settings
+                .and_then(|settings| settings.max_message_size)
+                .unwrap_or(self.max_message_size),
+        }
+    }
+}
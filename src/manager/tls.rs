@@ -0,0 +1,537 @@
+// SNMP over TLS on TCP (RFC 6353), as net-snmp's tlstcp transport speaks
+// it. Messages go out exactly as the credentials build them; the Transport
+// Security Model (RFC 5591) that maps certificates to securityNames isn't
+// implemented, so agents must accept the message's own security model.
+// BER is self-delimiting, so messages need no framing on the stream.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{Context, Result, anyhow, bail};
+use futures::FutureExt;
+use futures::future::BoxFuture;
+use sha1::{Digest, Sha1};
+use sha2::{Sha256, Sha384, Sha512};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+use tokio_rustls::TlsConnector;
+use tokio_rustls::client::TlsStream;
+use tokio_rustls::rustls::client::WebPkiServerVerifier;
+use tokio_rustls::rustls::client::danger::{
+    HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier,
+};
+use tokio_rustls::rustls::crypto::{self, CryptoProvider, ring};
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime};
+use tokio_rustls::rustls::{
+    self, CertificateError, ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme,
+};
+
+use crate::ber::BerError;
+use crate::ber::stream::StreamDecoder;
+
+use super::error::{MessageTooLargeError, TimeoutError};
+use super::pool::DEFAULT_MAX_IDLE_SOCKETS;
+use super::transport::Transport;
+
+/// Where agents listen for SNMP over TLS (RFC 6353).
+pub const TLS_PORT: u16 = 10161;
+
+/// Where notification receivers listen for SNMP over TLS.
+pub const TLS_TRAP_PORT: u16 = 10162;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FingerprintAlgorithm {
+    Sha1,
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl FingerprintAlgorithm {
+    pub fn digest_len(&self) -> usize {
+        match self {
+            FingerprintAlgorithm::Sha1 => 20,
+            FingerprintAlgorithm::Sha256 => 32,
+            FingerprintAlgorithm::Sha384 => 48,
+            FingerprintAlgorithm::Sha512 => 64,
+        }
+    }
+
+    fn digest(&self, data: &[u8]) -> Vec<u8> {
+        match self {
+            FingerprintAlgorithm::Sha1 => Sha1::digest(data).to_vec(),
+            FingerprintAlgorithm::Sha256 => Sha256::digest(data).to_vec(),
+            FingerprintAlgorithm::Sha384 => Sha384::digest(data).to_vec(),
+            FingerprintAlgorithm::Sha512 => Sha512::digest(data).to_vec(),
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().replace('-', "").as_str() {
+            "sha1" => Some(FingerprintAlgorithm::Sha1),
+            "sha256" => Some(FingerprintAlgorithm::Sha256),
+            "sha384" => Some(FingerprintAlgorithm::Sha384),
+            "sha512" => Some(FingerprintAlgorithm::Sha512),
+            _ => None,
+        }
+    }
+
+    fn from_digest_len(len: usize) -> Option<Self> {
+        [
+            FingerprintAlgorithm::Sha1,
+            FingerprintAlgorithm::Sha256,
+            FingerprintAlgorithm::Sha384,
+            FingerprintAlgorithm::Sha512,
+        ]
+        .into_iter()
+        .find(|algorithm| algorithm.digest_len() == len)
+    }
+
+    // the TLS HashAlgorithm numbers SnmpTLSFingerprint starts with
+    fn from_tls_id(id: u8) -> Option<Self> {
+        match id {
+            2 => Some(FingerprintAlgorithm::Sha1),
+            4 => Some(FingerprintAlgorithm::Sha256),
+            5 => Some(FingerprintAlgorithm::Sha384),
+            6 => Some(FingerprintAlgorithm::Sha512),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            FingerprintAlgorithm::Sha1 => "SHA1",
+            FingerprintAlgorithm::Sha256 => "SHA256",
+            FingerprintAlgorithm::Sha384 => "SHA384",
+            FingerprintAlgorithm::Sha512 => "SHA512",
+        }
+    }
+}
+
+/// A hash of a DER certificate, for pinning an agent's certificate the
+/// way net-snmp's `serverCert` does.
+///
+/// Parses from hex, colons optional, with an algorithm prefix such as
+/// `SHA256:48:A1:...`, without one (the algorithm follows from the
+/// length), or in the RFC 5592 form that starts with the TLS hash
+/// algorithm number (`04:48:A1:...` for SHA-256).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fingerprint {
+    pub algorithm: FingerprintAlgorithm,
+    pub digest: Vec<u8>,
+}
+
+impl Fingerprint {
+    /// The fingerprint of a DER encoded certificate.
+    pub fn of(algorithm: FingerprintAlgorithm, certificate: &[u8]) -> Self {
+        Self {
+            algorithm,
+            digest: algorithm.digest(certificate),
+        }
+    }
+
+    pub fn matches(&self, certificate: &[u8]) -> bool {
+        self.algorithm.digest(certificate) == self.digest
+    }
+}
+
+impl FromStr for Fingerprint {
+    type Err = anyhow::Error;
+
+    fn from_str(text: &str) -> Result<Self> {
+        let invalid = || anyhow!("Invalid certificate fingerprint '{}'", text);
+        let (named, hex) = match text.split_once(':').and_then(|(name, rest)| {
+            FingerprintAlgorithm::from_name(name).map(|algorithm| (algorithm, rest))
+        }) {
+            Some((algorithm, rest)) => (Some(algorithm), rest),
+            None => (None, text),
+        };
+
+        let digits: Vec<u8> = hex.bytes().filter(|b| *b != b':').collect();
+        if digits.is_empty()
+            || !digits.len().is_multiple_of(2)
+            || !digits.iter().all(u8::is_ascii_hexdigit)
+        {
+            return Err(invalid());
+        }
+        let mut digest = digits
+            .chunks(2)
+            .map(|pair| {
+                let pair = std::str::from_utf8(pair).ok()?;
+                u8::from_str_radix(pair, 16).ok()
+            })
+            .collect::<Option<Vec<u8>>>()
+            .ok_or_else(invalid)?;
+
+        let algorithm = match named {
+            Some(algorithm) => algorithm,
+            None => match FingerprintAlgorithm::from_digest_len(digest.len()) {
+                Some(algorithm) => algorithm,
+                None => {
+                    let algorithm = FingerprintAlgorithm::from_tls_id(digest[0])
+                        .filter(|algorithm| algorithm.digest_len() + 1 == digest.len())
+                        .ok_or_else(invalid)?;
+                    digest.remove(0);
+                    algorithm
+                }
+            },
+        };
+        if digest.len() != algorithm.digest_len() {
+            bail!(
+                "{} fingerprint '{}' should be {} bytes",
+                algorithm.name(),
+                text,
+                algorithm.digest_len()
+            );
+        }
+        Ok(Self { algorithm, digest })
+    }
+}
+
+impl fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let hex: Vec<String> = self.digest.iter().map(|b| format!("{:02X}", b)).collect();
+        write!(f, "{}:{}", self.algorithm.name(), hex.join(":"))
+    }
+}
+
+// a pinned fingerprint vouches for the certificate on its own; anything
+// else has to chain up to a trusted certificate
+#[derive(Debug)]
+struct AgentVerifier {
+    fingerprints: Vec<Fingerprint>,
+    chain: Option<Arc<WebPkiServerVerifier>>,
+    provider: Arc<CryptoProvider>,
+}
+
+impl ServerCertVerifier for AgentVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        if self.fingerprints.iter().any(|f| f.matches(end_entity)) {
+            return Ok(ServerCertVerified::assertion());
+        }
+        match &self.chain {
+            Some(chain) => {
+                chain.verify_server_cert(end_entity, intermediates, server_name, ocsp_response, now)
+            }
+            None => Err(rustls::Error::InvalidCertificate(
+                CertificateError::ApplicationVerificationFailure,
+            )),
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
+
+/// Configures a [`TlsTcpTransport`]. Start from [`TlsTcpTransport::builder`].
+/// Agents are accepted if their certificate matches a pinned
+/// [`Fingerprint`] or chains up to a trusted certificate and names the
+/// agent's IP address; at least one of the two is needed.
+#[derive(Clone)]
+pub struct TlsTcpBuilder {
+    fingerprints: Vec<Fingerprint>,
+    trusted: Vec<Vec<u8>>,
+    client_certificate: Option<(Vec<Vec<u8>>, Vec<u8>)>,
+    max_idle_connections: usize,
+}
+
+impl Default for TlsTcpBuilder {
+    fn default() -> Self {
+        Self {
+            fingerprints: Vec::new(),
+            trusted: Vec::new(),
+            client_certificate: None,
+            max_idle_connections: DEFAULT_MAX_IDLE_SOCKETS,
+        }
+    }
+}
+
+impl TlsTcpBuilder {
+    /// Accepts agents presenting the certificate with this fingerprint.
+    pub fn fingerprint(mut self, fingerprint: Fingerprint) -> Self {
+        self.fingerprints.push(fingerprint);
+        self
+    }
+
+    /// Trusts a DER encoded CA certificate, like net-snmp's `trustCert`.
+    pub fn trust_certificate(mut self, der: impl Into<Vec<u8>>) -> Self {
+        self.trusted.push(der.into());
+        self
+    }
+
+    /// The certificate chain, leaf first, and PKCS#8, PKCS#1 or SEC1
+    /// private key to identify ourselves with, all DER encoded. net-snmp
+    /// agents require one (`localCert`).
+    pub fn client_certificate(mut self, chain: Vec<Vec<u8>>, key: Vec<u8>) -> Self {
+        self.client_certificate = Some((chain, key));
+        self
+    }
+
+    /// How many connections to keep open for reuse once their request is
+    /// done, over all agents. Defaults to 256.
+    pub fn max_idle_connections(mut self, max: usize) -> Self {
+        self.max_idle_connections = max;
+        self
+    }
+
+    pub fn build(self) -> Result<TlsTcpTransport> {
+        if self.fingerprints.is_empty() && self.trusted.is_empty() {
+            bail!("TLS needs a fingerprint or a trusted certificate to verify agents");
+        }
+        let provider = Arc::new(ring::default_provider());
+
+        let chain = if self.trusted.is_empty() {
+            None
+        } else {
+            let mut roots = RootCertStore::empty();
+            for der in self.trusted {
+                roots
+                    .add(CertificateDer::from(der))
+                    .context("Invalid trusted certificate")?;
+            }
+            let verifier =
+                WebPkiServerVerifier::builder_with_provider(Arc::new(roots), provider.clone())
+                    .build()?;
+            Some(verifier)
+        };
+        let verifier = AgentVerifier {
+            fingerprints: self.fingerprints,
+            chain,
+            provider: provider.clone(),
+        };
+
+        let config = ClientConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()?
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(verifier));
+        let config = match self.client_certificate {
+            Some((chain, key)) => {
+                let chain = chain.into_iter().map(CertificateDer::from).collect();
+                let key = PrivateKeyDer::try_from(key).map_err(|e| anyhow!(e))?;
+                config
+                    .with_client_auth_cert(chain, key)
+                    .context("Invalid client certificate")?
+            }
+            None => config.with_no_client_auth(),
+        };
+
+        Ok(TlsTcpTransport {
+            connector: TlsConnector::from(Arc::new(config)),
+            idle: Mutex::new(Idle::default()),
+            max_idle: self.max_idle_connections,
+        })
+    }
+}
+
+#[derive(Default)]
+struct Idle {
+    by_address: HashMap<SocketAddr, Vec<TlsStream<TcpStream>>>,
+    count: usize,
+}
+
+/// A [`Transport`] carrying each request over TLS on a TCP connection to
+/// the agent, port [`TLS_PORT`] by convention. Connections are kept open
+/// for the next request to the same agent.
+pub struct TlsTcpTransport {
+    connector: TlsConnector,
+    idle: Mutex<Idle>,
+    max_idle: usize,
+}
+
+impl TlsTcpTransport {
+    pub fn builder() -> TlsTcpBuilder {
+        TlsTcpBuilder::default()
+    }
+
+    async fn connect(&self, address: SocketAddr) -> Result<TlsStream<TcpStream>> {
+        let stream = TcpStream::connect(address)
+            .await
+            .with_context(|| format!("Failed to connect to {}", address))?;
+        stream.set_nodelay(true)?;
+        // certificates checked against a trust anchor must name the address
+        let server_name = ServerName::IpAddress(address.ip().into());
+        self.connector
+            .connect(server_name, stream)
+            .await
+            .with_context(|| format!("TLS handshake with {} failed", address))
+    }
+
+    fn take(&self, address: SocketAddr) -> Option<TlsStream<TcpStream>> {
+        let mut idle = self.idle.lock().unwrap();
+        let stream = idle.by_address.get_mut(&address).and_then(Vec::pop);
+        if stream.is_some() {
+            idle.count -= 1;
+        }
+        stream
+    }
+
+    fn put(&self, address: SocketAddr, stream: TlsStream<TcpStream>) {
+        let mut idle = self.idle.lock().unwrap();
+        if idle.count < self.max_idle {
+            idle.by_address.entry(address).or_default().push(stream);
+            idle.count += 1;
+        }
+    }
+
+    async fn exchange(
+        &self,
+        address: SocketAddr,
+        packet: &[u8],
+        max_size: usize,
+        accept: &(dyn Fn(&[u8]) -> bool + Send + Sync),
+    ) -> Result<(TlsStream<TcpStream>, Vec<u8>)> {
+        // the agent may have closed an idle connection since
+        if let Some(mut stream) = self.take(address)
+            && let Ok(response) = exchange_on(&mut stream, address, packet, max_size, accept).await
+        {
+            return Ok((stream, response));
+        }
+        let mut stream = self.connect(address).await?;
+        let response = exchange_on(&mut stream, address, packet, max_size, accept).await?;
+        Ok((stream, response))
+    }
+}
+
+impl Transport for TlsTcpTransport {
+    fn send_recv<'a>(
+        &'a self,
+        address: SocketAddr,
+        packet: &'a [u8],
+        wait: Duration,
+        max_size: usize,
+        accept: &'a (dyn Fn(&[u8]) -> bool + Send + Sync),
+    ) -> BoxFuture<'a, Result<Vec<u8>>> {
+        async move {
+            // a connection that timed out may still deliver the late
+            // answer, so only finished ones are kept
+            match timeout(wait, self.exchange(address, packet, max_size, accept)).await {
+                Ok(Ok((stream, response))) => {
+                    self.put(address, stream);
+                    Ok(response)
+                }
+                Ok(Err(e)) => Err(e),
+                Err(_) => Err(TimeoutError {
+                    address,
+                    after: wait,
+                }
+                .into()),
+            }
+        }
+        .boxed()
+    }
+
+    fn send<'a>(
+        &'a self,
+        address: SocketAddr,
+        packet: &'a [u8],
+        wait: Duration,
+    ) -> BoxFuture<'a, Result<()>> {
+        async move {
+            let send = async {
+                let mut stream = self.connect(address).await?;
+                stream.write_all(packet).await?;
+                stream.shutdown().await?;
+                anyhow::Ok(())
+            };
+            match timeout(wait, send).await {
+                Ok(result) => {
+                    result.with_context(|| format!("Failed to send packet to {}", address))
+                }
+                Err(_) => Err(TimeoutError {
+                    address,
+                    after: wait,
+                }
+                .into()),
+            }
+        }
+        .boxed()
+    }
+}
+
+async fn exchange_on(
+    stream: &mut TlsStream<TcpStream>,
+    address: SocketAddr,
+    packet: &[u8],
+    max_size: usize,
+    accept: &(dyn Fn(&[u8]) -> bool + Send + Sync),
+) -> Result<Vec<u8>> {
+    stream
+        .write_all(packet)
+        .await
+        .context("Failed to send packet")?;
+    loop {
+        let message = read_message(stream, address, max_size)
+            .await
+            .context("Failed to receive data")?;
+        if accept(&message) {
+            return Ok(message);
+        }
+    }
+}
+
+// one whole message, tag and length included. Only what the frame
+// still needs is read, so the next message stays in the stream.
+async fn read_message(
+    stream: &mut (impl AsyncRead + Unpin),
+    address: SocketAddr,
+    max_size: usize,
+) -> Result<Vec<u8>> {
+    let mut decoder = StreamDecoder::new(max_size);
+    let mut chunk = Vec::new();
+    loop {
+        match decoder.next_frame() {
+            Ok(Some(message)) => return Ok(message),
+            Ok(None) => {}
+            Err(BerError::FrameTooLarge(_)) => {
+                return Err(MessageTooLargeError { address, max_size }.into());
+            }
+            Err(e) => return Err(anyhow!(e).context(format!("Malformed message from {}", address))),
+        }
+        chunk.resize(decoder.needed()?, 0);
+        stream.read_exact(&mut chunk).await?;
+        decoder.extend(&chunk);
+    }
+}
@@ -0,0 +1,118 @@
+// What actually moves packets. The Manager builds, matches and decodes
+// messages; a Transport only gets bytes to an agent and bytes back. UDP
+// is the default, but tests can answer from memory and wrappers can drop
+// or delay packets without the protocol code knowing.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use futures::FutureExt;
+use futures::future::BoxFuture;
+
+use super::network::{self, SourceBinding};
+use super::pool::{DEFAULT_MAX_IDLE_SOCKETS, SocketPool};
+
+/// Carries requests to agents for a [`Manager`](super::Manager). Set one
+/// with [`ManagerBuilder::transport`](super::ManagerBuilder::transport).
+pub trait Transport: Send + Sync {
+    /// Sends `packet` to `address` and returns the first reply `accept`
+    /// takes, at most `max_size` bytes of it. Replies `accept` turns down,
+    /// such as late answers to earlier requests, are skipped. Gives up
+    /// after `wait` with a [`TimeoutError`](super::TimeoutError); only
+    /// those are retried.
+    fn send_recv<'a>(
+        &'a self,
+        address: SocketAddr,
+        packet: &'a [u8],
+        wait: Duration,
+        max_size: usize,
+        accept: &'a (dyn Fn(&[u8]) -> bool + Send + Sync),
+    ) -> BoxFuture<'a, Result<Vec<u8>>>;
+
+    /// Sends `packet` without waiting for a reply, as for traps. A
+    /// transport that has to connect first gives up after `wait`.
+    fn send<'a>(
+        &'a self,
+        address: SocketAddr,
+        packet: &'a [u8],
+        wait: Duration,
+    ) -> BoxFuture<'a, Result<()>>;
+}
+
+// so a caller can keep a handle on the transport it gave a Manager, or
+// give the same one to several
+impl<T: Transport + ?Sized> Transport for Arc<T> {
+    fn send_recv<'a>(
+        &'a self,
+        address: SocketAddr,
+        packet: &'a [u8],
+        wait: Duration,
+        max_size: usize,
+        accept: &'a (dyn Fn(&[u8]) -> bool + Send + Sync),
+    ) -> BoxFuture<'a, Result<Vec<u8>>> {
+        (**self).send_recv(address, packet, wait, max_size, accept)
+    }
+
+    fn send<'a>(
+        &'a self,
+        address: SocketAddr,
+        packet: &'a [u8],
+        wait: Duration,
+    ) -> BoxFuture<'a, Result<()>> {
+        (**self).send(address, packet, wait)
+    }
+}
+
+/// The default [`Transport`]: one datagram per request, on connected
+/// sockets that are kept for the next request to the same agent.
+pub struct UdpTransport {
+    pool: SocketPool,
+}
+
+impl UdpTransport {
+    /// Keeps up to `max_idle_sockets` sockets between requests, over all
+    /// agents; 0 binds a fresh socket for every request.
+    pub fn new(max_idle_sockets: usize) -> Self {
+        Self {
+            pool: SocketPool::new(max_idle_sockets, SourceBinding::default()),
+        }
+    }
+
+    /// Binds every socket to `source` instead of letting the OS choose.
+    pub fn with_source(mut self, source: SourceBinding) -> Self {
+        self.pool.source = source;
+        self
+    }
+}
+
+impl Default for UdpTransport {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_IDLE_SOCKETS)
+    }
+}
+
+impl Transport for UdpTransport {
+    fn send_recv<'a>(
+        &'a self,
+        address: SocketAddr,
+        packet: &'a [u8],
+        wait: Duration,
+        max_size: usize,
+        accept: &'a (dyn Fn(&[u8]) -> bool + Send + Sync),
+    ) -> BoxFuture<'a, Result<Vec<u8>>> {
+        self.pool
+            .round_trip(address, packet, wait, max_size, accept)
+            .boxed()
+    }
+
+    fn send<'a>(
+        &'a self,
+        address: SocketAddr,
+        packet: &'a [u8],
+        _wait: Duration,
+    ) -> BoxFuture<'a, Result<()>> {
+        network::send_only_from(address, packet, &self.pool.source).boxed()
+    }
+}
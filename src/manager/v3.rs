@@ -0,0 +1,253 @@
+// SNMPv3 operations over USM, at any security level.
+
+use std::sync::atomic::Ordering;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Result, anyhow};
+
+use super::{Expected, Manager, basic_request};
+use crate::ber::Asn1Tag;
+use crate::snmp::engine_id::EngineId;
+use crate::snmp::message::{
+    FLAG_AUTH, FLAG_PRIV, FLAG_REPORTABLE, HeaderData, SECURITY_MODEL_USM, ScopedPdu,
+    ScopedPduData, SnmpV3Message, parse_v3_message,
+};
+use crate::snmp::pdu::Pdu;
+use crate::snmp::report::ReportError;
+use crate::snmp::usm::{self, UsmError, UsmSecurityParameters, UsmUser};
+
+// a Report in place of the response becomes a typed error callers can
+// downcast to
+fn reject_report(pdu: Pdu) -> Result<Pdu> {
+    match ReportError::from_pdu(&pdu) {
+        Some(report) => Err(report.into()),
+        None => Ok(pdu),
+    }
+}
+
+/// What we know about a v3 agent's engine after discovery.
+#[derive(Debug, Clone)]
+pub(super) struct EngineState {
+    engine_id: Vec<u8>,
+    boots: i32,
+    time: i32,
+    synced_at: Instant,
+}
+
+impl EngineState {
+    // the agent's clock keeps running after we last heard from it
+    fn estimated_time(&self) -> i32 {
+        let elapsed = self.synced_at.elapsed().as_secs().min(i32::MAX as u64) as i32;
+        self.time.saturating_add(elapsed)
+    }
+}
+
+// start the salt somewhere unpredictable so restarts don't reuse IVs
+pub(crate) fn initial_salt() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or_default()
+}
+
+impl Manager {
+    pub(super) async fn request_v3(&self, target: &str, user: &UsmUser, pdu: Pdu) -> Result<Pdu> {
+        let (engine, discovered) = match self.cached_engine(target) {
+            Some(engine) => (engine, false),
+            None => (self.discover_engine(target).await?, true),
+        };
+
+        let response = self.exchange_v3(target, user, &engine, pdu.clone()).await;
+        // a replaced or reset agent comes back under a new engine ID, and
+        // with it new keys: forget the old one and discover it again
+        let unknown_engine = match &response {
+            Ok(response) => ReportError::from_pdu(response) == Some(ReportError::UnknownEngineId),
+            Err(e) => e.downcast_ref::<ReportError>() == Some(&ReportError::UnknownEngineId),
+        };
+        let response = if unknown_engine && !discovered {
+            self.engines.lock().unwrap().remove(target);
+            let engine = self.discover_engine(target).await?;
+            self.exchange_v3(target, user, &engine, pdu.clone()).await?
+        } else {
+            response?
+        };
+
+        // our clock estimate drifted, the authenticated report carried the
+        // agent's real boots/time and exchange_v3 already took them over
+        if ReportError::from_pdu(&response) == Some(ReportError::NotInTimeWindow)
+            && user.auth_protocol().is_some()
+        {
+            let engine = self
+                .cached_engine(target)
+                .ok_or_else(|| anyhow!("Engine state for {} vanished", target))?;
+            let response = self.exchange_v3(target, user, &engine, pdu).await?;
+            return reject_report(response);
+        }
+
+        reject_report(response)
+    }
+
+    async fn exchange_v3(
+        &self,
+        target: &str,
+        user: &UsmUser,
+        engine: &EngineState,
+        pdu: Pdu,
+    ) -> Result<Pdu> {
+        let mut message = SnmpV3Message {
+            header: HeaderData {
+                msg_id: self.next_request_id(),
+                max_size: self.settings(target).max_message_size as i32,
+                flags: user.security_flags() | FLAG_REPORTABLE,
+                security_model: SECURITY_MODEL_USM,
+            },
+            security_params: UsmSecurityParameters {
+                authoritative_engine_id: engine.engine_id.clone(),
+                engine_boots: engine.boots,
+                engine_time: engine.estimated_time(),
+                user_name: user.name.clone(),
+                ..Default::default()
+            },
+            data: ScopedPduData::Plaintext(ScopedPdu {
+                context_engine_id: user
+                    .context_engine_id
+                    .clone()
+                    .unwrap_or_else(|| engine.engine_id.clone()),
+                context_name: user.context_name.clone(),
+                pdu,
+            }),
+        };
+
+        let privacy = user.localized_priv(&engine.engine_id);
+        if let Some((protocol, key)) = &privacy {
+            usm::encrypt_scoped_pdu(&mut message, *protocol, key, self.next_salt());
+        }
+
+        let auth = user.localized_auth(&engine.engine_id);
+        let packet_bytes = match &auth {
+            Some((protocol, key)) => usm::authenticate_message(&mut message, *protocol, key),
+            None => message.to_bytes(),
+        };
+
+        let response_bytes = self
+            .send(target, &packet_bytes, Expected::v3(message.header.msg_id))
+            .await?;
+
+        let mut response = parse_v3_message(&response_bytes)
+            .map_err(|e| anyhow!(e).context("Failed to parse response"))?;
+        // not the engine we localized our keys for
+        if response.security_params.authoritative_engine_id != engine.engine_id {
+            return Err(ReportError::UnknownEngineId.into());
+        }
+
+        if let Some((protocol, key)) = &auth {
+            if response.header.flags & FLAG_AUTH != 0 {
+                usm::verify_message(&response_bytes, *protocol, key)?;
+                // only trust the agent's clock once the digest checked out
+                self.resync_engine(target, &response.security_params);
+            } else if !response
+                .scoped_pdu()
+                .is_some_and(|scoped| scoped.pdu.tag == Asn1Tag::Report)
+            {
+                return Err(UsmError::NotAuthenticated.into());
+            }
+        }
+
+        // the response comes at the request's level (RFC 3412 7.2), only
+        // reports may come in the clear
+        if let Some((protocol, key)) = &privacy {
+            let encrypted = response.header.flags & FLAG_PRIV != 0
+                && matches!(response.data, ScopedPduData::Encrypted(_));
+            if encrypted {
+                usm::decrypt_scoped_pdu(&mut response, *protocol, key)?;
+            } else if !response
+                .scoped_pdu()
+                .is_some_and(|scoped| scoped.pdu.tag == Asn1Tag::Report)
+            {
+                return Err(UsmError::NotEncrypted.into());
+            }
+        }
+
+        match response.data {
+            ScopedPduData::Plaintext(scoped_pdu) => Ok(scoped_pdu.pdu),
+            ScopedPduData::Encrypted(_) => Err(anyhow!(
+                "Received an encrypted response but no privacy protocol is configured"
+            )),
+        }
+    }
+
+    /// Engine discovery (RFC 3414 section 4): an empty, unauthenticated
+    /// request makes the agent report its snmpEngineID, boots and time.
+    pub(super) async fn discover_engine(&self, target: &str) -> Result<EngineState> {
+        let message = SnmpV3Message {
+            header: HeaderData {
+                msg_id: self.next_request_id(),
+                max_size: self.settings(target).max_message_size as i32,
+                flags: FLAG_REPORTABLE,
+                security_model: SECURITY_MODEL_USM,
+            },
+            security_params: UsmSecurityParameters::default(),
+            data: ScopedPduData::Plaintext(ScopedPdu {
+                context_engine_id: Vec::new(),
+                context_name: Vec::new(),
+                pdu: basic_request(Asn1Tag::GetRequest, Vec::new()),
+            }),
+        };
+
+        let response_bytes = self
+            .send(
+                target,
+                &message.to_bytes(),
+                Expected::v3(message.header.msg_id),
+            )
+            .await?;
+        let response = parse_v3_message(&response_bytes)
+            .map_err(|e| anyhow!(e).context("Failed to parse discovery response"))?;
+
+        let params = response.security_params;
+        if params.authoritative_engine_id.is_empty() {
+            return Err(anyhow!(
+                "Engine discovery failed: {} did not report an engine ID",
+                target
+            ));
+        }
+
+        let engine = EngineState {
+            engine_id: params.authoritative_engine_id,
+            boots: params.engine_boots,
+            time: params.engine_time,
+            synced_at: Instant::now(),
+        };
+        self.engines
+            .lock()
+            .unwrap()
+            .insert(target.to_string(), engine.clone());
+        Ok(engine)
+    }
+
+    /// The engine ID discovered for `target`, if we have talked v3 to it.
+    pub fn engine_id(&self, target: &str) -> Option<EngineId> {
+        self.cached_engine(target)
+            .and_then(|engine| EngineId::from_bytes(engine.engine_id).ok())
+    }
+
+    // privacy salts must not repeat under the same key
+    fn next_salt(&self) -> u64 {
+        self.salt.fetch_add(1, Ordering::Relaxed)
+    }
+
+    pub(super) fn cached_engine(&self, target: &str) -> Option<EngineState> {
+        self.engines.lock().unwrap().get(target).cloned()
+    }
+
+    fn resync_engine(&self, target: &str, params: &UsmSecurityParameters) {
+        let mut engines = self.engines.lock().unwrap();
+        if let Some(engine) = engines.get_mut(target)
+            && engine.engine_id == params.authoritative_engine_id
+        {
+            engine.boots = params.engine_boots;
+            engine.time = params.engine_time;
+            engine.synced_at = Instant::now();
+        }
+    }
+}
@@ -0,0 +1,79 @@
+// Optional phase before a large run: resolve every target and discover v3
+// engines up front, so the measurement phase starts with everything cached
+// and its timings aren't skewed by first-contact round trips.
+
+use std::time::Duration;
+
+use anyhow::{Result, anyhow};
+use futures::future::join_all;
+use tokio::time::{Instant, timeout_at};
+
+use super::Manager;
+
+/// How each target fared during [`Manager::warm_up`].
+#[derive(Debug, Default)]
+pub struct WarmUpReport {
+    pub ready: Vec<String>,
+    pub failed: Vec<(String, anyhow::Error)>,
+    /// Still unfinished when the time box ran out.
+    pub timed_out: Vec<String>,
+}
+
+impl Manager {
+    /// Resolves all `targets` concurrently and, with `discover_engines`,
+    /// runs v3 engine discovery against them. Everything learned is cached
+    /// for later requests; addresses for five minutes, or until a request
+    /// to the target times out or is refused. The whole phase is bounded
+    /// by `time_box`, and `on_progress` is called as each target finishes.
+    pub async fn warm_up<F>(
+        &self,
+        targets: &[String],
+        discover_engines: bool,
+        time_box: Duration,
+        on_progress: F,
+    ) -> WarmUpReport
+    where
+        F: Fn(&str, &Result<()>),
+    {
+        let deadline = Instant::now() + time_box;
+
+        let tasks = targets.iter().map(|target| {
+            let on_progress = &on_progress;
+            async move {
+                match timeout_at(deadline, self.warm_up_target(target, discover_engines)).await {
+                    Ok(result) => {
+                        on_progress(target, &result);
+                        Some(result)
+                    }
+                    Err(_) => {
+                        on_progress(target, &Err(anyhow!("Warm-up timed out")));
+                        None
+                    }
+                }
+            }
+        });
+
+        let mut report = WarmUpReport::default();
+        for (target, result) in targets.iter().zip(join_all(tasks).await) {
+            match result {
+                Some(Ok(())) => report.ready.push(target.clone()),
+                Some(Err(e)) => report.failed.push((target.clone(), e)),
+                None => report.timed_out.push(target.clone()),
+            }
+        }
+        report
+    }
+
+    async fn warm_up_target(&self, target: &str, discover_engines: bool) -> Result<()> {
+        self.resolve(target).await?;
+        #[cfg(feature = "v3")]
+        if discover_engines && self.cached_engine(target).is_none() {
+            self.discover_engine(target).await?;
+        }
+        #[cfg(not(feature = "v3"))]
+        if discover_engines {
+            return Err(anyhow!("Engine discovery needs the v3 feature"));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,176 @@
+// Arbitrary impls for structure-aware fuzzing and property tests. Every
+// value generated is one the encoder and parser agree on, so
+// `parse(encode(x)) == x` holds for all of them.
+
+use ::arbitrary::{Arbitrary, Result, Unstructured};
+
+use crate::ber::{Asn1Tag, TagClass};
+use crate::snmp::message::SnmpMessage;
+use crate::snmp::pdu::{
+    ErrorStatus, GenericTrap, ObjectSyntax, Pdu, PduData, VarBind, decode_opaque_float,
+};
+
+// PDUs whose body is request-id, error-status, error-index
+const BASIC_PDU_TAGS: [Asn1Tag; 7] = [
+    Asn1Tag::GetRequest,
+    Asn1Tag::GetNextRequest,
+    Asn1Tag::GetResponse,
+    Asn1Tag::SetRequest,
+    Asn1Tag::InformRequest,
+    Asn1Tag::SnmpV2Trap,
+    Asn1Tag::Report,
+];
+
+/// An OID the encoder can pack: at least two arcs, the first 0, 1 or 2,
+/// and the second below 40 unless the first is 2.
+fn oid(u: &mut Unstructured) -> Result<Vec<u64>> {
+    let first = u.int_in_range(0..=2)?;
+    let second = match first {
+        2 => u.int_in_range(0..=u64::MAX - 80)?,
+        _ => u.int_in_range(0..=39)?,
+    };
+    let mut oid = vec![first, second];
+    for _ in 0..u.int_in_range(0..=16)? {
+        oid.push(u.arbitrary()?);
+    }
+    Ok(oid)
+}
+
+// NaN never equals itself, so it could not round-trip
+fn finite_f32(u: &mut Unstructured) -> Result<f32> {
+    let value = f32::from_bits(u.arbitrary()?);
+    Ok(if value.is_nan() { 0.0 } else { value })
+}
+
+fn finite_f64(u: &mut Unstructured) -> Result<f64> {
+    let value = f64::from_bits(u.arbitrary()?);
+    Ok(if value.is_nan() { 0.0 } else { value })
+}
+
+// a primitive tag no decoder claims, so it comes back as Tagged
+fn unclaimed_tag(u: &mut Unstructured) -> Result<(TagClass, u32)> {
+    let class = *u.choose(&[TagClass::Application, TagClass::Context, TagClass::Private])?;
+    if u.arbitrary()? {
+        return Ok((class, u.int_in_range(0x1F..=u32::MAX)?));
+    }
+    let number = match class {
+        // 0..=4 and 6 are the SNMP application types
+        TagClass::Application => *u.choose(&[5, 7, 8, 9, 10, 30])?,
+        // 0..=2 are the exceptions
+        TagClass::Context => u.int_in_range(3..=30)?,
+        _ => u.int_in_range(0..=30)?,
+    };
+    Ok((class, number))
+}
+
+impl<'a> Arbitrary<'a> for ObjectSyntax {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(match u.int_in_range(0..=16)? {
+            0 => ObjectSyntax::Integer(u.arbitrary()?),
+            1 => ObjectSyntax::OctetString(u.arbitrary()?),
+            2 => ObjectSyntax::Null,
+            3 => ObjectSyntax::ObjectIdentifier(oid(u)?),
+            4 => ObjectSyntax::IpAddress(u.arbitrary()?),
+            5 => ObjectSyntax::Counter32(u.arbitrary()?),
+            6 => ObjectSyntax::Gauge32(u.arbitrary()?),
+            7 => ObjectSyntax::TimeTicks(u.arbitrary()?),
+            8 => {
+                let mut bytes: Vec<u8> = u.arbitrary()?;
+                // one byte more and it no longer reads as a wrapped float
+                if decode_opaque_float(&bytes).is_some() {
+                    bytes.push(0);
+                }
+                ObjectSyntax::Opaque(bytes)
+            }
+            9 => ObjectSyntax::Counter64(u.arbitrary()?),
+            10 => ObjectSyntax::Float(finite_f32(u)?),
+            11 => ObjectSyntax::Double(finite_f64(u)?),
+            12 => ObjectSyntax::NoSuchObject,
+            13 => ObjectSyntax::NoSuchInstance,
+            14 => ObjectSyntax::EndOfMib,
+            _ => {
+                let (class, number) = unclaimed_tag(u)?;
+                ObjectSyntax::Tagged {
+                    class,
+                    number,
+                    constructed: false,
+                    bytes: u.arbitrary()?,
+                }
+            }
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for VarBind {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(VarBind {
+            oid: oid(u)?,
+            value: u.arbitrary()?,
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for Pdu {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let (tag, request_id, data) = match u.int_in_range(0..=2)? {
+            0 => (
+                *u.choose(&BASIC_PDU_TAGS)?,
+                u.arbitrary()?,
+                PduData::Basic {
+                    error_status: u.arbitrary()?,
+                    error_index: u.arbitrary()?,
+                },
+            ),
+            1 => (
+                Asn1Tag::GetBulkRequest,
+                u.arbitrary()?,
+                PduData::Bulk {
+                    non_repeaters: u.arbitrary()?,
+                    max_repititions: u.arbitrary()?,
+                },
+            ),
+            // a Trap-PDU has no request-id, it decodes as 0
+            _ => (
+                Asn1Tag::Trap,
+                0,
+                PduData::Trap {
+                    enterprise: oid(u)?,
+                    agent_addr: u.arbitrary()?,
+                    generic_trap: u.arbitrary()?,
+                    specific_trap: u.arbitrary()?,
+                    time_stamp: u.arbitrary()?,
+                },
+            ),
+        };
+        Ok(Pdu {
+            tag,
+            request_id,
+            data,
+            varbinds: u.arbitrary()?,
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for SnmpMessage {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(SnmpMessage {
+            version: u.int_in_range(0..=1)?,
+            community: u.arbitrary()?,
+            pdu: u.arbitrary()?,
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for ErrorStatus {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let code = u.int_in_range(0..=18)?;
+        Ok(ErrorStatus::try_from(code).expect("every code up to 18 is defined"))
+    }
+}
+
+impl<'a> Arbitrary<'a> for GenericTrap {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let code = u.int_in_range(0..=6)?;
+        Ok(GenericTrap::try_from(code).expect("every code up to 6 is defined"))
+    }
+}
@@ -0,0 +1,261 @@
+// DISPLAY-HINT clauses of textual conventions (RFC 2579 section 3.1).
+//
+// A hint either formats an INTEGER ("d-2" for hundredths, "x" for hex) or
+// is a list of octet specs applied in turn to an OCTET STRING, the last
+// one repeating until the string runs out: "1x:" renders a MacAddress as
+// 0:1a:2b:3c:4d:5e, "1d.1d.1d.1d" an IPv4 address, "255a" plain text.
+
+use std::fmt::Write;
+use std::str::FromStr;
+
+use thiserror::Error;
+
+use crate::snmp::pdu::ObjectSyntax;
+
+// 'd', 'x' and 'o' read the octets as one big-endian number
+const MAX_NUMBER_LEN: usize = 8;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum DisplayHintError {
+    #[error("Empty display hint")]
+    Empty,
+
+    #[error("Expected an octet length at '{0}'")]
+    MissingLength(String),
+
+    #[error("Octet length must be at least 1")]
+    ZeroLength,
+
+    #[error("Format '{format}' reads at most {MAX_NUMBER_LEN} octets, got {len}")]
+    NumberTooLong { format: char, len: usize },
+
+    #[error("Unknown format character '{0}'")]
+    UnknownFormat(char),
+
+    #[error("Unexpected '{0}' after the integer format")]
+    TrailingInput(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntegerFormat {
+    /// Decimal, with this many digits after an implied decimal point.
+    Decimal(u8),
+    Hex,
+    Octal,
+    Binary,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OctetFormat {
+    Decimal,
+    Hex,
+    Octal,
+    Ascii,
+    Utf8,
+}
+
+/// One `[*]<length><format>[separator[terminator]]` part of an octet hint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OctetSpec {
+    /// The first octet says how many times the spec applies.
+    pub repeat: bool,
+    pub length: usize,
+    pub format: OctetFormat,
+    pub separator: Option<char>,
+    /// Only with `repeat` and a separator, emitted after each repetition.
+    pub terminator: Option<char>,
+}
+
+/// A parsed DISPLAY-HINT, e.g. `"1x:".parse::<DisplayHint>()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DisplayHint {
+    Integer(IntegerFormat),
+    Octets(Vec<OctetSpec>),
+}
+
+impl FromStr for DisplayHint {
+    type Err = DisplayHintError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.chars().next() {
+            None => Err(DisplayHintError::Empty),
+            Some(c) if c.is_ascii_digit() || c == '*' => parse_octet_specs(s).map(Self::Octets),
+            Some(_) => parse_integer_format(s).map(Self::Integer),
+        }
+    }
+}
+
+fn parse_integer_format(s: &str) -> Result<IntegerFormat, DisplayHintError> {
+    let mut chars = s.chars();
+    let format = match chars.next() {
+        Some('d') => {
+            let rest = chars.as_str();
+            if rest.is_empty() {
+                return Ok(IntegerFormat::Decimal(0));
+            }
+            return rest
+                .strip_prefix('-')
+                .filter(|digits| !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()))
+                .and_then(|digits| digits.parse().ok())
+                .map(IntegerFormat::Decimal)
+                .ok_or_else(|| DisplayHintError::TrailingInput(rest.to_string()));
+        }
+        Some('x') => IntegerFormat::Hex,
+        Some('o') => IntegerFormat::Octal,
+        Some('b') => IntegerFormat::Binary,
+        Some(c) => return Err(DisplayHintError::UnknownFormat(c)),
+        None => return Err(DisplayHintError::Empty),
+    };
+    match chars.as_str() {
+        "" => Ok(format),
+        rest => Err(DisplayHintError::TrailingInput(rest.to_string())),
+    }
+}
+
+fn parse_octet_specs(s: &str) -> Result<Vec<OctetSpec>, DisplayHintError> {
+    // separators and terminators are any character that can't start the
+    // next spec
+    let is_delimiter = |c: &char| !c.is_ascii_digit() && *c != '*';
+    let mut specs = Vec::new();
+    let mut chars = s.chars().peekable();
+    while chars.peek().is_some() {
+        let repeat = chars.next_if_eq(&'*').is_some();
+        let mut digits = String::new();
+        while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+            digits.push(digit);
+        }
+        let length: usize = digits
+            .parse()
+            .map_err(|_| DisplayHintError::MissingLength(chars.clone().collect()))?;
+        if length == 0 {
+            return Err(DisplayHintError::ZeroLength);
+        }
+        let format = match chars.next() {
+            Some('d') => OctetFormat::Decimal,
+            Some('x') => OctetFormat::Hex,
+            Some('o') => OctetFormat::Octal,
+            Some('a') => OctetFormat::Ascii,
+            Some('t') => OctetFormat::Utf8,
+            Some(c) => return Err(DisplayHintError::UnknownFormat(c)),
+            None => return Err(DisplayHintError::MissingLength(String::new())),
+        };
+        let numeric = match format {
+            OctetFormat::Decimal => Some('d'),
+            OctetFormat::Hex => Some('x'),
+            OctetFormat::Octal => Some('o'),
+            OctetFormat::Ascii | OctetFormat::Utf8 => None,
+        };
+        if let Some(format) = numeric
+            && length > MAX_NUMBER_LEN
+        {
+            return Err(DisplayHintError::NumberTooLong {
+                format,
+                len: length,
+            });
+        }
+        let separator = chars.next_if(is_delimiter);
+        let terminator = if repeat && separator.is_some() {
+            chars.next_if(is_delimiter)
+        } else {
+            None
+        };
+        specs.push(OctetSpec {
+            repeat,
+            length,
+            format,
+            separator,
+            terminator,
+        });
+    }
+    Ok(specs)
+}
+
+impl DisplayHint {
+    /// Renders `value` the way net-snmp does, or `None` if the hint is for
+    /// another type: integer hints take any of the integer types, octet
+    /// hints an OCTET STRING.
+    pub fn format(&self, value: &ObjectSyntax) -> Option<String> {
+        match self {
+            DisplayHint::Integer(format) => {
+                let value = match *value {
+                    ObjectSyntax::Integer(value) => i128::from(value),
+                    _ => i128::from(value.as_u64()?),
+                };
+                Some(format_integer(*format, value))
+            }
+            DisplayHint::Octets(specs) => match value {
+                ObjectSyntax::OctetString(bytes) => Some(format_octets(specs, bytes)),
+                _ => None,
+            },
+        }
+    }
+}
+
+fn format_integer(format: IntegerFormat, value: i128) -> String {
+    let sign = if value < 0 { "-" } else { "" };
+    let magnitude = value.unsigned_abs();
+    match format {
+        IntegerFormat::Decimal(0) => value.to_string(),
+        IntegerFormat::Decimal(places) => {
+            // zero-padded so there is always a digit before the point
+            let digits = format!("{:0width$}", magnitude, width = places as usize + 1);
+            let (whole, fraction) = digits.split_at(digits.len() - places as usize);
+            format!("{}{}.{}", sign, whole, fraction)
+        }
+        IntegerFormat::Hex => format!("{}{:x}", sign, magnitude),
+        IntegerFormat::Octal => format!("{}{:o}", sign, magnitude),
+        IntegerFormat::Binary => format!("{}{:b}", sign, magnitude),
+    }
+}
+
+fn format_octets(specs: &[OctetSpec], mut bytes: &[u8]) -> String {
+    let mut out = String::new();
+    let mut next = 0;
+    while !bytes.is_empty() {
+        // the last spec applies to whatever is left
+        let spec = &specs[next.min(specs.len() - 1)];
+        next += 1;
+        let count = if spec.repeat {
+            let count = bytes[0];
+            bytes = &bytes[1..];
+            count as usize
+        } else {
+            1
+        };
+        for i in 0..count {
+            if bytes.is_empty() {
+                break;
+            }
+            let (chunk, rest) = bytes.split_at(spec.length.min(bytes.len()));
+            bytes = rest;
+            write_chunk(&mut out, spec.format, chunk);
+            let terminated = spec.terminator.is_some() && i + 1 == count;
+            if let Some(separator) = spec.separator
+                && !bytes.is_empty()
+                && !terminated
+            {
+                out.push(separator);
+            }
+        }
+        if let Some(terminator) = spec.terminator
+            && !bytes.is_empty()
+        {
+            out.push(terminator);
+        }
+    }
+    out
+}
+
+fn write_chunk(out: &mut String, format: OctetFormat, chunk: &[u8]) {
+    let number = || chunk.iter().fold(0u64, |n, &b| n << 8 | u64::from(b));
+    // net-snmp doesn't zero-pad: a MAC address comes out as 0:c:29:...
+    let _ = match format {
+        OctetFormat::Decimal => write!(out, "{}", number()),
+        OctetFormat::Hex => write!(out, "{:x}", number()),
+        OctetFormat::Octal => write!(out, "{:o}", number()),
+        OctetFormat::Ascii | OctetFormat::Utf8 => {
+            out.push_str(&String::from_utf8_lossy(chunk));
+            Ok(())
+        }
+    };
+}
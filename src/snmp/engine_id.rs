@@ -0,0 +1,262 @@
+// SnmpEngineID (RFC 3411 section 5, SNMP-FRAMEWORK-MIB)
+//
+// Engine IDs are 5 to 32 octets. If the high bit of the first octet is set
+// the first four octets are the IANA enterprise number and the fifth octet
+// says how the rest should be read. Old style (RFC 1910) IDs have the bit
+// clear and are always 12 octets: enterprise number + 8 opaque octets.
+
+use std::fmt;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use thiserror::Error;
+
+/// net-snmp's enterprise number, the usual choice for software agents.
+pub const NET_SNMP_ENTERPRISE: u32 = 8072;
+
+const MIN_LEN: usize = 5;
+const MAX_LEN: usize = 32;
+// what is left for text/octets after enterprise + format octet
+const MAX_DATA_LEN: usize = MAX_LEN - 5;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum EngineIdError {
+    #[error("Engine ID must be {MIN_LEN} to {MAX_LEN} octets long, got {0}")]
+    InvalidLength(usize),
+
+    #[error("Engine ID data must be at most {MAX_DATA_LEN} octets, got {0}")]
+    DataTooLong(usize),
+
+    #[error("Enterprise number {0} does not fit in 31 bits")]
+    InvalidEnterprise(u32),
+
+    #[error("Invalid hex in engine ID: '{0}'")]
+    InvalidHex(String),
+}
+
+/// The fifth octet of an RFC 3411 engine ID and the data it describes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EngineIdFormat {
+    Ipv4(Ipv4Addr),
+    Ipv6(Ipv6Addr),
+    Mac([u8; 6]),
+    Text(String),
+    Octets(Vec<u8>),
+    /// 6-127 are reserved, 128-255 enterprise specific
+    Other {
+        format: u8,
+        data: Vec<u8>,
+    },
+}
+
+impl EngineIdFormat {
+    fn code(&self) -> u8 {
+        match self {
+            EngineIdFormat::Ipv4(_) => 1,
+            EngineIdFormat::Ipv6(_) => 2,
+            EngineIdFormat::Mac(_) => 3,
+            EngineIdFormat::Text(_) => 4,
+            EngineIdFormat::Octets(_) => 5,
+            EngineIdFormat::Other { format, .. } => *format,
+        }
+    }
+
+    fn data(&self) -> Vec<u8> {
+        match self {
+            EngineIdFormat::Ipv4(addr) => addr.octets().to_vec(),
+            EngineIdFormat::Ipv6(addr) => addr.octets().to_vec(),
+            EngineIdFormat::Mac(mac) => mac.to_vec(),
+            EngineIdFormat::Text(text) => text.as_bytes().to_vec(),
+            EngineIdFormat::Octets(data) | EngineIdFormat::Other { data, .. } => data.clone(),
+        }
+    }
+
+    fn decode(format: u8, data: &[u8]) -> Self {
+        match (format, data.len()) {
+            (1, 4) => EngineIdFormat::Ipv4(Ipv4Addr::new(data[0], data[1], data[2], data[3])),
+            (2, 16) => {
+                let mut octets = [0u8; 16];
+                octets.copy_from_slice(data);
+                EngineIdFormat::Ipv6(Ipv6Addr::from(octets))
+            }
+            (3, 6) => {
+                let mut mac = [0u8; 6];
+                mac.copy_from_slice(data);
+                EngineIdFormat::Mac(mac)
+            }
+            (4, _) => match std::str::from_utf8(data) {
+                Ok(text) => EngineIdFormat::Text(text.to_string()),
+                Err(_) => EngineIdFormat::Other {
+                    format,
+                    data: data.to_vec(),
+                },
+            },
+            (5, _) => EngineIdFormat::Octets(data.to_vec()),
+            // a well known format with the wrong length is kept as-is
+            _ => EngineIdFormat::Other {
+                format,
+                data: data.to_vec(),
+            },
+        }
+    }
+}
+
+/// The structure of a received engine ID.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EngineIdInfo {
+    Rfc3411 {
+        enterprise: u32,
+        format: EngineIdFormat,
+    },
+    Rfc1910 {
+        enterprise: u32,
+        data: Vec<u8>,
+    },
+    /// Doesn't follow either convention; only the raw octets are meaningful.
+    Unstructured,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EngineId(Vec<u8>);
+
+impl EngineId {
+    /// Wraps octets received on the wire (or configured by hand).
+    pub fn from_bytes(bytes: impl Into<Vec<u8>>) -> Result<Self, EngineIdError> {
+        let bytes = bytes.into();
+        if !(MIN_LEN..=MAX_LEN).contains(&bytes.len()) {
+            return Err(EngineIdError::InvalidLength(bytes.len()));
+        }
+        Ok(Self(bytes))
+    }
+
+    /// Parses the `80001f8803...` hex form used in agent configuration files.
+    /// An optional `0x` prefix and `:` separators are accepted.
+    pub fn from_hex(hex: &str) -> Result<Self, EngineIdError> {
+        let digits: String = hex
+            .trim()
+            .trim_start_matches("0x")
+            .chars()
+            .filter(|c| *c != ':')
+            .collect();
+        // checked first, since the pairs are sliced out by byte
+        if !digits.len().is_multiple_of(2) || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(EngineIdError::InvalidHex(hex.to_string()));
+        }
+
+        let bytes = (0..digits.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&digits[i..i + 2], 16))
+            .collect::<Result<Vec<u8>, _>>()
+            .map_err(|_| EngineIdError::InvalidHex(hex.to_string()))?;
+        Self::from_bytes(bytes)
+    }
+
+    /// Builds an RFC 3411 engine ID: enterprise number with the high bit set,
+    /// the format octet, then the data.
+    pub fn new(enterprise: u32, format: EngineIdFormat) -> Result<Self, EngineIdError> {
+        if enterprise & 0x8000_0000 != 0 {
+            return Err(EngineIdError::InvalidEnterprise(enterprise));
+        }
+
+        let data = format.data();
+        if data.len() > MAX_DATA_LEN {
+            return Err(EngineIdError::DataTooLong(data.len()));
+        }
+
+        let mut bytes = Vec::with_capacity(5 + data.len());
+        bytes.extend_from_slice(&(enterprise | 0x8000_0000).to_be_bytes());
+        bytes.push(format.code());
+        bytes.extend_from_slice(&data);
+        Ok(Self(bytes))
+    }
+
+    pub fn from_ipv4(enterprise: u32, addr: Ipv4Addr) -> Result<Self, EngineIdError> {
+        Self::new(enterprise, EngineIdFormat::Ipv4(addr))
+    }
+
+    pub fn from_ipv6(enterprise: u32, addr: Ipv6Addr) -> Result<Self, EngineIdError> {
+        Self::new(enterprise, EngineIdFormat::Ipv6(addr))
+    }
+
+    pub fn from_mac(enterprise: u32, mac: [u8; 6]) -> Result<Self, EngineIdError> {
+        Self::new(enterprise, EngineIdFormat::Mac(mac))
+    }
+
+    pub fn from_text(enterprise: u32, text: &str) -> Result<Self, EngineIdError> {
+        Self::new(enterprise, EngineIdFormat::Text(text.to_string()))
+    }
+
+    /// 12 random octets in the "octets" format; unique enough for a local
+    /// engine that has nothing better to derive its ID from. Persist it,
+    /// agents are expected to keep their engine ID across restarts.
+    pub fn random(enterprise: u32) -> Result<Self, EngineIdError> {
+        let data: [u8; 12] = rand::random();
+        Self::new(enterprise, EngineIdFormat::Octets(data.to_vec()))
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.0
+    }
+
+    pub fn info(&self) -> EngineIdInfo {
+        let bytes = &self.0;
+        let enterprise = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+
+        if bytes[0] & 0x80 != 0 {
+            EngineIdInfo::Rfc3411 {
+                enterprise: enterprise & 0x7FFF_FFFF,
+                format: EngineIdFormat::decode(bytes[4], &bytes[5..]),
+            }
+        } else if bytes.len() == 12 {
+            EngineIdInfo::Rfc1910 {
+                enterprise,
+                data: bytes[4..].to_vec(),
+            }
+        } else {
+            EngineIdInfo::Unstructured
+        }
+    }
+
+    /// Human readable breakdown, e.g. `enterprise 8072, IPv4 192.0.2.1`.
+    pub fn describe(&self) -> String {
+        match self.info() {
+            EngineIdInfo::Rfc3411 { enterprise, format } => {
+                let detail = match format {
+                    EngineIdFormat::Ipv4(addr) => format!("IPv4 {}", addr),
+                    EngineIdFormat::Ipv6(addr) => format!("IPv6 {}", addr),
+                    EngineIdFormat::Mac(mac) => format!(
+                        "MAC {}",
+                        mac.iter()
+                            .map(|b| format!("{:02x}", b))
+                            .collect::<Vec<_>>()
+                            .join(":")
+                    ),
+                    EngineIdFormat::Text(text) => format!("text \"{}\"", text),
+                    EngineIdFormat::Octets(data) => format!("octets {}", hex(&data)),
+                    EngineIdFormat::Other { format, data } => {
+                        format!("format {} data {}", format, hex(&data))
+                    }
+                };
+                format!("enterprise {}, {}", enterprise, detail)
+            }
+            EngineIdInfo::Rfc1910 { enterprise, data } => {
+                format!("enterprise {} (RFC 1910), data {}", enterprise, hex(&data))
+            }
+            EngineIdInfo::Unstructured => format!("unstructured {}", hex(&self.0)),
+        }
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// the plain hex form agents print and accept in their config
+impl fmt::Display for EngineId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex(&self.0))
+    }
+}
@@ -1,9 +1,21 @@
 use crate::{
-    ber::{Asn1Tag, BerError, BerResult, decoder::decode_integer, encoder, parse_ber_object},
+    ber::{
+        Asn1Tag, BerError, BerObject, BerResult, decoder::decode_integer, encoder, parse_ber_object,
+    },
     snmp::pdu::{Pdu, parse_pdu},
 };
 
+#[cfg(feature = "v3")]
+mod v3;
+
+#[cfg(feature = "v3")]
+pub use v3::{
+    FLAG_AUTH, FLAG_PRIV, FLAG_REPORTABLE, HeaderData, SECURITY_MODEL_USM, SNMP_VERSION_3,
+    ScopedPdu, ScopedPduData, SnmpV3Message, parse_scoped_pdu, parse_v3_message,
+};
+
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SnmpMessage {
     pub version: i32,
     pub community: Vec<u8>,
@@ -11,6 +23,16 @@ pub struct SnmpMessage {
 }
 
 pub fn parse_message(inpt: &[u8]) -> BerResult<SnmpMessage> {
+    let (version, community, pdu_object) = parse_message_header(inpt)?;
+    Ok(SnmpMessage {
+        version,
+        community: community.to_vec(),
+        pdu: parse_pdu(pdu_object)?,
+    })
+}
+
+// the version and community of a v1/v2c message, leaving its PDU unparsed
+pub(crate) fn parse_message_header(inpt: &[u8]) -> BerResult<(i32, &[u8], BerObject<'_>)> {
     let (msgobj, rest) = parse_ber_object(inpt)?;
 
     if msgobj.tag != Asn1Tag::Sequence {
@@ -46,11 +68,9 @@ pub fn parse_message(inpt: &[u8]) -> BerResult<SnmpMessage> {
         });
     }
 
-    let community = comm.value.to_vec();
     current_slice = rest;
 
     let (pdu_object, rest) = parse_ber_object(current_slice)?;
-    let pdu = parse_pdu(pdu_object)?;
     current_slice = rest;
 
     // at this point there should be nothing
@@ -58,25 +78,120 @@ pub fn parse_message(inpt: &[u8]) -> BerResult<SnmpMessage> {
         return Err(BerError::TrailingData);
     }
 
-    Ok(SnmpMessage {
-        version,
-        community,
-        pdu,
-    })
+    Ok((version, comm.value, pdu_object))
 }
 
 impl SnmpMessage {
+    fn content_len(&self) -> usize {
+        encoder::tlv_len(encoder::integer_len(self.version))
+            + encoder::tlv_len(self.community.len())
+            + self.pdu.encoded_len()
+    }
+
+    /// Size of the encoded message.
+    pub fn encoded_len(&self) -> usize {
+        encoder::tlv_len(self.content_len())
+    }
+
     pub fn write_to_buf(&self, buf: &mut Vec<u8>) {
-        encoder::encode_sequence_with(buf, |content_buf| {
-            encoder::encode_integer(content_buf, self.version);
-            encoder::encode_octet_string(content_buf, &self.community);
-            self.pdu.write_to_buf(content_buf);
-        });
+        encoder::encode_header(buf, Asn1Tag::Sequence, self.content_len());
+        encoder::encode_integer(buf, self.version);
+        encoder::encode_octet_string(buf, &self.community);
+        self.pdu.write_to_buf(buf);
     }
 
+    /// Encodes into one buffer sized up front.
     pub fn to_bytes(&self) -> Vec<u8> {
-        let mut buf = Vec::new();
+        let mut buf = Vec::with_capacity(self.encoded_len());
         self.write_to_buf(&mut buf);
         buf
     }
 }
+
+/// Reads only the version field, so callers can pick between
+/// `parse_message` and `parse_v3_message`.
+pub fn peek_version(input: &[u8]) -> BerResult<i32> {
+    let (msgobj, _) = parse_ber_object(input)?;
+    if msgobj.tag != Asn1Tag::Sequence {
+        return Err(BerError::UnexpectedTag {
+            expected: Asn1Tag::Sequence,
+            got: msgobj.tag,
+        });
+    }
+    let (ver_obj, _) = parse_ber_object(msgobj.value)?;
+    if ver_obj.tag != Asn1Tag::Integer {
+        return Err(BerError::UnexpectedTag {
+            expected: Asn1Tag::Integer,
+            got: ver_obj.tag,
+        });
+    }
+    decode_integer(ver_obj.value)
+}
+
+/// The fields that say whose answer a message is, read without decoding
+/// the varbinds. v3 keeps the community and PDU behind its security
+/// parameters, so they are `None` there and `request_id` is the msgID.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MessageHeader<'a> {
+    pub version: i32,
+    pub community: Option<&'a [u8]>,
+    pub pdu_tag: Option<Asn1Tag>,
+    pub request_id: i32,
+}
+
+pub fn peek_header(input: &[u8]) -> BerResult<MessageHeader<'_>> {
+    let (msgobj, _) = parse_ber_object(input)?;
+    if msgobj.tag != Asn1Tag::Sequence {
+        return Err(BerError::UnexpectedTag {
+            expected: Asn1Tag::Sequence,
+            got: msgobj.tag,
+        });
+    }
+    let (version, rest) = parse_integer_field(msgobj.value)?;
+    if version == 3 {
+        // msgID comes first in the header
+        let (header, _) = parse_ber_object(rest)?;
+        return Ok(MessageHeader {
+            version,
+            community: None,
+            pdu_tag: None,
+            request_id: parse_integer_field(header.value)?.0,
+        });
+    }
+    let (community, rest) = parse_octet_string_field(rest)?;
+    let (pdu, _) = parse_ber_object(rest)?;
+    Ok(MessageHeader {
+        version,
+        community: Some(community),
+        pdu_tag: Some(pdu.tag),
+        request_id: parse_integer_field(pdu.value)?.0,
+    })
+}
+
+/// Reads the request-id of a v1/v2c message, or the msgID of a v3 one,
+/// without decoding the rest. Enough to tell whose answer a datagram is.
+pub fn peek_request_id(input: &[u8]) -> BerResult<i32> {
+    peek_header(input).map(|header| header.request_id)
+}
+
+pub(crate) fn parse_integer_field(input: &[u8]) -> BerResult<(i32, &[u8])> {
+    let (obj, rest) = parse_ber_object(input)?;
+    if obj.tag != Asn1Tag::Integer {
+        return Err(BerError::UnexpectedTag {
+            expected: Asn1Tag::Integer,
+            got: obj.tag,
+        });
+    }
+    Ok((decode_integer(obj.value)?, rest))
+}
+
+pub(crate) fn parse_octet_string_field(input: &[u8]) -> BerResult<(&[u8], &[u8])> {
+    let (obj, rest) = parse_ber_object(input)?;
+    if obj.tag != Asn1Tag::OctetString {
+        return Err(BerError::UnexpectedTag {
+            expected: Asn1Tag::OctetString,
+            got: obj.tag,
+        });
+    }
+    Ok((obj.value, rest))
+}
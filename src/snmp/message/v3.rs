@@ -0,0 +1,214 @@
+// SNMPv3 messages (RFC 3412 section 6). The security parameters inside are
+// USM's, so this only exists with the v3 feature.
+
+use super::{parse_integer_field, parse_octet_string_field};
+use crate::{
+    ber::{Asn1Tag, BerError, BerResult, encoder, parse_ber_object},
+    snmp::pdu::{Pdu, parse_pdu},
+    snmp::usm::{UsmSecurityParameters, parse_usm_security_parameters},
+};
+
+// https://datatracker.ietf.org/doc/html/rfc3412#section-6
+pub const SNMP_VERSION_3: i32 = 3;
+pub const SECURITY_MODEL_USM: i32 = 3;
+
+// msgFlags bits
+pub const FLAG_AUTH: u8 = 0x01;
+pub const FLAG_PRIV: u8 = 0x02;
+pub const FLAG_REPORTABLE: u8 = 0x04;
+
+/// msgGlobalData: everything the message processing model needs before
+/// it hands the rest of the message to the security model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeaderData {
+    pub msg_id: i32,
+    pub max_size: i32,
+    pub flags: u8,
+    pub security_model: i32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScopedPdu {
+    pub context_engine_id: Vec<u8>,
+    pub context_name: Vec<u8>,
+    pub pdu: Pdu,
+}
+
+/// msgData: the scopedPDU in the clear, or as the OCTET STRING produced by
+/// the privacy protocol when the priv flag is set.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScopedPduData {
+    Plaintext(ScopedPdu),
+    Encrypted(Vec<u8>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnmpV3Message {
+    pub header: HeaderData,
+    pub security_params: UsmSecurityParameters,
+    pub data: ScopedPduData,
+}
+
+fn parse_header_data(input: &[u8]) -> BerResult<(HeaderData, &[u8])> {
+    let (header_obj, rest) = parse_ber_object(input)?;
+    if header_obj.tag != Asn1Tag::Sequence {
+        return Err(BerError::UnexpectedTag {
+            expected: Asn1Tag::Sequence,
+            got: header_obj.tag,
+        });
+    }
+
+    let (msg_id, current_slice) = parse_integer_field(header_obj.value)?;
+    let (max_size, current_slice) = parse_integer_field(current_slice)?;
+    let (flags, current_slice) = parse_octet_string_field(current_slice)?;
+    let (security_model, current_slice) = parse_integer_field(current_slice)?;
+
+    if !current_slice.is_empty() {
+        return Err(BerError::TrailingData);
+    }
+
+    // msgFlags is always exactly one octet
+    let flags = match flags {
+        [flags] => *flags,
+        _ => return Err(BerError::MalformedLength),
+    };
+
+    Ok((
+        HeaderData {
+            msg_id,
+            max_size,
+            flags,
+            security_model,
+        },
+        rest,
+    ))
+}
+
+pub fn parse_scoped_pdu(input: &[u8]) -> BerResult<ScopedPdu> {
+    let (scoped_obj, rest) = parse_ber_object(input)?;
+    if scoped_obj.tag != Asn1Tag::Sequence {
+        return Err(BerError::UnexpectedTag {
+            expected: Asn1Tag::Sequence,
+            got: scoped_obj.tag,
+        });
+    }
+    if !rest.is_empty() {
+        return Err(BerError::TrailingData);
+    }
+
+    let (context_engine_id, current_slice) = parse_octet_string_field(scoped_obj.value)?;
+    let (context_name, current_slice) = parse_octet_string_field(current_slice)?;
+
+    let (pdu_object, current_slice) = parse_ber_object(current_slice)?;
+    let pdu = parse_pdu(pdu_object)?;
+
+    if !current_slice.is_empty() {
+        return Err(BerError::TrailingData);
+    }
+
+    Ok(ScopedPdu {
+        context_engine_id: context_engine_id.to_vec(),
+        context_name: context_name.to_vec(),
+        pdu,
+    })
+}
+
+pub fn parse_v3_message(input: &[u8]) -> BerResult<SnmpV3Message> {
+    let (msgobj, rest) = parse_ber_object(input)?;
+
+    if msgobj.tag != Asn1Tag::Sequence {
+        return Err(BerError::UnexpectedTag {
+            expected: Asn1Tag::Sequence,
+            got: msgobj.tag,
+        });
+    }
+
+    if !rest.is_empty() {
+        return Err(BerError::TrailingData);
+    }
+
+    let (version, current_slice) = parse_integer_field(msgobj.value)?;
+    if version != SNMP_VERSION_3 {
+        return Err(BerError::InvalidEnumValue(version));
+    }
+
+    let (header, current_slice) = parse_header_data(current_slice)?;
+    if header.security_model != SECURITY_MODEL_USM {
+        return Err(BerError::InvalidEnumValue(header.security_model));
+    }
+
+    // msgSecurityParameters is an OCTET STRING wrapping the USM SEQUENCE
+    let (security_bytes, current_slice) = parse_octet_string_field(current_slice)?;
+    let security_params = parse_usm_security_parameters(security_bytes)?;
+
+    let data = if header.flags & FLAG_PRIV != 0 {
+        let (encrypted, rest) = parse_octet_string_field(current_slice)?;
+        if !rest.is_empty() {
+            return Err(BerError::TrailingData);
+        }
+        ScopedPduData::Encrypted(encrypted.to_vec())
+    } else {
+        ScopedPduData::Plaintext(parse_scoped_pdu(current_slice)?)
+    };
+
+    Ok(SnmpV3Message {
+        header,
+        security_params,
+        data,
+    })
+}
+
+impl HeaderData {
+    pub fn write_to_buf(&self, buf: &mut Vec<u8>) {
+        encoder::encode_sequence_with(buf, |content_buf| {
+            encoder::encode_integer(content_buf, self.msg_id);
+            encoder::encode_integer(content_buf, self.max_size);
+            encoder::encode_octet_string(content_buf, &[self.flags]);
+            encoder::encode_integer(content_buf, self.security_model);
+        });
+    }
+}
+
+impl ScopedPdu {
+    pub fn write_to_buf(&self, buf: &mut Vec<u8>) {
+        encoder::encode_sequence_with(buf, |content_buf| {
+            encoder::encode_octet_string(content_buf, &self.context_engine_id);
+            encoder::encode_octet_string(content_buf, &self.context_name);
+            self.pdu.write_to_buf(content_buf);
+        });
+    }
+}
+
+impl ScopedPduData {
+    pub fn write_to_buf(&self, buf: &mut Vec<u8>) {
+        match self {
+            ScopedPduData::Plaintext(scoped_pdu) => scoped_pdu.write_to_buf(buf),
+            ScopedPduData::Encrypted(bytes) => encoder::encode_octet_string(buf, bytes),
+        }
+    }
+}
+
+impl SnmpV3Message {
+    pub fn write_to_buf(&self, buf: &mut Vec<u8>) {
+        encoder::encode_sequence_with(buf, |content_buf| {
+            encoder::encode_integer(content_buf, SNMP_VERSION_3);
+            self.header.write_to_buf(content_buf);
+            encoder::encode_octet_string(content_buf, &self.security_params.to_bytes());
+            self.data.write_to_buf(content_buf);
+        });
+    }
+
+    /// The scopedPDU, if it has been decrypted (or was never encrypted).
+    pub fn scoped_pdu(&self) -> Option<&ScopedPdu> {
+        match &self.data {
+            ScopedPduData::Plaintext(scoped_pdu) => Some(scoped_pdu),
+            ScopedPduData::Encrypted(_) => None,
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        self.write_to_buf(&mut buf);
+        buf
+    }
+}
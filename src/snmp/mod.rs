@@ -1,3 +1,14 @@
+#[cfg(feature = "arbitrary")]
+mod arbitrary;
+pub mod display_hint;
 pub mod encoder;
+#[cfg(feature = "v3")]
+pub mod engine_id;
 pub mod message;
 pub mod pdu;
+pub mod report;
+pub mod snmprec;
+pub mod tc;
+#[cfg(feature = "v3")]
+pub mod usm;
+pub mod varbind_ref;
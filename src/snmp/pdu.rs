@@ -1,39 +1,92 @@
+use std::fmt;
+use std::net::IpAddr;
+use std::sync::RwLock;
+
 use crate::ber::decoder::{decode_unsigned_integer, decode_unsigned_integer64};
 use crate::ber::encoder;
-use crate::ber::{Asn1Tag, BerError, parse_ber_object};
-use crate::ber::{BerObject, BerResult, decode_oid, decoder::decode_integer};
+use crate::ber::{Asn1Tag, BerError, TagClass, parse_ber_object, parse_raw_ber_object};
+use crate::ber::{BerObject, BerResult, RawBerObject, decode_oid, decoder::decode_integer};
+use crate::snmp::tc::{DateAndTime, MacAddress};
+
+/// Decodes the content octets of a vendor-specific value tag.
+pub type ValueDecoder = fn(&[u8]) -> BerResult<ObjectSyntax>;
+
+// net-snmp wraps floats in Opaque as a [CONTEXT 120/121] value, which
+// needs the long tag form: 0x9f 0x78 for Float, 0x9f 0x79 for Double.
+const OPAQUE_TAG_LONG: u8 = 0x9F;
+const OPAQUE_FLOAT: u8 = 0x78;
+const OPAQUE_DOUBLE: u8 = 0x79;
+
+static VALUE_DECODERS: RwLock<Vec<(u8, ValueDecoder)>> = RwLock::new(Vec::new());
+
+/// Registers a decoder for varbind values carrying `tag_byte`, for agents
+/// that wrap values in tags outside SNMP's SMI. Applies process-wide and
+/// replaces any decoder registered earlier for the same tag. Tags the
+/// crate already understands can't be overridden, and values under
+/// multi-octet (long-form) tags always come out as `Tagged`.
+pub fn register_value_decoder(tag_byte: u8, decoder: ValueDecoder) {
+    let mut decoders = VALUE_DECODERS.write().unwrap();
+    decoders.retain(|(tag, _)| *tag != tag_byte);
+    decoders.push((tag_byte, decoder));
+}
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct VarBind {
-    pub oid: Vec<u32>,
+    pub oid: Vec<u64>,
     pub value: ObjectSyntax,
 }
 
 impl VarBind {
+    fn content_len(&self) -> usize {
+        encoder::tlv_len(encoder::oid_len(&self.oid)) + self.value.encoded_len()
+    }
+
+    /// Size of the encoded varbind SEQUENCE.
+    pub fn encoded_len(&self) -> usize {
+        encoder::tlv_len(self.content_len())
+    }
+
     pub fn write_to_buf(&self, buf: &mut Vec<u8>) {
-        encoder::encode_sequence_with(buf, |content_buf| {
-            encoder::encode_oid(content_buf, &self.oid);
-            self.value.write_to_buf(content_buf);
-        });
+        encoder::encode_header(buf, Asn1Tag::Sequence, self.content_len());
+        encoder::encode_oid(buf, &self.oid);
+        self.value.write_to_buf(buf);
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ObjectSyntax {
     Integer(i32),
     OctetString(Vec<u8>),
     Null,
-    ObjectIdentifier(Vec<u32>),
+    ObjectIdentifier(Vec<u64>),
     IpAddress(Vec<u8>),
     Counter32(u32),
     Gauge32(u32),
     TimeTicks(u32),
     Opaque(Vec<u8>),
     Counter64(u64),
+    /// net-snmp's Opaque-wrapped float.
+    Float(f32),
+    /// net-snmp's Opaque-wrapped double.
+    Double(f64),
 
     NoSuchObject,
     NoSuchInstance,
     EndOfMib,
+
+    /// A value under a tag SNMP doesn't define, e.g. a [CONTEXT n]
+    /// wrapped vendor value, with no decoder registered for it.
+    Tagged {
+        class: TagClass,
+        number: u32,
+        /// Whether the tag had its constructed bit set, so `bytes` are
+        /// nested TLVs.
+        #[cfg_attr(feature = "serde", serde(default))]
+        constructed: bool,
+        bytes: Vec<u8>,
+    },
 }
 
 impl ObjectSyntax {
@@ -65,10 +118,8 @@ impl ObjectSyntax {
                 let val = decode_unsigned_integer(obj.value)?;
                 Ok(ObjectSyntax::TimeTicks(val))
             }
-            Asn1Tag::Opaque => {
-                // Opaque is also just an OctetString
-                Ok(ObjectSyntax::Opaque(obj.value.to_vec()))
-            }
+            Asn1Tag::Opaque => Ok(decode_opaque_float(obj.value)
+                .unwrap_or_else(|| ObjectSyntax::Opaque(obj.value.to_vec()))),
             Asn1Tag::Counter64 => {
                 let val = decode_unsigned_integer64(obj.value)?;
                 Ok(ObjectSyntax::Counter64(val))
@@ -80,6 +131,207 @@ impl ObjectSyntax {
         }
     }
 
+    /// Decodes a value whose tag has no [`Asn1Tag`], using a registered
+    /// decoder if there is one.
+    pub fn from_raw_ber(obj: RawBerObject) -> BerResult<Self> {
+        let decoder = VALUE_DECODERS
+            .read()
+            .unwrap()
+            .iter()
+            .find(|(tag, _)| *tag == obj.tag_byte && obj.number < 0x1F)
+            .map(|(_, decoder)| *decoder);
+
+        match decoder {
+            Some(decoder) => decoder(obj.value),
+            None => Ok(ObjectSyntax::Tagged {
+                class: obj.class(),
+                number: obj.number,
+                constructed: obj.is_constructed(),
+                bytes: obj.value.to_vec(),
+            }),
+        }
+    }
+
+    /// The text of an OCTET STRING, if it is valid UTF-8.
+    pub fn as_str(&self) -> Option<&str> {
+        let ObjectSyntax::OctetString(bytes) = self else {
+            return None;
+        };
+        std::str::from_utf8(bytes).ok()
+    }
+
+    /// The contents of an OCTET STRING or Opaque.
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            ObjectSyntax::OctetString(bytes) | ObjectSyntax::Opaque(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    /// Any of the integer types, if the value fits.
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            ObjectSyntax::Integer(value) => Some(value.into()),
+            _ => self.as_u64()?.try_into().ok(),
+        }
+    }
+
+    /// The unsigned types, or a non-negative Integer. Counters, gauges and
+    /// TimeTicks all come out the same, so callers needn't care which one
+    /// the agent used.
+    pub fn as_u64(&self) -> Option<u64> {
+        match *self {
+            ObjectSyntax::Integer(value) => value.try_into().ok(),
+            ObjectSyntax::Counter32(value)
+            | ObjectSyntax::Gauge32(value)
+            | ObjectSyntax::TimeTicks(value) => Some(value.into()),
+            ObjectSyntax::Counter64(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// An IpAddress holding 4 octets, or 16 from agents that put IPv6
+    /// addresses there.
+    pub fn as_ipaddr(&self) -> Option<IpAddr> {
+        let ObjectSyntax::IpAddress(bytes) = self else {
+            return None;
+        };
+        match bytes.len() {
+            4 => Some(IpAddr::from(<[u8; 4]>::try_from(&bytes[..]).ok()?)),
+            16 => Some(IpAddr::from(<[u8; 16]>::try_from(&bytes[..]).ok()?)),
+            _ => None,
+        }
+    }
+
+    /// A DateAndTime OCTET STRING. `None` for any other type, or octets
+    /// that aren't a valid DateAndTime.
+    pub fn as_date_and_time(&self) -> Option<DateAndTime> {
+        let ObjectSyntax::OctetString(bytes) = self else {
+            return None;
+        };
+        DateAndTime::from_bytes(bytes)
+    }
+
+    /// A MacAddress (or PhysAddress) of 6 octets.
+    pub fn as_mac_address(&self) -> Option<MacAddress> {
+        let ObjectSyntax::OctetString(bytes) = self else {
+            return None;
+        };
+        Some(MacAddress(bytes[..].try_into().ok()?))
+    }
+
+    /// A TruthValue: true(1) or false(2).
+    pub fn as_truth_value(&self) -> Option<bool> {
+        match self {
+            ObjectSyntax::Integer(1) => Some(true),
+            ObjectSyntax::Integer(2) => Some(false),
+            _ => None,
+        }
+    }
+
+    pub fn as_oid(&self) -> Option<&[u64]> {
+        match self {
+            ObjectSyntax::ObjectIdentifier(oid) => Some(oid),
+            _ => None,
+        }
+    }
+
+    /// Reads an OCTET STRING as a BITS value (RFC 2578 section 7.1.4) and
+    /// returns the positions of the set bits. Bit 0 is the most significant
+    /// bit of the first octet. `None` for any other type.
+    pub fn bits(&self) -> Option<Vec<u32>> {
+        let ObjectSyntax::OctetString(bytes) = self else {
+            return None;
+        };
+        let mut positions = Vec::new();
+        for (i, byte) in bytes.iter().enumerate() {
+            for bit in 0..8 {
+                if byte & (0x80 >> bit) != 0 {
+                    positions.push(i as u32 * 8 + bit);
+                }
+            }
+        }
+        Some(positions)
+    }
+
+    /// Like [`ObjectSyntax::bits`], but names each set bit from `labels`,
+    /// the `(position, name)` pairs of the object's BITS definition. Bits
+    /// without a label come out as their position.
+    pub fn bit_names(&self, labels: &[(u32, &str)]) -> Option<Vec<String>> {
+        let names = self
+            .bits()?
+            .into_iter()
+            .map(|position| {
+                labels
+                    .iter()
+                    .find(|(label_position, _)| *label_position == position)
+                    .map(|(_, name)| name.to_string())
+                    .unwrap_or_else(|| position.to_string())
+            })
+            .collect();
+        Some(names)
+    }
+
+    /// Names an INTEGER from `labels`, the `(value, name)` pairs of an
+    /// enumerated INTEGER definition, the way net-snmp prints it: `up(1)`.
+    /// Values without a label come out as the number. `None` for any other
+    /// type.
+    pub fn enum_label(&self, labels: &[(i32, &str)]) -> Option<String> {
+        let ObjectSyntax::Integer(value) = *self else {
+            return None;
+        };
+        let label = labels
+            .iter()
+            .find(|(label_value, _)| *label_value == value)
+            .map_or_else(
+                || value.to_string(),
+                |(_, name)| format!("{}({})", name, value),
+            );
+        Some(label)
+    }
+
+    /// The INTEGER named `label` in `labels`, given as `up`, `up(1)` or
+    /// just the number. `None` if the name isn't there, or its number
+    /// doesn't match the one in parentheses.
+    pub fn from_enum_label(label: &str, labels: &[(i32, &str)]) -> Option<Self> {
+        if let Ok(value) = label.parse() {
+            return Some(ObjectSyntax::Integer(value));
+        }
+        let (name, number) = match label.strip_suffix(')').and_then(|l| l.split_once('(')) {
+            Some((name, number)) => (name, Some(number.parse::<i32>().ok()?)),
+            None => (label, None),
+        };
+        let (value, _) = labels.iter().find(|(_, label_name)| *label_name == name)?;
+        if number.is_some_and(|number| number != *value) {
+            return None;
+        }
+        Some(ObjectSyntax::Integer(*value))
+    }
+
+    /// Size of the encoded TLV, without encoding it.
+    pub fn encoded_len(&self) -> usize {
+        match self {
+            ObjectSyntax::Integer(val) => encoder::tlv_len(encoder::integer_len(*val)),
+            ObjectSyntax::OctetString(bytes)
+            | ObjectSyntax::IpAddress(bytes)
+            | ObjectSyntax::Opaque(bytes) => encoder::tlv_len(bytes.len()),
+            ObjectSyntax::Null
+            | ObjectSyntax::NoSuchObject
+            | ObjectSyntax::NoSuchInstance
+            | ObjectSyntax::EndOfMib => 2,
+            ObjectSyntax::ObjectIdentifier(val) => encoder::tlv_len(encoder::oid_len(val)),
+            ObjectSyntax::Counter32(val)
+            | ObjectSyntax::Gauge32(val)
+            | ObjectSyntax::TimeTicks(val) => encoder::tlv_len(encoder::unsigned_len(*val as u64)),
+            ObjectSyntax::Counter64(val) => encoder::tlv_len(encoder::unsigned_len(*val)),
+            ObjectSyntax::Float(_) => encoder::tlv_len(3 + 4),
+            ObjectSyntax::Double(_) => encoder::tlv_len(3 + 8),
+            ObjectSyntax::Tagged { number, bytes, .. } => {
+                encoder::tag_len(*number) + encoder::length_len(bytes.len()) + bytes.len()
+            }
+        }
+    }
+
     // for encoder
     pub fn write_to_buf(&self, buf: &mut Vec<u8>) {
         match self {
@@ -93,6 +345,10 @@ impl ObjectSyntax {
             ObjectSyntax::TimeTicks(val) => encoder::encode_timeticks(buf, *val),
             ObjectSyntax::Opaque(val) => encoder::encode_opaque(buf, val),
             ObjectSyntax::Counter64(val) => encoder::encode_counter64(buf, *val),
+            ObjectSyntax::Float(val) => encode_opaque_float(buf, OPAQUE_FLOAT, &val.to_be_bytes()),
+            ObjectSyntax::Double(val) => {
+                encode_opaque_float(buf, OPAQUE_DOUBLE, &val.to_be_bytes())
+            }
             ObjectSyntax::NoSuchObject => {
                 buf.push(Asn1Tag::NoSuchObject as u8);
                 buf.push(0x00);
@@ -105,10 +361,39 @@ impl ObjectSyntax {
                 buf.push(Asn1Tag::EndOfMib as u8);
                 buf.push(0x00);
             }
+            ObjectSyntax::Tagged {
+                class,
+                number,
+                constructed,
+                bytes,
+            } => {
+                encoder::encode_tag(buf, *class, *constructed, *number);
+                encoder::encode_length(buf, bytes.len());
+                buf.extend_from_slice(bytes);
+            }
         }
     }
 }
 
+// Anything that isn't exactly a wrapped float or double stays Opaque.
+pub(crate) fn decode_opaque_float(bytes: &[u8]) -> Option<ObjectSyntax> {
+    match bytes {
+        [OPAQUE_TAG_LONG, OPAQUE_FLOAT, 4, value @ ..] => Some(ObjectSyntax::Float(
+            f32::from_be_bytes(value.try_into().ok()?),
+        )),
+        [OPAQUE_TAG_LONG, OPAQUE_DOUBLE, 8, value @ ..] => Some(ObjectSyntax::Double(
+            f64::from_be_bytes(value.try_into().ok()?),
+        )),
+        _ => None,
+    }
+}
+
+fn encode_opaque_float(buf: &mut Vec<u8>, tag: u8, value: &[u8]) {
+    encoder::encode_header(buf, Asn1Tag::Opaque, 3 + value.len());
+    buf.extend_from_slice(&[OPAQUE_TAG_LONG, tag, value.len() as u8]);
+    buf.extend_from_slice(value);
+}
+
 pub fn parse_varbind(obj: BerObject) -> BerResult<VarBind> {
     if obj.tag != Asn1Tag::Sequence {
         return Err(BerError::UnexpectedTag {
@@ -127,14 +412,23 @@ pub fn parse_varbind(obj: BerObject) -> BerResult<VarBind> {
     }
 
     let oid = decode_oid(oid_obj.value)?;
-    let (value_obj, rest) = parse_ber_object(rest_after_oid)?;
+
+    let value_tag = rest_after_oid.first().ok_or(BerError::IncompleteData)?;
+    let (value, rest) = match Asn1Tag::from_u8(*value_tag) {
+        Ok(_) => {
+            let (value_obj, rest) = parse_ber_object(rest_after_oid)?;
+            (ObjectSyntax::from_ber(value_obj)?, rest)
+        }
+        Err(_) => {
+            let (raw_obj, rest) = parse_raw_ber_object(rest_after_oid)?;
+            (ObjectSyntax::from_raw_ber(raw_obj)?, rest)
+        }
+    };
 
     if !rest.is_empty() {
         return Err(BerError::TrailingData);
     }
 
-    let value = ObjectSyntax::from_ber(value_obj)?;
-
     Ok(VarBind { oid, value })
 }
 
@@ -163,7 +457,9 @@ pub fn parse_varbind_list(obj: BerObject) -> BerResult<Vec<VarBind>> {
 }
 
 // https://datatracker.ietf.org/doc/html/rfc1157#section-4.1.1
+// 6-18 added by SNMPv2, https://datatracker.ietf.org/doc/html/rfc3416#section-3
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(i32)]
 pub enum ErrorStatus {
     NoError = 0,
@@ -172,6 +468,52 @@ pub enum ErrorStatus {
     BadValue = 3,
     ReadOnly = 4,
     GenErr = 5,
+    NoAccess = 6,
+    WrongType = 7,
+    WrongLength = 8,
+    WrongEncoding = 9,
+    WrongValue = 10,
+    NoCreation = 11,
+    InconsistentValue = 12,
+    ResourceUnavailable = 13,
+    CommitFailed = 14,
+    UndoFailed = 15,
+    AuthorizationError = 16,
+    NotWritable = 17,
+    InconsistentName = 18,
+}
+
+impl ErrorStatus {
+    /// The name RFC 3416 gives the code, e.g. `notWritable`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ErrorStatus::NoError => "noError",
+            ErrorStatus::TooBig => "tooBig",
+            ErrorStatus::NoSuchName => "noSuchName",
+            ErrorStatus::BadValue => "badValue",
+            ErrorStatus::ReadOnly => "readOnly",
+            ErrorStatus::GenErr => "genErr",
+            ErrorStatus::NoAccess => "noAccess",
+            ErrorStatus::WrongType => "wrongType",
+            ErrorStatus::WrongLength => "wrongLength",
+            ErrorStatus::WrongEncoding => "wrongEncoding",
+            ErrorStatus::WrongValue => "wrongValue",
+            ErrorStatus::NoCreation => "noCreation",
+            ErrorStatus::InconsistentValue => "inconsistentValue",
+            ErrorStatus::ResourceUnavailable => "resourceUnavailable",
+            ErrorStatus::CommitFailed => "commitFailed",
+            ErrorStatus::UndoFailed => "undoFailed",
+            ErrorStatus::AuthorizationError => "authorizationError",
+            ErrorStatus::NotWritable => "notWritable",
+            ErrorStatus::InconsistentName => "inconsistentName",
+        }
+    }
+}
+
+impl fmt::Display for ErrorStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}({})", self.name(), *self as i32)
+    }
 }
 
 impl TryFrom<i32> for ErrorStatus {
@@ -185,12 +527,57 @@ impl TryFrom<i32> for ErrorStatus {
             3 => Ok(ErrorStatus::BadValue),
             4 => Ok(ErrorStatus::ReadOnly),
             5 => Ok(ErrorStatus::GenErr),
+            6 => Ok(ErrorStatus::NoAccess),
+            7 => Ok(ErrorStatus::WrongType),
+            8 => Ok(ErrorStatus::WrongLength),
+            9 => Ok(ErrorStatus::WrongEncoding),
+            10 => Ok(ErrorStatus::WrongValue),
+            11 => Ok(ErrorStatus::NoCreation),
+            12 => Ok(ErrorStatus::InconsistentValue),
+            13 => Ok(ErrorStatus::ResourceUnavailable),
+            14 => Ok(ErrorStatus::CommitFailed),
+            15 => Ok(ErrorStatus::UndoFailed),
+            16 => Ok(ErrorStatus::AuthorizationError),
+            17 => Ok(ErrorStatus::NotWritable),
+            18 => Ok(ErrorStatus::InconsistentName),
             _ => Err(BerError::InvalidEnumValue(value)),
         }
     }
 }
 
+// https://datatracker.ietf.org/doc/html/rfc1157#section-4.1.6
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(i32)]
+pub enum GenericTrap {
+    ColdStart = 0,
+    WarmStart = 1,
+    LinkDown = 2,
+    LinkUp = 3,
+    AuthenticationFailure = 4,
+    EgpNeighborLoss = 5,
+    EnterpriseSpecific = 6,
+}
+
+impl TryFrom<i32> for GenericTrap {
+    type Error = BerError;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(GenericTrap::ColdStart),
+            1 => Ok(GenericTrap::WarmStart),
+            2 => Ok(GenericTrap::LinkDown),
+            3 => Ok(GenericTrap::LinkUp),
+            4 => Ok(GenericTrap::AuthenticationFailure),
+            5 => Ok(GenericTrap::EgpNeighborLoss),
+            6 => Ok(GenericTrap::EnterpriseSpecific),
+            _ => Err(BerError::InvalidEnumValue(value)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PduData {
     Basic {
         error_status: ErrorStatus,
@@ -200,9 +587,19 @@ pub enum PduData {
         non_repeaters: i32,
         max_repititions: i32,
     },
+    /// SNMPv1 Trap-PDU body. It has no request-id; `Pdu::request_id` is
+    /// ignored when encoding and 0 when decoding.
+    Trap {
+        enterprise: Vec<u64>,
+        agent_addr: [u8; 4],
+        generic_trap: GenericTrap,
+        specific_trap: i32,
+        time_stamp: u32,
+    },
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Pdu {
     pub tag: Asn1Tag,
     pub request_id: i32,
@@ -211,36 +608,155 @@ pub struct Pdu {
 }
 
 impl Pdu {
+    fn varbind_list_len(&self) -> usize {
+        self.varbinds.iter().map(VarBind::encoded_len).sum()
+    }
+
+    fn content_len(&self) -> usize {
+        let header_len = match &self.data {
+            PduData::Basic {
+                error_status,
+                error_index,
+            } => {
+                encoder::tlv_len(encoder::integer_len(self.request_id))
+                    + encoder::tlv_len(encoder::integer_len(*error_status as i32))
+                    + encoder::tlv_len(encoder::integer_len(*error_index))
+            }
+            PduData::Bulk {
+                non_repeaters,
+                max_repititions,
+            } => {
+                encoder::tlv_len(encoder::integer_len(self.request_id))
+                    + encoder::tlv_len(encoder::integer_len(*non_repeaters))
+                    + encoder::tlv_len(encoder::integer_len(*max_repititions))
+            }
+            PduData::Trap {
+                enterprise,
+                agent_addr,
+                generic_trap,
+                specific_trap,
+                time_stamp,
+            } => {
+                encoder::tlv_len(encoder::oid_len(enterprise))
+                    + encoder::tlv_len(agent_addr.len())
+                    + encoder::tlv_len(encoder::integer_len(*generic_trap as i32))
+                    + encoder::tlv_len(encoder::integer_len(*specific_trap))
+                    + encoder::tlv_len(encoder::unsigned_len(*time_stamp as u64))
+            }
+        };
+        header_len + encoder::tlv_len(self.varbind_list_len())
+    }
+
+    /// Size of the encoded PDU. Lengths are worked out up front, so encoding
+    /// writes every nested TLV straight into the caller's buffer.
+    pub fn encoded_len(&self) -> usize {
+        encoder::tlv_len(self.content_len())
+    }
+
     pub fn write_to_buf(&self, buf: &mut Vec<u8>) {
-        encoder::encode_container_with(buf, self.tag, |content_buf| {
-            encoder::encode_integer(content_buf, self.request_id);
-            match self.data {
-                PduData::Basic {
-                    error_status,
-                    error_index,
-                } => {
-                    encoder::encode_integer(content_buf, error_status as i32);
-                    encoder::encode_integer(content_buf, error_index);
-                }
-                PduData::Bulk {
-                    non_repeaters,
-                    max_repititions,
-                } => {
-                    encoder::encode_integer(content_buf, non_repeaters);
-                    encoder::encode_integer(content_buf, max_repititions);
-                }
+        encoder::encode_header(buf, self.tag, self.content_len());
+        if !matches!(self.data, PduData::Trap { .. }) {
+            encoder::encode_integer(buf, self.request_id);
+        }
+        match &self.data {
+            PduData::Basic {
+                error_status,
+                error_index,
+            } => {
+                encoder::encode_integer(buf, *error_status as i32);
+                encoder::encode_integer(buf, *error_index);
             }
-            encoder::encode_sequence_with(content_buf, |varbind_list_buf| {
-                for varbind in &self.varbinds {
-                    varbind.write_to_buf(varbind_list_buf);
-                }
-            });
+            PduData::Bulk {
+                non_repeaters,
+                max_repititions,
+            } => {
+                encoder::encode_integer(buf, *non_repeaters);
+                encoder::encode_integer(buf, *max_repititions);
+            }
+            PduData::Trap {
+                enterprise,
+                agent_addr,
+                generic_trap,
+                specific_trap,
+                time_stamp,
+            } => {
+                encoder::encode_oid(buf, enterprise);
+                encoder::encode_ip_address(buf, agent_addr);
+                encoder::encode_integer(buf, *generic_trap as i32);
+                encoder::encode_integer(buf, *specific_trap);
+                encoder::encode_timeticks(buf, *time_stamp);
+            }
+        }
+        encoder::encode_header(buf, Asn1Tag::Sequence, self.varbind_list_len());
+        for varbind in &self.varbinds {
+            varbind.write_to_buf(buf);
+        }
+    }
+}
+
+fn parse_field(input: &[u8], expected: Asn1Tag) -> BerResult<(BerObject<'_>, &[u8])> {
+    let (obj, rest) = parse_ber_object(input)?;
+    if obj.tag != expected {
+        return Err(BerError::UnexpectedTag {
+            expected,
+            got: obj.tag,
         });
     }
+    Ok((obj, rest))
+}
+
+// https://datatracker.ietf.org/doc/html/rfc1157#section-4.1.6
+fn parse_trap_header(obj: BerObject<'_>) -> BerResult<(i32, PduData, BerObject<'_>)> {
+    let (enterprise_obj, rest) = parse_field(obj.value, Asn1Tag::ObjectIdentifier)?;
+    let enterprise = decode_oid(enterprise_obj.value)?;
+
+    let (agent_addr_obj, rest) = parse_field(rest, Asn1Tag::IpAddress)?;
+    let agent_addr: [u8; 4] = agent_addr_obj
+        .value
+        .try_into()
+        .map_err(|_| BerError::MalformedLength)?;
+
+    let (generic_obj, rest) = parse_field(rest, Asn1Tag::Integer)?;
+    let generic_trap = GenericTrap::try_from(decode_integer(generic_obj.value)?)?;
+
+    let (specific_obj, rest) = parse_field(rest, Asn1Tag::Integer)?;
+    let specific_trap = decode_integer(specific_obj.value)?;
+
+    let (time_stamp_obj, rest) = parse_field(rest, Asn1Tag::TimeTicks)?;
+    let time_stamp = decode_unsigned_integer(time_stamp_obj.value)?;
+
+    let (varbind_list_obj, rest) = parse_ber_object(rest)?;
+    if !rest.is_empty() {
+        return Err(BerError::TrailingData);
+    }
+
+    let data = PduData::Trap {
+        enterprise,
+        agent_addr,
+        generic_trap,
+        specific_trap,
+        time_stamp,
+    };
+    Ok((0, data, varbind_list_obj))
 }
 
 pub fn parse_pdu(obj: BerObject) -> BerResult<Pdu> {
+    let tag = obj.tag;
+    let (request_id, data, varbind_list_obj) = parse_pdu_header(obj)?;
+    Ok(Pdu {
+        tag,
+        request_id,
+        data,
+        varbinds: parse_varbind_list(varbind_list_obj)?,
+    })
+}
+
+// the request-id and data of a PDU, leaving its varbind list unparsed
+pub(crate) fn parse_pdu_header(obj: BerObject<'_>) -> BerResult<(i32, PduData, BerObject<'_>)> {
     let pdu_tag = obj.tag;
+    if pdu_tag == Asn1Tag::Trap {
+        return parse_trap_header(obj);
+    }
 
     let mut current_slice = obj.value;
 
@@ -316,17 +832,11 @@ pub fn parse_pdu(obj: BerObject) -> BerResult<Pdu> {
     current_slice = rest;
 
     let (varbind_list_obj, rest) = parse_ber_object(current_slice)?;
-    let varbinds = parse_varbind_list(varbind_list_obj)?;
     current_slice = rest;
 
     if !current_slice.is_empty() {
         return Err(BerError::TrailingData);
     }
 
-    Ok(Pdu {
-        tag: pdu_tag,
-        request_id,
-        data: pdu_data,
-        varbinds,
-    })
+    Ok((request_id, pdu_data, varbind_list_obj))
 }
@@ -0,0 +1,107 @@
+// Report PDUs (RFC 3412 section 7.2.4). An agent answers a v3 request it
+// could not process with a Report carrying the counter it incremented, so
+// the first varbind's OID says what went wrong.
+
+use thiserror::Error;
+
+use crate::ber::Asn1Tag;
+use crate::snmp::pdu::Pdu;
+
+// SNMP-USER-BASED-SM-MIB usmStats (RFC 3414)
+const USM_STATS: [u64; 9] = [1, 3, 6, 1, 6, 3, 15, 1, 1];
+// SNMP-MPD-MIB snmpMPDStats (RFC 3412)
+const MPD_STATS: [u64; 9] = [1, 3, 6, 1, 6, 3, 11, 2, 1];
+// SNMP-TARGET-MIB (RFC 3413)
+const TARGET_OBJECTS: [u64; 8] = [1, 3, 6, 1, 6, 3, 12, 1];
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ReportError {
+    #[error("Agent does not support the requested security level")]
+    UnsupportedSecurityLevel,
+
+    /// Our boots/time estimate is off; resync and retry.
+    #[error("Message outside the agent's time window")]
+    NotInTimeWindow,
+
+    #[error("Agent does not know this user name")]
+    UnknownUserName,
+
+    /// Sent in reply to engine discovery.
+    #[error("Unknown engine ID")]
+    UnknownEngineId,
+
+    #[error("Agent rejected the authentication digest (wrong auth password?)")]
+    WrongDigest,
+
+    #[error("Agent could not decrypt the request (wrong privacy password?)")]
+    DecryptionError,
+
+    #[error("Agent does not support the security model")]
+    UnknownSecurityModel,
+
+    #[error("Agent considered the message invalid")]
+    InvalidMessage,
+
+    #[error("Agent has no handler for the PDU type")]
+    UnknownPduHandler,
+
+    #[error("Context is currently unavailable")]
+    UnavailableContext,
+
+    #[error("Unknown context")]
+    UnknownContext,
+
+    #[error("Agent answered with a Report PDU: {}", format_oid(oid))]
+    Other { oid: Vec<u64> },
+}
+
+impl ReportError {
+    /// Maps the OID of a report's first varbind to the error it signals.
+    pub fn from_oid(oid: &[u64]) -> Self {
+        // the counters are all scalars, ignore the .0 instance
+        let counter = oid.strip_suffix(&[0]).unwrap_or(oid);
+        match counter.split_last() {
+            Some((last, base)) if base == USM_STATS => match last {
+                1 => ReportError::UnsupportedSecurityLevel,
+                2 => ReportError::NotInTimeWindow,
+                3 => ReportError::UnknownUserName,
+                4 => ReportError::UnknownEngineId,
+                5 => ReportError::WrongDigest,
+                6 => ReportError::DecryptionError,
+                _ => ReportError::Other { oid: oid.to_vec() },
+            },
+            Some((last, base)) if base == MPD_STATS => match last {
+                1 => ReportError::UnknownSecurityModel,
+                2 => ReportError::InvalidMessage,
+                3 => ReportError::UnknownPduHandler,
+                _ => ReportError::Other { oid: oid.to_vec() },
+            },
+            Some((last, base)) if base == TARGET_OBJECTS => match last {
+                4 => ReportError::UnavailableContext,
+                5 => ReportError::UnknownContext,
+                _ => ReportError::Other { oid: oid.to_vec() },
+            },
+            _ => ReportError::Other { oid: oid.to_vec() },
+        }
+    }
+
+    /// The error a Report PDU signals, `None` for any other PDU.
+    pub fn from_pdu(pdu: &Pdu) -> Option<Self> {
+        if pdu.tag != Asn1Tag::Report {
+            return None;
+        }
+        Some(
+            pdu.varbinds
+                .first()
+                .map(|vb| Self::from_oid(&vb.oid))
+                .unwrap_or(ReportError::Other { oid: Vec::new() }),
+        )
+    }
+}
+
+fn format_oid(oid: &[u64]) -> String {
+    oid.iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(".")
+}
@@ -0,0 +1,91 @@
+// The snmprec format used by SNMP simulators: one `OID|TAG|VALUE` line per
+// object, TAG being the BER tag of the value in decimal. A trailing `x` on
+// the tag means the value is hex-encoded.
+
+use std::fmt::Write;
+
+use crate::ber::Asn1Tag;
+use crate::snmp::pdu::{ObjectSyntax, VarBind};
+
+// the exception tags snmpsim uses for varbinds without a value
+const NO_SUCH_OBJECT: u8 = 128;
+const NO_SUCH_INSTANCE: u8 = 129;
+const END_OF_MIB_VIEW: u8 = 130;
+
+/// Formats one varbind as an snmprec line, without the newline.
+pub fn format_line(varbind: &VarBind) -> String {
+    let oid = join(&varbind.oid, ".");
+    let (tag, value) = match &varbind.value {
+        ObjectSyntax::Integer(val) => (tag(Asn1Tag::Integer), val.to_string()),
+        ObjectSyntax::OctetString(val) if is_printable(val) => (
+            tag(Asn1Tag::OctetString),
+            String::from_utf8_lossy(val).into_owned(),
+        ),
+        ObjectSyntax::OctetString(val) => (hex_tag(Asn1Tag::OctetString as u8), hex(val)),
+        ObjectSyntax::Null => (tag(Asn1Tag::Null), String::new()),
+        ObjectSyntax::ObjectIdentifier(val) => (tag(Asn1Tag::ObjectIdentifier), join(val, ".")),
+        ObjectSyntax::IpAddress(val) => (tag(Asn1Tag::IpAddress), join(val, ".")),
+        ObjectSyntax::Counter32(val) => (tag(Asn1Tag::Counter32), val.to_string()),
+        ObjectSyntax::Gauge32(val) => (tag(Asn1Tag::Gauge32), val.to_string()),
+        ObjectSyntax::TimeTicks(val) => (tag(Asn1Tag::TimeTicks), val.to_string()),
+        ObjectSyntax::Counter64(val) => (tag(Asn1Tag::Counter64), val.to_string()),
+        ObjectSyntax::NoSuchObject => (NO_SUCH_OBJECT.to_string(), String::new()),
+        ObjectSyntax::NoSuchInstance => (NO_SUCH_INSTANCE.to_string(), String::new()),
+        ObjectSyntax::EndOfMib => (END_OF_MIB_VIEW.to_string(), String::new()),
+        ObjectSyntax::Opaque(val) => (hex_tag(Asn1Tag::Opaque as u8), hex(val)),
+        // written back as the Opaque wrapping they arrived in
+        ObjectSyntax::Float(_) | ObjectSyntax::Double(_) => {
+            let mut buf = Vec::new();
+            varbind.value.write_to_buf(&mut buf);
+            (hex_tag(Asn1Tag::Opaque as u8), hex(&buf[2..]))
+        }
+        ObjectSyntax::Tagged {
+            class,
+            number,
+            constructed,
+            bytes,
+        } if *number < 0x1F => {
+            let constructed = if *constructed { 0x20 } else { 0 };
+            (
+                hex_tag(class.bits() | constructed | *number as u8),
+                hex(bytes),
+            )
+        }
+        // a long-form tag doesn't fit the format's one-octet tag, so keep
+        // the whole TLV inside an Opaque
+        ObjectSyntax::Tagged { .. } => {
+            let mut buf = Vec::new();
+            varbind.value.write_to_buf(&mut buf);
+            (hex_tag(Asn1Tag::Opaque as u8), hex(&buf))
+        }
+    };
+    format!("{}|{}|{}", oid, tag, value)
+}
+
+fn tag(tag: Asn1Tag) -> String {
+    (tag as u8).to_string()
+}
+
+fn hex_tag(tag_byte: u8) -> String {
+    format!("{}x", tag_byte)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut out, byte| {
+        let _ = write!(out, "{:02x}", byte);
+        out
+    })
+}
+
+fn join<T: ToString>(parts: &[T], separator: &str) -> String {
+    parts
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+// anything that would break the line-based format is written as hex
+fn is_printable(bytes: &[u8]) -> bool {
+    bytes.iter().all(|b| b.is_ascii_graphic() || *b == b' ')
+}
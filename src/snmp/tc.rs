@@ -0,0 +1,148 @@
+// Textual conventions that nearly every MIB uses (SNMPv2-TC, RFC 2579, and
+// INET-ADDRESS-MIB, RFC 4001), decoded from the OCTET STRING or INTEGER
+// they travel as.
+
+use std::fmt;
+use std::net::IpAddr;
+
+use crate::snmp::pdu::ObjectSyntax;
+
+/// A DateAndTime: 8 octets of local time, or 11 with the offset from UTC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateAndTime {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    /// Up to 60, for leap seconds.
+    pub second: u8,
+    pub deci_seconds: u8,
+    /// Minutes east of UTC, when the agent says.
+    pub utc_offset: Option<i16>,
+}
+
+impl DateAndTime {
+    /// `None` unless `bytes` is 8 or 11 octets with every field in range.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let (time, offset) = match bytes.len() {
+            8 => (bytes, None),
+            11 => (&bytes[..8], Some(&bytes[8..])),
+            _ => return None,
+        };
+        let utc_offset = match offset {
+            Some(&[direction, hours, minutes]) if hours <= 14 && minutes < 60 => {
+                let offset = i16::from(hours) * 60 + i16::from(minutes);
+                match direction {
+                    b'+' => Some(offset),
+                    b'-' => Some(-offset),
+                    _ => return None,
+                }
+            }
+            Some(_) => return None,
+            None => None,
+        };
+        let value = DateAndTime {
+            year: u16::from_be_bytes([time[0], time[1]]),
+            month: time[2],
+            day: time[3],
+            hour: time[4],
+            minute: time[5],
+            second: time[6],
+            deci_seconds: time[7],
+            utc_offset,
+        };
+        let in_range = (1..=12).contains(&value.month)
+            && (1..=31).contains(&value.day)
+            && value.hour < 24
+            && value.minute < 60
+            && value.second <= 60
+            && value.deci_seconds < 10;
+        in_range.then_some(value)
+    }
+
+    /// The encoding an agent expects in a SET.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.year.to_be_bytes().to_vec();
+        bytes.extend([
+            self.month,
+            self.day,
+            self.hour,
+            self.minute,
+            self.second,
+            self.deci_seconds,
+        ]);
+        if let Some(offset) = self.utc_offset {
+            let direction = if offset < 0 { b'-' } else { b'+' };
+            let offset = offset.unsigned_abs();
+            bytes.extend([direction, (offset / 60) as u8, (offset % 60) as u8]);
+        }
+        bytes
+    }
+
+    /// The timestamp, taking a DateAndTime without an offset to be UTC.
+    /// `None` for dates that don't exist, such as February 30th. chrono
+    /// has no leap seconds, so second 60 is read as 59.
+    #[cfg(feature = "chrono")]
+    pub fn to_chrono(&self) -> Option<chrono::DateTime<chrono::FixedOffset>> {
+        use chrono::{FixedOffset, NaiveDate};
+
+        let offset = FixedOffset::east_opt(i32::from(self.utc_offset.unwrap_or(0)) * 60)?;
+        NaiveDate::from_ymd_opt(self.year.into(), self.month.into(), self.day.into())?
+            .and_hms_milli_opt(
+                self.hour.into(),
+                self.minute.into(),
+                self.second.min(59).into(),
+                u32::from(self.deci_seconds) * 100,
+            )?
+            .and_local_timezone(offset)
+            .single()
+    }
+}
+
+/// The DISPLAY-HINT rendering net-snmp uses, e.g. 2026-10-17,13:45:30.5,+2:0
+impl fmt::Display for DateAndTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}-{}-{},{}:{}:{}.{}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second, self.deci_seconds
+        )?;
+        if let Some(offset) = self.utc_offset {
+            let direction = if offset < 0 { '-' } else { '+' };
+            let offset = offset.unsigned_abs();
+            write!(f, ",{}{}:{}", direction, offset / 60, offset % 60)?;
+        }
+        Ok(())
+    }
+}
+
+/// A MacAddress, shown colon-separated with two digits per octet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MacAddress(pub [u8; 6]);
+
+impl fmt::Display for MacAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [a, b, c, d, e, g] = self.0;
+        write!(
+            f,
+            "{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}",
+            a, b, c, d, e, g
+        )
+    }
+}
+
+/// The address of an InetAddressType/InetAddress pair, as the two columns
+/// of a row hold them. Zone indexes of ipv4z and ipv6z addresses are
+/// dropped; `None` for dns names and unknown types, or an address of the
+/// wrong length for its type.
+pub fn inet_address(address_type: &ObjectSyntax, address: &ObjectSyntax) -> Option<IpAddr> {
+    let ObjectSyntax::OctetString(bytes) = address else {
+        return None;
+    };
+    match (address_type.as_i64()?, bytes.len()) {
+        (1, 4) | (3, 8) => Some(IpAddr::from(<[u8; 4]>::try_from(&bytes[..4]).ok()?)),
+        (2, 16) | (4, 20) => Some(IpAddr::from(<[u8; 16]>::try_from(&bytes[..16]).ok()?)),
+        _ => None,
+    }
+}
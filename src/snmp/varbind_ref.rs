@@ -0,0 +1,205 @@
+// Varbinds that borrow from the packet they were parsed from, for callers
+// that look at most values once and don't want an allocation per varbind.
+
+use crate::ber::decoder::{decode_integer, decode_unsigned_integer, decode_unsigned_integer64};
+use crate::ber::{Asn1Tag, BerError, BerObject, BerResult, RawBerObject};
+use crate::ber::{decode_oid, parse_ber_object, parse_raw_ber_object};
+use crate::snmp::message::parse_message_header;
+use crate::snmp::pdu::{ObjectSyntax, PduData, VarBind, decode_opaque_float, parse_pdu_header};
+
+/// [`ObjectSyntax`] with byte-string and OID values left in the packet.
+/// OIDs stay BER-encoded until [`ObjectSyntaxRef::into_owned`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ObjectSyntaxRef<'a> {
+    Integer(i32),
+    OctetString(&'a [u8]),
+    Null,
+    ObjectIdentifier(&'a [u8]),
+    IpAddress(&'a [u8]),
+    Counter32(u32),
+    Gauge32(u32),
+    TimeTicks(u32),
+    Opaque(&'a [u8]),
+    Counter64(u64),
+    Float(f32),
+    Double(f64),
+
+    NoSuchObject,
+    NoSuchInstance,
+    EndOfMib,
+
+    /// A tag SNMP doesn't define. Registered value decoders run in
+    /// [`ObjectSyntaxRef::into_owned`].
+    Tagged(RawBerObject<'a>),
+}
+
+impl<'a> ObjectSyntaxRef<'a> {
+    pub fn from_ber(obj: BerObject<'a>) -> BerResult<Self> {
+        Ok(match obj.tag {
+            Asn1Tag::Integer => ObjectSyntaxRef::Integer(decode_integer(obj.value)?),
+            Asn1Tag::OctetString => ObjectSyntaxRef::OctetString(obj.value),
+            Asn1Tag::Null => ObjectSyntaxRef::Null,
+            Asn1Tag::ObjectIdentifier => ObjectSyntaxRef::ObjectIdentifier(obj.value),
+            Asn1Tag::IpAddress => ObjectSyntaxRef::IpAddress(obj.value),
+            Asn1Tag::Counter32 => ObjectSyntaxRef::Counter32(decode_unsigned_integer(obj.value)?),
+            Asn1Tag::Gauge32 => ObjectSyntaxRef::Gauge32(decode_unsigned_integer(obj.value)?),
+            Asn1Tag::TimeTicks => ObjectSyntaxRef::TimeTicks(decode_unsigned_integer(obj.value)?),
+            Asn1Tag::Opaque => match decode_opaque_float(obj.value) {
+                Some(ObjectSyntax::Float(val)) => ObjectSyntaxRef::Float(val),
+                Some(ObjectSyntax::Double(val)) => ObjectSyntaxRef::Double(val),
+                _ => ObjectSyntaxRef::Opaque(obj.value),
+            },
+            Asn1Tag::Counter64 => ObjectSyntaxRef::Counter64(decode_unsigned_integer64(obj.value)?),
+            Asn1Tag::NoSuchObject => ObjectSyntaxRef::NoSuchObject,
+            Asn1Tag::NoSuchInstance => ObjectSyntaxRef::NoSuchInstance,
+            Asn1Tag::EndOfMib => ObjectSyntaxRef::EndOfMib,
+            _ => return Err(BerError::UnsupportedType(obj.tag as u8)),
+        })
+    }
+
+    /// Copies the value out of the packet. Fails only on a malformed OID.
+    pub fn into_owned(self) -> BerResult<ObjectSyntax> {
+        Ok(match self {
+            ObjectSyntaxRef::Integer(val) => ObjectSyntax::Integer(val),
+            ObjectSyntaxRef::OctetString(val) => ObjectSyntax::OctetString(val.to_vec()),
+            ObjectSyntaxRef::Null => ObjectSyntax::Null,
+            ObjectSyntaxRef::ObjectIdentifier(val) => {
+                ObjectSyntax::ObjectIdentifier(decode_oid(val)?)
+            }
+            ObjectSyntaxRef::IpAddress(val) => ObjectSyntax::IpAddress(val.to_vec()),
+            ObjectSyntaxRef::Counter32(val) => ObjectSyntax::Counter32(val),
+            ObjectSyntaxRef::Gauge32(val) => ObjectSyntax::Gauge32(val),
+            ObjectSyntaxRef::TimeTicks(val) => ObjectSyntax::TimeTicks(val),
+            ObjectSyntaxRef::Opaque(val) => ObjectSyntax::Opaque(val.to_vec()),
+            ObjectSyntaxRef::Counter64(val) => ObjectSyntax::Counter64(val),
+            ObjectSyntaxRef::Float(val) => ObjectSyntax::Float(val),
+            ObjectSyntaxRef::Double(val) => ObjectSyntax::Double(val),
+            ObjectSyntaxRef::NoSuchObject => ObjectSyntax::NoSuchObject,
+            ObjectSyntaxRef::NoSuchInstance => ObjectSyntax::NoSuchInstance,
+            ObjectSyntaxRef::EndOfMib => ObjectSyntax::EndOfMib,
+            ObjectSyntaxRef::Tagged(raw) => ObjectSyntax::from_raw_ber(raw)?,
+        })
+    }
+}
+
+/// A varbind borrowing from the packet. `oid` holds the BER-encoded
+/// sub-identifiers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VarBindRef<'a> {
+    pub oid: &'a [u8],
+    pub value: ObjectSyntaxRef<'a>,
+}
+
+impl VarBindRef<'_> {
+    pub fn oid(&self) -> BerResult<Vec<u64>> {
+        decode_oid(self.oid)
+    }
+
+    pub fn into_owned(self) -> BerResult<VarBind> {
+        Ok(VarBind {
+            oid: self.oid()?,
+            value: self.value.into_owned()?,
+        })
+    }
+}
+
+/// Borrowing counterpart of [`parse_varbind`](crate::snmp::pdu::parse_varbind).
+pub fn parse_varbind_ref(obj: BerObject<'_>) -> BerResult<VarBindRef<'_>> {
+    if obj.tag != Asn1Tag::Sequence {
+        return Err(BerError::UnexpectedTag {
+            expected: Asn1Tag::Sequence,
+            got: obj.tag,
+        });
+    }
+
+    let (oid_obj, rest_after_oid) = parse_ber_object(obj.value)?;
+    if oid_obj.tag != Asn1Tag::ObjectIdentifier {
+        return Err(BerError::UnexpectedTag {
+            expected: Asn1Tag::ObjectIdentifier,
+            got: oid_obj.tag,
+        });
+    }
+
+    let value_tag = rest_after_oid.first().ok_or(BerError::IncompleteData)?;
+    let (value, rest) = match Asn1Tag::from_u8(*value_tag) {
+        Ok(_) => {
+            let (value_obj, rest) = parse_ber_object(rest_after_oid)?;
+            (ObjectSyntaxRef::from_ber(value_obj)?, rest)
+        }
+        Err(_) => {
+            let (raw_obj, rest) = parse_raw_ber_object(rest_after_oid)?;
+            (ObjectSyntaxRef::Tagged(raw_obj), rest)
+        }
+    };
+
+    if !rest.is_empty() {
+        return Err(BerError::TrailingData);
+    }
+
+    Ok(VarBindRef {
+        oid: oid_obj.value,
+        value,
+    })
+}
+
+/// Borrowing counterpart of
+/// [`parse_varbind_list`](crate::snmp::pdu::parse_varbind_list).
+pub fn parse_varbind_list_ref(obj: BerObject<'_>) -> BerResult<Vec<VarBindRef<'_>>> {
+    if obj.tag != Asn1Tag::Sequence {
+        return Err(BerError::UnexpectedTag {
+            expected: Asn1Tag::Sequence,
+            got: obj.tag,
+        });
+    }
+
+    let mut varbinds = Vec::new();
+    let mut current_slice = obj.value;
+    while !current_slice.is_empty() {
+        let (varbind_object, rest) = parse_ber_object(current_slice)?;
+        varbinds.push(parse_varbind_ref(varbind_object)?);
+        current_slice = rest;
+    }
+    Ok(varbinds)
+}
+
+/// A [`Pdu`](crate::snmp::pdu::Pdu) whose varbinds borrow from the packet.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PduRef<'a> {
+    pub tag: Asn1Tag,
+    pub request_id: i32,
+    pub data: PduData,
+    pub varbinds: Vec<VarBindRef<'a>>,
+}
+
+/// Borrowing counterpart of [`parse_pdu`](crate::snmp::pdu::parse_pdu).
+pub fn parse_pdu_ref(obj: BerObject<'_>) -> BerResult<PduRef<'_>> {
+    let tag = obj.tag;
+    let (request_id, data, varbind_list_obj) = parse_pdu_header(obj)?;
+    Ok(PduRef {
+        tag,
+        request_id,
+        data,
+        varbinds: parse_varbind_list_ref(varbind_list_obj)?,
+    })
+}
+
+/// A v1/v2c [`SnmpMessage`](crate::snmp::message::SnmpMessage) borrowing
+/// its community and varbinds from the datagram.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MessageRef<'a> {
+    pub version: i32,
+    pub community: &'a [u8],
+    pub pdu: PduRef<'a>,
+}
+
+/// Borrowing counterpart of
+/// [`parse_message`](crate::snmp::message::parse_message), for trap
+/// receivers and walkers that read each value of a datagram once.
+pub fn parse_message_ref(bytes: &[u8]) -> BerResult<MessageRef<'_>> {
+    let (version, community, pdu_object) = parse_message_header(bytes)?;
+    Ok(MessageRef {
+        version,
+        community,
+        pdu: parse_pdu_ref(pdu_object)?,
+    })
+}
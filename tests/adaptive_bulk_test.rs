@@ -0,0 +1,114 @@
+use std::sync::{Arc, Mutex};
+
+use common::FakeAgent;
+use rusnmp::manager::{Credentials, Manager};
+use rusnmp::snmp::pdu::{ErrorStatus, ObjectSyntax, PduData, VarBind};
+
+mod common;
+
+const TABLE: [u64; 7] = [1, 3, 6, 1, 4, 1, 99];
+const ROWS: u64 = 30;
+
+// a table of ROWS integers under TABLE. GetBulks asking for more than
+// `fits` rows get tooBig, and at most `sends(n)` rows go into the answer
+// to the nth request. Records each request's max-repetitions.
+async fn agent(fits: i32, sends: fn(usize) -> i32, asked: Arc<Mutex<Vec<i32>>>) -> String {
+    let agent = FakeAgent::new().serve(move |message| {
+        let PduData::Bulk {
+            max_repititions, ..
+        } = message.pdu.data
+        else {
+            panic!("expected GetBulk");
+        };
+        let request = {
+            let mut asked = asked.lock().unwrap();
+            asked.push(max_repititions);
+            asked.len() - 1
+        };
+
+        let after = message.pdu.varbinds[0].oid.get(TABLE.len()).copied();
+        let first = after.map_or(1, |row| row + 1);
+        let too_big = max_repititions > fits;
+        message.pdu.data = PduData::Basic {
+            error_status: if too_big {
+                ErrorStatus::TooBig
+            } else {
+                ErrorStatus::NoError
+            },
+            error_index: 0,
+        };
+        if !too_big {
+            message.pdu.varbinds = (first..)
+                .take(max_repititions.min(sends(request)) as usize)
+                .map(|row| VarBind {
+                    oid: [&TABLE[..], &[row]].concat(),
+                    value: if row > ROWS {
+                        ObjectSyntax::EndOfMib
+                    } else {
+                        ObjectSyntax::Integer(row as i32)
+                    },
+                })
+                .collect();
+        }
+        true
+    });
+    agent.await.target
+}
+
+#[tokio::test]
+async fn test_bulk_walk_backs_off_too_big() {
+    let asked = Arc::new(Mutex::new(Vec::new()));
+    let target = agent(6, |_| 100, Arc::clone(&asked)).await;
+    let manager = Manager::new();
+    let community = Credentials::v2c("public");
+
+    let walked = manager
+        .bulk_walk(&target, &community, "1.3.6.1.4.1.99", 40)
+        .await
+        .unwrap();
+    assert_eq!(walked.len(), ROWS as usize);
+    assert_eq!(asked.lock().unwrap()[..4], [40, 20, 10, 5]);
+    assert!(manager.supports_bulk(&target));
+
+    // the next walk starts from what worked
+    asked.lock().unwrap().clear();
+    manager
+        .bulk_walk(&target, &community, "1.3.6.1.4.1.99", 40)
+        .await
+        .unwrap();
+    assert!(asked.lock().unwrap()[0] <= 6);
+}
+
+#[tokio::test]
+async fn test_bulk_walk_follows_short_answers() {
+    let asked = Arc::new(Mutex::new(Vec::new()));
+    let target = agent(100, |_| 4, Arc::clone(&asked)).await;
+
+    let walked = Manager::new()
+        .bulk_walk(&target, &Credentials::v2c("public"), "1.3.6.1.4.1.99", 25)
+        .await
+        .unwrap();
+    assert_eq!(walked.len(), ROWS as usize);
+    // after a short answer it asks for what the agent sent, and tries
+    // for more again after a full one
+    assert_eq!(asked.lock().unwrap()[..4], [25, 4, 8, 4]);
+}
+
+#[tokio::test]
+async fn test_bulk_walk_regrows_after_short_answers() {
+    let asked = Arc::new(Mutex::new(Vec::new()));
+    // short of breath for the first two answers only
+    let target = agent(
+        100,
+        |request| if request < 2 { 4 } else { 100 },
+        Arc::clone(&asked),
+    )
+    .await;
+
+    let walked = Manager::new()
+        .bulk_walk(&target, &Credentials::v2c("public"), "1.3.6.1.4.1.99", 25)
+        .await
+        .unwrap();
+    assert_eq!(walked.len(), ROWS as usize);
+    assert_eq!(*asked.lock().unwrap(), [25, 4, 8, 16]);
+}
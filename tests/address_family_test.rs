@@ -0,0 +1,67 @@
+use std::net::SocketAddr;
+
+use rusnmp::manager::{AddressFamilyPolicy, Credentials, Manager};
+
+fn dual_stack() -> Vec<SocketAddr> {
+    vec![
+        "192.0.2.1:161".parse().unwrap(),
+        "[2001:db8::1]:161".parse().unwrap(),
+        "192.0.2.2:161".parse().unwrap(),
+        "[2001:db8::2]:161".parse().unwrap(),
+    ]
+}
+
+#[test]
+fn test_family_policy_selection() {
+    let pick = |policy: AddressFamilyPolicy, addrs: Vec<SocketAddr>| {
+        policy.select(addrs).map(|a| a.to_string())
+    };
+
+    assert_eq!(
+        pick(AddressFamilyPolicy::Any, dual_stack()).as_deref(),
+        Some("192.0.2.1:161")
+    );
+    assert_eq!(
+        pick(AddressFamilyPolicy::PreferIpv6, dual_stack()).as_deref(),
+        Some("[2001:db8::1]:161")
+    );
+    assert_eq!(
+        pick(AddressFamilyPolicy::PreferIpv4, dual_stack()[1..].to_vec()).as_deref(),
+        Some("192.0.2.2:161")
+    );
+
+    let v4_only = vec!["192.0.2.1:161".parse().unwrap()];
+    assert_eq!(
+        pick(AddressFamilyPolicy::PreferIpv6, v4_only.clone()).as_deref(),
+        Some("192.0.2.1:161")
+    );
+    assert_eq!(pick(AddressFamilyPolicy::RequireIpv6, v4_only), None);
+}
+
+#[tokio::test]
+async fn test_require_ipv6_rejects_ipv4_target() {
+    let manager = Manager::new()
+        .with_family_policy(AddressFamilyPolicy::RequireIpv6)
+        .with_target_family_policy("127.0.0.2", AddressFamilyPolicy::Any);
+
+    let error = manager
+        .get(
+            "127.0.0.1",
+            &Credentials::v2c("public"),
+            "1.3.6.1.2.1.1.3.0",
+        )
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("did not resolve"), "{}", error);
+
+    // the per-target override lets this one through to the network
+    let error = manager
+        .get(
+            "127.0.0.2",
+            &Credentials::v2c("public"),
+            "1.3.6.1.2.1.1.3.0",
+        )
+        .await
+        .unwrap_err();
+    assert!(!error.to_string().contains("did not resolve"), "{}", error);
+}
@@ -0,0 +1,96 @@
+use std::time::Duration;
+
+use rusnmp::agent::Agent;
+use rusnmp::ber::Asn1Tag;
+use rusnmp::snmp::message::{SnmpMessage, parse_message};
+use rusnmp::snmp::pdu::{ObjectSyntax, VarBind};
+use tokio::net::UdpSocket;
+
+// linkDown with ifIndex 3
+const LINK_DOWN: &str = "1.3.6.1.6.3.1.1.5.3";
+
+fn payload() -> Vec<VarBind> {
+    vec![VarBind {
+        oid: vec![1, 3, 6, 1, 2, 1, 2, 2, 1, 1, 3],
+        value: ObjectSyntax::Integer(3),
+    }]
+}
+
+async fn sink() -> (UdpSocket, String) {
+    let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let target = socket.local_addr().unwrap().to_string();
+    (socket, target)
+}
+
+fn check_notification(message: &SnmpMessage, community: &[u8]) {
+    assert_eq!(message.version, 1);
+    assert_eq!(message.community, community);
+    let varbinds = &message.pdu.varbinds;
+    assert_eq!(varbinds.len(), 3);
+    assert_eq!(varbinds[0].oid, [1, 3, 6, 1, 2, 1, 1, 3, 0]);
+    assert!(matches!(varbinds[0].value, ObjectSyntax::TimeTicks(_)));
+    assert_eq!(
+        varbinds[1].value,
+        ObjectSyntax::ObjectIdentifier(vec![1, 3, 6, 1, 6, 3, 1, 1, 5, 3])
+    );
+    assert_eq!(varbinds[2..], payload()[..]);
+}
+
+#[tokio::test]
+async fn test_notify_traps_and_informs() {
+    let (traps, trap_target) = sink().await;
+    let (informs, inform_target) = sink().await;
+    let agent = Agent::builder()
+        .trap_sink(&trap_target, "public")
+        .inform_sink(&inform_target, "alarms", 2)
+        .inform_timeout(Duration::from_millis(200))
+        .build();
+
+    // acknowledges only the second copy of the inform
+    let receiver = tokio::spawn(async move {
+        let mut buf = [0u8; 1500];
+        let (len, _) = informs.recv_from(&mut buf).await.unwrap();
+        let first = parse_message(&buf[..len]).unwrap();
+        let (len, from) = informs.recv_from(&mut buf).await.unwrap();
+        let mut second = parse_message(&buf[..len]).unwrap();
+        assert_eq!(second.pdu.request_id, first.pdu.request_id);
+        second.pdu.tag = Asn1Tag::GetResponse;
+        informs.send_to(&second.to_bytes(), from).await.unwrap();
+        first
+    });
+
+    agent.notify(LINK_DOWN, payload()).await.unwrap();
+
+    let mut buf = [0u8; 1500];
+    let len = traps.recv(&mut buf).await.unwrap();
+    let trap = parse_message(&buf[..len]).unwrap();
+    assert_eq!(trap.pdu.tag, Asn1Tag::SnmpV2Trap);
+    check_notification(&trap, b"public");
+
+    let inform = receiver.await.unwrap();
+    assert_eq!(inform.pdu.tag, Asn1Tag::InformRequest);
+    check_notification(&inform, b"alarms");
+}
+
+#[tokio::test]
+async fn test_unacknowledged_inform_fails() {
+    let (traps, trap_target) = sink().await;
+    let (_silent, silent_target) = sink().await;
+    let agent = Agent::builder()
+        .inform_sink(&silent_target, "public", 1)
+        .trap_sink(&trap_target, "public")
+        .inform_timeout(Duration::from_millis(100))
+        .build();
+
+    let error = agent.notify(LINK_DOWN, payload()).await.unwrap_err();
+    assert!(format!("{:#}", error).contains(&silent_target));
+    // the other sinks still get theirs
+    let mut buf = [0u8; 1500];
+    let len = traps.recv(&mut buf).await.unwrap();
+    assert_eq!(
+        parse_message(&buf[..len]).unwrap().pdu.tag,
+        Asn1Tag::SnmpV2Trap
+    );
+
+    assert!(agent.notify("1.3.x", Vec::new()).await.is_err());
+}
@@ -0,0 +1,261 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use rusnmp::agent::{Agent, SubtreeHandler};
+use rusnmp::ber::Asn1Tag;
+use rusnmp::manager::{Credentials, Manager, SnmpError};
+use rusnmp::snmp::message::{SnmpMessage, parse_message};
+use rusnmp::snmp::pdu::{ErrorStatus, ObjectSyntax, Pdu, PduData, VarBind};
+
+mod common;
+use common::serve;
+
+const APP: [u64; 7] = [1, 3, 6, 1, 4, 1, 99];
+
+// integers from 0 to 100; writes to .9.0 fail at commit, and undoing a
+// write to .8.0 fails too
+#[derive(Default)]
+struct Settings {
+    values: Mutex<BTreeMap<Vec<u64>, ObjectSyntax>>,
+}
+
+impl Settings {
+    fn oid(arc: u64) -> Vec<u64> {
+        [&APP[..], &[arc, 0]].concat()
+    }
+
+    fn value(&self, arc: u64) -> Option<ObjectSyntax> {
+        self.values.lock().unwrap().get(&Self::oid(arc)).cloned()
+    }
+}
+
+impl SubtreeHandler for Settings {
+    fn get(&self, oid: &[u64]) -> Option<ObjectSyntax> {
+        self.values.lock().unwrap().get(oid).cloned()
+    }
+
+    fn get_next(&self, oid: &[u64]) -> Option<VarBind> {
+        let values = self.values.lock().unwrap();
+        let (oid, value) = values
+            .range::<[u64], _>((std::ops::Bound::Excluded(oid), std::ops::Bound::Unbounded))
+            .next()?;
+        Some(VarBind {
+            oid: oid.clone(),
+            value: value.clone(),
+        })
+    }
+
+    fn test_set(&self, oid: &[u64], value: &ObjectSyntax) -> Result<(), ErrorStatus> {
+        if oid.len() != APP.len() + 2 || oid[APP.len() + 1] != 0 {
+            return Err(ErrorStatus::NoCreation);
+        }
+        match value {
+            ObjectSyntax::Integer(0..=100) => Ok(()),
+            ObjectSyntax::Integer(_) => Err(ErrorStatus::WrongValue),
+            _ => Err(ErrorStatus::WrongType),
+        }
+    }
+
+    fn commit_set(&self, oid: &[u64], value: &ObjectSyntax) -> bool {
+        if oid == Self::oid(9) {
+            return false;
+        }
+        self.values
+            .lock()
+            .unwrap()
+            .insert(oid.to_vec(), value.clone());
+        true
+    }
+
+    fn undo_set(&self, oid: &[u64], previous: Option<&ObjectSyntax>) -> bool {
+        if oid == Self::oid(8) {
+            return false;
+        }
+        let mut values = self.values.lock().unwrap();
+        match previous {
+            Some(value) => values.insert(oid.to_vec(), value.clone()),
+            None => values.remove(oid),
+        };
+        true
+    }
+}
+
+fn agent() -> (Arc<Agent>, Arc<Settings>) {
+    let settings = Arc::new(Settings::default());
+    settings
+        .values
+        .lock()
+        .unwrap()
+        .insert(Settings::oid(1), ObjectSyntax::Integer(10));
+    let agent = Agent::new("public");
+    agent
+        .register("1.3.6.1.4.1.99", Shared(settings.clone()))
+        .unwrap();
+    (Arc::new(agent), settings)
+}
+
+// lets the test look at the values the agent wrote
+struct Shared(Arc<Settings>);
+
+impl SubtreeHandler for Shared {
+    fn get(&self, oid: &[u64]) -> Option<ObjectSyntax> {
+        self.0.get(oid)
+    }
+
+    fn get_next(&self, oid: &[u64]) -> Option<VarBind> {
+        self.0.get_next(oid)
+    }
+
+    fn test_set(&self, oid: &[u64], value: &ObjectSyntax) -> Result<(), ErrorStatus> {
+        self.0.test_set(oid, value)
+    }
+
+    fn commit_set(&self, oid: &[u64], value: &ObjectSyntax) -> bool {
+        self.0.commit_set(oid, value)
+    }
+
+    fn undo_set(&self, oid: &[u64], previous: Option<&ObjectSyntax>) -> bool {
+        self.0.undo_set(oid, previous)
+    }
+}
+
+fn set(agent: &Agent, version: i32, varbinds: &[(u64, ObjectSyntax)]) -> (ErrorStatus, i32) {
+    let request = SnmpMessage {
+        version,
+        community: b"public".to_vec(),
+        pdu: Pdu {
+            tag: Asn1Tag::SetRequest,
+            request_id: 42,
+            data: PduData::Basic {
+                error_status: ErrorStatus::NoError,
+                error_index: 0,
+            },
+            varbinds: varbinds
+                .iter()
+                .map(|(arc, value)| VarBind {
+                    oid: Settings::oid(*arc),
+                    value: value.clone(),
+                })
+                .collect(),
+        },
+    };
+    let response = parse_message(&agent.handle(&request.to_bytes()).unwrap()).unwrap();
+    assert_eq!(response.pdu.varbinds, request.pdu.varbinds);
+    match response.pdu.data {
+        PduData::Basic {
+            error_status,
+            error_index,
+        } => (error_status, error_index),
+        data => panic!("unexpected {:?}", data),
+    }
+}
+
+#[tokio::test]
+async fn test_manager_sets() {
+    let (agent, settings) = agent();
+    let target = serve(agent).await;
+
+    let manager = Manager::new();
+    let credentials = Credentials::v2c("public");
+    manager
+        .set(
+            &target,
+            &credentials,
+            "1.3.6.1.4.1.99.2.0",
+            ObjectSyntax::Integer(50),
+        )
+        .await
+        .unwrap();
+    assert_eq!(settings.value(2), Some(ObjectSyntax::Integer(50)));
+
+    let error = manager
+        .set(
+            &target,
+            &credentials,
+            "1.3.6.1.4.1.99.1.0",
+            ObjectSyntax::Integer(101),
+        )
+        .await
+        .unwrap_err();
+    let error = error.downcast_ref::<SnmpError>().unwrap();
+    assert_eq!(error.status, ErrorStatus::WrongValue);
+    assert_eq!(error.index, 1);
+    assert_eq!(settings.value(1), Some(ObjectSyntax::Integer(10)));
+}
+
+#[test]
+fn test_nothing_is_written_unless_all_pass() {
+    let (agent, settings) = agent();
+    let varbinds = [
+        (1, ObjectSyntax::Integer(20)),
+        (2, ObjectSyntax::OctetString(b"x".to_vec())),
+    ];
+    assert_eq!(set(&agent, 1, &varbinds), (ErrorStatus::WrongType, 2));
+    assert_eq!(settings.value(1), Some(ObjectSyntax::Integer(10)));
+
+    let varbinds = [
+        (1, ObjectSyntax::Integer(20)),
+        (2, ObjectSyntax::Integer(30)),
+    ];
+    assert_eq!(set(&agent, 1, &varbinds), (ErrorStatus::NoError, 0));
+    assert_eq!(settings.value(1), Some(ObjectSyntax::Integer(20)));
+    assert_eq!(settings.value(2), Some(ObjectSyntax::Integer(30)));
+}
+
+#[test]
+fn test_failed_commit_is_undone() {
+    let (agent, settings) = agent();
+    let varbinds = [
+        (1, ObjectSyntax::Integer(20)),
+        (2, ObjectSyntax::Integer(30)),
+        (9, ObjectSyntax::Integer(40)),
+    ];
+    assert_eq!(set(&agent, 1, &varbinds), (ErrorStatus::CommitFailed, 3));
+    assert_eq!(settings.value(1), Some(ObjectSyntax::Integer(10)));
+    assert_eq!(settings.value(2), None);
+
+    // .8.0 can't be undone, but .1.0 still is
+    let varbinds = [
+        (1, ObjectSyntax::Integer(20)),
+        (8, ObjectSyntax::Integer(30)),
+        (9, ObjectSyntax::Integer(40)),
+    ];
+    assert_eq!(set(&agent, 1, &varbinds), (ErrorStatus::UndoFailed, 3));
+    assert_eq!(settings.value(1), Some(ObjectSyntax::Integer(10)));
+    assert_eq!(settings.value(8), Some(ObjectSyntax::Integer(30)));
+}
+
+#[test]
+fn test_v1_error_status() {
+    let (agent, _) = agent();
+    let wrong_value = [(1, ObjectSyntax::Integer(-1))];
+    assert_eq!(set(&agent, 0, &wrong_value), (ErrorStatus::BadValue, 1));
+    let failed = [(9, ObjectSyntax::Integer(1))];
+    assert_eq!(set(&agent, 0, &failed), (ErrorStatus::GenErr, 1));
+
+    // and outside any registration nothing is writable
+    let request = SnmpMessage {
+        version: 0,
+        community: b"public".to_vec(),
+        pdu: Pdu {
+            tag: Asn1Tag::SetRequest,
+            request_id: 1,
+            data: PduData::Basic {
+                error_status: ErrorStatus::NoError,
+                error_index: 0,
+            },
+            varbinds: vec![VarBind {
+                oid: vec![1, 3, 6, 1, 2, 1, 1, 5, 0],
+                value: ObjectSyntax::OctetString(b"name".to_vec()),
+            }],
+        },
+    };
+    let response = parse_message(&agent.handle(&request.to_bytes()).unwrap()).unwrap();
+    assert_eq!(
+        response.pdu.data,
+        PduData::Basic {
+            error_status: ErrorStatus::NoSuchName,
+            error_index: 1,
+        }
+    );
+}
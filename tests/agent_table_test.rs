@@ -0,0 +1,166 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use rusnmp::agent::{Agent, TableProvider, TableRow};
+use rusnmp::manager::{Credentials, IndexSyntax, Manager};
+use rusnmp::snmp::pdu::ObjectSyntax;
+
+mod common;
+use common::serve;
+
+// a table indexed by { name, port }, listed out of order, with a row
+// whose index doesn't fit
+struct Services;
+
+fn row(name: &str, port: i32, state: i32) -> TableRow {
+    TableRow {
+        index: vec![
+            ObjectSyntax::OctetString(name.as_bytes().to_vec()),
+            ObjectSyntax::Integer(port),
+        ],
+        cells: BTreeMap::from([
+            (2, ObjectSyntax::Integer(state)),
+            (3, ObjectSyntax::OctetString(name.as_bytes().to_vec())),
+        ]),
+    }
+}
+
+impl TableProvider for Services {
+    fn index(&self) -> Vec<IndexSyntax> {
+        vec![IndexSyntax::OctetString, IndexSyntax::Integer]
+    }
+
+    fn rows(&self) -> Vec<TableRow> {
+        let mut sparse = row("dns", 53, 1);
+        sparse.cells.remove(&3);
+        vec![
+            row("web", 443, 1),
+            row("web", 80, 2),
+            sparse,
+            row("bad", -1, 1),
+        ]
+    }
+}
+
+// Services, counting how often its rows are listed
+struct Counted(Arc<AtomicUsize>);
+
+impl TableProvider for Counted {
+    fn index(&self) -> Vec<IndexSyntax> {
+        Services.index()
+    }
+
+    fn rows(&self) -> Vec<TableRow> {
+        self.0.fetch_add(1, Ordering::Relaxed);
+        Services.rows()
+    }
+}
+
+const TABLE: &str = "1.3.6.1.4.1.99.5";
+
+async fn services() -> String {
+    let agent = Agent::new("public");
+    agent.register_table(TABLE, Services).unwrap();
+    serve(agent).await
+}
+
+#[tokio::test]
+async fn test_walk_in_column_then_index_order() {
+    let target = services().await;
+    let manager = Manager::new();
+    let credentials = Credentials::v2c("public");
+
+    let cells = manager.walk(&target, &credentials, TABLE).await.unwrap();
+    let oids: Vec<String> = cells
+        .iter()
+        .map(|cell| {
+            cell.oid[8..]
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(".")
+        })
+        .collect();
+    // "dns" sorts before "web", length first; 80 before 443
+    assert_eq!(
+        oids,
+        [
+            "1.2.3.100.110.115.53",
+            "1.2.3.119.101.98.80",
+            "1.2.3.119.101.98.443",
+            "1.3.3.119.101.98.80",
+            "1.3.3.119.101.98.443",
+        ]
+    );
+    assert_eq!(cells[1].value, ObjectSyntax::Integer(2));
+
+    let bulk = manager
+        .bulk_walk(&target, &credentials, TABLE, 3)
+        .await
+        .unwrap();
+    assert_eq!(bulk, cells);
+}
+
+#[tokio::test]
+async fn test_get_table_indexed_round_trip() {
+    let target = services().await;
+    let rows = Manager::new()
+        .get_table_indexed(
+            &target,
+            &Credentials::v2c("public"),
+            TABLE,
+            &Services.index(),
+        )
+        .await
+        .unwrap();
+    let indexes: Vec<_> = rows.iter().map(|(index, _)| index.clone()).collect();
+    assert_eq!(
+        indexes,
+        [row("dns", 53, 1), row("web", 80, 2), row("web", 443, 1)].map(|row| row.index)
+    );
+    assert!(!rows[0].1.contains_key(&3));
+}
+
+#[tokio::test]
+async fn test_missing_cells() {
+    let target = services().await;
+    let varbinds = Manager::new()
+        .get_multi(
+            &target,
+            &Credentials::v2c("public"),
+            &[
+                "1.3.6.1.4.1.99.5.1.3.3.100.110.115.53",
+                "1.3.6.1.4.1.99.5.1.2.3.119.101.98.80",
+            ],
+        )
+        .await
+        .unwrap();
+    assert_eq!(varbinds[0].value, ObjectSyntax::NoSuchInstance);
+    assert_eq!(varbinds[1].value, ObjectSyntax::Integer(2));
+}
+
+#[tokio::test]
+async fn test_rows_listed_once_per_request() {
+    let listed = Arc::new(AtomicUsize::new(0));
+    let agent = Agent::new("public");
+    agent
+        .register_table(TABLE, Counted(listed.clone()))
+        .unwrap();
+    let target = serve(agent).await;
+    let manager = Manager::new();
+    let credentials = Credentials::v2c("public");
+
+    // every cell and the end of the MIB, asking for the next cell each
+    // time
+    let varbinds = manager
+        .get_bulk(&target, &credentials, 0, 50, &[TABLE])
+        .await
+        .unwrap();
+    assert_eq!(varbinds.len(), 6);
+    assert_eq!(listed.load(Ordering::Relaxed), 1);
+
+    // a walk is one request per cell, and one past the last
+    let cells = manager.walk(&target, &credentials, TABLE).await.unwrap();
+    assert_eq!(listed.load(Ordering::Relaxed), 1 + cells.len() + 1);
+}
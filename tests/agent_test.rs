@@ -0,0 +1,208 @@
+use std::sync::Arc;
+
+use rusnmp::agent::Agent;
+use rusnmp::ber::Asn1Tag;
+use rusnmp::manager::{Credentials, Manager, SnmpError};
+use rusnmp::snmp::message::{SnmpMessage, parse_message};
+use rusnmp::snmp::pdu::{ErrorStatus, ObjectSyntax, Pdu, PduData, VarBind};
+
+mod common;
+use common::{Values, serve};
+
+const APP: [u64; 7] = [1, 3, 6, 1, 4, 1, 99];
+const OTHER: [u64; 7] = [1, 3, 6, 1, 4, 1, 100];
+
+fn agent() -> Arc<Agent> {
+    let agent = Agent::new("public");
+    let app = Values::new(
+        &APP,
+        &[
+            (1, ObjectSyntax::Integer(7)),
+            (2, ObjectSyntax::OctetString(b"ok".to_vec())),
+            (3, ObjectSyntax::Counter64(1 << 40)),
+        ],
+    );
+    agent.register("1.3.6.1.4.1.99", app).unwrap();
+    let other = Values::new(&OTHER, &[(1, ObjectSyntax::Gauge32(3))]);
+    agent.register("1.3.6.1.4.1.100", other).unwrap();
+    Arc::new(agent)
+}
+
+fn request(version: i32, tag: Asn1Tag, oids: &[&[u64]]) -> Vec<u8> {
+    SnmpMessage {
+        version,
+        community: b"public".to_vec(),
+        pdu: Pdu {
+            tag,
+            request_id: 42,
+            data: PduData::Basic {
+                error_status: ErrorStatus::NoError,
+                error_index: 0,
+            },
+            varbinds: oids
+                .iter()
+                .map(|oid| VarBind {
+                    oid: oid.to_vec(),
+                    value: ObjectSyntax::Null,
+                })
+                .collect(),
+        },
+    }
+    .to_bytes()
+}
+
+#[tokio::test]
+async fn test_manager_against_agent() {
+    let target = serve(agent()).await;
+    let manager = Manager::new();
+    let credentials = Credentials::v2c("public");
+
+    let varbind = manager
+        .get(&target, &credentials, "1.3.6.1.4.1.99.2.0")
+        .await
+        .unwrap();
+    assert_eq!(varbind.value, ObjectSyntax::OctetString(b"ok".to_vec()));
+    let missing = manager
+        .get_multi(
+            &target,
+            &credentials,
+            &["1.3.6.1.4.1.99.9.0", "1.3.6.1.4.1.5.0"],
+        )
+        .await
+        .unwrap();
+    assert_eq!(missing[0].value, ObjectSyntax::NoSuchInstance);
+    assert_eq!(missing[1].value, ObjectSyntax::NoSuchObject);
+
+    // the walk crosses from one registration into the next
+    let walked = manager
+        .walk(&target, &credentials, "1.3.6.1.4.1")
+        .await
+        .unwrap();
+    assert_eq!(walked.len(), 4);
+    assert_eq!(walked[3].oid, [&OTHER[..], &[1, 0]].concat());
+    let bulk = manager
+        .bulk_walk(&target, &credentials, "1.3.6.1.4.1", 2)
+        .await
+        .unwrap();
+    assert_eq!(bulk, walked);
+}
+
+#[tokio::test]
+async fn test_set_is_refused() {
+    let target = serve(agent()).await;
+    let error = Manager::new()
+        .set(
+            &target,
+            &Credentials::v2c("public"),
+            "1.3.6.1.4.1.99.1.0",
+            ObjectSyntax::Integer(1),
+        )
+        .await
+        .unwrap_err();
+    let error = error.downcast_ref::<SnmpError>().unwrap();
+    assert_eq!(error.status, ErrorStatus::NotWritable);
+    assert_eq!(error.index, 1);
+}
+
+#[test]
+fn test_v1_errors_and_counter64() {
+    let agent = agent();
+    let app: Vec<u64> = APP.to_vec();
+    let counter = [&app[..], &[3, 0]].concat();
+
+    let response = agent
+        .handle(&request(0, Asn1Tag::GetRequest, &[&[1, 3], &counter]))
+        .unwrap();
+    let response = parse_message(&response).unwrap();
+    assert_eq!(
+        response.pdu.data,
+        PduData::Basic {
+            error_status: ErrorStatus::NoSuchName,
+            error_index: 1,
+        }
+    );
+
+    // GetNext steps over the Counter64 into the next registration
+    let after_string = [&app[..], &[2, 0]].concat();
+    let response = agent
+        .handle(&request(0, Asn1Tag::GetNextRequest, &[&after_string]))
+        .unwrap();
+    let response = parse_message(&response).unwrap();
+    assert_eq!(response.pdu.varbinds[0].value, ObjectSyntax::Gauge32(3));
+
+    // and past the end there is no endOfMibView in v1
+    let past_end = [&OTHER[..], &[2]].concat();
+    let response = agent
+        .handle(&request(0, Asn1Tag::GetNextRequest, &[&APP, &past_end]))
+        .unwrap();
+    let response = parse_message(&response).unwrap();
+    assert_eq!(
+        response.pdu.data,
+        PduData::Basic {
+            error_status: ErrorStatus::NoSuchName,
+            error_index: 2,
+        }
+    );
+}
+
+#[test]
+fn test_dropped_requests() {
+    let agent = agent();
+    let mut wrong_community = parse_message(&request(1, Asn1Tag::GetRequest, &[&APP])).unwrap();
+    wrong_community.community = b"private".to_vec();
+    assert_eq!(agent.handle(&wrong_community.to_bytes()), None);
+    assert_eq!(agent.handle(&[0x30, 0x00]), None);
+    assert_eq!(
+        agent.handle(&request(1, Asn1Tag::GetResponse, &[&APP])),
+        None
+    );
+}
+
+#[test]
+fn test_bulk_is_cut_to_fit() {
+    let agent = Agent::builder().max_message_size(100).build();
+    let values: Vec<(u64, ObjectSyntax)> = (1..=50)
+        .map(|arc| (arc, ObjectSyntax::Integer(arc as i32)))
+        .collect();
+    agent
+        .register("1.3.6.1.4.1.99", Values::new(&APP, &values))
+        .unwrap();
+
+    let mut bulk = parse_message(&request(1, Asn1Tag::GetBulkRequest, &[&APP])).unwrap();
+    bulk.pdu.tag = Asn1Tag::GetBulkRequest;
+    bulk.pdu.data = PduData::Bulk {
+        non_repeaters: 0,
+        max_repititions: 50,
+    };
+    let response = agent.handle(&bulk.to_bytes()).unwrap();
+    assert!(response.len() <= 100);
+    let varbinds = parse_message(&response).unwrap().pdu.varbinds;
+    assert!(!varbinds.is_empty() && varbinds.len() < 50);
+
+    // anything else that can't fit is tooBig
+    let long: Vec<u64> = [&APP[..], &[1; 40]].concat();
+    let response = agent
+        .handle(&request(1, Asn1Tag::GetRequest, &[&long, &long]))
+        .unwrap();
+    let response = parse_message(&response).unwrap();
+    assert_eq!(
+        response.pdu.data,
+        PduData::Basic {
+            error_status: ErrorStatus::TooBig,
+            error_index: 0,
+        }
+    );
+    assert!(response.pdu.varbinds.is_empty());
+}
+
+#[test]
+fn test_overlapping_registrations() {
+    let agent = agent();
+    let empty = Values::default;
+    assert!(agent.register("1.3.6.1.4.1.99.5", empty()).is_err());
+    assert!(agent.register("1.3.6.1.4", empty()).is_err());
+    assert!(agent.register("1.3.6.1.4.1.101", empty()).is_ok());
+    assert!(agent.unregister("1.3.6.1.4.1.99").unwrap());
+    assert!(!agent.unregister("1.3.6.1.4.1.99").unwrap());
+    assert!(agent.register("1.3.6.1.4.1.99.5", empty()).is_ok());
+}
@@ -0,0 +1,342 @@
+#![cfg(feature = "v3")]
+
+use std::sync::Arc;
+
+use rusnmp::agent::{Agent, SecurityLevel, SecurityModel, Vacm};
+use rusnmp::ber::Asn1Tag;
+use rusnmp::manager::{Credentials, Manager, SnmpError};
+use rusnmp::snmp::engine_id::EngineId;
+use rusnmp::snmp::message::{
+    FLAG_AUTH, FLAG_PRIV, FLAG_REPORTABLE, HeaderData, SECURITY_MODEL_USM, ScopedPdu,
+    ScopedPduData, SnmpV3Message, parse_v3_message,
+};
+use rusnmp::snmp::pdu::{ErrorStatus, ObjectSyntax, Pdu, PduData, VarBind};
+use rusnmp::snmp::report::ReportError;
+use rusnmp::snmp::usm::{
+    AuthProtocol, PrivProtocol, UsmError, UsmSecurityParameters, UsmUser, authenticate_message,
+    decrypt_scoped_pdu, verify_message,
+};
+use tokio::net::UdpSocket;
+
+mod common;
+use common::{Values, serve};
+
+const APP: [u64; 7] = [1, 3, 6, 1, 4, 1, 99];
+
+fn values() -> Values {
+    let values: Vec<_> = (1..=3)
+        .map(|arc| (arc, ObjectSyntax::Integer(arc as i32)))
+        .collect();
+    Values::new(&APP, &values)
+}
+
+fn alice() -> UsmUser {
+    UsmUser::new("alice")
+        .with_auth(AuthProtocol::Sha256, b"alice-auth")
+        .unwrap()
+        .with_privacy(PrivProtocol::Aes128, b"alice-priv")
+        .unwrap()
+}
+
+fn bob() -> UsmUser {
+    UsmUser::new("bob")
+        .with_auth(AuthProtocol::Md5, b"bob-password")
+        .unwrap()
+}
+
+// serves `agent` with the APP subtree registered
+async fn serve_app(agent: Agent) -> String {
+    agent.register("1.3.6.1.4.1.99", values()).unwrap();
+    serve(agent).await
+}
+
+fn report(error: &anyhow::Error) -> Option<&ReportError> {
+    error.downcast_ref::<ReportError>()
+}
+
+#[tokio::test]
+async fn test_manager_against_v3_agent() {
+    let agent = Agent::builder().usm_user(alice()).usm_user(bob()).build();
+    let engine_id = agent.engine_id().clone();
+    let target = serve_app(agent).await;
+    let manager = Manager::new();
+
+    for user in [alice(), bob()] {
+        let credentials = Credentials::from(user);
+        let varbind = manager
+            .get(&target, &credentials, "1.3.6.1.4.1.99.2.0")
+            .await
+            .unwrap();
+        assert_eq!(varbind.value, ObjectSyntax::Integer(2));
+        let walked = manager
+            .bulk_walk(&target, &credentials, "1.3.6.1.4.1.99", 2)
+            .await
+            .unwrap();
+        assert_eq!(walked.len(), 3);
+    }
+    assert_eq!(manager.engine_id(&target), Some(engine_id));
+}
+
+#[tokio::test]
+async fn test_usm_reports() {
+    let agent = Agent::builder().usm_user(alice()).build();
+    let target = serve_app(agent).await;
+    let manager = Manager::new();
+    let oid = "1.3.6.1.4.1.99.1.0";
+
+    let stranger = Credentials::from(UsmUser::new("mallory"));
+    let error = manager.get(&target, &stranger, oid).await.unwrap_err();
+    assert_eq!(report(&error), Some(&ReportError::UnknownUserName));
+
+    let wrong_password = UsmUser::new("alice")
+        .with_auth(AuthProtocol::Sha256, b"not-alices")
+        .unwrap()
+        .with_privacy(PrivProtocol::Aes128, b"alice-priv")
+        .unwrap();
+    let error = manager
+        .get(&target, &Credentials::from(wrong_password), oid)
+        .await
+        .unwrap_err();
+    assert_eq!(report(&error), Some(&ReportError::WrongDigest));
+
+    let wrong_privacy = UsmUser::new("alice")
+        .with_auth(AuthProtocol::Sha256, b"alice-auth")
+        .unwrap()
+        .with_privacy(PrivProtocol::Aes128, b"not-alices")
+        .unwrap();
+    let error = manager
+        .get(&target, &Credentials::from(wrong_privacy), oid)
+        .await
+        .unwrap_err();
+    assert_eq!(report(&error), Some(&ReportError::DecryptionError));
+
+    // without VACM, only at the level alice is configured for
+    let no_privacy = UsmUser::new("alice")
+        .with_auth(AuthProtocol::Sha256, b"alice-auth")
+        .unwrap();
+    let error = manager
+        .get(&target, &Credentials::from(no_privacy), oid)
+        .await
+        .unwrap_err();
+    assert_eq!(report(&error), Some(&ReportError::UnsupportedSecurityLevel));
+}
+
+#[test]
+fn test_not_in_time_window() {
+    let agent = Agent::builder().usm_user(bob()).engine_boots(7).build();
+    agent.register("1.3.6.1.4.1.99", values()).unwrap();
+    let engine_id = agent.engine_id().as_bytes().to_vec();
+    let (protocol, key) = bob().localized_auth(&engine_id).unwrap();
+    let request = |boots, time| {
+        let mut message = SnmpV3Message {
+            header: HeaderData {
+                msg_id: 9,
+                max_size: 1472,
+                flags: FLAG_AUTH | FLAG_REPORTABLE,
+                security_model: SECURITY_MODEL_USM,
+            },
+            security_params: UsmSecurityParameters {
+                authoritative_engine_id: engine_id.clone(),
+                engine_boots: boots,
+                engine_time: time,
+                user_name: b"bob".to_vec(),
+                ..Default::default()
+            },
+            data: ScopedPduData::Plaintext(ScopedPdu {
+                context_engine_id: engine_id.clone(),
+                context_name: Vec::new(),
+                pdu: Pdu {
+                    tag: Asn1Tag::GetRequest,
+                    request_id: 5,
+                    data: PduData::Basic {
+                        error_status: ErrorStatus::NoError,
+                        error_index: 0,
+                    },
+                    varbinds: vec![VarBind {
+                        oid: [&APP[..], &[1, 0]].concat(),
+                        value: ObjectSyntax::Null,
+                    }],
+                },
+            }),
+        };
+        authenticate_message(&mut message, protocol, &key)
+    };
+
+    for (boots, time) in [(6, 0), (7, 1000)] {
+        let response = agent.handle(&request(boots, time)).unwrap();
+        // signed, so the manager can take over our boots and time
+        assert!(verify_message(&response, protocol, &key).is_ok());
+        let response = parse_v3_message(&response).unwrap();
+        assert_eq!(response.header.msg_id, 9);
+        assert_eq!(response.header.flags, FLAG_AUTH);
+        assert_eq!(response.security_params.engine_boots, 7);
+        let pdu = &response.scoped_pdu().unwrap().pdu;
+        assert_eq!(
+            ReportError::from_pdu(pdu),
+            Some(ReportError::NotInTimeWindow)
+        );
+    }
+
+    // inside the window the request is answered
+    let response = parse_v3_message(&agent.handle(&request(7, 100)).unwrap()).unwrap();
+    let pdu = &response.scoped_pdu().unwrap().pdu;
+    assert_eq!(pdu.tag, Asn1Tag::GetResponse);
+    assert_eq!(pdu.varbinds[0].value, ObjectSyntax::Integer(1));
+}
+
+#[tokio::test]
+async fn test_vacm_for_users() {
+    let vacm = Vacm::new()
+        .group(SecurityModel::Usm, "alice", "admins")
+        .group(SecurityModel::Usm, "bob", "readers")
+        .include("all", "1")
+        .unwrap()
+        .access("admins", SecurityLevel::AuthPriv, "all", "all")
+        .access("readers", SecurityLevel::AuthNoPriv, "all", "")
+        .access("readers", SecurityLevel::AuthPriv, "all", "all");
+    let agent = Agent::builder()
+        .vacm(vacm)
+        .usm_user(alice())
+        .usm_user(bob())
+        .build();
+    let target = serve_app(agent).await;
+    let manager = Manager::new();
+
+    let error = manager
+        .set(
+            &target,
+            &Credentials::from(bob()),
+            "1.3.6.1.4.1.99.1.0",
+            ObjectSyntax::Integer(5),
+        )
+        .await
+        .unwrap_err();
+    let error = error.downcast_ref::<SnmpError>().unwrap();
+    assert_eq!(error.status, ErrorStatus::AuthorizationError);
+
+    // with VACM, alice may also come at a lower level, where it grants nothing
+    let no_privacy = UsmUser::new("alice")
+        .with_auth(AuthProtocol::Sha256, b"alice-auth")
+        .unwrap();
+    let error = manager
+        .get(
+            &target,
+            &Credentials::from(no_privacy),
+            "1.3.6.1.4.1.99.1.0",
+        )
+        .await
+        .unwrap_err();
+    let error = error.downcast_ref::<SnmpError>().unwrap();
+    assert_eq!(error.status, ErrorStatus::AuthorizationError);
+}
+
+#[test]
+fn test_engine_file() {
+    let path = std::env::temp_dir().join(format!("rusnmp-engine-{}.conf", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let engine_id = EngineId::from_text(8072, "test agent").unwrap();
+
+    let first = Agent::builder()
+        .engine_id(engine_id.clone())
+        .engine_file(&path)
+        .unwrap()
+        .build();
+    assert_eq!(first.engine_boots(), 1);
+    // a restart keeps the saved ID and counts another boot
+    let second = Agent::builder().engine_file(&path).unwrap().build();
+    assert_eq!(second.engine_id(), &engine_id);
+    assert_eq!(second.engine_boots(), 2);
+
+    let saved = std::fs::read_to_string(&path).unwrap();
+    assert!(saved.contains("engineBoots 2"));
+    assert!(saved.contains("oldEngineID 0x80001f88"));
+    assert!(!path.with_extension("conf.tmp").exists());
+
+    // as net-snmp leaves it, among lines of its own
+    std::fs::write(
+        &path,
+        "usmUser 1 3 0x80001f8804 0x626f6200\nengineBoots 41\noldEngineID 0x80001f88047465737420616765\n",
+    )
+    .unwrap();
+    let restarted = Agent::builder().engine_file(&path).unwrap().build();
+    assert_eq!(restarted.engine_boots(), 42);
+    assert_eq!(
+        restarted.engine_id(),
+        &EngineId::from_hex("80001f88047465737420616765").unwrap()
+    );
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn test_cleartext_response_to_private_request() {
+    // answers alice's authPriv requests at authNoPriv, in the clear
+    let agent = Agent::builder().usm_user(alice()).build();
+    agent.register("1.3.6.1.4.1.99", values()).unwrap();
+    let engine_id = agent.engine_id().as_bytes().to_vec();
+    let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let target = socket.local_addr().unwrap().to_string();
+    tokio::spawn(async move {
+        let (auth, auth_key) = alice().localized_auth(&engine_id).unwrap();
+        let (privacy, priv_key) = alice().localized_priv(&engine_id).unwrap();
+        let mut buf = [0u8; 1500];
+        loop {
+            let (len, from) = socket.recv_from(&mut buf).await.unwrap();
+            let Some(answer) = agent.handle(&buf[..len]) else {
+                continue;
+            };
+            let mut message = parse_v3_message(&answer).unwrap();
+            let answer = if message.header.flags & FLAG_PRIV != 0 {
+                decrypt_scoped_pdu(&mut message, privacy, &priv_key).unwrap();
+                message.header.flags &= !FLAG_PRIV;
+                message.security_params.priv_params.clear();
+                authenticate_message(&mut message, auth, &auth_key)
+            } else {
+                answer
+            };
+            socket.send_to(&answer, from).await.unwrap();
+        }
+    });
+
+    let error = Manager::new()
+        .get(&target, &Credentials::from(alice()), "1.3.6.1.4.1.99.1.0")
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error.downcast_ref::<UsmError>(),
+        Some(&UsmError::NotEncrypted)
+    );
+}
+
+#[tokio::test]
+async fn test_replaced_agent_is_rediscovered() {
+    let serve_on = |socket: UdpSocket, engine: &str| {
+        let agent = Agent::builder()
+            .engine_id(EngineId::from_text(8072, engine).unwrap())
+            .usm_user(alice())
+            .build();
+        agent.register("1.3.6.1.4.1.99", values()).unwrap();
+        let agent = Arc::new(agent);
+        tokio::spawn(async move { agent.serve(socket).await })
+    };
+    let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let target = socket.local_addr().unwrap().to_string();
+    let served = serve_on(socket, "old device");
+    let manager = Manager::new();
+    let credentials = Credentials::from(alice());
+    let oid = "1.3.6.1.4.1.99.1.0";
+    manager.get(&target, &credentials, oid).await.unwrap();
+
+    // the same address, answered by a new engine
+    served.abort();
+    let _ = served.await;
+    let socket = UdpSocket::bind(&target).await.unwrap();
+    serve_on(socket, "new device");
+    for _ in 0..2 {
+        let varbind = manager.get(&target, &credentials, oid).await.unwrap();
+        assert_eq!(varbind.value, ObjectSyntax::Integer(1));
+    }
+    assert_eq!(
+        manager.engine_id(&target),
+        Some(EngineId::from_text(8072, "new device").unwrap())
+    );
+}
@@ -0,0 +1,298 @@
+#![cfg(feature = "agentx")]
+// A master agent played by hand over an in-memory stream, speaking just
+// enough AgentX (RFC 2741) to open a session and send requests.
+
+use std::sync::Arc;
+
+use rusnmp::agent::{Agent, SubtreeHandler};
+use rusnmp::snmp::pdu::{ErrorStatus, ObjectSyntax};
+use tokio::io::{AsyncReadExt, AsyncWriteExt, DuplexStream, duplex};
+
+mod common;
+use common::Values;
+
+const APP: [u64; 7] = [1, 3, 6, 1, 4, 1, 99];
+
+fn oid(arc: u64) -> Vec<u64> {
+    [&APP[..], &[arc, 0]].concat()
+}
+
+struct Master {
+    stream: DuplexStream,
+    packet_id: u32,
+}
+
+// a PDU as the master reads it, already in network byte order
+struct Received {
+    kind: u8,
+    session_id: u32,
+    packet_id: u32,
+    payload: Vec<u8>,
+}
+
+impl Master {
+    async fn receive(&mut self) -> Received {
+        let mut header = [0; 20];
+        self.stream.read_exact(&mut header).await.unwrap();
+        assert_eq!(header[0], 1);
+        assert_eq!(header[2] & 0x10, 0x10);
+        let word = |at: usize| u32::from_be_bytes(header[at..at + 4].try_into().unwrap());
+        let mut payload = vec![0; word(16) as usize];
+        self.stream.read_exact(&mut payload).await.unwrap();
+        Received {
+            kind: header[1],
+            session_id: word(4),
+            packet_id: word(12),
+            payload,
+        }
+    }
+
+    async fn send(&mut self, kind: u8, packet_id: u32, payload: &[u8], network_order: bool) {
+        let word = |value: u32| {
+            if network_order {
+                value.to_be_bytes()
+            } else {
+                value.to_le_bytes()
+            }
+        };
+        let flags = if network_order { 0x10 } else { 0 };
+        let mut pdu = vec![1, kind, flags, 0];
+        for value in [7, 1, packet_id, payload.len() as u32] {
+            pdu.extend_from_slice(&word(value));
+        }
+        pdu.extend_from_slice(payload);
+        self.stream.write_all(&pdu).await.unwrap();
+    }
+
+    async fn respond(&mut self, to: &Received, error: u16) {
+        let mut payload = vec![0; 4];
+        payload.extend_from_slice(&error.to_be_bytes());
+        payload.extend_from_slice(&[0, 0]);
+        self.send(18, to.packet_id, &payload, true).await;
+    }
+
+    // sends a request and returns res.error, res.index and the varbinds
+    async fn request(&mut self, kind: u8, payload: &[u8]) -> (u16, u16, Vec<u8>) {
+        self.packet_id += 1;
+        self.send(kind, self.packet_id, payload, true).await;
+        let response = self.receive().await;
+        assert_eq!(response.kind, 18);
+        assert_eq!(response.packet_id, self.packet_id);
+        let error = u16::from_be_bytes([response.payload[4], response.payload[5]]);
+        let index = u16::from_be_bytes([response.payload[6], response.payload[7]]);
+        (error, index, response.payload[8..].to_vec())
+    }
+}
+
+fn encode_oid(oid: &[u64], include: bool) -> Vec<u8> {
+    let mut bytes = vec![oid.len() as u8, 0, include as u8, 0];
+    for &subid in oid {
+        bytes.extend_from_slice(&(subid as u32).to_be_bytes());
+    }
+    bytes
+}
+
+fn range(start: &[u64], include: bool, end: &[u64]) -> Vec<u8> {
+    [encode_oid(start, include), encode_oid(end, false)].concat()
+}
+
+// the (name, type, data) of each varbind in a response
+fn decode_varbinds(mut bytes: &[u8]) -> Vec<(Vec<u64>, u16, Vec<u8>)> {
+    let mut varbinds = Vec::new();
+    while !bytes.is_empty() {
+        let kind = u16::from_be_bytes([bytes[0], bytes[1]]);
+        let (n_subid, prefix) = (bytes[4] as usize, bytes[5]);
+        let mut name = if prefix == 0 {
+            Vec::new()
+        } else {
+            vec![1, 3, 6, 1, u64::from(prefix)]
+        };
+        for i in 0..n_subid {
+            let at = 8 + i * 4;
+            name.push(u64::from(u32::from_be_bytes(
+                bytes[at..at + 4].try_into().unwrap(),
+            )));
+        }
+        bytes = &bytes[8 + n_subid * 4..];
+        let len = match kind {
+            2 | 65 | 66 | 67 => 4,
+            70 => 8,
+            4 => {
+                4 + (u32::from_be_bytes(bytes[..4].try_into().unwrap()) as usize)
+                    .next_multiple_of(4)
+            }
+            _ => 0,
+        };
+        varbinds.push((name, kind, bytes[..len].to_vec()));
+        bytes = &bytes[len..];
+    }
+    varbinds
+}
+
+async fn open(agent: Arc<Agent>) -> (Master, tokio::task::JoinHandle<anyhow::Result<()>>) {
+    let (subagent, stream) = duplex(4096);
+    let served = tokio::spawn(async move { agent.serve_agentx(subagent).await });
+    let mut master = Master {
+        stream,
+        packet_id: 0,
+    };
+
+    let open = master.receive().await;
+    assert_eq!(open.kind, 1);
+    master.respond(&open, 0).await;
+    let register = master.receive().await;
+    assert_eq!(register.kind, 3);
+    // the subtree goes with the 1.3.6.1.4 prefix
+    assert_eq!(register.session_id, 7);
+    assert_eq!(&register.payload[4..8], &[2, 4, 0, 0]);
+    master.respond(&register, 0).await;
+    (master, served)
+}
+
+fn agent() -> (Arc<Agent>, Values) {
+    let values = Values::new(
+        &APP,
+        &[
+            (1, ObjectSyntax::Integer(7)),
+            (2, ObjectSyntax::OctetString(b"ok".to_vec())),
+        ],
+    )
+    .writable();
+    let agent = Agent::new("public");
+    agent.register("1.3.6.1.4.1.99", values.clone()).unwrap();
+    (Arc::new(agent), values)
+}
+
+#[tokio::test]
+async fn test_get_and_get_next() {
+    let (agent, _) = agent();
+    let (mut master, served) = open(agent).await;
+
+    let payload = [range(&oid(1), false, &[]), range(&oid(9), false, &[])].concat();
+    let (error, _, varbinds) = master.request(5, &payload).await;
+    assert_eq!(error, 0);
+    let varbinds = decode_varbinds(&varbinds);
+    assert_eq!(varbinds[0], (oid(1), 2, 7u32.to_be_bytes().to_vec()));
+    assert_eq!(varbinds[1], (oid(9), 129, Vec::new()));
+
+    // include takes the start itself; the end bounds the search
+    let payload = [
+        range(&oid(1), true, &[]),
+        range(&oid(1), false, &[]),
+        range(&oid(1), false, &oid(2)),
+    ]
+    .concat();
+    let (_, _, varbinds) = master.request(6, &payload).await;
+    let varbinds = decode_varbinds(&varbinds);
+    assert_eq!(varbinds[0].0, oid(1));
+    assert_eq!(varbinds[1].0, oid(2));
+    assert_eq!(varbinds[1].1, 4);
+    assert_eq!(varbinds[2], (oid(1), 130, Vec::new()));
+
+    // GetBulk: no non-repeaters, three repetitions of one range
+    let payload = [vec![0, 0, 0, 3], range(&APP, false, &[])].concat();
+    let (_, _, varbinds) = master.request(7, &payload).await;
+    let kinds: Vec<u16> = decode_varbinds(&varbinds).iter().map(|v| v.1).collect();
+    assert_eq!(kinds, [2, 4, 130]);
+
+    // close
+    master.send(2, 99, &[1, 0, 0, 0], true).await;
+    served.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn test_unencodable_oids_are_gen_err() {
+    let (agent, values) = agent();
+    let long = [&APP[..], &[5], &[1; 130]].concat();
+    values.insert(
+        oid(3),
+        ObjectSyntax::ObjectIdentifier(vec![1, 3, 6, 1, 1 << 32]),
+    );
+    values.insert(long, ObjectSyntax::Integer(5));
+    let (mut master, _served) = open(agent).await;
+
+    // a value whose sub-identifier needs more than 32 bits
+    let payload = [range(&oid(1), false, &[]), range(&oid(3), false, &[])].concat();
+    let (error, index, varbinds) = master.request(5, &payload).await;
+    assert_eq!(error, ErrorStatus::GenErr as u16);
+    assert_eq!(index, 2);
+    assert!(varbinds.is_empty());
+
+    // a name longer than AgentX allows
+    let (error, index, _) = master.request(6, &range(&oid(4), false, &[])).await;
+    assert_eq!((error, index), (ErrorStatus::GenErr as u16, 1));
+}
+
+#[tokio::test]
+async fn test_little_endian_request() {
+    let (agent, _) = agent();
+    let (mut master, served) = open(agent).await;
+
+    let mut start = vec![oid(1).len() as u8, 0, 1, 0];
+    for subid in oid(1) {
+        start.extend_from_slice(&(subid as u32).to_le_bytes());
+    }
+    let payload = [start, vec![0; 4]].concat();
+    master.send(5, 1, &payload, false).await;
+    let response = master.receive().await;
+    let varbinds = decode_varbinds(&response.payload[8..]);
+    assert_eq!(varbinds[0], (oid(1), 2, 7u32.to_be_bytes().to_vec()));
+
+    drop(master);
+    served.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn test_set_phases() {
+    let (agent, values) = agent();
+    let (mut master, _served) = open(agent).await;
+
+    let varbind = |arc: u64, kind: u16, data: &[u8]| {
+        [
+            kind.to_be_bytes().to_vec(),
+            vec![0, 0],
+            encode_oid(&oid(arc), false),
+            data.to_vec(),
+        ]
+        .concat()
+    };
+    let set = [varbind(1, 2, &[0, 0, 0, 20]), varbind(3, 2, &[0, 0, 0, 30])].concat();
+    assert_eq!(master.request(8, &set).await.0, 0);
+    assert_eq!(values.get(&oid(1)), Some(ObjectSyntax::Integer(7)));
+    assert_eq!(master.request(9, &[]).await.0, 0);
+    assert_eq!(values.get(&oid(1)), Some(ObjectSyntax::Integer(20)));
+    assert_eq!(values.get(&oid(3)), Some(ObjectSyntax::Integer(30)));
+
+    // another subagent failed to commit: the master undoes ours
+    assert_eq!(master.request(10, &[]).await.0, 0);
+    assert_eq!(values.get(&oid(1)), Some(ObjectSyntax::Integer(7)));
+    assert_eq!(values.get(&oid(3)), None);
+    master.send(11, 50, &[], true).await;
+
+    let set = [
+        varbind(1, 2, &[0, 0, 0, 20]),
+        varbind(2, 4, &[0, 0, 0, 1, b'x', 0, 0, 0]),
+    ]
+    .concat();
+    let (error, index, _) = master.request(8, &set).await;
+    assert_eq!(error, ErrorStatus::WrongType as u16);
+    assert_eq!(index, 2);
+}
+
+#[tokio::test]
+async fn test_refused_registration() {
+    let (agent, _) = agent();
+    let (subagent, stream) = duplex(4096);
+    let served = tokio::spawn(async move { agent.serve_agentx(subagent).await });
+    let mut master = Master {
+        stream,
+        packet_id: 0,
+    };
+    let open = master.receive().await;
+    master.respond(&open, 0).await;
+    let register = master.receive().await;
+    // duplicateRegistration
+    master.respond(&register, 263).await;
+    let error = served.await.unwrap().unwrap_err();
+    assert!(format!("{:#}", error).contains("duplicateRegistration"));
+}
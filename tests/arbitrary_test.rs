@@ -0,0 +1,62 @@
+#![cfg(feature = "arbitrary")]
+
+use arbitrary::{Arbitrary, Unstructured};
+use rusnmp::ber::parse_ber_object;
+use rusnmp::snmp::message::{SnmpMessage, parse_message};
+use rusnmp::snmp::pdu::{VarBind, parse_varbind};
+
+// xorshift, so failures reproduce from the printed seed
+fn noise(seed: u64, len: usize) -> Vec<u8> {
+    let mut state = seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1;
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        })
+        .collect()
+}
+
+#[test]
+fn test_message_round_trip() {
+    for seed in 0..2000 {
+        let bytes = noise(seed, 4096);
+        let message = SnmpMessage::arbitrary(&mut Unstructured::new(&bytes)).unwrap();
+
+        let encoded = message.to_bytes();
+        assert_eq!(encoded.len(), message.encoded_len(), "seed {}", seed);
+        assert_eq!(parse_message(&encoded).unwrap(), message, "seed {}", seed);
+    }
+}
+
+#[test]
+fn test_varbind_round_trip() {
+    for seed in 0..2000 {
+        let bytes = noise(seed, 512);
+        let varbind = VarBind::arbitrary(&mut Unstructured::new(&bytes)).unwrap();
+
+        let mut encoded = Vec::new();
+        varbind.write_to_buf(&mut encoded);
+        let (obj, rest) = parse_ber_object(&encoded).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(parse_varbind(obj).unwrap(), varbind, "seed {}", seed);
+    }
+}
+
+#[test]
+fn test_parser_survives_mutations() {
+    for seed in 0..500 {
+        let bytes = noise(seed, 1024);
+        let mut encoded = SnmpMessage::arbitrary(&mut Unstructured::new(&bytes))
+            .unwrap()
+            .to_bytes();
+
+        // flip a few bytes; parsing may fail but must not panic
+        for (i, flip) in noise(!seed, 4).into_iter().enumerate() {
+            let position = (flip as usize * (i + 1) * 31) % encoded.len();
+            encoded[position] ^= flip | 1;
+        }
+        let _ = parse_message(&encoded);
+    }
+}
@@ -0,0 +1,21 @@
+use rusnmp::snmp::pdu::ObjectSyntax;
+
+// labels as they would appear in a BITS { ... } definition
+const LABELS: [(u32, &str); 3] = [(0, "unknown"), (1, "halfDuplex"), (2, "fullDuplex")];
+
+#[test]
+fn test_bits_positions() {
+    let value = ObjectSyntax::OctetString(vec![0b0110_0000, 0b0000_0001]);
+    assert_eq!(value.bits(), Some(vec![1, 2, 15]));
+    assert_eq!(ObjectSyntax::OctetString(Vec::new()).bits(), Some(vec![]));
+    assert_eq!(ObjectSyntax::Integer(3).bits(), None);
+}
+
+#[test]
+fn test_bit_names() {
+    let value = ObjectSyntax::OctetString(vec![0b0010_0000, 0b0000_0001]);
+    assert_eq!(
+        value.bit_names(&LABELS),
+        Some(vec!["fullDuplex".to_string(), "15".to_string()])
+    );
+}
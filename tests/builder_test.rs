@@ -0,0 +1,84 @@
+use std::time::Duration;
+
+use common::FakeAgent;
+use rusnmp::manager::{Credentials, ErrorClass, Manager};
+use rusnmp::snmp::pdu::{ErrorStatus, ObjectSyntax, PduData};
+
+mod common;
+
+// answers every GetRequest after ignoring the first `ignore` packets
+async fn agent(ignore: usize) -> u16 {
+    let mut seen = 0;
+    let agent = FakeAgent::new().serve(move |message| {
+        seen += 1;
+        if seen <= ignore {
+            return false;
+        }
+        message.pdu.data = PduData::Basic {
+            error_status: ErrorStatus::NoError,
+            error_index: 0,
+        };
+        for varbind in &mut message.pdu.varbinds {
+            varbind.value = ObjectSyntax::OctetString(b"fake".to_vec());
+        }
+        true
+    });
+    agent.await.port
+}
+
+#[tokio::test]
+async fn test_port_and_default_credentials() {
+    let manager = Manager::builder()
+        .port(agent(0).await)
+        .community("public")
+        .build();
+
+    let credentials = manager.default_credentials().unwrap().clone();
+    let varbind = manager
+        .get("127.0.0.1", &credentials, "1.3.6.1.2.1.1.5.0")
+        .await
+        .unwrap();
+    assert_eq!(varbind.value, ObjectSyntax::OctetString(b"fake".to_vec()));
+    assert!(Manager::new().default_credentials().is_none());
+}
+
+#[tokio::test]
+async fn test_retries_after_timeout() {
+    let manager = Manager::builder()
+        .port(agent(1).await)
+        .timeout(Duration::from_millis(200))
+        .retries(1)
+        .build();
+
+    manager
+        .get(
+            "127.0.0.1",
+            &Credentials::v2c("public"),
+            "1.3.6.1.2.1.1.5.0",
+        )
+        .await
+        .unwrap();
+    let stats = manager.stats("127.0.0.1").unwrap();
+    assert_eq!(stats.packets_sent, 2);
+    assert_eq!(stats.timeouts, 1);
+    assert_eq!(stats.retransmits, 1);
+}
+
+#[tokio::test]
+async fn test_timeout_without_retries() {
+    let manager = Manager::builder()
+        .port(agent(usize::MAX).await)
+        .timeout(Duration::from_millis(100))
+        .build();
+
+    let error = manager
+        .get(
+            "127.0.0.1",
+            &Credentials::v2c("public"),
+            "1.3.6.1.2.1.1.5.0",
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(ErrorClass::of(&error), ErrorClass::Timeout);
+    assert_eq!(manager.stats("127.0.0.1").unwrap().packets_sent, 1);
+}
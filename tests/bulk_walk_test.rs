@@ -0,0 +1,50 @@
+use common::cells_agent;
+use rusnmp::manager::{Credentials, ErrorClass, Manager};
+use rusnmp::snmp::pdu::{ObjectSyntax, VarBind};
+
+mod common;
+
+#[tokio::test]
+async fn test_refused_bulk_walk_keeps_bulk() {
+    let manager = Manager::new();
+    assert!(manager.supports_bulk("127.0.0.1"));
+
+    // nothing listens on the loopback SNMP port; that says nothing about
+    // GETBULK support, so there is no GetNext retry to remember
+    let error = manager
+        .bulk_walk(
+            "127.0.0.1",
+            &Credentials::v2c("public"),
+            "1.3.6.1.2.1.1",
+            10,
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(ErrorClass::of(&error), ErrorClass::Refused);
+    assert!(manager.supports_bulk("127.0.0.1"));
+}
+
+#[tokio::test]
+async fn test_offloaded_parsing_walks_the_same() {
+    let cells: Vec<VarBind> = (1..=40)
+        .map(|row| VarBind {
+            oid: vec![1, 3, 6, 1, 4, 1, 99, 1, row],
+            value: ObjectSyntax::OctetString(format!("row {}", row).into_bytes()),
+        })
+        .collect();
+    let target = cells_agent(cells.clone()).await;
+    let community = Credentials::v2c("public");
+
+    // every response is big enough to go to the blocking pool
+    let offloaded = Manager::new()
+        .with_offloaded_parsing(Some(0))
+        .bulk_walk(&target, &community, "1.3.6.1.4.1.99", 10)
+        .await
+        .unwrap();
+    let inline = Manager::new()
+        .bulk_walk(&target, &community, "1.3.6.1.4.1.99", 10)
+        .await
+        .unwrap();
+    assert_eq!(offloaded, inline);
+    assert_eq!(offloaded, cells);
+}
@@ -0,0 +1,111 @@
+use std::future::pending;
+use std::time::Duration;
+
+use common::FakeAgent;
+use rusnmp::manager::{CancelledError, Credentials, ErrorClass, Manager};
+use rusnmp::snmp::pdu::{ErrorStatus, ObjectSyntax, PduData};
+use tokio::net::UdpSocket;
+use tokio::time::sleep;
+
+mod common;
+
+const TABLE: [u64; 7] = [1, 3, 6, 1, 4, 1, 99];
+
+// a table of `rows` integers under TABLE, one row per GetNext or GetBulk,
+// each answered after `delay`
+async fn agent(rows: u64, delay: Duration) -> String {
+    let agent = FakeAgent::new().delay(delay).serve(move |message| {
+        message.pdu.data = PduData::Basic {
+            error_status: ErrorStatus::NoError,
+            error_index: 0,
+        };
+        message.pdu.varbinds.truncate(1);
+        let varbind = &mut message.pdu.varbinds[0];
+        let row = varbind.oid.get(TABLE.len()).map_or(1, |row| row + 1);
+        varbind.oid = [&TABLE[..], &[row]].concat();
+        varbind.value = if row > rows {
+            ObjectSyntax::EndOfMib
+        } else {
+            ObjectSyntax::Integer(row as i32)
+        };
+        true
+    });
+    agent.await.target
+}
+
+fn collected(error: &anyhow::Error) -> usize {
+    assert_eq!(ErrorClass::of(error), ErrorClass::Cancelled);
+    error
+        .downcast_ref::<CancelledError>()
+        .unwrap()
+        .collected
+        .len()
+}
+
+#[tokio::test]
+async fn test_walk_until_keeps_partial_results() {
+    let target = agent(100, Duration::from_millis(40)).await;
+    let manager = Manager::new();
+    let community = Credentials::v2c("public");
+
+    let error = manager
+        .walk_until(
+            &target,
+            &community,
+            "1.3.6.1.4.1.99",
+            sleep(Duration::from_millis(150)),
+        )
+        .await
+        .unwrap_err();
+    let walked = collected(&error);
+    assert!((1..100).contains(&walked), "walked {}", walked);
+
+    let error = manager
+        .bulk_walk_until(
+            &target,
+            &community,
+            "1.3.6.1.4.1.99",
+            10,
+            sleep(Duration::from_millis(150)),
+        )
+        .await
+        .unwrap_err();
+    let walked = collected(&error);
+    assert!((1..100).contains(&walked), "walked {}", walked);
+}
+
+#[tokio::test]
+async fn test_until_finishes_without_stop() {
+    let target = agent(3, Duration::ZERO).await;
+    let manager = Manager::new();
+    let community = Credentials::v2c("public");
+
+    let walked = manager
+        .walk_until(&target, &community, "1.3.6.1.4.1.99", pending())
+        .await
+        .unwrap();
+    assert_eq!(walked.len(), 3);
+    let walked = manager
+        .bulk_walk_until(&target, &community, "1.3.6.1.4.1.99", 10, pending())
+        .await
+        .unwrap();
+    assert_eq!(walked.len(), 3);
+}
+
+#[tokio::test]
+async fn test_get_until_stops_waiting() {
+    // never answers
+    let silent = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let target = silent.local_addr().unwrap().to_string();
+
+    let error = Manager::new()
+        .get_until(
+            &target,
+            &Credentials::v2c("public"),
+            "1.3.6.1.2.1.1.1.0",
+            sleep(Duration::from_millis(50)),
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(collected(&error), 0);
+}
@@ -0,0 +1,101 @@
+#![cfg(feature = "cli")]
+// `rusnmp get --auto-instance` run as a process against a local agent,
+// for each way agents say an OID names an object rather than its
+// instance.
+
+use std::process::Command;
+use std::sync::Arc;
+
+use rusnmp::agent::{Agent, SubtreeHandler};
+use rusnmp::ber::Asn1Tag;
+use rusnmp::snmp::message::parse_message;
+use rusnmp::snmp::pdu::{ErrorStatus, ObjectSyntax, PduData, VarBind};
+use tokio::net::UdpSocket;
+
+// answers only sysUpTime.0-style instances: `<subtree>.0`
+struct Scalar(Vec<u64>);
+
+impl SubtreeHandler for Scalar {
+    fn get(&self, oid: &[u64]) -> Option<ObjectSyntax> {
+        (oid == [&self.0[..], &[0]].concat()).then_some(ObjectSyntax::Integer(42))
+    }
+
+    fn get_next(&self, _: &[u64]) -> Option<VarBind> {
+        None
+    }
+}
+
+async fn get(target: &str, oid: &str) -> String {
+    let args = ["get", "-c", "public", "-o", oid, "--auto-instance", target].map(String::from);
+    let output = tokio::task::spawn_blocking(move || {
+        Command::new(env!("CARGO_BIN_EXE_rusnmp"))
+            .args(args)
+            .output()
+            .unwrap()
+    })
+    .await
+    .unwrap();
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+async fn serve_agent() -> String {
+    let agent = Agent::new("public");
+    // noSuchInstance: inside a registration, but no such instance
+    agent
+        .register("1.3.6.1.4.1.99.1", Scalar(vec![1, 3, 6, 1, 4, 1, 99, 1]))
+        .unwrap();
+    // noSuchObject: only the instance itself is registered
+    agent
+        .register("1.3.6.1.4.1.99.2.0", Scalar(vec![1, 3, 6, 1, 4, 1, 99, 2]))
+        .unwrap();
+    let agent = Arc::new(agent);
+    let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let target = socket.local_addr().unwrap().to_string();
+    tokio::spawn(async move { agent.serve(socket).await });
+    target
+}
+
+#[tokio::test]
+async fn test_retries_after_no_such_instance() {
+    let target = serve_agent().await;
+    let output = get(&target, "1.3.6.1.4.1.99.1").await;
+    assert!(output.contains("1.3.6.1.4.1.99.1.0"), "{}", output);
+    assert!(output.contains("42"), "{}", output);
+}
+
+#[tokio::test]
+async fn test_retries_after_no_such_object() {
+    let target = serve_agent().await;
+    let output = get(&target, "1.3.6.1.4.1.99.2").await;
+    assert!(output.contains("1.3.6.1.4.1.99.2.0"), "{}", output);
+    assert!(output.contains("42"), "{}", output);
+}
+
+#[tokio::test]
+async fn test_retries_after_no_such_name() {
+    // an agent answering v2c the v1 way, with an error status
+    let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let target = socket.local_addr().unwrap().to_string();
+    tokio::spawn(async move {
+        let mut buf = [0u8; 1500];
+        loop {
+            let (len, from) = socket.recv_from(&mut buf).await.unwrap();
+            let mut message = parse_message(&buf[..len]).unwrap();
+            message.pdu.tag = Asn1Tag::GetResponse;
+            let varbind = &mut message.pdu.varbinds[0];
+            if varbind.oid.last() == Some(&0) {
+                varbind.value = ObjectSyntax::Integer(42);
+            } else {
+                message.pdu.data = PduData::Basic {
+                    error_status: ErrorStatus::NoSuchName,
+                    error_index: 1,
+                };
+            }
+            socket.send_to(&message.to_bytes(), from).await.unwrap();
+        }
+    });
+
+    let output = get(&target, "1.3.6.1.4.1.99.3").await;
+    assert!(output.contains("1.3.6.1.4.1.99.3.0"), "{}", output);
+    assert!(output.contains("42"), "{}", output);
+}
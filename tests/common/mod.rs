@@ -0,0 +1,214 @@
+// A fake UDP agent for the manager tests. Each request is handed to a
+// closure, which edits it into the answer, or returns false to leave it
+// unanswered; the agent then sends it back as a GetResponse. The agent
+// tests share a handler and a way to serve a real Agent.
+#![allow(dead_code)]
+
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::ops::Bound;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use rusnmp::agent::{Agent, SubtreeHandler};
+use rusnmp::ber::Asn1Tag;
+use rusnmp::snmp::message::{SnmpMessage, parse_message};
+use rusnmp::snmp::pdu::{ErrorStatus, ObjectSyntax, PduData, VarBind};
+use tokio::net::UdpSocket;
+use tokio::sync::mpsc;
+
+pub struct FakeAgent {
+    address: String,
+    delay: Duration,
+    batch: usize,
+}
+
+pub struct Running {
+    pub target: String,
+    pub port: u16,
+    // where each request came from, in the order they arrived
+    pub seen: mpsc::UnboundedReceiver<SocketAddr>,
+}
+
+impl FakeAgent {
+    pub fn new() -> Self {
+        FakeAgent {
+            address: "127.0.0.1:0".to_string(),
+            delay: Duration::ZERO,
+            batch: 1,
+        }
+    }
+
+    pub fn bind(mut self, address: &str) -> Self {
+        self.address = address.to_string();
+        self
+    }
+
+    // waits this long before answering
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    // holds requests until `batch` have come, then answers the newest first
+    pub fn batch(mut self, batch: usize) -> Self {
+        self.batch = batch;
+        self
+    }
+
+    pub async fn serve<F>(self, mut respond: F) -> Running
+    where
+        F: FnMut(&mut SnmpMessage) -> bool + Send + 'static,
+    {
+        let socket = UdpSocket::bind(&self.address).await.unwrap();
+        let address = socket.local_addr().unwrap();
+        let (senders, seen) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let mut buf = [0; 4096];
+            loop {
+                let mut answers = Vec::new();
+                for _ in 0..self.batch {
+                    let (len, from) = socket.recv_from(&mut buf).await.unwrap();
+                    let _ = senders.send(from);
+                    let mut message = parse_message(&buf[..len]).unwrap();
+                    if respond(&mut message) {
+                        message.pdu.tag = Asn1Tag::GetResponse;
+                        answers.push((message, from));
+                    }
+                }
+                if !self.delay.is_zero() {
+                    tokio::time::sleep(self.delay).await;
+                }
+                for (message, from) in answers.into_iter().rev() {
+                    socket.send_to(&message.to_bytes(), from).await.unwrap();
+                }
+            }
+        });
+        Running {
+            target: address.to_string(),
+            port: address.port(),
+            seen,
+        }
+    }
+}
+
+// serves `cells` to GetNext and GetBulk
+pub async fn cells_agent(mut cells: Vec<VarBind>) -> String {
+    cells.sort_by(|a, b| a.oid.cmp(&b.oid));
+    let agent = FakeAgent::new().serve(move |message| {
+        next_cells(&cells, message);
+        true
+    });
+    agent.await.target
+}
+
+// answers a GetNext or GetBulk from `cells`, sorted by OID: each
+// repetition moves every requested OID on by one cell
+pub fn next_cells(cells: &[VarBind], message: &mut SnmpMessage) {
+    let count = match message.pdu.data {
+        PduData::Bulk {
+            max_repititions, ..
+        } => max_repititions as usize,
+        _ => 1,
+    };
+    let mut cursors: Vec<Vec<u64>> = message.pdu.varbinds.iter().map(|v| v.oid.clone()).collect();
+    let mut next = Vec::new();
+    for _ in 0..count {
+        for cursor in &mut cursors {
+            let varbind = match cells.iter().find(|cell| cell.oid > *cursor) {
+                Some(cell) => cell.clone(),
+                None => VarBind {
+                    oid: cursor.clone(),
+                    value: ObjectSyntax::EndOfMib,
+                },
+            };
+            cursor.clone_from(&varbind.oid);
+            next.push(varbind);
+        }
+    }
+    message.pdu.data = PduData::Basic {
+        error_status: ErrorStatus::NoError,
+        error_index: 0,
+    };
+    message.pdu.varbinds = next;
+}
+
+// serves `agent` on a loopback port
+pub async fn serve(agent: impl Into<Arc<Agent>>) -> String {
+    let agent = agent.into();
+    let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let target = socket.local_addr().unwrap().to_string();
+    tokio::spawn(async move { agent.serve(socket).await });
+    target
+}
+
+// a set of instances, as an application's handler might keep them.
+// Clones share the instances, so a test can keep one to look at. Read-only
+// unless made writable, and then only Integers may be set.
+#[derive(Clone, Default)]
+pub struct Values {
+    values: Arc<Mutex<BTreeMap<Vec<u64>, ObjectSyntax>>>,
+    writable: bool,
+}
+
+impl Values {
+    // each value as the instance `subtree.arc.0`
+    pub fn new(subtree: &[u64], values: &[(u64, ObjectSyntax)]) -> Self {
+        let values = values
+            .iter()
+            .map(|(arc, value)| ([subtree, &[*arc, 0]].concat(), value.clone()))
+            .collect();
+        Values {
+            values: Arc::new(Mutex::new(values)),
+            writable: false,
+        }
+    }
+
+    pub fn writable(mut self) -> Self {
+        self.writable = true;
+        self
+    }
+
+    pub fn insert(&self, oid: Vec<u64>, value: ObjectSyntax) {
+        self.values.lock().unwrap().insert(oid, value);
+    }
+}
+
+impl SubtreeHandler for Values {
+    fn get(&self, oid: &[u64]) -> Option<ObjectSyntax> {
+        self.values.lock().unwrap().get(oid).cloned()
+    }
+
+    fn get_next(&self, oid: &[u64]) -> Option<VarBind> {
+        let values = self.values.lock().unwrap();
+        let (oid, value) = values
+            .range::<[u64], _>((Bound::Excluded(oid), Bound::Unbounded))
+            .next()?;
+        Some(VarBind {
+            oid: oid.clone(),
+            value: value.clone(),
+        })
+    }
+
+    fn test_set(&self, _: &[u64], value: &ObjectSyntax) -> Result<(), ErrorStatus> {
+        match value {
+            _ if !self.writable => Err(ErrorStatus::NotWritable),
+            ObjectSyntax::Integer(_) => Ok(()),
+            _ => Err(ErrorStatus::WrongType),
+        }
+    }
+
+    fn commit_set(&self, oid: &[u64], value: &ObjectSyntax) -> bool {
+        self.insert(oid.to_vec(), value.clone());
+        true
+    }
+
+    fn undo_set(&self, oid: &[u64], previous: Option<&ObjectSyntax>) -> bool {
+        let mut values = self.values.lock().unwrap();
+        match previous {
+            Some(value) => values.insert(oid.to_vec(), value.clone()),
+            None => values.remove(oid),
+        };
+        true
+    }
+}
@@ -0,0 +1,42 @@
+use rusnmp::manager::{Credentials, ErrorClass, Manager};
+#[cfg(feature = "v3")]
+use rusnmp::snmp::usm::UsmUser;
+
+#[test]
+#[cfg(feature = "v3")]
+fn test_debug_hides_communities() {
+    let debug = format!("{:?}", Credentials::v2c("s3cret"));
+    assert!(!debug.contains("s3cret"));
+
+    let user: Credentials = UsmUser::new(b"monitor").into();
+    assert_eq!(format!("{:?}", user), "UsmV3(\"monitor\")");
+}
+
+#[tokio::test]
+async fn test_v1_has_no_get_bulk() {
+    let error = Manager::new()
+        .get_bulk("127.0.0.1", &Credentials::v1("public"), 0, 10, &["1.3.6"])
+        .await
+        .unwrap_err();
+    assert_eq!(ErrorClass::of(&error), ErrorClass::Other);
+}
+
+#[tokio::test]
+async fn test_v1_get_goes_out() {
+    // nothing listens in the sandbox, so reaching the network is the proof
+    let error = Manager::new()
+        .get("127.0.0.1", &Credentials::v1("public"), "1.3.6.1.2.1.1.3.0")
+        .await
+        .unwrap_err();
+    assert_eq!(ErrorClass::of(&error), ErrorClass::Refused);
+}
+
+#[tokio::test]
+#[cfg(feature = "v3")]
+async fn test_notifications_need_v2c() {
+    let error = Manager::new()
+        .inform("127.0.0.1", &UsmUser::new(b"monitor").into(), Vec::new(), 0)
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("v2c"));
+}
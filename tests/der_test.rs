@@ -0,0 +1,59 @@
+use rusnmp::ber::decoder::validate_der;
+use rusnmp::ber::{Asn1Tag, BerError};
+use rusnmp::snmp::message::SnmpMessage;
+use rusnmp::snmp::pdu::{ErrorStatus, ObjectSyntax, Pdu, PduData, VarBind};
+
+#[test]
+fn test_encoder_output_is_der() {
+    let message = SnmpMessage {
+        version: 1,
+        community: b"public".to_vec(),
+        pdu: Pdu {
+            tag: Asn1Tag::GetResponse,
+            request_id: 128,
+            data: PduData::Basic {
+                error_status: ErrorStatus::NoError,
+                error_index: 0,
+            },
+            varbinds: vec![VarBind {
+                oid: vec![1, 3, 6, 1, 2, 1, 1, 1, 0],
+                value: ObjectSyntax::OctetString(vec![b'x'; 200]),
+            }],
+        },
+    };
+    assert_eq!(validate_der(&message.to_bytes()), Ok(()));
+}
+
+#[test]
+fn test_non_canonical_encodings() {
+    let not_der = |bytes: &[u8]| matches!(validate_der(bytes), Err(BerError::NotDer(_)));
+
+    // INTEGER 1 as 00 01
+    assert!(not_der(&[0x02, 0x02, 0x00, 0x01]));
+    // INTEGER -1 as ff ff
+    assert!(not_der(&[0x02, 0x02, 0xff, 0xff]));
+    // but 128 needs its leading zero
+    assert_eq!(validate_der(&[0x02, 0x02, 0x00, 0x80]), Ok(()));
+
+    // length 1 in long form
+    assert!(not_der(&[0x04, 0x81, 0x01, 0x61]));
+    // length with a zero octet in front
+    let mut padded = vec![0x04, 0x82, 0x00, 0x80];
+    padded.extend([0x61; 128]);
+    assert!(not_der(&padded));
+    // indefinite length
+    assert!(not_der(&[0x30, 0x80, 0x05, 0x00, 0x00, 0x00]));
+
+    // sub-identifier 6 padded to 80 06
+    assert!(not_der(&[0x06, 0x03, 0x2b, 0x80, 0x06]));
+
+    // SET OF { INTEGER 2, INTEGER 1 }
+    assert!(not_der(&[0x31, 0x06, 0x02, 0x01, 0x02, 0x02, 0x01, 0x01]));
+    assert_eq!(
+        validate_der(&[0x31, 0x06, 0x02, 0x01, 0x01, 0x02, 0x01, 0x02]),
+        Ok(())
+    );
+
+    // nested members are checked too
+    assert!(not_der(&[0x30, 0x04, 0x02, 0x02, 0x00, 0x01]));
+}
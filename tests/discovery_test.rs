@@ -0,0 +1,116 @@
+use std::net::{IpAddr, Ipv4Addr};
+use std::time::Duration;
+
+use common::FakeAgent;
+use rusnmp::ber::Asn1Tag;
+use rusnmp::manager::{Credentials, Manager};
+use rusnmp::snmp::message::parse_message;
+use rusnmp::snmp::pdu::{ErrorStatus, ObjectSyntax, PduData};
+use tokio::net::UdpSocket;
+
+mod common;
+
+// answers with its identity, or with noSuchName if `identify` is false
+async fn agent(address: &str, identify: bool) -> u16 {
+    let agent = FakeAgent::new().bind(address).serve(move |message| {
+        if identify {
+            message.pdu.varbinds[0].value = ObjectSyntax::OctetString(b"Linux".to_vec());
+            message.pdu.varbinds[1].value =
+                ObjectSyntax::ObjectIdentifier(vec![1, 3, 6, 1, 4, 1, 8072]);
+        } else {
+            message.pdu.data = PduData::Basic {
+                error_status: ErrorStatus::NoSuchName,
+                error_index: 1,
+            };
+        }
+        true
+    });
+    agent.await.port
+}
+
+#[tokio::test]
+async fn test_discover_finds_answering_agents() {
+    let port = agent("127.0.0.1:0", true).await;
+    agent(&format!("127.0.0.5:{}", port), false).await;
+    let manager = Manager::builder().port(port).build();
+
+    // nothing listens on the other addresses, which are refused
+    let found = manager
+        .discover("127.0.0.0/29", &Credentials::v1("public"), 4)
+        .await
+        .unwrap();
+
+    assert_eq!(found.len(), 2);
+    assert_eq!(found[0].address, IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)));
+    assert_eq!(found[0].description.as_deref(), Some("Linux"));
+    assert_eq!(found[0].object_id, Some(vec![1, 3, 6, 1, 4, 1, 8072]));
+    assert_eq!(found[1].address, IpAddr::V4(Ipv4Addr::new(127, 0, 0, 5)));
+    assert_eq!(found[1].description, None);
+    // the network and broadcast addresses were never asked
+    assert!(manager.stats("127.0.0.0").is_none());
+    assert!(manager.stats("127.0.0.7").is_none());
+    assert!(manager.stats("127.0.0.6").is_some());
+}
+
+#[tokio::test]
+async fn test_discover_rejects_bad_ranges() {
+    let manager = Manager::new();
+    let community = Credentials::v2c("public");
+    for cidr in ["10.0.0.0/8", "10.0.0.0/33", "10.0.0/24", "fe80::/64"] {
+        assert!(
+            manager.discover(cidr, &community, 1).await.is_err(),
+            "{}",
+            cidr
+        );
+    }
+}
+
+// stands in for a LAN segment: the request arrives once, and the agents
+// on 127.0.0.1 and 127.0.0.8 answer it. So do a confused host, with the
+// wrong request-id, and a slow one, after the window
+async fn segment() -> String {
+    let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let target = socket.local_addr().unwrap().to_string();
+    let other = UdpSocket::bind("127.0.0.8:0").await.unwrap();
+    tokio::spawn(async move {
+        let mut buf = [0; 1500];
+        let (len, from) = socket.recv_from(&mut buf).await.unwrap();
+        let mut message = parse_message(&buf[..len]).unwrap();
+        message.pdu.tag = Asn1Tag::GetResponse;
+        message.pdu.varbinds[0].value = ObjectSyntax::OctetString(b"Linux".to_vec());
+        socket.send_to(&message.to_bytes(), from).await.unwrap();
+        other.send_to(&message.to_bytes(), from).await.unwrap();
+
+        message.pdu.request_id += 1;
+        other.send_to(&message.to_bytes(), from).await.unwrap();
+        message.pdu.request_id -= 1;
+        tokio::time::sleep(Duration::from_millis(400)).await;
+        other.send_to(&message.to_bytes(), from).await.unwrap();
+    });
+    target
+}
+
+#[tokio::test]
+async fn test_broadcast_get_collects_every_answer() {
+    let target = segment().await;
+    let manager = Manager::new();
+    let answers = manager
+        .broadcast_get(
+            &target,
+            &Credentials::v2c("public"),
+            &["1.3.6.1.2.1.1.1.0"],
+            Duration::from_millis(150),
+        )
+        .await
+        .unwrap();
+
+    let sources: Vec<IpAddr> = answers.iter().map(|(from, _)| from.ip()).collect();
+    assert_eq!(
+        sources,
+        [Ipv4Addr::new(127, 0, 0, 1), Ipv4Addr::new(127, 0, 0, 8)]
+    );
+    assert_eq!(
+        answers[1].1[0].value,
+        ObjectSyntax::OctetString(b"Linux".to_vec())
+    );
+}
@@ -0,0 +1,87 @@
+use rusnmp::snmp::display_hint::{DisplayHint, DisplayHintError};
+use rusnmp::snmp::pdu::ObjectSyntax;
+
+fn format(hint: &str, value: ObjectSyntax) -> Option<String> {
+    hint.parse::<DisplayHint>().unwrap().format(&value)
+}
+
+fn octets(hint: &str, bytes: &[u8]) -> String {
+    format(hint, ObjectSyntax::OctetString(bytes.to_vec())).unwrap()
+}
+
+#[test]
+fn test_mac_address() {
+    // net-snmp doesn't pad the octets
+    let mac = [0x00, 0x0c, 0x29, 0xa8, 0x8b, 0xa2];
+    assert_eq!(octets("1x:", &mac), "0:c:29:a8:8b:a2");
+}
+
+#[test]
+fn test_textual_conventions() {
+    assert_eq!(octets("1d.1d.1d.1d", &[192, 168, 1, 20]), "192.168.1.20");
+    assert_eq!(octets("255a", b"eth0"), "eth0");
+    assert_eq!(octets("255t", "t\u{e9}st".as_bytes()), "t\u{e9}st");
+    // InetAddressIPv4z: address, then a 4-octet zone index
+    assert_eq!(
+        octets("1d.1d.1d.1d%4d", &[10, 0, 0, 1, 0, 0, 1, 0]),
+        "10.0.0.1%256"
+    );
+    // DateAndTime: 2026-10-17,13:45:30.5
+    let date = [0x07, 0xea, 10, 17, 13, 45, 30, 5];
+    assert_eq!(
+        octets("2d-1d-1d,1d:1d:1d.1d,1a1d:1d", &date),
+        "2026-10-17,13:45:30.5"
+    );
+    assert_eq!(octets("2o", &[0x01, 0xff]), "777");
+}
+
+#[test]
+fn test_repeat_and_terminator() {
+    // two groups: a count octet, then that many octets
+    let bytes = [2, 1, 2, 3, 3, 4, 5];
+    assert_eq!(octets("*1x:/", &bytes), "1:2/3:4:5");
+}
+
+#[test]
+fn test_integer_hints() {
+    assert_eq!(
+        format("d-1", ObjectSyntax::Integer(215)),
+        Some("21.5".into())
+    );
+    assert_eq!(format("d-2", ObjectSyntax::Integer(5)), Some("0.05".into()));
+    assert_eq!(
+        format("d-2", ObjectSyntax::Integer(-5)),
+        Some("-0.05".into())
+    );
+    assert_eq!(format("d", ObjectSyntax::Integer(-12)), Some("-12".into()));
+    assert_eq!(format("x", ObjectSyntax::Gauge32(255)), Some("ff".into()));
+    assert_eq!(format("o", ObjectSyntax::Counter64(8)), Some("10".into()));
+    assert_eq!(format("b", ObjectSyntax::Integer(5)), Some("101".into()));
+}
+
+#[test]
+fn test_hint_for_other_type() {
+    assert_eq!(format("1x:", ObjectSyntax::Integer(1)), None);
+    assert_eq!(
+        format("d-1", ObjectSyntax::OctetString(b"1".to_vec())),
+        None
+    );
+}
+
+#[test]
+fn test_invalid_hints() {
+    let parse = |hint: &str| hint.parse::<DisplayHint>().unwrap_err();
+    assert_eq!(parse(""), DisplayHintError::Empty);
+    assert_eq!(parse("1q"), DisplayHintError::UnknownFormat('q'));
+    assert_eq!(parse("0x"), DisplayHintError::ZeroLength);
+    assert_eq!(parse("*x"), DisplayHintError::MissingLength("x".into()));
+    assert_eq!(
+        parse("9d"),
+        DisplayHintError::NumberTooLong {
+            format: 'd',
+            len: 9
+        }
+    );
+    assert_eq!(parse("d-"), DisplayHintError::TrailingInput("-".into()));
+    assert_eq!(parse("xx"), DisplayHintError::TrailingInput("x".into()));
+}
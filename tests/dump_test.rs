@@ -0,0 +1,53 @@
+use rusnmp::ber::dump;
+
+#[test]
+fn test_dump_response() {
+    let packet = [
+        0x30, 0x29, 0x02, 0x01, 0x01, 0x04, 0x06, b'p', b'u', b'b', b'l', b'i', b'c', 0xa2, 0x1c,
+        0x02, 0x01, 0x01, 0x02, 0x01, 0x00, 0x02, 0x01, 0x00, 0x30, 0x11, 0x30, 0x0f, 0x06, 0x08,
+        0x2b, 0x06, 0x01, 0x02, 0x01, 0x01, 0x03, 0x00, 0x43, 0x03, 0x01, 0x02, 0x03,
+    ];
+    let expected = "    0: SEQUENCE [universal 16, constructed] len 41
+    2:   INTEGER [universal 2] len 1: 01 (1)
+    5:   OCTET STRING [universal 4] len 6: 70 75 62 6c 69 63 (\"public\")
+   13:   Response [context 2, constructed] len 28
+   15:     INTEGER [universal 2] len 1: 01 (1)
+   18:     INTEGER [universal 2] len 1: 00 (0)
+   21:     INTEGER [universal 2] len 1: 00 (0)
+   24:     SEQUENCE [universal 16, constructed] len 17
+   26:       SEQUENCE [universal 16, constructed] len 15
+   28:         OBJECT IDENTIFIER [universal 6] len 8: 2b 06 01 02 01 01 03 00 (1.3.6.1.2.1.1.3.0)
+   38:         TimeTicks [application 3] len 3: 01 02 03
+";
+    assert_eq!(dump(&packet), expected);
+}
+
+#[test]
+fn test_dump_shows_where_parsing_stopped() {
+    // the second TLV claims 5 bytes but has 1
+    let dumped = dump(&[0x05, 0x00, 0x04, 0x05, 0xff]);
+    assert_eq!(
+        dumped,
+        "    0: NULL [universal 5] len 0\n    2: error: Incomplete data: not enough bytes: 04 05 ff\n"
+    );
+}
+
+#[test]
+fn test_dump_unknown_and_long_tags() {
+    let dumped = dump(&[0x9f, 0x78, 0x02, 0xab, 0xcd, 0xc5, 0x00]);
+    assert_eq!(
+        dumped,
+        "    0: [context 120] len 2: ab cd\n    5: [private 5] len 0\n"
+    );
+}
+
+#[test]
+fn test_dump_caps_nesting() {
+    // 40 SEQUENCEs inside each other
+    let mut packet = Vec::new();
+    for _ in 0..40 {
+        let len = packet.len() as u8;
+        packet.splice(0..0, [0x30, len]);
+    }
+    assert!(dump(&packet).contains("nested too deep to show"));
+}
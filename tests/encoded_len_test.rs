@@ -0,0 +1,115 @@
+use rusnmp::ber::{Asn1Tag, TagClass};
+use rusnmp::snmp::message::{SnmpMessage, parse_message};
+use rusnmp::snmp::pdu::{ErrorStatus, GenericTrap, ObjectSyntax, Pdu, PduData, VarBind};
+
+fn every_value() -> Vec<ObjectSyntax> {
+    vec![
+        ObjectSyntax::Integer(0),
+        ObjectSyntax::Integer(128),
+        ObjectSyntax::Integer(-129),
+        ObjectSyntax::Integer(i32::MIN),
+        ObjectSyntax::OctetString(vec![b'x'; 300]),
+        ObjectSyntax::Null,
+        ObjectSyntax::ObjectIdentifier(vec![2, 999, 3]),
+        ObjectSyntax::IpAddress(vec![10, 0, 0, 1]),
+        ObjectSyntax::Counter32(u32::MAX),
+        ObjectSyntax::Gauge32(0x8000_0000),
+        ObjectSyntax::TimeTicks(127),
+        ObjectSyntax::Opaque(vec![0x01, 0x02]),
+        ObjectSyntax::Counter64(u64::MAX),
+        ObjectSyntax::Float(1.5),
+        ObjectSyntax::Double(-2.25),
+        ObjectSyntax::NoSuchObject,
+        ObjectSyntax::NoSuchInstance,
+        ObjectSyntax::EndOfMib,
+        ObjectSyntax::Tagged {
+            class: TagClass::Context,
+            number: 200,
+            constructed: false,
+            bytes: vec![0xAA; 130],
+        },
+    ]
+}
+
+#[test]
+fn test_encoded_len_matches_bytes() {
+    for value in every_value() {
+        let varbind = VarBind {
+            oid: vec![1, 3, 6, 1, 4, 1, 2021, 10, 1, 3, 1],
+            value,
+        };
+        let mut bytes = Vec::new();
+        varbind.write_to_buf(&mut bytes);
+        assert_eq!(varbind.encoded_len(), bytes.len(), "{:?}", varbind.value);
+        assert_eq!(varbind.value.encoded_len(), {
+            let mut value_bytes = Vec::new();
+            varbind.value.write_to_buf(&mut value_bytes);
+            value_bytes.len()
+        });
+    }
+}
+
+#[test]
+fn test_message_round_trips() {
+    let message = SnmpMessage {
+        version: 1,
+        community: b"public".to_vec(),
+        pdu: Pdu {
+            tag: Asn1Tag::GetResponse,
+            request_id: 0x0102_0304,
+            data: PduData::Basic {
+                error_status: ErrorStatus::NoError,
+                error_index: 0,
+            },
+            varbinds: (0..50)
+                .flat_map(|i| {
+                    every_value().into_iter().map(move |value| VarBind {
+                        oid: vec![1, 3, 6, 1, 2, 1, 2, 2, 1, 10, i],
+                        value,
+                    })
+                })
+                // the decoder only keeps short-form tags as they are
+                .filter(|varbind| !matches!(varbind.value, ObjectSyntax::Tagged { .. }))
+                .collect(),
+        },
+    };
+
+    let bytes = message.to_bytes();
+    assert_eq!(bytes.len(), message.encoded_len());
+    assert_eq!(parse_message(&bytes).unwrap(), message);
+}
+
+#[test]
+fn test_trap_encoded_len() {
+    let pdu = Pdu {
+        tag: Asn1Tag::Trap,
+        request_id: 0,
+        data: PduData::Trap {
+            enterprise: vec![1, 3, 6, 1, 4, 1, 8072],
+            agent_addr: [192, 168, 1, 1],
+            generic_trap: GenericTrap::LinkDown,
+            specific_trap: 0,
+            time_stamp: 0xFFFF_FFFF,
+        },
+        varbinds: Vec::new(),
+    };
+    let mut bytes = Vec::new();
+    pdu.write_to_buf(&mut bytes);
+    assert_eq!(pdu.encoded_len(), bytes.len());
+}
+
+#[test]
+fn test_high_bit_unsigned_gets_leading_zero() {
+    let mut bytes = Vec::new();
+    ObjectSyntax::Counter32(0x8000_0000).write_to_buf(&mut bytes);
+    assert_eq!(bytes, [0x41, 0x05, 0x00, 0x80, 0x00, 0x00, 0x00]);
+
+    let mut bytes = Vec::new();
+    ObjectSyntax::Counter64(u64::MAX).write_to_buf(&mut bytes);
+    assert_eq!(
+        bytes,
+        [
+            0x46, 0x09, 0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff
+        ]
+    );
+}
@@ -0,0 +1,62 @@
+#![cfg(feature = "v3")]
+
+use std::net::Ipv4Addr;
+
+use rusnmp::snmp::engine_id::{
+    EngineId, EngineIdError, EngineIdFormat, EngineIdInfo, NET_SNMP_ENTERPRISE,
+};
+
+#[test]
+fn test_ipv4_engine_id_layout() {
+    let id = EngineId::from_ipv4(NET_SNMP_ENTERPRISE, Ipv4Addr::new(192, 0, 2, 1)).unwrap();
+    assert_eq!(id.as_bytes(), &[0x80, 0x00, 0x1f, 0x88, 0x01, 192, 0, 2, 1]);
+    assert_eq!(id.to_string(), "80001f8801c0000201");
+    assert_eq!(id.describe(), "enterprise 8072, IPv4 192.0.2.1");
+}
+
+#[test]
+fn test_parse_received_engine_ids() {
+    // net-snmp's default: enterprise specific format 0x80
+    let id = EngineId::from_hex("0x80001f88803a5b4d0e6a1b2c6500000000").unwrap();
+    match id.info() {
+        EngineIdInfo::Rfc3411 { enterprise, format } => {
+            assert_eq!(enterprise, NET_SNMP_ENTERPRISE);
+            assert!(matches!(format, EngineIdFormat::Other { format: 0x80, .. }));
+        }
+        other => panic!("Expected RFC 3411 engine ID, got {:?}", other),
+    }
+
+    // RFC 3414 A.3 example engine ID is old style
+    let id = EngineId::from_bytes(vec![0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2]).unwrap();
+    assert!(matches!(
+        id.info(),
+        EngineIdInfo::Rfc1910 { enterprise: 0, .. }
+    ));
+}
+
+#[test]
+fn test_engine_id_limits() {
+    assert_eq!(
+        EngineId::from_bytes(vec![0x80, 0, 0]),
+        Err(EngineIdError::InvalidLength(3))
+    );
+    assert_eq!(
+        EngineId::from_text(NET_SNMP_ENTERPRISE, &"x".repeat(28)),
+        Err(EngineIdError::DataTooLong(28))
+    );
+
+    let a = EngineId::random(NET_SNMP_ENTERPRISE).unwrap();
+    let b = EngineId::random(NET_SNMP_ENTERPRISE).unwrap();
+    assert_eq!(a.as_bytes().len(), 17);
+    assert_ne!(a, b);
+}
+
+#[test]
+fn test_invalid_hex() {
+    for hex in ["aéb", "80001f88é", "0x8000zz", "800"] {
+        assert_eq!(
+            EngineId::from_hex(hex),
+            Err(EngineIdError::InvalidHex(hex.to_string()))
+        );
+    }
+}
@@ -0,0 +1,34 @@
+use rusnmp::snmp::pdu::ObjectSyntax;
+
+// ifOperStatus, as its INTEGER { ... } definition names the values
+const LABELS: [(i32, &str); 3] = [(1, "up"), (2, "down"), (3, "testing")];
+
+#[test]
+fn test_enum_label() {
+    assert_eq!(
+        ObjectSyntax::Integer(2).enum_label(&LABELS),
+        Some("down(2)".to_string())
+    );
+    assert_eq!(
+        ObjectSyntax::Integer(7).enum_label(&LABELS),
+        Some("7".to_string())
+    );
+    assert_eq!(ObjectSyntax::Gauge32(1).enum_label(&LABELS), None);
+}
+
+#[test]
+fn test_from_enum_label() {
+    let up = Some(ObjectSyntax::Integer(1));
+    assert_eq!(ObjectSyntax::from_enum_label("up", &LABELS), up);
+    assert_eq!(ObjectSyntax::from_enum_label("up(1)", &LABELS), up);
+    assert_eq!(ObjectSyntax::from_enum_label("1", &LABELS), up);
+    // numbers outside the enumeration are the agent's to refuse
+    assert_eq!(
+        ObjectSyntax::from_enum_label("9", &LABELS),
+        Some(ObjectSyntax::Integer(9))
+    );
+
+    assert_eq!(ObjectSyntax::from_enum_label("dormant", &LABELS), None);
+    assert_eq!(ObjectSyntax::from_enum_label("up(2)", &LABELS), None);
+    assert_eq!(ObjectSyntax::from_enum_label("up(x)", &LABELS), None);
+}
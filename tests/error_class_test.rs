@@ -0,0 +1,70 @@
+use std::time::Duration;
+
+use anyhow::anyhow;
+use rusnmp::ber::BerError;
+use rusnmp::manager::{ErrorClass, OidNotIncreasingError, SnmpError, TimeoutError};
+use rusnmp::snmp::pdu::ErrorStatus;
+use rusnmp::snmp::report::ReportError;
+
+#[test]
+fn test_error_classification() {
+    let timeout = anyhow::Error::from(TimeoutError {
+        address: "192.0.2.1:161".parse().unwrap(),
+        after: Duration::from_secs(5),
+    });
+    assert_eq!(ErrorClass::of(&timeout), ErrorClass::Timeout);
+    assert_eq!(
+        timeout.to_string(),
+        "Timeout: No response from 192.0.2.1:161 after 5s"
+    );
+
+    let refused = anyhow!(std::io::Error::from(std::io::ErrorKind::ConnectionRefused))
+        .context("Failed to receive data");
+    assert_eq!(ErrorClass::of(&refused), ErrorClass::Refused);
+
+    let snmp = anyhow::Error::from(SnmpError {
+        status: ErrorStatus::GenErr,
+        index: 1,
+    });
+    assert_eq!(ErrorClass::of(&snmp).as_str(), "snmp-error");
+
+    let parse = anyhow!(BerError::TrailingData).context("Failed to parse response");
+    assert_eq!(ErrorClass::of(&parse), ErrorClass::Parse);
+
+    let report = anyhow::Error::from(ReportError::UnknownUserName);
+    assert_eq!(ErrorClass::of(&report), ErrorClass::Report);
+
+    assert_eq!(
+        ErrorClass::of(&anyhow!("something else")),
+        ErrorClass::Other
+    );
+}
+
+#[test]
+fn test_snmpv2_error_status_codes() {
+    for code in 0..=18 {
+        let status = ErrorStatus::try_from(code).unwrap();
+        assert_eq!(status as i32, code);
+    }
+    assert!(ErrorStatus::try_from(19).is_err());
+
+    let error = SnmpError {
+        status: ErrorStatus::try_from(17).unwrap(),
+        index: 2,
+    };
+    assert_eq!(error.to_string(), "SNMP Error: notWritable(17) (Index: 2)");
+}
+
+#[test]
+fn test_oid_not_increasing_message() {
+    let error = anyhow::Error::from(OidNotIncreasingError {
+        previous: vec![1, 3, 6, 1, 2, 1, 2, 2, 1, 2, 3],
+        returned: vec![1, 3, 6, 1, 2, 1, 2, 2, 1, 2, 1],
+    });
+    assert_eq!(
+        error.to_string(),
+        "OID not increasing: asked after 1.3.6.1.2.1.2.2.1.2.3, agent returned 1.3.6.1.2.1.2.2.1.2.1"
+    );
+    assert!(error.downcast_ref::<OidNotIncreasingError>().is_some());
+    assert_eq!(ErrorClass::of(&error), ErrorClass::Other);
+}
@@ -0,0 +1,101 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use common::FakeAgent;
+use rusnmp::ber::Asn1Tag;
+use rusnmp::manager::{Credentials, ErrorClass, Manager};
+use rusnmp::snmp::pdu::ObjectSyntax;
+
+mod common;
+
+const DELAY: Duration = Duration::from_millis(30);
+
+#[derive(Default)]
+struct InFlight {
+    arrivals: Mutex<Vec<Instant>>,
+    most: AtomicUsize,
+}
+
+// answers after a short delay with each OID's last arc as the value
+async fn agent(in_flight: Arc<InFlight>) -> String {
+    let agent = FakeAgent::new().delay(DELAY).serve(move |message| {
+        // a request that came within DELAY of another is still waiting
+        let now = Instant::now();
+        let mut arrivals = in_flight.arrivals.lock().unwrap();
+        arrivals.retain(|arrival| now - *arrival < DELAY);
+        arrivals.push(now);
+        in_flight.most.fetch_max(arrivals.len(), Ordering::SeqCst);
+
+        let walking = message.pdu.tag == Asn1Tag::GetNextRequest;
+        for varbind in &mut message.pdu.varbinds {
+            // GetNext walks three rows under 1.3.6.1.4.1.99
+            if walking {
+                let row = varbind.oid.get(7).map_or(1, |row| row + 1);
+                varbind.oid = vec![1, 3, 6, 1, 4, 1, 99, row];
+            }
+            varbind.value = match *varbind.oid.last().unwrap() {
+                4.. if walking => ObjectSyntax::EndOfMib,
+                last => ObjectSyntax::Integer(last as i32),
+            };
+        }
+        true
+    });
+    agent.await.target
+}
+
+#[tokio::test]
+async fn test_get_many() {
+    let in_flight = Arc::new(InFlight::default());
+    let mut targets = Vec::new();
+    for _ in 0..4 {
+        targets.push(agent(Arc::clone(&in_flight)).await);
+    }
+    // nothing listens on the loopback SNMP port
+    targets.push("127.0.0.1".to_string());
+    let targets: Vec<&str> = targets.iter().map(String::as_str).collect();
+
+    let results = Manager::new()
+        .get_many(
+            &targets,
+            &Credentials::v2c("public"),
+            &["1.3.6.1.2.1.1.3.0", "1.3.6.1.2.1.1.7"],
+            2,
+        )
+        .await;
+
+    assert_eq!(results.len(), 5);
+    for target in &targets[..4] {
+        let values: Vec<_> = results[*target]
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|varbind| varbind.value.clone())
+            .collect();
+        assert_eq!(values, [ObjectSyntax::Integer(0), ObjectSyntax::Integer(7)]);
+    }
+    let refused = results["127.0.0.1"].as_ref().unwrap_err();
+    assert_eq!(ErrorClass::of(refused), ErrorClass::Refused);
+    assert_eq!(in_flight.most.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_walk_many() {
+    let in_flight = Arc::new(InFlight::default());
+    let mut targets = Vec::new();
+    for _ in 0..3 {
+        targets.push(agent(Arc::clone(&in_flight)).await);
+    }
+    targets.push(targets[0].clone());
+    let targets: Vec<&str> = targets.iter().map(String::as_str).collect();
+
+    let results = Manager::new()
+        .walk_many(&targets, &Credentials::v2c("public"), "1.3.6.1.4.1.99", 1)
+        .await;
+
+    assert_eq!(results.len(), 3);
+    for result in results.values() {
+        assert_eq!(result.as_ref().unwrap().len(), 3);
+    }
+    assert_eq!(in_flight.most.load(Ordering::SeqCst), 1);
+}
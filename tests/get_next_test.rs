@@ -0,0 +1,9 @@
+use rusnmp::manager::{Credentials, Manager};
+
+#[tokio::test]
+async fn test_get_next_needs_an_oid() {
+    let result = Manager::new()
+        .get_next("127.0.0.1", &Credentials::v2c("public"), &[])
+        .await;
+    assert!(result.is_err());
+}
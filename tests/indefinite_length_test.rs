@@ -0,0 +1,51 @@
+use rusnmp::ber::{BerError, parse_ber_object};
+use rusnmp::snmp::message::parse_message;
+use rusnmp::snmp::pdu::ObjectSyntax;
+
+// GetResponse for sysUpTime.0 = 4200 with every constructed TLV in
+// indefinite form
+const INDEFINITE_RESPONSE: [u8; 48] = [
+    0x30, 0x80, // message
+    0x02, 0x01, 0x01, // version v2c
+    0x04, 0x06, b'p', b'u', b'b', b'l', b'i', b'c', // community
+    0xa2, 0x80, // GetResponse
+    0x02, 0x01, 0x07, // request-id
+    0x02, 0x01, 0x00, // error-status
+    0x02, 0x01, 0x00, // error-index
+    0x30, 0x80, // varbind list
+    0x30, 0x0e, 0x06, 0x08, 0x2b, 0x06, 0x01, 0x02, 0x01, 0x01, 0x03, 0x00, 0x43, 0x02, 0x10,
+    0x68, // varbind, definite
+    0x00, 0x00, // end of varbind list
+    0x00, 0x00, // end of GetResponse
+    0x00, 0x00, // end of message
+];
+
+#[test]
+fn test_parse_indefinite_length_message() {
+    let message = parse_message(&INDEFINITE_RESPONSE).unwrap();
+    assert_eq!(message.community, b"public");
+    assert_eq!(message.pdu.request_id, 7);
+    assert_eq!(message.pdu.varbinds.len(), 1);
+    assert_eq!(message.pdu.varbinds[0].oid, [1, 3, 6, 1, 2, 1, 1, 3, 0]);
+    assert_eq!(message.pdu.varbinds[0].value, ObjectSyntax::TimeTicks(4200));
+}
+
+#[test]
+fn test_indefinite_length_errors() {
+    // missing end-of-contents
+    let truncated = &INDEFINITE_RESPONSE[..INDEFINITE_RESPONSE.len() - 2];
+    assert_eq!(parse_ber_object(truncated), Err(BerError::IncompleteData));
+
+    // primitive types can't use indefinite length
+    assert_eq!(
+        parse_ber_object(&[0x04, 0x80, 0x61, 0x00, 0x00]),
+        Err(BerError::MalformedLength)
+    );
+
+    let mut nested = [0x30, 0x80].repeat(64);
+    nested.extend([0x00, 0x00].repeat(64));
+    assert_eq!(
+        parse_ber_object(&nested),
+        Err(BerError::IndefiniteLengthTooDeep)
+    );
+}
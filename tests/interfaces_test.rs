@@ -0,0 +1,73 @@
+use common::cells_agent;
+use rusnmp::manager::{Credentials, IfStatus, Interface, Manager};
+use rusnmp::snmp::pdu::{ObjectSyntax, VarBind};
+
+mod common;
+
+fn cell(table: &[u64], column: u64, index: u64, value: ObjectSyntax) -> VarBind {
+    VarBind {
+        oid: [table, &[1, column, index]].concat(),
+        value,
+    }
+}
+
+const IF_TABLE: [u64; 8] = [1, 3, 6, 1, 2, 1, 2, 2];
+const IF_X_TABLE: [u64; 9] = [1, 3, 6, 1, 2, 1, 31, 1, 1];
+
+fn string(text: &str) -> ObjectSyntax {
+    ObjectSyntax::OctetString(text.as_bytes().to_vec())
+}
+
+#[tokio::test]
+async fn test_get_interfaces_merges_tables() {
+    let target = cells_agent(vec![
+        cell(&IF_TABLE, 2, 1, string("Intel 82599")),
+        cell(&IF_TABLE, 2, 2, string("lo")),
+        cell(&IF_TABLE, 5, 1, ObjectSyntax::Gauge32(u32::MAX)),
+        cell(&IF_TABLE, 5, 2, ObjectSyntax::Gauge32(10_000_000)),
+        cell(&IF_TABLE, 7, 1, ObjectSyntax::Integer(1)),
+        cell(&IF_TABLE, 7, 2, ObjectSyntax::Integer(1)),
+        cell(&IF_TABLE, 8, 1, ObjectSyntax::Integer(1)),
+        cell(&IF_TABLE, 8, 2, ObjectSyntax::Integer(7)),
+        cell(&IF_TABLE, 10, 1, ObjectSyntax::Counter32(5)),
+        cell(&IF_TABLE, 10, 2, ObjectSyntax::Counter32(6)),
+        cell(&IF_TABLE, 16, 2, ObjectSyntax::Counter32(7)),
+        cell(&IF_X_TABLE, 1, 1, string("eth0")),
+        cell(&IF_X_TABLE, 6, 1, ObjectSyntax::Counter64(1 << 40)),
+        cell(&IF_X_TABLE, 15, 1, ObjectSyntax::Gauge32(10_000)),
+        cell(&IF_X_TABLE, 18, 1, string("uplink")),
+    ])
+    .await;
+
+    let interfaces = Manager::new()
+        .get_interfaces(&target, &Credentials::v2c("public"))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        interfaces,
+        [
+            Interface {
+                index: 1,
+                name: Some("eth0".to_string()),
+                description: Some("Intel 82599".to_string()),
+                alias: Some("uplink".to_string()),
+                speed: Some(10_000_000_000),
+                admin_status: Some(IfStatus::Up),
+                oper_status: Some(IfStatus::Up),
+                in_octets: Some(1 << 40),
+                ..Interface::default()
+            },
+            Interface {
+                index: 2,
+                description: Some("lo".to_string()),
+                speed: Some(10_000_000),
+                admin_status: Some(IfStatus::Up),
+                oper_status: Some(IfStatus::LowerLayerDown),
+                in_octets: Some(6),
+                out_octets: Some(7),
+                ..Interface::default()
+            },
+        ]
+    );
+}
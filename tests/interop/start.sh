@@ -0,0 +1,4 @@
+#!/bin/sh
+# both log to stdout, which the tests read back through `docker logs`
+snmptrapd -f -Lo -On -c /etc/snmp/snmptrapd.conf udp:162 &
+exec snmpd -f -Lo -C -c /etc/snmp/snmpd.conf udp:161
@@ -0,0 +1,161 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use common::cells_agent;
+use rusnmp::manager::{Credentials, IpAddressEntry, Manager, RouteEntry};
+use rusnmp::snmp::pdu::{ObjectSyntax, VarBind};
+
+mod common;
+
+fn cell(table: &[u64], column: u64, index: &[u64], value: ObjectSyntax) -> VarBind {
+    VarBind {
+        oid: [table, &[1, column], index].concat(),
+        value,
+    }
+}
+
+const IP_ADDRESS_TABLE: [u64; 8] = [1, 3, 6, 1, 2, 1, 4, 34];
+const INET_CIDR_ROUTE_TABLE: [u64; 9] = [1, 3, 6, 1, 2, 1, 4, 24, 7];
+const IP_ADDR_TABLE: [u64; 8] = [1, 3, 6, 1, 2, 1, 4, 20];
+const IP_ROUTE_TABLE: [u64; 8] = [1, 3, 6, 1, 2, 1, 4, 21];
+
+#[tokio::test]
+async fn test_new_tables() {
+    let v4 = [1, 4, 10, 0, 0, 1];
+    let mut v6 = vec![2, 16];
+    v6.extend([0; 15]);
+    v6.push(1);
+    // 0.0.0.0/0 via 10.0.0.254, and 10.0.0.0/24 connected; policy 0.0
+    let default = [1, 4, 0, 0, 0, 0, 0, 2, 0, 0, 1, 4, 10, 0, 0, 254];
+    let connected = [1, 4, 10, 0, 0, 0, 24, 2, 0, 0, 0, 0];
+    let target = cells_agent(vec![
+        cell(&IP_ADDRESS_TABLE, 3, &v4, ObjectSyntax::Integer(2)),
+        cell(&IP_ADDRESS_TABLE, 3, &v6, ObjectSyntax::Integer(1)),
+        cell(
+            &IP_ADDRESS_TABLE,
+            5,
+            &v4,
+            ObjectSyntax::ObjectIdentifier(vec![
+                1, 3, 6, 1, 2, 1, 4, 32, 1, 5, 2, 1, 4, 10, 0, 0, 0, 24,
+            ]),
+        ),
+        cell(
+            &IP_ADDRESS_TABLE,
+            5,
+            &v6,
+            ObjectSyntax::ObjectIdentifier(vec![0, 0]),
+        ),
+        cell(
+            &INET_CIDR_ROUTE_TABLE,
+            7,
+            &default,
+            ObjectSyntax::Integer(2),
+        ),
+        cell(
+            &INET_CIDR_ROUTE_TABLE,
+            7,
+            &connected,
+            ObjectSyntax::Integer(2),
+        ),
+        cell(
+            &INET_CIDR_ROUTE_TABLE,
+            12,
+            &default,
+            ObjectSyntax::Integer(1),
+        ),
+        cell(
+            &INET_CIDR_ROUTE_TABLE,
+            12,
+            &connected,
+            ObjectSyntax::Integer(-1),
+        ),
+    ])
+    .await;
+    let manager = Manager::new();
+    let community = Credentials::v2c("public");
+
+    assert_eq!(
+        manager.get_ip_addresses(&target, &community).await.unwrap(),
+        [
+            IpAddressEntry {
+                address: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+                prefix_len: Some(24),
+                if_index: Some(2),
+            },
+            IpAddressEntry {
+                address: IpAddr::V6(Ipv6Addr::LOCALHOST),
+                prefix_len: None,
+                if_index: Some(1),
+            },
+        ]
+    );
+    assert_eq!(
+        manager.get_routes(&target, &community).await.unwrap(),
+        [
+            RouteEntry {
+                destination: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+                prefix_len: 0,
+                next_hop: Some(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 254))),
+                if_index: Some(2),
+                metric: Some(1),
+            },
+            RouteEntry {
+                destination: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 0)),
+                prefix_len: 24,
+                next_hop: None,
+                if_index: Some(2),
+                metric: None,
+            },
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_old_tables() {
+    let address = [192, 168, 1, 5];
+    let default = [0, 0, 0, 0];
+    let target = cells_agent(vec![
+        cell(&IP_ADDR_TABLE, 2, &address, ObjectSyntax::Integer(3)),
+        cell(
+            &IP_ADDR_TABLE,
+            3,
+            &address,
+            ObjectSyntax::IpAddress(vec![255, 255, 255, 0]),
+        ),
+        cell(&IP_ROUTE_TABLE, 2, &default, ObjectSyntax::Integer(3)),
+        cell(&IP_ROUTE_TABLE, 3, &default, ObjectSyntax::Integer(-1)),
+        cell(
+            &IP_ROUTE_TABLE,
+            7,
+            &default,
+            ObjectSyntax::IpAddress(vec![192, 168, 1, 1]),
+        ),
+        cell(
+            &IP_ROUTE_TABLE,
+            11,
+            &default,
+            ObjectSyntax::IpAddress(vec![0, 0, 0, 0]),
+        ),
+    ])
+    .await;
+    let manager = Manager::new();
+    let community = Credentials::v1("public");
+
+    assert_eq!(
+        manager.get_ip_addresses(&target, &community).await.unwrap(),
+        [IpAddressEntry {
+            address: IpAddr::V4(Ipv4Addr::new(192, 168, 1, 5)),
+            prefix_len: Some(24),
+            if_index: Some(3),
+        }]
+    );
+    assert_eq!(
+        manager.get_routes(&target, &community).await.unwrap(),
+        [RouteEntry {
+            destination: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            prefix_len: 0,
+            next_hop: Some(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 1))),
+            if_index: Some(3),
+            metric: None,
+        }]
+    );
+}
@@ -0,0 +1,43 @@
+#![cfg(feature = "v3")]
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use rusnmp::manager::{ErrorClass, Manager, TargetHealth};
+use rusnmp::snmp::usm::{AuthProtocol, UsmUser};
+
+#[tokio::test]
+async fn test_keepalive_marks_unreachable_target_degraded() {
+    let manager = Arc::new(Manager::new());
+    let user = UsmUser::new("operator")
+        .with_auth(AuthProtocol::Sha1, b"maplesyrup")
+        .unwrap();
+    assert_eq!(manager.health("127.0.0.1"), None);
+
+    // nothing listens on the loopback SNMP port, the first check fails fast
+    let keepalive = manager
+        .spawn_keepalive("127.0.0.1", user.into(), Duration::from_secs(3600))
+        .unwrap();
+    let mut health = None;
+    for _ in 0..100 {
+        health = manager.health("127.0.0.1");
+        if health.is_some() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    keepalive.abort();
+
+    match health {
+        Some(TargetHealth::Degraded { class, .. }) => assert_eq!(class, ErrorClass::Refused),
+        other => panic!("expected a degraded target, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_zero_keepalive_interval_is_rejected() {
+    let manager = Arc::new(Manager::new());
+    let user = UsmUser::new("operator");
+    let result = manager.spawn_keepalive("127.0.0.1", user.into(), Duration::ZERO);
+    assert!(result.is_err());
+}
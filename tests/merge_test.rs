@@ -0,0 +1,41 @@
+use rusnmp::manager::merge_ordered;
+use rusnmp::snmp::pdu::{ObjectSyntax, VarBind};
+
+fn vb(oid: &[u64], value: i32) -> VarBind {
+    VarBind {
+        oid: oid.to_vec(),
+        value: ObjectSyntax::Integer(value),
+    }
+}
+
+#[test]
+fn test_merge_is_in_oid_order() {
+    // ifIndex and ifType columns, plus rows of ifEntry overlapping both
+    let if_type = vec![
+        vb(&[1, 3, 6, 1, 2, 1, 2, 2, 1, 3, 1], 6),
+        vb(&[1, 3, 6, 1, 2, 1, 2, 2, 1, 3, 10], 6),
+    ];
+    let if_index = vec![
+        vb(&[1, 3, 6, 1, 2, 1, 2, 2, 1, 1, 1], 1),
+        vb(&[1, 3, 6, 1, 2, 1, 2, 2, 1, 1, 2], 2),
+    ];
+    let if_entry = vec![
+        vb(&[1, 3, 6, 1, 2, 1, 2, 2, 1, 1, 2], 99),
+        vb(&[1, 3, 6, 1, 2, 1, 2, 2, 1, 3, 2], 24),
+    ];
+
+    let merged = merge_ordered(vec![if_type.clone(), if_index.clone(), if_entry.clone()]);
+    let oids: Vec<_> = merged
+        .iter()
+        .map(|v| v.oid.last().copied().unwrap())
+        .collect();
+    assert_eq!(merged.len(), 5);
+    // numeric, not string, order: .3.2 before .3.10
+    assert_eq!(merged[3].oid, [1, 3, 6, 1, 2, 1, 2, 2, 1, 3, 2]);
+    assert_eq!(oids, [1, 2, 1, 2, 10]);
+    // the duplicate comes from the earlier list
+    assert_eq!(merged[1].value, ObjectSyntax::Integer(2));
+
+    let reordered = merge_ordered(vec![if_index, if_type, if_entry]);
+    assert_eq!(reordered, merged);
+}
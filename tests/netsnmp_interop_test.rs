@@ -0,0 +1,259 @@
+#![cfg(feature = "v3")]
+// Interop against a real net-snmp snmpd and snmptrapd in Docker. Skipped
+// unless RUSNMP_INTEROP=1, since it needs a Docker daemon and the image
+// from tests/interop. The agent is reached on its bridge address, so this
+// needs Docker on Linux where the host can route to containers.
+
+use std::env;
+use std::process::Command;
+use std::time::Duration;
+
+use rusnmp::manager::{Credentials, Manager};
+use rusnmp::snmp::pdu::{ObjectSyntax, VarBind};
+use rusnmp::snmp::usm::{AuthProtocol, PrivProtocol, UsmUser};
+
+const IMAGE: &str = "rusnmp-interop";
+const SYSTEM: &str = "1.3.6.1.2.1.1";
+const SYS_DESCR: &str = "1.3.6.1.2.1.1.1.0";
+const SYS_LOCATION: &str = "1.3.6.1.2.1.1.6.0";
+const SYS_NAME: &str = "1.3.6.1.2.1.1.5.0";
+
+fn enabled() -> bool {
+    env::var("RUSNMP_INTEROP").is_ok_and(|value| value == "1")
+}
+
+fn docker(args: &[&str]) -> String {
+    let output = Command::new("docker")
+        .args(args)
+        .output()
+        .expect("failed to run docker");
+    assert!(
+        output.status.success(),
+        "docker {:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+/// One container running snmpd and snmptrapd, removed on drop.
+struct Agent {
+    id: String,
+    address: String,
+}
+
+impl Agent {
+    async fn start() -> Self {
+        let context = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/interop");
+        docker(&["build", "-q", "-t", IMAGE, context]);
+        let id = docker(&["run", "-d", "--rm", IMAGE]);
+        let address = docker(&[
+            "inspect",
+            "-f",
+            "{{range .NetworkSettings.Networks}}{{.IPAddress}}{{end}}",
+            &id,
+        ]);
+        let agent = Agent { id, address };
+
+        // snmpd takes a moment to read its config and create the users
+        let manager = Manager::new();
+        for _ in 0..50 {
+            if manager
+                .get(&agent.address, &Credentials::v2c("public"), SYS_DESCR)
+                .await
+                .is_ok()
+            {
+                return agent;
+            }
+            tokio::time::sleep(Duration::from_millis(200)).await;
+        }
+        panic!("net-snmp agent at {} never answered", agent.address);
+    }
+
+    fn logs(&self) -> String {
+        let output = Command::new("docker")
+            .args(["logs", &self.id])
+            .output()
+            .expect("failed to run docker");
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    }
+
+    // snmptrapd logs asynchronously, so give it a moment
+    async fn wait_for_log(&self, needle: &str) -> bool {
+        for _ in 0..25 {
+            if self.logs().contains(needle) {
+                return true;
+            }
+            tokio::time::sleep(Duration::from_millis(200)).await;
+        }
+        false
+    }
+}
+
+impl Drop for Agent {
+    fn drop(&mut self) {
+        let _ = Command::new("docker").args(["rm", "-f", &self.id]).output();
+    }
+}
+
+fn auth_priv_user() -> Credentials {
+    UsmUser::new("interop-priv")
+        .with_auth(AuthProtocol::Sha1, b"interop-auth-pass")
+        .unwrap()
+        .with_privacy(PrivProtocol::Aes128, b"interop-priv-pass")
+        .unwrap()
+        .into()
+}
+
+fn auth_no_priv_user() -> Credentials {
+    UsmUser::new("interop-auth")
+        .with_auth(AuthProtocol::Md5, b"interop-auth-pass")
+        .unwrap()
+        .into()
+}
+
+fn octets(varbind: &VarBind) -> &[u8] {
+    match &varbind.value {
+        ObjectSyntax::OctetString(bytes) => bytes,
+        other => panic!("expected an OCTET STRING, got {:?}", other),
+    }
+}
+
+fn oids(varbinds: &[VarBind]) -> Vec<Vec<u64>> {
+    varbinds.iter().map(|varbind| varbind.oid.clone()).collect()
+}
+
+async fn check_reads(manager: &Manager, agent: &Agent, credentials: &Credentials) {
+    let location = manager
+        .get(&agent.address, credentials, SYS_LOCATION)
+        .await
+        .unwrap();
+    assert_eq!(octets(&location), b"interop-lab", "{:?}", credentials);
+
+    let walked = manager
+        .walk(&agent.address, credentials, SYSTEM)
+        .await
+        .unwrap();
+    assert!(
+        walked.iter().any(|varbind| varbind.oid == location.oid),
+        "{:?}",
+        credentials
+    );
+
+    // v1 falls back to GetNext, everything else really uses GETBULK
+    let bulk_walked = manager
+        .bulk_walk(&agent.address, credentials, SYSTEM, 5)
+        .await
+        .unwrap();
+    assert_eq!(oids(&bulk_walked), oids(&walked), "{:?}", credentials);
+}
+
+async fn check_write(manager: &Manager, agent: &Agent, credentials: &Credentials, name: &str) {
+    let value = ObjectSyntax::OctetString(name.as_bytes().to_vec());
+    manager
+        .set(&agent.address, credentials, SYS_NAME, value)
+        .await
+        .unwrap();
+    let read_back = manager
+        .get(&agent.address, credentials, SYS_NAME)
+        .await
+        .unwrap();
+    assert_eq!(octets(&read_back), name.as_bytes());
+}
+
+#[tokio::test]
+async fn test_community_interop() {
+    if !enabled() {
+        return;
+    }
+    let agent = Agent::start().await;
+    let manager = Manager::new();
+
+    check_reads(&manager, &agent, &Credentials::v1("public")).await;
+    check_reads(&manager, &agent, &Credentials::v2c("public")).await;
+    check_write(&manager, &agent, &Credentials::v1("private"), "interop-v1").await;
+    check_write(
+        &manager,
+        &agent,
+        &Credentials::v2c("private"),
+        "interop-v2c",
+    )
+    .await;
+
+    let successors = manager
+        .get_next(
+            &agent.address,
+            &Credentials::v2c("public"),
+            &[SYSTEM, SYS_DESCR],
+        )
+        .await
+        .unwrap();
+    assert_eq!(successors.len(), 2);
+}
+
+#[tokio::test]
+async fn test_usm_interop() {
+    if !enabled() {
+        return;
+    }
+    let agent = Agent::start().await;
+    let manager = Manager::new();
+
+    check_reads(&manager, &agent, &auth_no_priv_user()).await;
+    check_reads(&manager, &agent, &auth_priv_user()).await;
+    check_write(&manager, &agent, &auth_priv_user(), "interop-v3").await;
+
+    // interop-auth is read-only
+    assert!(
+        manager
+            .set(
+                &agent.address,
+                &auth_no_priv_user(),
+                SYS_NAME,
+                ObjectSyntax::OctetString(b"denied".to_vec()),
+            )
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn test_notification_interop() {
+    if !enabled() {
+        return;
+    }
+    let agent = Agent::start().await;
+    let manager = Manager::new();
+    let community = Credentials::v2c("public");
+
+    let trap_payload = vec![VarBind {
+        oid: vec![1, 3, 6, 1, 4, 1, 8072, 2, 3, 2, 1],
+        value: ObjectSyntax::OctetString(b"rusnmp-interop-trap".to_vec()),
+    }];
+    manager
+        .send_trap(
+            &agent.address,
+            162,
+            &community,
+            100,
+            "1.3.6.1.4.1.8072.2.3.0.1",
+            trap_payload,
+        )
+        .await
+        .unwrap();
+    assert!(agent.wait_for_log("rusnmp-interop-trap").await);
+
+    let inform_payload = rusnmp::manager::notification_varbinds(
+        200,
+        vec![1, 3, 6, 1, 4, 1, 8072, 2, 3, 0, 1],
+        vec![VarBind {
+            oid: vec![1, 3, 6, 1, 4, 1, 8072, 2, 3, 2, 1],
+            value: ObjectSyntax::OctetString(b"rusnmp-interop-inform".to_vec()),
+        }],
+    );
+    manager
+        .inform(&agent.address, &community, inform_payload, 2)
+        .await
+        .unwrap();
+    assert!(agent.wait_for_log("rusnmp-interop-inform").await);
+}
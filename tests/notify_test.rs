@@ -0,0 +1,68 @@
+use rusnmp::ber::Asn1Tag;
+use rusnmp::manager::{Credentials, ErrorClass, Manager, notification_varbinds};
+use rusnmp::snmp::message::parse_message;
+use rusnmp::snmp::pdu::{ObjectSyntax, VarBind};
+use tokio::net::UdpSocket;
+
+#[tokio::test]
+async fn test_send_trap_to_sink() {
+    let sink = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let port = sink.local_addr().unwrap().port();
+
+    // linkDown with ifIndex 3
+    let payload = vec![VarBind {
+        oid: vec![1, 3, 6, 1, 2, 1, 2, 2, 1, 1, 3],
+        value: ObjectSyntax::Integer(3),
+    }];
+    Manager::new()
+        .send_trap(
+            "127.0.0.1",
+            port,
+            &Credentials::v2c("public"),
+            4200,
+            "1.3.6.1.6.3.1.1.5.3",
+            payload.clone(),
+        )
+        .await
+        .unwrap();
+
+    let mut buf = [0u8; 1500];
+    let len = sink.recv(&mut buf).await.unwrap();
+    let message = parse_message(&buf[..len]).unwrap();
+
+    assert_eq!(message.version, 1);
+    assert_eq!(message.community, b"public");
+    assert_eq!(message.pdu.tag, Asn1Tag::SnmpV2Trap);
+
+    let varbinds = message.pdu.varbinds;
+    assert_eq!(varbinds.len(), 3);
+    assert_eq!(varbinds[0].oid, [1, 3, 6, 1, 2, 1, 1, 3, 0]);
+    assert_eq!(varbinds[0].value, ObjectSyntax::TimeTicks(4200));
+    assert_eq!(varbinds[1].oid, [1, 3, 6, 1, 6, 3, 1, 1, 4, 1, 0]);
+    assert_eq!(
+        varbinds[1].value,
+        ObjectSyntax::ObjectIdentifier(vec![1, 3, 6, 1, 6, 3, 1, 1, 5, 3])
+    );
+    assert_eq!(varbinds[2], payload[0]);
+}
+
+#[tokio::test]
+async fn test_refused_inform_is_not_retried() {
+    // a port nothing listens on any more
+    let port = UdpSocket::bind("127.0.0.1:0")
+        .await
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let target = format!("127.0.0.1:{}", port);
+    let manager = Manager::new();
+
+    let varbinds = notification_varbinds(0, vec![1, 3, 6, 1, 6, 3, 1, 1, 5, 1], Vec::new());
+    let error = manager
+        .inform(&target, &Credentials::v2c("public"), varbinds, 3)
+        .await
+        .unwrap_err();
+    assert_eq!(ErrorClass::of(&error), ErrorClass::Refused);
+    assert_eq!(manager.stats(&target).unwrap().retransmits, 0);
+}
@@ -0,0 +1,46 @@
+use rusnmp::ber::{BerError, decode_oid, encoder};
+
+fn encode(oid: &[u64]) -> Vec<u8> {
+    let mut buf = Vec::new();
+    encoder::encode_oid(&mut buf, oid);
+    buf
+}
+
+#[test]
+fn test_multi_byte_sub_id_round_trip() {
+    // 1.3.6.1.4.1.2021 -> 2b 06 01 04 01 8f 65
+    let bytes = encode(&[1, 3, 6, 1, 4, 1, 2021]);
+    assert_eq!(
+        bytes,
+        [0x06, 0x07, 0x2b, 0x06, 0x01, 0x04, 0x01, 0x8f, 0x65]
+    );
+    assert_eq!(decode_oid(&bytes[2..]).unwrap(), [1, 3, 6, 1, 4, 1, 2021]);
+}
+
+#[test]
+fn test_sub_id_beyond_u32() {
+    let oid = [1, 3, 6, 1, 4, 1, 5_000_000_000, u64::MAX];
+    let bytes = encode(&oid);
+    assert_eq!(decode_oid(&bytes[2..]).unwrap(), oid);
+
+    // eleven continuation groups no longer fit in 64 bits
+    let mut too_wide = vec![0x2b];
+    too_wide.extend_from_slice(&[0xff; 10]);
+    too_wide.push(0x7f);
+    assert_eq!(decode_oid(&too_wide), Err(BerError::IntegerOverflow));
+}
+
+#[test]
+fn test_joint_iso_itu_t_arc() {
+    // X.690's own example, 2.999.3 -> 88 37 03
+    let bytes = encode(&[2, 999, 3]);
+    assert_eq!(bytes, [0x06, 0x03, 0x88, 0x37, 0x03]);
+    assert_eq!(decode_oid(&bytes[2..]).unwrap(), [2, 999, 3]);
+
+    // id-at-commonName, 2.5.4.3, still fits the first byte
+    assert_eq!(encode(&[2, 5, 4, 3]), [0x06, 0x03, 0x55, 0x04, 0x03]);
+    assert_eq!(decode_oid(&[0x55, 0x04, 0x03]).unwrap(), [2, 5, 4, 3]);
+
+    // a first byte of 120 or more is 2.40 and up, not 3.0
+    assert_eq!(decode_oid(&[0x78]).unwrap(), [2, 40]);
+}
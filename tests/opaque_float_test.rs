@@ -0,0 +1,43 @@
+use rusnmp::ber::parse_ber_object;
+use rusnmp::snmp::pdu::{ObjectSyntax, VarBind, parse_varbind};
+
+// SEQUENCE { OID 1.3.6.1.4.1.2021.13, <value> }
+fn varbind_bytes(value: &[u8]) -> Vec<u8> {
+    let mut content = vec![0x06, 0x08, 0x2b, 0x06, 0x01, 0x04, 0x01, 0x8f, 0x65, 0x0d];
+    content.extend_from_slice(value);
+    let mut bytes = vec![0x30, content.len() as u8];
+    bytes.extend_from_slice(&content);
+    bytes
+}
+
+fn decode(value: &[u8]) -> VarBind {
+    let bytes = varbind_bytes(value);
+    let (obj, _) = parse_ber_object(&bytes).unwrap();
+    let varbind = parse_varbind(obj).unwrap();
+
+    // and it encodes back to the same bytes
+    let mut encoded = Vec::new();
+    varbind.write_to_buf(&mut encoded);
+    assert_eq!(encoded, bytes);
+    varbind
+}
+
+#[test]
+fn test_opaque_float() {
+    let varbind = decode(&[0x44, 0x07, 0x9f, 0x78, 0x04, 0x3f, 0xc0, 0x00, 0x00]);
+    assert_eq!(varbind.value, ObjectSyntax::Float(1.5));
+}
+
+#[test]
+fn test_opaque_double() {
+    let varbind = decode(&[
+        0x44, 0x0b, 0x9f, 0x79, 0x08, 0xc0, 0x09, 0x21, 0xfb, 0x54, 0x44, 0x2d, 0x18,
+    ]);
+    assert_eq!(varbind.value, ObjectSyntax::Double(-std::f64::consts::PI));
+}
+
+#[test]
+fn test_other_opaque_stays_raw() {
+    let varbind = decode(&[0x44, 0x03, 0x9f, 0x78, 0x01]);
+    assert_eq!(varbind.value, ObjectSyntax::Opaque(vec![0x9f, 0x78, 0x01]));
+}
@@ -34,7 +34,7 @@ fn test_parse_v2c_get_request() {
     assert_eq!(pdu.varbinds.len(), 1);
 
     let varbind = &pdu.varbinds[0];
-    let expected_oid: Vec<u32> = vec![1, 3, 6, 1, 2, 1, 1, 1, 0];
+    let expected_oid: Vec<u64> = vec![1, 3, 6, 1, 2, 1, 1, 1, 0];
     assert_eq!(varbind.oid, expected_oid);
 
     assert_eq!(varbind.value, ObjectSyntax::Null);
@@ -69,7 +69,7 @@ fn test_parse_v2c_get_response() {
     assert_eq!(pdu.varbinds.len(), 1);
 
     let varbind = &pdu.varbinds[0];
-    let expected_oid: Vec<u32> = vec![1, 3, 6, 1, 2, 1, 1, 1, 0];
+    let expected_oid: Vec<u64> = vec![1, 3, 6, 1, 2, 1, 1, 1, 0];
     assert_eq!(varbind.oid, expected_oid);
 
     let expected_value = b"Sample system description";
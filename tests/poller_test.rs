@@ -0,0 +1,124 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use common::FakeAgent;
+use rusnmp::ber::Asn1Tag;
+use rusnmp::manager::{Credentials, Manager, PollJob, PollRequest, Poller};
+use rusnmp::snmp::pdu::{ErrorStatus, ObjectSyntax, PduData};
+
+mod common;
+
+const TABLE: [u64; 7] = [1, 3, 6, 1, 4, 1, 99];
+
+// answers GETs with each OID's last arc, and walks of a three-row table
+// under TABLE
+async fn agent() -> String {
+    let agent = FakeAgent::new().serve(|message| {
+        let walking = message.pdu.tag != Asn1Tag::GetRequest;
+        message.pdu.data = PduData::Basic {
+            error_status: ErrorStatus::NoError,
+            error_index: 0,
+        };
+        if walking {
+            message.pdu.varbinds.truncate(1);
+            let row = message.pdu.varbinds[0]
+                .oid
+                .get(TABLE.len())
+                .map_or(1, |row| row + 1);
+            message.pdu.varbinds[0].oid = [&TABLE[..], &[row]].concat();
+        }
+        for varbind in &mut message.pdu.varbinds {
+            varbind.value = match *varbind.oid.last().unwrap() {
+                4.. if walking => ObjectSyntax::EndOfMib,
+                last => ObjectSyntax::Integer(last as i32),
+            };
+        }
+        true
+    });
+    agent.await.target
+}
+
+#[tokio::test]
+async fn test_poller_delivers_to_channel() {
+    let target = agent().await;
+    let (mut poller, mut results) = Poller::new(Arc::new(Manager::new()), 8);
+    let community = Credentials::v2c("public");
+    let get = poller.add(
+        PollJob::new(
+            &target,
+            community.clone(),
+            PollRequest::Get(vec!["1.3.6.1.2.1.1.3.0".to_string()]),
+            Duration::from_millis(20),
+        )
+        .unwrap(),
+    );
+    let walk = poller.add(
+        PollJob::new(
+            &target,
+            community,
+            PollRequest::Walk("1.3.6.1.4.1.99".to_string()),
+            Duration::from_secs(3600),
+        )
+        .unwrap(),
+    );
+
+    let (mut gets, mut walks) = (0, 0);
+    while gets < 3 || walks < 1 {
+        let result = results.recv().await.unwrap();
+        assert_eq!(result.target, target);
+        let varbinds = result.result.unwrap();
+        if result.job == get {
+            assert_eq!(varbinds[0].value, ObjectSyntax::Integer(0));
+            gets += 1;
+        } else {
+            assert_eq!(result.job, walk);
+            assert_eq!(varbinds.len(), 3);
+            walks += 1;
+        }
+    }
+
+    assert!(poller.remove(get));
+    assert!(!poller.remove(get));
+    assert_eq!(poller.jobs().collect::<Vec<_>>(), [walk]);
+}
+
+#[tokio::test]
+async fn test_poller_callback_and_drop() {
+    let runs = Arc::new(AtomicUsize::new(0));
+    let counted = Arc::clone(&runs);
+    let mut poller = Poller::with_callback(Arc::new(Manager::new()), move |result| {
+        // nothing listens on the loopback SNMP port
+        assert!(result.result.is_err());
+        counted.fetch_add(1, Ordering::SeqCst);
+    });
+    poller.add(
+        PollJob::new(
+            "127.0.0.1",
+            Credentials::v2c("public"),
+            PollRequest::Get(vec!["1.3.6.1.2.1.1.3.0".to_string()]),
+            Duration::from_millis(10),
+        )
+        .unwrap(),
+    );
+
+    while runs.load(Ordering::SeqCst) < 2 {
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+    drop(poller);
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    let stopped_at = runs.load(Ordering::SeqCst);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(runs.load(Ordering::SeqCst), stopped_at);
+}
+
+#[test]
+fn test_zero_interval_is_rejected() {
+    let job = PollJob::new(
+        "127.0.0.1",
+        Credentials::v2c("public"),
+        PollRequest::Walk("1.3.6.1.2.1.1".to_string()),
+        Duration::ZERO,
+    );
+    assert!(job.is_err());
+}
@@ -0,0 +1,15 @@
+#![cfg(feature = "precheck")]
+
+use std::time::Duration;
+
+use rusnmp::manager::{Manager, ProbeMethod};
+
+#[tokio::test]
+async fn test_udp_probe_finds_loopback_up() {
+    // nothing listens on the probe port, so the refusal answers for it
+    let reachable = Manager::new()
+        .is_reachable("127.0.0.1", ProbeMethod::Udp, Duration::from_secs(1))
+        .await
+        .unwrap();
+    assert!(reachable);
+}
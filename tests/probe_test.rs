@@ -0,0 +1,50 @@
+use std::time::{Duration, Instant};
+
+use rusnmp::ber::Asn1Tag;
+use rusnmp::manager::{Credentials, ErrorClass, Manager};
+use rusnmp::snmp::message::parse_message;
+use rusnmp::snmp::pdu::ObjectSyntax;
+use tokio::net::UdpSocket;
+
+#[tokio::test]
+async fn test_probe_returns_rtt() {
+    let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let target = socket.local_addr().unwrap().to_string();
+    tokio::spawn(async move {
+        let mut buf = [0; 1500];
+        let (len, from) = socket.recv_from(&mut buf).await.unwrap();
+        let mut message = parse_message(&buf[..len]).unwrap();
+        message.pdu.tag = Asn1Tag::GetResponse;
+        message.pdu.varbinds[0].value = ObjectSyntax::TimeTicks(100);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        socket.send_to(&message.to_bytes(), from).await.unwrap();
+    });
+
+    let rtt = Manager::new()
+        .probe(&target, &Credentials::v2c("public"))
+        .await
+        .unwrap();
+    assert!(rtt >= Duration::from_millis(20));
+}
+
+#[tokio::test]
+async fn test_probe_gives_up_quickly() {
+    // never answers, and the Manager itself would wait much longer
+    let silent = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let target = silent.local_addr().unwrap().to_string();
+    let manager = Manager::builder().timeout(Duration::from_secs(30)).build();
+
+    let started = Instant::now();
+    let error = manager
+        .probe(&target, &Credentials::v2c("public"))
+        .await
+        .unwrap_err();
+    assert_eq!(ErrorClass::of(&error), ErrorClass::Timeout);
+    assert!(started.elapsed() < Duration::from_secs(5));
+
+    let error = manager
+        .probe("127.0.0.1", &Credentials::v2c("public"))
+        .await
+        .unwrap_err();
+    assert_eq!(ErrorClass::of(&error), ErrorClass::Refused);
+}
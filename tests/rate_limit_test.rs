@@ -0,0 +1,49 @@
+use std::time::{Duration, Instant};
+
+use common::FakeAgent;
+use rusnmp::manager::{Credentials, Manager, Target};
+use rusnmp::snmp::pdu::ObjectSyntax;
+
+mod common;
+
+// answers every GET straight away
+async fn agent() -> String {
+    let agent = FakeAgent::new().serve(|message| {
+        message.pdu.varbinds[0].value = ObjectSyntax::Integer(1);
+        true
+    });
+    agent.await.target
+}
+
+// how long `count` GETs to `target` take
+async fn poll(manager: &Manager, target: &str, count: usize) -> Duration {
+    let community = Credentials::v2c("public");
+    let started = Instant::now();
+    for _ in 0..count {
+        manager
+            .get(target, &community, "1.3.6.1.2.1.1.3.0")
+            .await
+            .unwrap();
+    }
+    started.elapsed()
+}
+
+#[tokio::test]
+async fn test_global_rate_limit() {
+    let (first, second) = (agent().await, agent().await);
+    let manager = Manager::builder().rate_limit(50.0).build();
+
+    // a second's worth goes out at once, the rest at 50 a second
+    assert!(poll(&manager, &first, 40).await < Duration::from_millis(150));
+    assert!(poll(&manager, &second, 20).await >= Duration::from_millis(180));
+}
+
+#[tokio::test]
+async fn test_target_rate_limit() {
+    let (slow, fast) = (agent().await, agent().await);
+    let manager =
+        Manager::new().with_target(Target::new(&slow, Credentials::v2c("public")).rate_limit(20.0));
+
+    assert!(poll(&manager, &slow, 25).await >= Duration::from_millis(230));
+    assert!(poll(&manager, &fast, 25).await < Duration::from_millis(150));
+}
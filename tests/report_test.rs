@@ -0,0 +1,77 @@
+use rusnmp::ber::{Asn1Tag, parse_ber_object};
+use rusnmp::snmp::pdu::{ErrorStatus, ObjectSyntax, Pdu, PduData, VarBind, parse_pdu};
+use rusnmp::snmp::report::ReportError;
+
+fn report(oid: Vec<u64>) -> Pdu {
+    Pdu {
+        tag: Asn1Tag::Report,
+        request_id: 7,
+        data: PduData::Basic {
+            error_status: ErrorStatus::NoError,
+            error_index: 0,
+        },
+        varbinds: vec![VarBind {
+            oid,
+            value: ObjectSyntax::Counter32(12),
+        }],
+    }
+}
+
+#[test]
+fn test_report_pdu_parses() {
+    let pdu = report(vec![1, 3, 6, 1, 6, 3, 15, 1, 1, 4, 0]);
+    let mut bytes = Vec::new();
+    pdu.write_to_buf(&mut bytes);
+    assert_eq!(bytes[0], 0xA8);
+
+    let (obj, _) = parse_ber_object(&bytes).unwrap();
+    let parsed = parse_pdu(obj).unwrap();
+    assert_eq!(parsed, pdu);
+    assert_eq!(
+        ReportError::from_pdu(&parsed),
+        Some(ReportError::UnknownEngineId)
+    );
+}
+
+#[test]
+fn test_report_oid_mapping() {
+    let cases = [
+        (
+            vec![1, 3, 6, 1, 6, 3, 15, 1, 1, 2, 0],
+            ReportError::NotInTimeWindow,
+        ),
+        (
+            vec![1, 3, 6, 1, 6, 3, 15, 1, 1, 3, 0],
+            ReportError::UnknownUserName,
+        ),
+        (
+            vec![1, 3, 6, 1, 6, 3, 15, 1, 1, 5, 0],
+            ReportError::WrongDigest,
+        ),
+        (
+            vec![1, 3, 6, 1, 6, 3, 15, 1, 1, 6, 0],
+            ReportError::DecryptionError,
+        ),
+        (
+            vec![1, 3, 6, 1, 6, 3, 11, 2, 1, 1, 0],
+            ReportError::UnknownSecurityModel,
+        ),
+        (
+            vec![1, 3, 6, 1, 6, 3, 12, 1, 5, 0],
+            ReportError::UnknownContext,
+        ),
+    ];
+    for (oid, expected) in cases {
+        assert_eq!(ReportError::from_oid(&oid), expected);
+    }
+
+    let unknown = vec![1, 3, 6, 1, 4, 1, 9, 9, 1, 0];
+    assert_eq!(
+        ReportError::from_pdu(&report(unknown.clone())),
+        Some(ReportError::Other { oid: unknown })
+    );
+
+    let mut response = report(vec![1, 3, 6, 1, 6, 3, 15, 1, 1, 4, 0]);
+    response.tag = Asn1Tag::GetResponse;
+    assert_eq!(ReportError::from_pdu(&response), None);
+}
@@ -0,0 +1,100 @@
+use rusnmp::ber::Asn1Tag;
+use rusnmp::manager::{Credentials, Manager};
+use rusnmp::snmp::message::{SnmpMessage, parse_message, peek_header, peek_request_id};
+use rusnmp::snmp::pdu::{ErrorStatus, ObjectSyntax, Pdu, PduData, VarBind};
+use tokio::net::UdpSocket;
+use tokio::sync::mpsc;
+
+fn message(request_id: i32, value: ObjectSyntax) -> SnmpMessage {
+    SnmpMessage {
+        version: 1,
+        community: b"public".to_vec(),
+        pdu: Pdu {
+            tag: Asn1Tag::GetResponse,
+            request_id,
+            data: PduData::Basic {
+                error_status: ErrorStatus::NoError,
+                error_index: 0,
+            },
+            varbinds: vec![VarBind {
+                oid: vec![1, 3, 6, 1, 2, 1, 1, 3, 0],
+                value,
+            }],
+        },
+    }
+}
+
+#[test]
+fn test_peek_request_id() {
+    let bytes = message(-5_000_000, ObjectSyntax::Null).to_bytes();
+    assert_eq!(peek_request_id(&bytes).unwrap(), -5_000_000);
+    assert!(peek_request_id(&bytes[..10]).is_err());
+
+    let header = peek_header(&bytes).unwrap();
+    assert_eq!(header.version, 1);
+    assert_eq!(header.community, Some(&b"public"[..]));
+    assert_eq!(header.pdu_tag, Some(Asn1Tag::GetResponse));
+}
+
+#[tokio::test]
+async fn test_stale_answers_are_skipped() {
+    let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let target = socket.local_addr().unwrap().to_string();
+    let (ids, mut seen) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let mut buf = [0; 1500];
+        loop {
+            let (len, from) = socket.recv_from(&mut buf).await.unwrap();
+            let id = parse_message(&buf[..len]).unwrap().pdu.request_id;
+            ids.send(id).unwrap();
+            // a late answer to some earlier request arrives first
+            let stale = message(id.wrapping_sub(1), ObjectSyntax::TimeTicks(1));
+            socket.send_to(&stale.to_bytes(), from).await.unwrap();
+            let answer = message(id, ObjectSyntax::TimeTicks(2));
+            socket.send_to(&answer.to_bytes(), from).await.unwrap();
+        }
+    });
+
+    let manager = Manager::new();
+    let community = Credentials::v2c("public");
+    for _ in 0..2 {
+        let varbind = manager
+            .get(&target, &community, "1.3.6.1.2.1.1.3.0")
+            .await
+            .unwrap();
+        assert_eq!(varbind.value, ObjectSyntax::TimeTicks(2));
+    }
+
+    let first = seen.recv().await.unwrap();
+    let second = seen.recv().await.unwrap();
+    assert_ne!(first, second);
+    assert!(first >= 0 && second >= 0);
+}
+
+#[tokio::test]
+async fn test_stray_packets_are_skipped() {
+    let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let target = socket.local_addr().unwrap().to_string();
+    tokio::spawn(async move {
+        let mut buf = [0; 1500];
+        let (len, from) = socket.recv_from(&mut buf).await.unwrap();
+        let id = parse_message(&buf[..len]).unwrap().pdu.request_id;
+
+        let mut wrong_community = message(id, ObjectSyntax::TimeTicks(1));
+        wrong_community.community = b"private".to_vec();
+        let mut wrong_version = message(id, ObjectSyntax::TimeTicks(1));
+        wrong_version.version = 0;
+        let mut not_a_response = message(id, ObjectSyntax::TimeTicks(1));
+        not_a_response.pdu.tag = Asn1Tag::SetRequest;
+        let answer = message(id, ObjectSyntax::TimeTicks(2));
+        for packet in [wrong_community, wrong_version, not_a_response, answer] {
+            socket.send_to(&packet.to_bytes(), from).await.unwrap();
+        }
+    });
+
+    let varbind = Manager::new()
+        .get(&target, &Credentials::v2c("public"), "1.3.6.1.2.1.1.3.0")
+        .await
+        .unwrap();
+    assert_eq!(varbind.value, ObjectSyntax::TimeTicks(2));
+}
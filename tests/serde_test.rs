@@ -0,0 +1,71 @@
+#![cfg(feature = "serde")]
+
+use rusnmp::ber::{Asn1Tag, TagClass};
+use rusnmp::snmp::message::SnmpMessage;
+use rusnmp::snmp::pdu::{ErrorStatus, ObjectSyntax, Pdu, PduData, VarBind};
+use serde_json::json;
+
+fn response() -> SnmpMessage {
+    SnmpMessage {
+        version: 1,
+        community: b"public".to_vec(),
+        pdu: Pdu {
+            tag: Asn1Tag::GetResponse,
+            request_id: 7,
+            data: PduData::Basic {
+                error_status: ErrorStatus::NoError,
+                error_index: 0,
+            },
+            varbinds: vec![
+                VarBind {
+                    oid: vec![1, 3, 6, 1, 2, 1, 1, 3, 0],
+                    value: ObjectSyntax::TimeTicks(4200),
+                },
+                VarBind {
+                    oid: vec![1, 3, 6, 1, 4, 1, 2021, 10, 1, 6, 1],
+                    value: ObjectSyntax::Float(0.25),
+                },
+                VarBind {
+                    oid: vec![1, 3, 6, 1, 4, 1, 99, 1],
+                    value: ObjectSyntax::Tagged {
+                        class: TagClass::Private,
+                        number: 3,
+                        constructed: false,
+                        bytes: vec![0xde, 0xad],
+                    },
+                },
+                VarBind {
+                    oid: vec![1, 3, 6, 1, 4, 1, 99, 2],
+                    value: ObjectSyntax::EndOfMib,
+                },
+            ],
+        },
+    }
+}
+
+#[test]
+fn test_message_json_round_trip() {
+    let message = response();
+    let text = serde_json::to_string(&message).unwrap();
+    let back: SnmpMessage = serde_json::from_str(&text).unwrap();
+    assert_eq!(back, message);
+}
+
+#[test]
+fn test_varbind_json_shape() {
+    let varbind = VarBind {
+        oid: vec![1, 3, 6, 1, 2, 1, 1, 5, 0],
+        value: ObjectSyntax::OctetString(b"core-1".to_vec()),
+    };
+    assert_eq!(
+        serde_json::to_value(&varbind).unwrap(),
+        json!({
+            "oid": [1, 3, 6, 1, 2, 1, 1, 5, 0],
+            "value": { "OctetString": [99, 111, 114, 101, 45, 49] },
+        })
+    );
+    assert_eq!(
+        serde_json::to_value(ErrorStatus::NotWritable).unwrap(),
+        json!("NotWritable")
+    );
+}
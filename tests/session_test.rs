@@ -0,0 +1,90 @@
+use std::collections::HashSet;
+use std::net::SocketAddr;
+
+use common::FakeAgent;
+use rusnmp::manager::{Credentials, ErrorClass, Manager, Session};
+use rusnmp::snmp::pdu::ObjectSyntax;
+use tokio::sync::mpsc;
+
+mod common;
+
+// walks a three-object table, reporting where each request came from
+async fn agent() -> (String, mpsc::UnboundedReceiver<SocketAddr>) {
+    let agent = FakeAgent::new().serve(|message| {
+        let varbind = &mut message.pdu.varbinds[0];
+        let next = if varbind.oid.len() == 8 {
+            varbind.oid[7] + 1
+        } else {
+            1
+        };
+        varbind.oid = vec![1, 3, 6, 1, 4, 1, 99, next];
+        varbind.value = if next > 3 {
+            ObjectSyntax::EndOfMib
+        } else {
+            ObjectSyntax::Integer(next as i32)
+        };
+        true
+    });
+    let agent = agent.await;
+    (agent.target, agent.seen)
+}
+
+#[tokio::test]
+async fn test_walk_reuses_one_socket() {
+    let (target, mut seen) = agent().await;
+    let session = Session::connect(&target, Credentials::v2c("public"))
+        .await
+        .unwrap();
+
+    let varbinds = session.walk("1.3.6.1.4.1.99").await.unwrap();
+    assert_eq!(varbinds.len(), 3);
+    assert_eq!(session.stats().packets_sent, 4);
+
+    let mut ports = HashSet::new();
+    while let Ok(from) = seen.try_recv() {
+        ports.insert(from);
+    }
+    assert_eq!(ports.len(), 1);
+}
+
+#[tokio::test]
+async fn test_session_from_builder() {
+    let error = Manager::builder().session("127.0.0.1").await.err().unwrap();
+    assert!(error.to_string().contains("credentials"));
+
+    let session = Manager::builder()
+        .community("public")
+        .session("127.0.0.1")
+        .await
+        .unwrap();
+    assert_eq!(session.target(), "127.0.0.1");
+    // nothing listens on the loopback SNMP port
+    let error = session.get("1.3.6.1.2.1.1.3.0").await.unwrap_err();
+    assert_eq!(ErrorClass::of(&error), ErrorClass::Refused);
+}
+
+#[tokio::test]
+async fn test_session_recovers_after_refusal() {
+    // a free port, with nothing listening on it yet
+    let socket = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let target = socket.local_addr().unwrap().to_string();
+    drop(socket);
+    let session = Session::connect(&target, Credentials::v2c("public"))
+        .await
+        .unwrap();
+
+    let error = session.get("1.3.6.1.4.1.99.1").await.unwrap_err();
+    assert_eq!(ErrorClass::of(&error), ErrorClass::Refused);
+
+    FakeAgent::new()
+        .bind(&target)
+        .serve(|message| {
+            message.pdu.varbinds[0].value = ObjectSyntax::Integer(1);
+            true
+        })
+        .await;
+    for _ in 0..2 {
+        let varbind = session.get("1.3.6.1.4.1.99.1").await.unwrap();
+        assert_eq!(varbind.value, ObjectSyntax::Integer(1));
+    }
+}
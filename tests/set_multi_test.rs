@@ -0,0 +1,38 @@
+use rusnmp::manager::{Credentials, Manager, SnmpError};
+use rusnmp::snmp::pdu::{ErrorStatus, ObjectSyntax, PduData};
+
+mod common;
+use common::FakeAgent;
+
+#[tokio::test]
+async fn test_error_index_names_the_binding() {
+    // refuses the second binding
+    let agent = FakeAgent::new()
+        .serve(|message| {
+            message.pdu.data = PduData::Basic {
+                error_status: ErrorStatus::WrongType,
+                error_index: 2,
+            };
+            true
+        })
+        .await;
+
+    let error = Manager::new()
+        .set_multi(
+            &agent.target,
+            &Credentials::v2c("private"),
+            &[
+                (
+                    "1.3.6.1.2.1.1.4.0",
+                    ObjectSyntax::OctetString(b"ops".to_vec()),
+                ),
+                ("1.3.6.1.2.1.1.6.0", ObjectSyntax::Integer(3)),
+            ],
+        )
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("1.3.6.1.2.1.1.6.0"));
+    let snmp = error.downcast_ref::<SnmpError>().unwrap();
+    assert_eq!(snmp.status, ErrorStatus::WrongType);
+    assert_eq!(snmp.index, 2);
+}
@@ -0,0 +1,54 @@
+use rusnmp::manager::{Credentials, ErrorClass, Manager, SetDeniedError, SetPolicy};
+use rusnmp::snmp::pdu::ObjectSyntax;
+
+#[test]
+fn test_set_policy_prefixes() {
+    let policy = SetPolicy::allow_all();
+    assert!(policy.permits(&[1, 3, 6, 1, 2, 1, 1, 5, 0]));
+
+    // the system group minus sysORTable, nothing outside it
+    let policy = SetPolicy::allow_all()
+        .allow("1.3.6.1.2.1.1")
+        .unwrap()
+        .deny("1.3.6.1.2.1.1.9")
+        .unwrap();
+    assert!(policy.permits(&[1, 3, 6, 1, 2, 1, 1, 5, 0]));
+    assert!(!policy.permits(&[1, 3, 6, 1, 2, 1, 1, 9, 1, 3, 1]));
+    assert!(!policy.permits(&[1, 3, 6, 1, 2, 1, 2, 2, 1, 7, 1]));
+}
+
+#[tokio::test]
+async fn test_denied_set_is_not_sent() {
+    // Cisco's tsMsgSend, writing 2 reloads the router
+    let manager =
+        Manager::new().with_set_policy(SetPolicy::allow_all().deny("1.3.6.1.4.1.9.2.9.9").unwrap());
+
+    let error = manager
+        .set_multi(
+            "127.0.0.1",
+            &Credentials::v2c("private"),
+            &[
+                (
+                    "1.3.6.1.2.1.1.6.0",
+                    ObjectSyntax::OctetString(b"lab".to_vec()),
+                ),
+                ("1.3.6.1.4.1.9.2.9.9.0", ObjectSyntax::Integer(2)),
+            ],
+        )
+        .await
+        .unwrap_err();
+    let denied = error.downcast_ref::<SetDeniedError>().unwrap();
+    assert_eq!(denied.oid, "1.3.6.1.4.1.9.2.9.9.0");
+
+    // anything else still goes out, and finds nothing listening
+    let error = manager
+        .set(
+            "127.0.0.1",
+            &Credentials::v2c("private"),
+            "1.3.6.1.2.1.1.6.0",
+            ObjectSyntax::Integer(1),
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(ErrorClass::of(&error), ErrorClass::Refused);
+}
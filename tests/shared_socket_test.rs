@@ -0,0 +1,95 @@
+use std::collections::HashSet;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use common::FakeAgent;
+use futures::future::join_all;
+use rusnmp::manager::{Credentials, ErrorClass, Manager, SharedSocketTransport};
+use rusnmp::snmp::pdu::ObjectSyntax;
+use tokio::sync::mpsc;
+
+mod common;
+
+// waits for `batch` requests, then answers them in reverse order with the
+// last arc of each OID, reporting where each came from
+async fn agent(batch: usize) -> (String, mpsc::UnboundedReceiver<SocketAddr>) {
+    let agent = FakeAgent::new().batch(batch).serve(|message| {
+        let varbind = &mut message.pdu.varbinds[0];
+        varbind.value = ObjectSyntax::Integer(*varbind.oid.last().unwrap() as i32);
+        true
+    });
+    let agent = agent.await;
+    (agent.target, agent.seen)
+}
+
+#[tokio::test]
+async fn test_concurrent_requests_share_one_socket() {
+    let (target, mut seen) = agent(200).await;
+    let manager = Manager::builder()
+        .transport(SharedSocketTransport::new())
+        .build();
+    let credentials = Credentials::v2c("public");
+
+    let oids: Vec<String> = (0..200).map(|i| format!("1.3.6.1.4.1.99.{}", i)).collect();
+    let results = join_all(
+        oids.iter()
+            .map(|oid| manager.get(&target, &credentials, oid)),
+    )
+    .await;
+
+    // answered out of order, each request still got its own response
+    for (i, result) in results.into_iter().enumerate() {
+        assert_eq!(result.unwrap().value, ObjectSyntax::Integer(i as i32));
+    }
+    let mut sources = HashSet::new();
+    while let Ok(from) = seen.try_recv() {
+        sources.insert(from);
+    }
+    assert_eq!(sources.len(), 1);
+}
+
+#[tokio::test]
+async fn test_timed_out_request_is_forgotten() {
+    let (target, _seen) = agent(2).await;
+    let transport = SharedSocketTransport::new();
+    let manager = Manager::builder()
+        .transport(transport)
+        .timeout(Duration::from_millis(100))
+        .build();
+
+    // the agent waits for a second request that never comes
+    let error = manager
+        .get(&target, &Credentials::v2c("public"), "1.3.6.1.2.1.1.5.0")
+        .await
+        .unwrap_err();
+    assert_eq!(ErrorClass::of(&error), ErrorClass::Timeout);
+    // now it has two, and answers the late one too, which nobody wants
+    let varbind = manager
+        .get(&target, &Credentials::v2c("public"), "1.3.6.1.2.1.1.7")
+        .await
+        .unwrap();
+    assert_eq!(varbind.value, ObjectSyntax::Integer(7));
+}
+
+#[tokio::test]
+async fn test_in_flight_count() {
+    let (target, _seen) = agent(3).await;
+    let transport = Arc::new(SharedSocketTransport::new());
+    let credentials = Credentials::v2c("public");
+    let manager = Manager::builder().transport(transport.clone()).build();
+
+    let gets = join_all(
+        ["1.3.6.1.4.1.99.1", "1.3.6.1.4.1.99.2"].map(|oid| manager.get(&target, &credentials, oid)),
+    );
+    let watch = async {
+        while transport.in_flight() < 2 {
+            tokio::task::yield_now().await;
+        }
+        manager.get(&target, &credentials, "1.3.6.1.4.1.99.3").await
+    };
+    let (gets, third) = tokio::join!(gets, watch);
+    assert!(gets.into_iter().all(|get| get.is_ok()));
+    assert!(third.is_ok());
+    assert_eq!(transport.in_flight(), 0);
+}
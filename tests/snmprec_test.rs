@@ -0,0 +1,54 @@
+use rusnmp::snmp::pdu::{ObjectSyntax, VarBind};
+use rusnmp::snmp::snmprec::format_line;
+
+fn line(oid: &[u64], value: ObjectSyntax) -> String {
+    format_line(&VarBind {
+        oid: oid.to_vec(),
+        value,
+    })
+}
+
+#[test]
+fn test_snmprec_lines() {
+    let sys_descr = [1, 3, 6, 1, 2, 1, 1, 1, 0];
+    assert_eq!(
+        line(&sys_descr, ObjectSyntax::OctetString(b"Linux box".to_vec())),
+        "1.3.6.1.2.1.1.1.0|4|Linux box"
+    );
+    assert_eq!(
+        line(
+            &sys_descr,
+            ObjectSyntax::OctetString(vec![0x00, 0x1b, 0x0a])
+        ),
+        "1.3.6.1.2.1.1.1.0|4x|001b0a"
+    );
+    assert_eq!(
+        line(
+            &[1, 3, 6, 1, 2, 1, 1, 2, 0],
+            ObjectSyntax::ObjectIdentifier(vec![1, 3, 6, 1, 4, 1, 8072])
+        ),
+        "1.3.6.1.2.1.1.2.0|6|1.3.6.1.4.1.8072"
+    );
+    assert_eq!(
+        line(&[1, 3, 6, 1, 2, 1, 1, 3, 0], ObjectSyntax::TimeTicks(4200)),
+        "1.3.6.1.2.1.1.3.0|67|4200"
+    );
+    assert_eq!(
+        line(
+            &[1, 3, 6, 1, 2, 1, 4, 20, 1, 1],
+            ObjectSyntax::IpAddress(vec![10, 0, 0, 1])
+        ),
+        "1.3.6.1.2.1.4.20.1.1|64|10.0.0.1"
+    );
+    assert_eq!(
+        line(
+            &[1, 3, 6, 1, 2, 1, 31, 1, 1, 1, 6, 1],
+            ObjectSyntax::Counter64(1 << 40)
+        ),
+        "1.3.6.1.2.1.31.1.1.1.6.1|70|1099511627776"
+    );
+    assert_eq!(
+        line(&[1, 3, 6, 1, 4, 1, 2021, 13], ObjectSyntax::Float(1.5)),
+        "1.3.6.1.4.1.2021.13|68x|9f78043fc00000"
+    );
+}
@@ -0,0 +1,79 @@
+use std::collections::HashSet;
+use std::net::SocketAddr;
+
+use common::FakeAgent;
+use rusnmp::manager::{Credentials, Manager};
+use rusnmp::snmp::pdu::ObjectSyntax;
+use tokio::sync::mpsc;
+
+mod common;
+
+// answers every request, reporting where each one came from
+async fn agent() -> (String, mpsc::UnboundedReceiver<SocketAddr>) {
+    let agent = FakeAgent::new().serve(|message| {
+        message.pdu.varbinds[0].value = ObjectSyntax::Integer(1);
+        true
+    });
+    let agent = agent.await;
+    (agent.target, agent.seen)
+}
+
+fn sources(seen: &mut mpsc::UnboundedReceiver<SocketAddr>) -> HashSet<SocketAddr> {
+    let mut sources = HashSet::new();
+    while let Ok(from) = seen.try_recv() {
+        sources.insert(from);
+    }
+    sources
+}
+
+#[tokio::test]
+async fn test_sequential_requests_share_a_socket() {
+    let (target, mut seen) = agent().await;
+    let manager = Manager::new();
+    let community = Credentials::v2c("public");
+    for _ in 0..5 {
+        manager
+            .get(&target, &community, "1.3.6.1.2.1.1.7.0")
+            .await
+            .unwrap();
+    }
+    assert_eq!(sources(&mut seen).len(), 1);
+}
+
+#[tokio::test]
+async fn test_concurrent_requests_take_their_own_socket() {
+    let (target, mut seen) = agent().await;
+    let manager = Manager::new();
+    let community = Credentials::v2c("public");
+    let (first, second) = tokio::join!(
+        manager.get(&target, &community, "1.3.6.1.2.1.1.7.0"),
+        manager.get(&target, &community, "1.3.6.1.2.1.1.7.0"),
+    );
+    first.unwrap();
+    second.unwrap();
+    assert_eq!(sources(&mut seen).len(), 2);
+
+    // both went back to the pool
+    for _ in 0..2 {
+        let (first, second) = tokio::join!(
+            manager.get(&target, &community, "1.3.6.1.2.1.1.7.0"),
+            manager.get(&target, &community, "1.3.6.1.2.1.1.7.0"),
+        );
+        first.unwrap();
+        second.unwrap();
+    }
+    assert_eq!(sources(&mut seen).len(), 2);
+}
+
+#[tokio::test]
+async fn test_pooling_can_be_turned_off() {
+    let (target, _seen) = agent().await;
+    let manager = Manager::builder().max_idle_sockets(0).build();
+    let community = Credentials::v2c("public");
+    for _ in 0..2 {
+        manager
+            .get(&target, &community, "1.3.6.1.2.1.1.7.0")
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,66 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+
+use common::FakeAgent;
+use rusnmp::manager::{Credentials, Manager};
+use tokio::sync::mpsc;
+
+mod common;
+
+// echoes requests back as responses, reporting where each came from
+async fn agent() -> (String, mpsc::UnboundedReceiver<SocketAddr>) {
+    let agent = FakeAgent::new().serve(|_| true).await;
+    (agent.target, agent.seen)
+}
+
+const SOURCE: IpAddr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 5));
+
+#[tokio::test]
+async fn test_requests_leave_from_source_address() {
+    let (target, mut seen) = agent().await;
+    let manager = Manager::builder().source_address(SOURCE).build();
+    manager
+        .get(&target, &Credentials::v2c("public"), "1.3.6.1.2.1.1.5.0")
+        .await
+        .unwrap();
+    assert_eq!(seen.recv().await.unwrap().ip(), SOURCE);
+}
+
+#[tokio::test]
+async fn test_session_leaves_from_source_address() {
+    let (target, mut seen) = agent().await;
+    let session = Manager::builder()
+        .source_address(SOURCE)
+        .community("public")
+        .session(target)
+        .await
+        .unwrap();
+    session.get("1.3.6.1.2.1.1.5.0").await.unwrap();
+    assert_eq!(seen.recv().await.unwrap().ip(), SOURCE);
+}
+
+#[tokio::test]
+async fn test_source_of_other_family() {
+    let (target, _seen) = agent().await;
+    let manager = Manager::builder()
+        .source_address(IpAddr::V6(Ipv6Addr::LOCALHOST))
+        .timeout(Duration::from_millis(200))
+        .build();
+    let error = manager
+        .get(&target, &Credentials::v2c("public"), "1.3.6.1.2.1.1.5.0")
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("can't reach"), "{:#}", error);
+}
+
+#[cfg(target_os = "linux")]
+#[tokio::test]
+async fn test_unknown_device() {
+    let (target, _seen) = agent().await;
+    let manager = Manager::builder().bind_device("rusnmp-none0").build();
+    let error = manager
+        .get(&target, &Credentials::v2c("public"), "1.3.6.1.2.1.1.5.0")
+        .await
+        .unwrap_err();
+    assert!(format!("{:#}", error).contains("rusnmp-none0"));
+}
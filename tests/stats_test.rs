@@ -0,0 +1,92 @@
+use rusnmp::manager::{Credentials, Manager, TransportStats};
+use tokio::net::UdpSocket;
+
+#[tokio::test]
+async fn test_refused_request_is_counted() {
+    // nothing listens in the sandbox: the packet goes out, no answer comes back
+    let manager = Manager::new();
+    let _ = manager
+        .get(
+            "127.0.0.1",
+            &Credentials::v2c("public"),
+            "1.3.6.1.2.1.1.3.0",
+        )
+        .await;
+
+    let stats = manager.stats("127.0.0.1").unwrap();
+    assert_eq!(stats.packets_sent, 1);
+    assert_eq!(stats.packets_received, 0);
+    assert!(stats.bytes_sent > 0);
+    assert_eq!(stats.average_rtt(), None);
+    assert!(manager.stats("127.0.0.2").is_none());
+}
+
+#[tokio::test]
+async fn test_totals_add_up_targets() {
+    let manager = Manager::new();
+    let community = Credentials::v2c("public");
+    for target in ["127.0.0.1", "127.0.0.1", "127.0.0.2"] {
+        let _ = manager.get(target, &community, "1.3.6.1.2.1.1.3.0").await;
+    }
+
+    let total = manager.total_stats();
+    assert_eq!(total.packets_sent, 3);
+    assert_eq!(
+        total.bytes_sent,
+        manager.stats("127.0.0.1").unwrap().bytes_sent
+            + manager.stats("127.0.0.2").unwrap().bytes_sent
+    );
+    // only answered targets have a round-trip time
+    assert!(manager.slowest_targets(5).is_empty());
+}
+
+#[test]
+fn test_retransmit_percent() {
+    assert_eq!(TransportStats::default().retransmit_percent(), 0.0);
+    let stats = TransportStats {
+        packets_sent: 4,
+        retransmits: 1,
+        ..Default::default()
+    };
+    assert_eq!(stats.retransmit_percent(), 25.0);
+}
+
+#[tokio::test]
+async fn test_failures_and_decode_errors() {
+    // answers every request with garbage
+    let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let garbled = socket.local_addr().unwrap().to_string();
+    tokio::spawn(async move {
+        let mut buf = [0; 1500];
+        loop {
+            let (len, from) = socket.recv_from(&mut buf).await.unwrap();
+            // a message header that matches, then a truncated PDU
+            let mut reply = buf[..len].to_vec();
+            reply.truncate(len - 3);
+            socket.send_to(&reply, from).await.unwrap();
+        }
+    });
+
+    let manager = Manager::new();
+    let community = Credentials::v2c("public");
+    assert!(
+        manager
+            .get(&garbled, &community, "1.3.6.1.2.1.1.3.0")
+            .await
+            .is_err()
+    );
+    assert!(
+        manager
+            .get("127.0.0.1", &community, "1.3.6.1.2.1.1.3.0")
+            .await
+            .is_err()
+    );
+
+    let snapshot = manager.stats_snapshot();
+    assert_eq!(snapshot.len(), 2);
+    assert_eq!(snapshot["127.0.0.1"].failures, 1);
+    assert_eq!(snapshot["127.0.0.1"].decode_errors, 0);
+    assert_eq!(snapshot[&garbled].failures, 1);
+    assert_eq!(snapshot[&garbled].decode_errors, 1);
+    assert_eq!(manager.total_stats().failures, 2);
+}
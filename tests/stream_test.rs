@@ -0,0 +1,67 @@
+use rusnmp::ber::stream::{Frame, StreamDecoder, frame_len};
+use rusnmp::ber::{Asn1Tag, BerError};
+use rusnmp::snmp::message::{SnmpMessage, parse_message};
+use rusnmp::snmp::pdu::{ErrorStatus, ObjectSyntax, Pdu, PduData, VarBind};
+
+fn message(request_id: i32, text_len: usize) -> SnmpMessage {
+    SnmpMessage {
+        version: 1,
+        community: b"public".to_vec(),
+        pdu: Pdu {
+            tag: Asn1Tag::GetResponse,
+            request_id,
+            data: PduData::Basic {
+                error_status: ErrorStatus::NoError,
+                error_index: 0,
+            },
+            varbinds: vec![VarBind {
+                oid: vec![1, 3, 6, 1, 2, 1, 1, 1, 0],
+                value: ObjectSyntax::OctetString(vec![b'x'; text_len]),
+            }],
+        },
+    }
+}
+
+#[test]
+fn test_frame_len() {
+    // 300 byte string, so the message length takes two octets
+    let bytes = message(1, 300).to_bytes();
+    assert_eq!(frame_len(&[]), Ok(Frame::NeedMore(1)));
+    assert_eq!(frame_len(&bytes[..1]), Ok(Frame::NeedMore(1)));
+    // 0x82 announces two length octets
+    assert_eq!(frame_len(&bytes[..2]), Ok(Frame::NeedMore(2)));
+    assert_eq!(
+        frame_len(&bytes[..10]),
+        Ok(Frame::NeedMore(bytes.len() - 10))
+    );
+    assert_eq!(frame_len(&bytes), Ok(Frame::Complete(bytes.len())));
+}
+
+#[test]
+fn test_stream_decoder_reassembles_messages() {
+    let first = message(1, 300);
+    let second = message(2, 10);
+    let mut stream = first.to_bytes();
+    stream.extend(second.to_bytes());
+
+    let mut decoder = StreamDecoder::new(1500);
+    let mut frames = Vec::new();
+    // arrives in awkward chunks
+    for chunk in stream.chunks(7) {
+        decoder.extend(chunk);
+        while let Some(frame) = decoder.next_frame().unwrap() {
+            frames.push(parse_message(&frame).unwrap());
+        }
+    }
+    assert_eq!(frames, vec![first, second]);
+    assert_eq!(decoder.buffered(), 0);
+    assert_eq!(decoder.needed(), Ok(1));
+}
+
+#[test]
+fn test_stream_decoder_frame_limit() {
+    let mut decoder = StreamDecoder::new(100);
+    // a SEQUENCE claiming 64 KiB
+    decoder.extend(&[0x30, 0x82, 0xff, 0xff]);
+    assert_eq!(decoder.next_frame(), Err(BerError::FrameTooLarge(65539)));
+}
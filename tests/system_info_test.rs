@@ -0,0 +1,48 @@
+use std::time::Duration;
+
+use common::FakeAgent;
+use rusnmp::manager::{Credentials, Manager, SystemInfo};
+use rusnmp::snmp::pdu::ObjectSyntax;
+
+mod common;
+
+// answers the system group, without sysContact
+async fn agent() -> String {
+    let agent = FakeAgent::new().serve(|message| {
+        for varbind in &mut message.pdu.varbinds {
+            varbind.value = match varbind.oid[7] {
+                1 => ObjectSyntax::OctetString(b"Linux core-1".to_vec()),
+                2 => ObjectSyntax::ObjectIdentifier(vec![1, 3, 6, 1, 4, 1, 8072, 3, 2, 10]),
+                3 => ObjectSyntax::TimeTicks(12345),
+                5 => ObjectSyntax::OctetString(b"core-1".to_vec()),
+                6 => ObjectSyntax::OctetString(b"rack \xff".to_vec()),
+                _ => ObjectSyntax::NoSuchObject,
+            };
+        }
+        true
+    });
+    agent.await.target
+}
+
+#[tokio::test]
+async fn test_get_system_info() {
+    let target = agent().await;
+    let manager = Manager::new();
+    let info = manager
+        .get_system_info(&target, &Credentials::v2c("public"))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        info,
+        SystemInfo {
+            description: Some("Linux core-1".to_string()),
+            object_id: Some(vec![1, 3, 6, 1, 4, 1, 8072, 3, 2, 10]),
+            uptime: Some(Duration::from_millis(123450)),
+            contact: None,
+            name: Some("core-1".to_string()),
+            location: Some("rack \u{fffd}".to_string()),
+        }
+    );
+    assert_eq!(manager.stats(&target).unwrap().packets_sent, 1);
+}
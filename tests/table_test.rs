@@ -0,0 +1,195 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use common::{FakeAgent, next_cells};
+use rusnmp::manager::{Credentials, IndexSyntax, Manager, decode_index, encode_index};
+use rusnmp::snmp::pdu::{ObjectSyntax, VarBind};
+
+mod common;
+
+// serves `cells` (sorted by OID) to GetNext and GetBulk, counting requests
+async fn agent(cells: Vec<VarBind>, requests: Arc<AtomicUsize>) -> String {
+    let agent = FakeAgent::new().serve(move |message| {
+        requests.fetch_add(1, Ordering::SeqCst);
+        next_cells(&cells, message);
+        true
+    });
+    agent.await.target
+}
+
+fn cell(oid: &[u64], value: i32) -> VarBind {
+    VarBind {
+        oid: oid.to_vec(),
+        value: ObjectSyntax::Integer(value),
+    }
+}
+
+// columns 1, 2 and 3 of a table indexed by two arcs; row 2.5 has no column 3
+fn table() -> Vec<VarBind> {
+    vec![
+        cell(&[1, 3, 6, 1, 4, 1, 99, 1, 1, 1, 1], 11),
+        cell(&[1, 3, 6, 1, 4, 1, 99, 1, 1, 2, 5], 12),
+        cell(&[1, 3, 6, 1, 4, 1, 99, 1, 2, 1, 1], 21),
+        cell(&[1, 3, 6, 1, 4, 1, 99, 1, 2, 2, 5], 22),
+        cell(&[1, 3, 6, 1, 4, 1, 99, 1, 3, 1, 1], 31),
+        cell(&[1, 3, 6, 1, 4, 1, 100, 1], 0),
+    ]
+}
+
+#[tokio::test]
+async fn test_get_table_pivots_sparse_rows() {
+    let target = agent(table(), Arc::default()).await;
+
+    for credentials in [Credentials::v1("public"), Credentials::v2c("public")] {
+        let rows = Manager::new()
+            .get_table(&target, &credentials, "1.3.6.1.4.1.99")
+            .await
+            .unwrap();
+
+        assert_eq!(rows.len(), 2);
+        let first = &rows[&vec![1, 1]];
+        assert_eq!(first.len(), 3);
+        assert_eq!(first[&3], ObjectSyntax::Integer(31));
+        let second = &rows[&vec![2, 5]];
+        assert_eq!(second.len(), 2);
+        assert_eq!(second[&1], ObjectSyntax::Integer(12));
+    }
+}
+
+#[tokio::test]
+async fn test_get_table_rejects_bad_oid() {
+    let manager = Manager::new();
+    let result = manager
+        .get_table("127.0.0.1", &Credentials::v2c("public"), "1.3.x")
+        .await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_get_table_columns() {
+    let requests = Arc::new(AtomicUsize::new(0));
+    let target = agent(table(), Arc::clone(&requests)).await;
+
+    for credentials in [Credentials::v2c("public"), Credentials::v1("public")] {
+        requests.store(0, Ordering::SeqCst);
+        let rows = Manager::new()
+            .get_table_columns(&target, &credentials, "1.3.6.1.4.1.99", &[3, 1])
+            .await
+            .unwrap();
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[&vec![1, 1]].keys().collect::<Vec<_>>(), [&1, &3]);
+        assert_eq!(rows[&vec![2, 5]][&1], ObjectSyntax::Integer(12));
+        assert!(!rows[&vec![2, 5]].contains_key(&3));
+    }
+    // v1 walks each column with GetNext: 2 + 1 cells, plus one step past
+    // the end of each column
+    assert_eq!(requests.load(Ordering::SeqCst), 5);
+}
+
+#[tokio::test]
+async fn test_get_table_columns_in_one_bulk() {
+    let requests = Arc::new(AtomicUsize::new(0));
+    let target = agent(table(), Arc::clone(&requests)).await;
+
+    let rows = Manager::new()
+        .get_table_columns(
+            &target,
+            &Credentials::v2c("public"),
+            "1.3.6.1.4.1.99",
+            &[1, 2],
+        )
+        .await
+        .unwrap();
+    assert_eq!(rows[&vec![2, 5]][&2], ObjectSyntax::Integer(22));
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn test_decode_index() {
+    // INDEX { ifIndex, ipAddress, name (length-prefixed), IMPLIED oid }
+    let arcs = [7, 10, 0, 0, 1, 3, 101, 116, 104, 1, 3, 6];
+    let syntax = [
+        IndexSyntax::Integer,
+        IndexSyntax::IpAddress,
+        IndexSyntax::OctetString,
+        IndexSyntax::ImpliedObjectIdentifier,
+    ];
+    assert_eq!(
+        decode_index(&arcs, &syntax),
+        Some(vec![
+            ObjectSyntax::Integer(7),
+            ObjectSyntax::IpAddress(vec![10, 0, 0, 1]),
+            ObjectSyntax::OctetString(b"eth".to_vec()),
+            ObjectSyntax::ObjectIdentifier(vec![1, 3, 6]),
+        ])
+    );
+
+    let mac = [0, 12, 41, 168, 139, 162];
+    assert_eq!(
+        decode_index(&mac, &[IndexSyntax::FixedString(6)]),
+        Some(vec![ObjectSyntax::OctetString(
+            mac.map(|arc| arc as u8).to_vec()
+        )])
+    );
+    assert_eq!(
+        decode_index(&[2, 9], &[IndexSyntax::ObjectIdentifier]),
+        None
+    );
+    // left over arcs, and an arc too big for an octet
+    assert_eq!(decode_index(&[1, 2], &[IndexSyntax::Unsigned]), None);
+    assert_eq!(decode_index(&[1, 256], &[IndexSyntax::OctetString]), None);
+}
+
+#[test]
+fn test_encode_index() {
+    let arcs = [7, 10, 0, 0, 1, 3, 101, 116, 104, 1, 3, 6];
+    let syntax = [
+        IndexSyntax::Integer,
+        IndexSyntax::IpAddress,
+        IndexSyntax::OctetString,
+        IndexSyntax::ImpliedObjectIdentifier,
+    ];
+    let values = decode_index(&arcs, &syntax).unwrap();
+    assert_eq!(encode_index(&values, &syntax), Some(arcs.to_vec()));
+
+    // negative integers, wrong types and a fixed string of another size
+    let integer = [IndexSyntax::Integer];
+    assert_eq!(encode_index(&[ObjectSyntax::Integer(-1)], &integer), None);
+    assert_eq!(encode_index(&[ObjectSyntax::Null], &integer), None);
+    let fixed = [IndexSyntax::FixedString(6)];
+    let short = ObjectSyntax::OctetString(vec![0; 4]);
+    assert_eq!(encode_index(&[short], &fixed), None);
+    assert_eq!(encode_index(&[], &integer), None);
+}
+
+#[tokio::test]
+async fn test_get_table_indexed() {
+    let target = agent(table(), Arc::default()).await;
+    let manager = Manager::new();
+    let credentials = Credentials::v2c("public");
+
+    let rows = manager
+        .get_table_indexed(
+            &target,
+            &credentials,
+            "1.3.6.1.4.1.99",
+            &[IndexSyntax::Integer, IndexSyntax::Unsigned],
+        )
+        .await
+        .unwrap();
+    let (index, row) = &rows[1];
+    assert_eq!(index, &[ObjectSyntax::Integer(2), ObjectSyntax::Gauge32(5)]);
+    assert_eq!(row[&2], ObjectSyntax::Integer(22));
+
+    let error = manager
+        .get_table_indexed(
+            &target,
+            &credentials,
+            "1.3.6.1.4.1.99",
+            &[IndexSyntax::Integer],
+        )
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("1.1"), "{}", error);
+}
@@ -0,0 +1,110 @@
+use rusnmp::ber::decoder::decode_unsigned_integer;
+use rusnmp::ber::{BerError, BerResult, TagClass, parse_ber_object};
+use rusnmp::snmp::pdu::{ObjectSyntax, VarBind, parse_varbind, register_value_decoder};
+
+// SEQUENCE { OID 1.3.6.1.4.1.9.1, <value> }
+fn varbind_bytes(value: &[u8]) -> Vec<u8> {
+    let mut content = vec![0x06, 0x06, 0x2b, 0x06, 0x01, 0x04, 0x01, 0x09];
+    content.extend_from_slice(value);
+    let mut bytes = vec![0x30, content.len() as u8];
+    bytes.extend_from_slice(&content);
+    bytes
+}
+
+#[test]
+fn test_context_tagged_value_is_exposed() {
+    let bytes = varbind_bytes(&[0x89, 0x02, 0xbe, 0xef]);
+    let (obj, _) = parse_ber_object(&bytes).unwrap();
+    let varbind = parse_varbind(obj).unwrap();
+
+    let expected = ObjectSyntax::Tagged {
+        class: TagClass::Context,
+        number: 9,
+        constructed: false,
+        bytes: vec![0xbe, 0xef],
+    };
+    assert_eq!(varbind.value, expected);
+
+    // and it encodes back to the same bytes
+    let mut encoded = Vec::new();
+    VarBind {
+        oid: varbind.oid,
+        value: expected,
+    }
+    .write_to_buf(&mut encoded);
+    assert_eq!(encoded, bytes);
+}
+
+#[test]
+fn test_constructed_tagged_value_keeps_its_bit() {
+    // [CONTEXT 9] constructed, holding INTEGER 5
+    let bytes = varbind_bytes(&[0xa9, 0x03, 0x02, 0x01, 0x05]);
+    let (obj, _) = parse_ber_object(&bytes).unwrap();
+    let varbind = parse_varbind(obj).unwrap();
+
+    assert_eq!(
+        varbind.value,
+        ObjectSyntax::Tagged {
+            class: TagClass::Context,
+            number: 9,
+            constructed: true,
+            bytes: vec![0x02, 0x01, 0x05],
+        }
+    );
+
+    let mut encoded = Vec::new();
+    varbind.write_to_buf(&mut encoded);
+    assert_eq!(encoded, bytes);
+}
+
+fn decode_uinteger32(value: &[u8]) -> BerResult<ObjectSyntax> {
+    Ok(ObjectSyntax::Gauge32(decode_unsigned_integer(value)?))
+}
+
+#[test]
+fn test_registered_value_decoder() {
+    // UInteger32 ([APPLICATION 7]), obsoleted by SMIv2 but still seen
+    register_value_decoder(0x47, decode_uinteger32);
+
+    let bytes = varbind_bytes(&[0x47, 0x02, 0x01, 0x00]);
+    let (obj, _) = parse_ber_object(&bytes).unwrap();
+    assert_eq!(
+        parse_varbind(obj).unwrap().value,
+        ObjectSyntax::Gauge32(256)
+    );
+}
+
+#[test]
+fn test_long_form_tag_value() {
+    // [CONTEXT 200]: 0x9f then 200 in base-128 as 0x81 0x48
+    let bytes = varbind_bytes(&[0x9f, 0x81, 0x48, 0x01, 0x2a]);
+    let (obj, _) = parse_ber_object(&bytes).unwrap();
+    let varbind = parse_varbind(obj).unwrap();
+
+    assert_eq!(
+        varbind.value,
+        ObjectSyntax::Tagged {
+            class: TagClass::Context,
+            number: 200,
+            constructed: false,
+            bytes: vec![0x2a],
+        }
+    );
+
+    let mut encoded = Vec::new();
+    varbind.write_to_buf(&mut encoded);
+    assert_eq!(encoded, bytes);
+}
+
+#[test]
+fn test_malformed_long_form_tag() {
+    // padding 0x80 before the tag number
+    let bytes = varbind_bytes(&[0x9f, 0x80, 0x48, 0x01, 0x2a]);
+    let (obj, _) = parse_ber_object(&bytes).unwrap();
+    assert_eq!(parse_varbind(obj), Err(BerError::MalformedTag));
+
+    // tag number never terminates
+    let bytes = varbind_bytes(&[0x9f, 0x81]);
+    let (obj, _) = parse_ber_object(&bytes).unwrap();
+    assert_eq!(parse_varbind(obj), Err(BerError::IncompleteData));
+}
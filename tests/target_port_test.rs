@@ -0,0 +1,48 @@
+use rusnmp::ber::Asn1Tag;
+use rusnmp::manager::network::split_port;
+use rusnmp::manager::{Credentials, Manager};
+use rusnmp::snmp::message::parse_message;
+use rusnmp::snmp::pdu::{ObjectSyntax, PduData};
+use tokio::net::UdpSocket;
+
+#[test]
+fn test_split_port() {
+    assert_eq!(split_port("router1").unwrap(), ("router1", None));
+    assert_eq!(split_port("router1:1161").unwrap(), ("router1", Some(1161)));
+    assert_eq!(
+        split_port("192.0.2.1:161").unwrap(),
+        ("192.0.2.1", Some(161))
+    );
+    assert_eq!(split_port("2001:db8::1").unwrap(), ("2001:db8::1", None));
+    assert_eq!(split_port("[2001:db8::1]").unwrap(), ("2001:db8::1", None));
+    assert_eq!(
+        split_port("[2001:db8::1]:1161").unwrap(),
+        ("2001:db8::1", Some(1161))
+    );
+
+    assert!(split_port("router1:snmp").is_err());
+    assert!(split_port("router1:70000").is_err());
+    assert!(split_port("[2001:db8::1").is_err());
+    assert!(split_port("[2001:db8::1]1161").is_err());
+}
+
+#[tokio::test]
+async fn test_get_from_target_port() {
+    let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let target = socket.local_addr().unwrap().to_string();
+    tokio::spawn(async move {
+        let mut buf = [0; 1500];
+        let (len, from) = socket.recv_from(&mut buf).await.unwrap();
+        let mut message = parse_message(&buf[..len]).unwrap();
+        message.pdu.tag = Asn1Tag::GetResponse;
+        message.pdu.varbinds[0].value = ObjectSyntax::TimeTicks(42);
+        assert!(matches!(message.pdu.data, PduData::Basic { .. }));
+        socket.send_to(&message.to_bytes(), from).await.unwrap();
+    });
+
+    let varbind = Manager::new()
+        .get(&target, &Credentials::v2c("public"), "1.3.6.1.2.1.1.3.0")
+        .await
+        .unwrap();
+    assert_eq!(varbind.value, ObjectSyntax::TimeTicks(42));
+}
@@ -0,0 +1,123 @@
+use std::time::Duration;
+
+use common::FakeAgent;
+use rusnmp::manager::{
+    Credentials, ErrorClass, Manager, MessageTooLargeError, Target, TimeoutError,
+};
+use rusnmp::snmp::pdu::ObjectSyntax;
+
+mod common;
+
+// answers with a `size`-byte string, or never when `size` is None
+async fn agent(size: Option<usize>) -> u16 {
+    let agent = FakeAgent::new().serve(move |message| {
+        let Some(size) = size else { return false };
+        message.pdu.varbinds[0].value = ObjectSyntax::OctetString(vec![b'x'; size]);
+        true
+    });
+    agent.await.port
+}
+
+#[tokio::test]
+async fn test_target_port_and_credentials() {
+    let target = Target::new("127.0.0.1", Credentials::v2c("public")).port(agent(Some(4)).await);
+    let manager = Manager::new().with_target(target);
+
+    let credentials = manager.credentials_for("127.0.0.1").unwrap().clone();
+    let varbind = manager
+        .get("127.0.0.1", &credentials, "1.3.6.1.2.1.1.5.0")
+        .await
+        .unwrap();
+    assert_eq!(varbind.value, ObjectSyntax::OctetString(b"xxxx".to_vec()));
+
+    // rather than go out as another version than registered
+    for other in [Credentials::v1("public"), Credentials::v2c("private")] {
+        let error = manager
+            .get("127.0.0.1", &other, "1.3.6.1.2.1.1.5.0")
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("registered"), "{}", error);
+    }
+    assert_eq!(manager.stats("127.0.0.1").unwrap().packets_sent, 1);
+
+    // other targets keep the Manager's settings
+    assert!(manager.credentials_for("127.0.0.2").is_none());
+    let error = manager
+        .get("127.0.0.2", &credentials, "1.3.6.1.2.1.1.5.0")
+        .await
+        .unwrap_err();
+    assert_eq!(ErrorClass::of(&error), ErrorClass::Refused);
+}
+
+#[tokio::test]
+async fn test_target_timeout_and_retries() {
+    let target = Target::new("127.0.0.1", Credentials::v2c("public"))
+        .port(agent(None).await)
+        .timeout(Duration::from_millis(50))
+        .retries(2);
+    let manager = Manager::builder()
+        .community("fallback")
+        .build()
+        .with_target(target);
+
+    let error = manager
+        .get(
+            "127.0.0.1",
+            &Credentials::v2c("public"),
+            "1.3.6.1.2.1.1.5.0",
+        )
+        .await
+        .unwrap_err();
+    let timeout = error.downcast_ref::<TimeoutError>().unwrap();
+    assert_eq!(timeout.after, Duration::from_millis(50));
+    assert_eq!(manager.stats("127.0.0.1").unwrap().packets_sent, 3);
+    assert!(matches!(
+        manager.credentials_for("127.0.0.3"),
+        Some(Credentials::CommunityV2c(community)) if community == "fallback"
+    ));
+}
+
+#[tokio::test]
+async fn test_target_max_message_size() {
+    let port = agent(Some(200)).await;
+    let manager = Manager::new().with_target(
+        Target::new("127.0.0.1", Credentials::v2c("public"))
+            .port(port)
+            .max_message_size(100),
+    );
+
+    let error = manager
+        .get(
+            "127.0.0.1",
+            &Credentials::v2c("public"),
+            "1.3.6.1.2.1.1.5.0",
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(ErrorClass::of(&error), ErrorClass::Parse);
+    let too_large = error.downcast_ref::<MessageTooLargeError>().unwrap();
+    assert_eq!(too_large.max_size, 100);
+}
+
+#[tokio::test]
+async fn test_manager_max_message_size() {
+    let port = agent(Some(6000)).await;
+    let credentials = Credentials::v2c("public");
+    let target = format!("127.0.0.1:{}", port);
+
+    // the default 4096 bytes would cut this response short
+    let error = Manager::new()
+        .get(&target, &credentials, "1.3.6.1.2.1.1.1.0")
+        .await
+        .unwrap_err();
+    let too_large = error.downcast_ref::<MessageTooLargeError>().unwrap();
+    assert_eq!(too_large.max_size, 4096);
+    assert!(error.to_string().contains("exceeded the max message size"));
+
+    let manager = Manager::builder().max_message_size(8192).build();
+    let varbind = manager
+        .get(&target, &credentials, "1.3.6.1.2.1.1.1.0")
+        .await
+        .unwrap();
+    assert_eq!(varbind.value, ObjectSyntax::OctetString(vec![b'x'; 6000]));
+}
@@ -0,0 +1,86 @@
+use std::net::IpAddr;
+
+use rusnmp::snmp::pdu::ObjectSyntax;
+use rusnmp::snmp::tc::{DateAndTime, MacAddress, inet_address};
+
+// 2026-10-17,13:45:30.5,+2:0
+const DATE: [u8; 11] = [0x07, 0xea, 10, 17, 13, 45, 30, 5, b'+', 2, 0];
+
+#[test]
+fn test_date_and_time() {
+    let value = ObjectSyntax::OctetString(DATE.to_vec())
+        .as_date_and_time()
+        .unwrap();
+    assert_eq!(value.year, 2026);
+    assert_eq!(value.deci_seconds, 5);
+    assert_eq!(value.utc_offset, Some(120));
+    assert_eq!(value.to_string(), "2026-10-17,13:45:30.5,+2:0");
+    assert_eq!(value.to_bytes(), DATE);
+
+    let local = DateAndTime::from_bytes(&DATE[..8]).unwrap();
+    assert_eq!(local.utc_offset, None);
+    assert_eq!(local.to_string(), "2026-10-17,13:45:30.5");
+
+    // month 13, an unknown direction, and a truncated value
+    let mut bad = DATE;
+    bad[2] = 13;
+    assert_eq!(DateAndTime::from_bytes(&bad), None);
+    let mut bad = DATE;
+    bad[8] = b'x';
+    assert_eq!(DateAndTime::from_bytes(&bad), None);
+    assert_eq!(DateAndTime::from_bytes(&DATE[..9]), None);
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn test_date_and_time_to_chrono() {
+    let value = DateAndTime::from_bytes(&DATE).unwrap().to_chrono().unwrap();
+    // 11:45:30.5 UTC
+    assert_eq!(value.timestamp(), 1_792_237_530);
+    assert_eq!(value.timestamp_subsec_millis(), 500);
+    assert_eq!(value.offset().local_minus_utc(), 7200);
+
+    let mut february = DATE;
+    february[2..4].copy_from_slice(&[2, 30]);
+    let february = DateAndTime::from_bytes(&february).unwrap();
+    assert_eq!(february.to_chrono(), None);
+}
+
+#[test]
+fn test_mac_address() {
+    let value = ObjectSyntax::OctetString(vec![0x00, 0x0c, 0x29, 0xa8, 0x8b, 0xa2]);
+    let mac = value.as_mac_address().unwrap();
+    assert_eq!(mac, MacAddress([0x00, 0x0c, 0x29, 0xa8, 0x8b, 0xa2]));
+    assert_eq!(mac.to_string(), "00:0c:29:a8:8b:a2");
+    assert_eq!(ObjectSyntax::OctetString(vec![0; 5]).as_mac_address(), None);
+}
+
+#[test]
+fn test_truth_value() {
+    assert_eq!(ObjectSyntax::Integer(1).as_truth_value(), Some(true));
+    assert_eq!(ObjectSyntax::Integer(2).as_truth_value(), Some(false));
+    assert_eq!(ObjectSyntax::Integer(0).as_truth_value(), None);
+    assert_eq!(ObjectSyntax::Gauge32(1).as_truth_value(), None);
+}
+
+#[test]
+fn test_inet_address() {
+    let ipv4 = ObjectSyntax::Integer(1);
+    let address = ObjectSyntax::OctetString(vec![192, 0, 2, 1]);
+    assert_eq!(
+        inet_address(&ipv4, &address),
+        Some(IpAddr::from([192, 0, 2, 1]))
+    );
+
+    // ipv6z, with the zone index after the address
+    let mut bytes = vec![0xfe, 0x80];
+    bytes.extend([0; 13]);
+    bytes.extend([1, 0, 0, 0, 3]);
+    let ipv6z = inet_address(&ObjectSyntax::Integer(4), &ObjectSyntax::OctetString(bytes));
+    assert_eq!(ipv6z, Some("fe80::1".parse().unwrap()));
+
+    // dns(16), and an ipv6 type with an ipv4 length
+    let name = ObjectSyntax::OctetString(b"host".to_vec());
+    assert_eq!(inet_address(&ObjectSyntax::Integer(16), &name), None);
+    assert_eq!(inet_address(&ObjectSyntax::Integer(2), &address), None);
+}
@@ -0,0 +1,260 @@
+#![cfg(feature = "tls")]
+// The certificates in tests/tls come from a throwaway CA: agent.der names
+// IP 127.0.0.1 for serverAuth, manager.der is for clientAuth. Both keys
+// are PKCS#8, everything is DER and valid until 2126.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use rusnmp::ber::Asn1Tag;
+use rusnmp::manager::{
+    Credentials, ErrorClass, Fingerprint, FingerprintAlgorithm, Manager, TlsTcpTransport,
+};
+use rusnmp::snmp::message::parse_message;
+use rusnmp::snmp::pdu::{ErrorStatus, ObjectSyntax, PduData};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio_rustls::TlsAcceptor;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::rustls::server::WebPkiClientVerifier;
+use tokio_rustls::rustls::{RootCertStore, ServerConfig};
+
+const CA: &[u8] = include_bytes!("tls/ca.der");
+const AGENT_CERT: &[u8] = include_bytes!("tls/agent.der");
+const AGENT_KEY: &[u8] = include_bytes!("tls/agent.key.der");
+const MANAGER_CERT: &[u8] = include_bytes!("tls/manager.der");
+const MANAGER_KEY: &[u8] = include_bytes!("tls/manager.key.der");
+const AGENT_SHA256: &str = "SHA256:48:A1:05:3C:33:28:22:B1:26:89:1F:DE:0C:59:38:9F:DB:07:5A:93:1D:B9:2E:20:FA:59:96:77:6E:BD:A0:9F";
+
+fn server_config(client_auth: bool) -> ServerConfig {
+    let builder = ServerConfig::builder();
+    let builder = if client_auth {
+        let mut roots = RootCertStore::empty();
+        roots.add(CertificateDer::from(CA)).unwrap();
+        builder.with_client_cert_verifier(
+            WebPkiClientVerifier::builder(Arc::new(roots))
+                .build()
+                .unwrap(),
+        )
+    } else {
+        builder.with_no_client_auth()
+    };
+    builder
+        .with_single_cert(
+            vec![CertificateDer::from(AGENT_CERT)],
+            PrivateKeyDer::try_from(AGENT_KEY).unwrap(),
+        )
+        .unwrap()
+}
+
+// answers every varbind with its last arc; counts connections accepted
+async fn agent(client_auth: bool, connections: Arc<AtomicUsize>) -> String {
+    serve(client_auth, connections, false).await
+}
+
+// as `agent`, with the answers in indefinite-length form if asked
+async fn serve(client_auth: bool, connections: Arc<AtomicUsize>, indefinite: bool) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let target = listener.local_addr().unwrap().to_string();
+    let acceptor = TlsAcceptor::from(Arc::new(server_config(client_auth)));
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            connections.fetch_add(1, Ordering::SeqCst);
+            let acceptor = acceptor.clone();
+            tokio::spawn(async move {
+                let Ok(mut stream) = acceptor.accept(stream).await else {
+                    return;
+                };
+                // requests here are short enough for a one-byte length
+                let mut header = [0; 2];
+                while stream.read_exact(&mut header).await.is_ok() {
+                    let mut packet = header.to_vec();
+                    packet.resize(2 + header[1] as usize, 0);
+                    stream.read_exact(&mut packet[2..]).await.unwrap();
+
+                    let mut message = parse_message(&packet).unwrap();
+                    message.pdu.tag = Asn1Tag::GetResponse;
+                    message.pdu.data = PduData::Basic {
+                        error_status: ErrorStatus::NoError,
+                        error_index: 0,
+                    };
+                    for varbind in &mut message.pdu.varbinds {
+                        varbind.value = ObjectSyntax::Integer(*varbind.oid.last().unwrap() as i32);
+                    }
+                    let mut answer = message.to_bytes();
+                    if indefinite {
+                        answer = indefinite_form(&answer);
+                    }
+                    stream.write_all(&answer).await.unwrap();
+                }
+            });
+        }
+    });
+    target
+}
+
+// re-frames a whole message's outer SEQUENCE with an indefinite length
+fn indefinite_form(message: &[u8]) -> Vec<u8> {
+    let header = match message[1] {
+        0..0x80 => 2,
+        long => 2 + (long & 0x7f) as usize,
+    };
+    let mut reframed = vec![message[0], 0x80];
+    reframed.extend_from_slice(&message[header..]);
+    reframed.extend_from_slice(&[0, 0]);
+    reframed
+}
+
+#[tokio::test]
+async fn test_get_over_tls_with_pinned_fingerprint() {
+    let connections = Arc::new(AtomicUsize::new(0));
+    let target = agent(false, connections.clone()).await;
+    let transport = TlsTcpTransport::builder()
+        .fingerprint(AGENT_SHA256.parse().unwrap())
+        .build()
+        .unwrap();
+    let manager = Manager::builder().transport(transport).build();
+    let credentials = Credentials::v2c("public");
+
+    for oid in ["1.3.6.1.2.1.1.7.0", "1.3.6.1.2.1.1.3.0"] {
+        let varbind = manager.get(&target, &credentials, oid).await.unwrap();
+        assert_eq!(varbind.value, ObjectSyntax::Integer(0));
+    }
+    // the second request reused the first one's connection
+    assert_eq!(connections.load(Ordering::SeqCst), 1);
+    assert_eq!(manager.stats(&target).unwrap().packets_received, 2);
+}
+
+#[tokio::test]
+async fn test_wrong_fingerprint_is_rejected() {
+    let target = agent(false, Arc::new(AtomicUsize::new(0))).await;
+    let transport = TlsTcpTransport::builder()
+        .fingerprint(Fingerprint::of(FingerprintAlgorithm::Sha256, CA))
+        .build()
+        .unwrap();
+    let manager = Manager::builder().transport(transport).build();
+
+    let error = manager
+        .get(&target, &Credentials::v2c("public"), "1.3.6.1.2.1.1.5.0")
+        .await
+        .unwrap_err();
+    assert_eq!(ErrorClass::of(&error), ErrorClass::Other);
+    assert!(format!("{:#}", error).contains("TLS handshake"));
+}
+
+#[tokio::test]
+async fn test_trusted_ca_and_client_certificate() {
+    let target = agent(true, Arc::new(AtomicUsize::new(0))).await;
+    let transport = TlsTcpTransport::builder()
+        .trust_certificate(CA)
+        .client_certificate(vec![MANAGER_CERT.to_vec()], MANAGER_KEY.to_vec())
+        .build()
+        .unwrap();
+    let manager = Manager::builder().transport(transport).build();
+
+    let varbinds = manager
+        .get_multi(
+            &target,
+            &Credentials::v2c("public"),
+            &["1.3.6.1.2.1.2.2.1.2.4", "1.3.6.1.2.1.2.2.1.2.6"],
+        )
+        .await
+        .unwrap();
+    assert_eq!(varbinds[1].value, ObjectSyntax::Integer(6));
+}
+
+#[tokio::test]
+async fn test_refused_connection() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let target = listener.local_addr().unwrap().to_string();
+    drop(listener);
+    let transport = TlsTcpTransport::builder()
+        .trust_certificate(CA)
+        .build()
+        .unwrap();
+    let manager = Manager::builder().transport(transport).build();
+
+    let error = manager
+        .get(&target, &Credentials::v2c("public"), "1.3.6.1.2.1.1.5.0")
+        .await
+        .unwrap_err();
+    assert_eq!(ErrorClass::of(&error), ErrorClass::Refused);
+}
+
+#[tokio::test]
+async fn test_indefinite_length_answers() {
+    let connections = Arc::new(AtomicUsize::new(0));
+    let target = serve(false, connections.clone(), true).await;
+    let transport = TlsTcpTransport::builder()
+        .trust_certificate(CA)
+        .build()
+        .unwrap();
+    let manager = Manager::builder().transport(transport).build();
+    let credentials = Credentials::v2c("public");
+
+    for oid in ["1.3.6.1.2.1.1.7.0", "1.3.6.1.2.1.1.9.0"] {
+        let varbind = manager.get(&target, &credentials, oid).await.unwrap();
+        assert_eq!(varbind.value, ObjectSyntax::Integer(0));
+    }
+    assert_eq!(connections.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn test_trap_send_uses_the_configured_timeout() {
+    // accepts connections but never completes a handshake
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let mut held = Vec::new();
+        while let Ok((stream, _)) = listener.accept().await {
+            held.push(stream);
+        }
+    });
+    let transport = TlsTcpTransport::builder()
+        .trust_certificate(CA)
+        .build()
+        .unwrap();
+    let manager = Manager::builder()
+        .transport(transport)
+        .timeout(Duration::from_millis(100))
+        .build();
+
+    let started = Instant::now();
+    let error = manager
+        .send_trap(
+            "127.0.0.1",
+            address.port(),
+            &Credentials::v2c("public"),
+            0,
+            "1.3.6.1.6.3.1.1.5.1",
+            vec![],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(ErrorClass::of(&error), ErrorClass::Timeout);
+    assert!(started.elapsed() < Duration::from_secs(2));
+}
+
+#[test]
+fn test_fingerprint_forms() {
+    let named: Fingerprint = AGENT_SHA256.parse().unwrap();
+    assert!(named.matches(AGENT_CERT));
+    assert_eq!(named.to_string(), AGENT_SHA256);
+
+    let bare = AGENT_SHA256.trim_start_matches("SHA256:").replace(':', "");
+    assert_eq!(bare.parse::<Fingerprint>().unwrap(), named);
+    // RFC 5592: hash algorithm 4 is SHA-256
+    let tagged = format!("04:{}", AGENT_SHA256.trim_start_matches("SHA256:"));
+    assert_eq!(tagged.parse::<Fingerprint>().unwrap(), named);
+
+    let sha1 = Fingerprint::of(FingerprintAlgorithm::Sha1, AGENT_CERT);
+    assert_eq!(sha1.to_string().parse::<Fingerprint>().unwrap(), sha1);
+    assert_eq!(sha1.digest.len(), 20);
+
+    assert!("SHA256:AB:CD".parse::<Fingerprint>().is_err());
+    assert!("not hex".parse::<Fingerprint>().is_err());
+    assert!("+1".parse::<Fingerprint>().is_err());
+    assert!(TlsTcpTransport::builder().build().is_err());
+}
@@ -0,0 +1,108 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use common::FakeAgent;
+use rusnmp::manager::{Credentials, Manager, SnmpError};
+use rusnmp::snmp::pdu::{ErrorStatus, ObjectSyntax, PduData, VarBind};
+
+mod common;
+
+// answers tooBig for more than two varbinds in a Get or GetNext, or more
+// than three repetitions in a GetBulk. OIDs ending in 0 don't exist.
+async fn agent(requests: Arc<AtomicUsize>) -> String {
+    let agent = FakeAgent::new().serve(move |message| {
+        requests.fetch_add(1, Ordering::SeqCst);
+        let asked: Vec<Vec<u64>> = message.pdu.varbinds.iter().map(|v| v.oid.clone()).collect();
+        let (too_big, rows) = match message.pdu.data {
+            PduData::Bulk {
+                max_repititions, ..
+            } => (max_repititions > 3, max_repititions as u64),
+            _ => (asked.len() > 2, 0),
+        };
+        let missing = asked.iter().position(|oid| oid.last() == Some(&0));
+
+        let (error_status, error_index) = match missing {
+            _ if too_big => (ErrorStatus::TooBig, 0),
+            Some(position) => (ErrorStatus::NoSuchName, position as i32 + 1),
+            None => (ErrorStatus::NoError, 0),
+        };
+        message.pdu.data = PduData::Basic {
+            error_status,
+            error_index,
+        };
+        if error_status == ErrorStatus::NoError && rows > 0 {
+            let base = &asked[0];
+            message.pdu.varbinds = (1..=rows)
+                .map(|row| VarBind {
+                    oid: [&base[..], &[row]].concat(),
+                    value: ObjectSyntax::Integer(row as i32),
+                })
+                .collect();
+        } else if error_status == ErrorStatus::NoError {
+            for varbind in &mut message.pdu.varbinds {
+                varbind.value = ObjectSyntax::Integer(*varbind.oid.last().unwrap() as i32);
+            }
+        }
+        true
+    });
+    agent.await.target
+}
+
+#[tokio::test]
+async fn test_get_multi_splits_on_too_big() {
+    let requests = Arc::new(AtomicUsize::new(0));
+    let target = agent(Arc::clone(&requests)).await;
+    let manager = Manager::new();
+    let community = Credentials::v2c("public");
+
+    let oids = [
+        "1.3.6.1.9.1",
+        "1.3.6.1.9.2",
+        "1.3.6.1.9.3",
+        "1.3.6.1.9.4",
+        "1.3.6.1.9.5",
+    ];
+    let values: Vec<_> = manager
+        .get_multi(&target, &community, &oids)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|varbind| varbind.value)
+        .collect();
+    assert_eq!(
+        values,
+        (1..=5).map(ObjectSyntax::Integer).collect::<Vec<_>>()
+    );
+    // 5 -> tooBig, 3 -> tooBig, then 2 + 2 + 1
+    assert_eq!(requests.load(Ordering::SeqCst), 5);
+
+    let successors = manager.get_next(&target, &community, &oids).await.unwrap();
+    assert_eq!(successors.len(), 5);
+}
+
+#[tokio::test]
+async fn test_split_error_index_counts_from_start() {
+    let target = agent(Arc::default()).await;
+    let error = Manager::new()
+        .get_multi(
+            &target,
+            &Credentials::v2c("public"),
+            &["1.3.6.1.9.1", "1.3.6.1.9.2", "1.3.6.1.9.3", "1.3.6.1.9.0"],
+        )
+        .await
+        .unwrap_err();
+    let error = error.downcast_ref::<SnmpError>().unwrap();
+    assert_eq!(error.status, ErrorStatus::NoSuchName);
+    assert_eq!(error.index, 4);
+}
+
+#[tokio::test]
+async fn test_get_bulk_lowers_repetitions() {
+    let target = agent(Arc::default()).await;
+    let rows = Manager::new()
+        .get_bulk(&target, &Credentials::v2c("public"), 0, 12, &["1.3.6.1.9"])
+        .await
+        .unwrap();
+    // 12 -> 6 -> 3
+    assert_eq!(rows.len(), 3);
+}
@@ -0,0 +1,169 @@
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use std::time::Duration;
+
+use anyhow::Result;
+use futures::FutureExt;
+use futures::future::BoxFuture;
+use rusnmp::ber::Asn1Tag;
+use rusnmp::manager::{Credentials, ErrorClass, Manager, TimeoutError, Transport};
+use rusnmp::snmp::message::parse_message;
+use rusnmp::snmp::pdu::{ErrorStatus, ObjectSyntax, PduData};
+
+// an agent in memory: every varbind comes back as an Integer of its last arc
+struct MemoryAgent;
+
+impl Transport for MemoryAgent {
+    fn send_recv<'a>(
+        &'a self,
+        _address: SocketAddr,
+        packet: &'a [u8],
+        _wait: Duration,
+        _max_size: usize,
+        accept: &'a (dyn Fn(&[u8]) -> bool + Send + Sync),
+    ) -> BoxFuture<'a, Result<Vec<u8>>> {
+        let mut message = parse_message(packet).unwrap();
+        message.pdu.tag = Asn1Tag::GetResponse;
+        message.pdu.data = PduData::Basic {
+            error_status: ErrorStatus::NoError,
+            error_index: 0,
+        };
+        for varbind in &mut message.pdu.varbinds {
+            varbind.value = ObjectSyntax::Integer(*varbind.oid.last().unwrap() as i32);
+        }
+        let response = message.to_bytes();
+        assert!(accept(&response));
+        async move { Ok(response) }.boxed()
+    }
+
+    fn send<'a>(
+        &'a self,
+        _address: SocketAddr,
+        _packet: &'a [u8],
+        _wait: Duration,
+    ) -> BoxFuture<'a, Result<()>> {
+        async { Ok(()) }.boxed()
+    }
+}
+
+// loses the first `drop` requests, then hands over to the agent
+struct Lossy {
+    agent: MemoryAgent,
+    drop: usize,
+    seen: AtomicUsize,
+}
+
+impl Transport for Lossy {
+    fn send_recv<'a>(
+        &'a self,
+        address: SocketAddr,
+        packet: &'a [u8],
+        wait: Duration,
+        max_size: usize,
+        accept: &'a (dyn Fn(&[u8]) -> bool + Send + Sync),
+    ) -> BoxFuture<'a, Result<Vec<u8>>> {
+        if self.seen.fetch_add(1, Ordering::SeqCst) < self.drop {
+            return async move {
+                Err(TimeoutError {
+                    address,
+                    after: wait,
+                }
+                .into())
+            }
+            .boxed();
+        }
+        self.agent
+            .send_recv(address, packet, wait, max_size, accept)
+    }
+
+    fn send<'a>(
+        &'a self,
+        address: SocketAddr,
+        packet: &'a [u8],
+        wait: Duration,
+    ) -> BoxFuture<'a, Result<()>> {
+        self.agent.send(address, packet, wait)
+    }
+}
+
+#[tokio::test]
+async fn test_requests_go_through_the_transport() {
+    let manager = Manager::builder().transport(MemoryAgent).build();
+    let credentials = Credentials::v2c("public");
+
+    let varbind = manager
+        .get("192.0.2.1", &credentials, "1.3.6.1.2.1.1.7.0")
+        .await
+        .unwrap();
+    assert_eq!(varbind.value, ObjectSyntax::Integer(0));
+    let varbinds = manager
+        .get_multi(
+            "192.0.2.1:1161",
+            &credentials,
+            &["1.3.6.1.2.1.2.1", "1.3.6.1.2.1.2.9"],
+        )
+        .await
+        .unwrap();
+    assert_eq!(varbinds[1].value, ObjectSyntax::Integer(9));
+    assert_eq!(manager.stats("192.0.2.1").unwrap().packets_received, 1);
+}
+
+#[tokio::test]
+async fn test_transport_faults_are_retried() {
+    let lossy = Lossy {
+        agent: MemoryAgent,
+        drop: 2,
+        seen: AtomicUsize::new(0),
+    };
+    let manager = Manager::builder()
+        .transport(lossy)
+        .timeout(Duration::from_millis(50))
+        .retries(2)
+        .build();
+
+    let varbind = manager
+        .get(
+            "192.0.2.1",
+            &Credentials::v2c("public"),
+            "1.3.6.1.2.1.1.3.0",
+        )
+        .await
+        .unwrap();
+    assert_eq!(varbind.value, ObjectSyntax::Integer(0));
+    let stats = manager.stats("192.0.2.1").unwrap();
+    assert_eq!(stats.retransmits, 2);
+    assert_eq!(stats.timeouts, 2);
+}
+
+#[tokio::test]
+async fn test_transport_timeout_is_a_timeout() {
+    let lossy = Lossy {
+        agent: MemoryAgent,
+        drop: usize::MAX,
+        seen: AtomicUsize::new(0),
+    };
+    let manager = Manager::builder().transport(lossy).build();
+    let error = manager
+        .get(
+            "192.0.2.1",
+            &Credentials::v2c("public"),
+            "1.3.6.1.2.1.1.3.0",
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(ErrorClass::of(&error), ErrorClass::Timeout);
+}
+
+#[tokio::test]
+async fn test_session_uses_the_transport() {
+    let session = Manager::builder()
+        .transport(MemoryAgent)
+        .community("public")
+        .session("192.0.2.1")
+        .await
+        .unwrap();
+    let varbind = session.get("1.3.6.1.2.1.1.5.0").await.unwrap();
+    assert_eq!(varbind.value, ObjectSyntax::Integer(0));
+    assert_eq!(session.stats().packets_sent, 1);
+}
@@ -0,0 +1,67 @@
+use rusnmp::ber::Asn1Tag;
+use rusnmp::snmp::message::{SnmpMessage, parse_message};
+use rusnmp::snmp::pdu::{GenericTrap, ObjectSyntax, Pdu, PduData, VarBind};
+
+#[test]
+fn test_v1_trap_round_trip() {
+    let message = SnmpMessage {
+        version: 0,
+        community: b"public".to_vec(),
+        pdu: Pdu {
+            tag: Asn1Tag::Trap,
+            request_id: 0,
+            data: PduData::Trap {
+                enterprise: vec![1, 3, 6, 1, 4, 1, 8072],
+                agent_addr: [192, 0, 2, 1],
+                generic_trap: GenericTrap::LinkDown,
+                specific_trap: 0,
+                time_stamp: 12345,
+            },
+            varbinds: vec![VarBind {
+                oid: vec![1, 3, 6, 1, 2, 1, 2, 2, 1, 1, 2],
+                value: ObjectSyntax::Integer(2),
+            }],
+        },
+    };
+    let bytes = message.to_bytes();
+
+    // version 0, "public", then the Trap-PDU starting with the enterprise
+    // OID where other PDUs have their request-id
+    assert_eq!(
+        bytes[2..13],
+        [
+            0x02, 0x01, 0x00, 0x04, 0x06, b'p', b'u', b'b', b'l', b'i', b'c'
+        ]
+    );
+    assert_eq!(bytes[13], 0xA4);
+    assert_eq!(bytes[15], 0x06);
+
+    assert_eq!(parse_message(&bytes).unwrap(), message);
+}
+
+#[test]
+fn test_v1_trap_rejects_bad_generic_trap() {
+    let mut bytes = SnmpMessage {
+        version: 0,
+        community: b"public".to_vec(),
+        pdu: Pdu {
+            tag: Asn1Tag::Trap,
+            request_id: 0,
+            data: PduData::Trap {
+                enterprise: vec![1, 3, 6, 1],
+                agent_addr: [0; 4],
+                generic_trap: GenericTrap::ColdStart,
+                specific_trap: 0,
+                time_stamp: 0,
+            },
+            varbinds: Vec::new(),
+        },
+    }
+    .to_bytes();
+
+    // generic-trap sits after the 5 byte OID and the 6 byte IpAddress
+    let generic_value = 15 + 5 + 6 + 2;
+    assert_eq!(bytes[generic_value], 0);
+    bytes[generic_value] = 9;
+    assert!(parse_message(&bytes).is_err());
+}
@@ -0,0 +1,221 @@
+#![cfg(feature = "v3")]
+
+use rusnmp::ber::Asn1Tag;
+use rusnmp::snmp::message::{
+    FLAG_AUTH, FLAG_PRIV, FLAG_REPORTABLE, HeaderData, SECURITY_MODEL_USM, ScopedPdu,
+    ScopedPduData, SnmpV3Message, parse_v3_message, peek_version,
+};
+use rusnmp::snmp::pdu::{ErrorStatus, ObjectSyntax, Pdu, PduData, VarBind};
+use rusnmp::snmp::usm::{
+    AuthProtocol, PrivProtocol, UsmError, UsmSecurityParameters, UsmUser, authenticate_message,
+    decrypt_scoped_pdu, encrypt_scoped_pdu, verify_message,
+};
+
+// RFC 3414 A.3
+const ENGINE_ID: &[u8] = &[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2];
+const PASSWORD: &[u8] = b"maplesyrup";
+
+#[test]
+fn test_md5_key_localization() {
+    let ku = AuthProtocol::Md5.password_to_key(PASSWORD).unwrap();
+    assert_eq!(
+        ku,
+        [
+            0x9f, 0xaf, 0x32, 0x83, 0x88, 0x4e, 0x92, 0x83, 0x4e, 0xbc, 0x98, 0x47, 0xd8, 0xed,
+            0xd9, 0x63
+        ]
+    );
+
+    let kul = AuthProtocol::Md5.localize_key(&ku, ENGINE_ID);
+    assert_eq!(
+        kul,
+        [
+            0x52, 0x6f, 0x5e, 0xed, 0x9f, 0xcc, 0xe2, 0x6f, 0x89, 0x64, 0xc2, 0x93, 0x07, 0x87,
+            0xd8, 0x2b
+        ]
+    );
+}
+
+#[test]
+fn test_sha1_key_localization() {
+    let ku = AuthProtocol::Sha1.password_to_key(PASSWORD).unwrap();
+    assert_eq!(
+        ku,
+        [
+            0x9f, 0xb5, 0xcc, 0x03, 0x81, 0x49, 0x7b, 0x37, 0x93, 0x52, 0x89, 0x39, 0xff, 0x78,
+            0x8d, 0x5d, 0x79, 0x14, 0x52, 0x11
+        ]
+    );
+
+    let kul = AuthProtocol::Sha1.localize_key(&ku, ENGINE_ID);
+    assert_eq!(
+        kul,
+        [
+            0x66, 0x95, 0xfe, 0xbc, 0x92, 0x88, 0xe3, 0x62, 0x82, 0x23, 0x5f, 0xc7, 0x15, 0x1f,
+            0x12, 0x84, 0x97, 0xb3, 0x8f, 0x3f
+        ]
+    );
+}
+
+#[test]
+fn test_sha2_key_localization() {
+    let ku = AuthProtocol::Sha256.password_to_key(PASSWORD).unwrap();
+    let kul = AuthProtocol::Sha256.localize_key(&ku, ENGINE_ID);
+    assert_eq!(
+        kul,
+        [
+            0x89, 0x82, 0xe0, 0xe5, 0x49, 0xe8, 0x66, 0xdb, 0x36, 0x1a, 0x6b, 0x62, 0x5d, 0x84,
+            0xcc, 0xcc, 0x11, 0x16, 0x2d, 0x45, 0x3e, 0xe8, 0xce, 0x3a, 0x64, 0x45, 0xc2, 0xd6,
+            0x77, 0x6f, 0x0f, 0x8b
+        ]
+    );
+
+    let mut message = sample_message(FLAG_AUTH | FLAG_REPORTABLE);
+    let bytes = authenticate_message(&mut message, AuthProtocol::Sha256, &kul);
+    assert_eq!(message.security_params.auth_params.len(), 24);
+    assert!(verify_message(&bytes, AuthProtocol::Sha256, &kul).is_ok());
+}
+
+#[test]
+fn test_short_password_rejected() {
+    assert_eq!(
+        AuthProtocol::Md5.password_to_key(b"short"),
+        Err(UsmError::PasswordTooShort { min: 8 })
+    );
+}
+
+fn sample_message(flags: u8) -> SnmpV3Message {
+    SnmpV3Message {
+        header: HeaderData {
+            msg_id: 42,
+            max_size: 4096,
+            flags,
+            security_model: SECURITY_MODEL_USM,
+        },
+        security_params: UsmSecurityParameters {
+            authoritative_engine_id: ENGINE_ID.to_vec(),
+            engine_boots: 3,
+            engine_time: 1200,
+            user_name: b"operator".to_vec(),
+            ..Default::default()
+        },
+        data: ScopedPduData::Plaintext(ScopedPdu {
+            context_engine_id: ENGINE_ID.to_vec(),
+            context_name: Vec::new(),
+            pdu: Pdu {
+                tag: Asn1Tag::GetRequest,
+                request_id: 42,
+                data: PduData::Basic {
+                    error_status: ErrorStatus::NoError,
+                    error_index: 0,
+                },
+                varbinds: vec![VarBind {
+                    oid: vec![1, 3, 6, 1, 2, 1, 1, 5, 0],
+                    value: ObjectSyntax::Null,
+                }],
+            },
+        }),
+    }
+}
+
+#[test]
+fn test_authenticated_v3_round_trip() {
+    let mut message = sample_message(FLAG_AUTH | FLAG_REPORTABLE);
+
+    let ku = AuthProtocol::Sha1.password_to_key(PASSWORD).unwrap();
+    let kul = AuthProtocol::Sha1.localize_key(&ku, ENGINE_ID);
+    let bytes = authenticate_message(&mut message, AuthProtocol::Sha1, &kul);
+
+    assert_eq!(peek_version(&bytes).unwrap(), 3);
+    assert_eq!(parse_v3_message(&bytes).unwrap(), message);
+    assert!(verify_message(&bytes, AuthProtocol::Sha1, &kul).is_ok());
+
+    // flip one bit in the varbind OID, the digest must no longer match
+    let mut tampered = bytes.clone();
+    let last = tampered.len() - 3;
+    tampered[last] ^= 0x01;
+    assert_eq!(
+        verify_message(&tampered, AuthProtocol::Sha1, &kul),
+        Err(UsmError::WrongDigest)
+    );
+}
+
+#[test]
+fn test_privacy_requires_auth() {
+    let result = UsmUser::new("operator").with_privacy(PrivProtocol::Des, PASSWORD);
+    assert!(matches!(result, Err(UsmError::PrivacyWithoutAuth)));
+}
+
+#[test]
+fn test_encrypted_v3_round_trip() {
+    let user = UsmUser::new("operator")
+        .with_auth(AuthProtocol::Md5, PASSWORD)
+        .unwrap();
+    let (auth_protocol, auth_key) = user.localized_auth(ENGINE_ID).unwrap();
+
+    for protocol in [
+        PrivProtocol::Des,
+        PrivProtocol::Aes128,
+        PrivProtocol::Aes192,
+        PrivProtocol::Aes256Reeder,
+    ] {
+        let user = user
+            .clone()
+            .with_privacy(protocol, b"privpassword")
+            .unwrap();
+        let (priv_protocol, priv_key) = user.localized_priv(ENGINE_ID).unwrap();
+
+        let original = sample_message(FLAG_AUTH | FLAG_PRIV | FLAG_REPORTABLE);
+        let mut message = original.clone();
+        encrypt_scoped_pdu(
+            &mut message,
+            priv_protocol,
+            &priv_key,
+            0x0102_0304_0506_0708,
+        );
+        assert!(matches!(message.data, ScopedPduData::Encrypted(_)));
+        assert_eq!(message.security_params.priv_params.len(), 8);
+
+        let bytes = authenticate_message(&mut message, auth_protocol, &auth_key);
+        assert!(verify_message(&bytes, auth_protocol, &auth_key).is_ok());
+
+        let mut received = parse_v3_message(&bytes).unwrap();
+        assert!(received.scoped_pdu().is_none());
+        decrypt_scoped_pdu(&mut received, priv_protocol, &priv_key).unwrap();
+        assert_eq!(received.scoped_pdu(), original.scoped_pdu());
+    }
+}
+
+#[test]
+fn test_aes256_key_extension_variants() {
+    let user = UsmUser::new("operator")
+        .with_auth(AuthProtocol::Sha1, PASSWORD)
+        .unwrap();
+
+    let blumenthal = user
+        .clone()
+        .with_privacy(PrivProtocol::Aes256, PASSWORD)
+        .unwrap();
+    let (_, key) = blumenthal.localized_priv(ENGINE_ID).unwrap();
+    assert_eq!(
+        key[..32],
+        [
+            0x66, 0x95, 0xfe, 0xbc, 0x92, 0x88, 0xe3, 0x62, 0x82, 0x23, 0x5f, 0xc7, 0x15, 0x1f,
+            0x12, 0x84, 0x97, 0xb3, 0x8f, 0x3f, 0x50, 0x5e, 0x07, 0xeb, 0x9a, 0xf2, 0x55, 0x68,
+            0xfa, 0x1f, 0x5d, 0xbe
+        ]
+    );
+
+    let reeder = user
+        .with_privacy(PrivProtocol::Aes256Reeder, PASSWORD)
+        .unwrap();
+    let (_, key) = reeder.localized_priv(ENGINE_ID).unwrap();
+    assert_eq!(
+        key[..32],
+        [
+            0x66, 0x95, 0xfe, 0xbc, 0x92, 0x88, 0xe3, 0x62, 0x82, 0x23, 0x5f, 0xc7, 0x15, 0x1f,
+            0x12, 0x84, 0x97, 0xb3, 0x8f, 0x3f, 0x9b, 0x8b, 0x6d, 0x78, 0x93, 0x6b, 0xa6, 0xe7,
+            0xd1, 0x9d, 0xfd, 0x9c
+        ]
+    );
+}
@@ -0,0 +1,172 @@
+use rusnmp::agent::{AccessKind, Agent, SecurityLevel, SecurityModel, Vacm, VacmError};
+use rusnmp::ber::Asn1Tag;
+use rusnmp::snmp::message::{SnmpMessage, parse_message};
+use rusnmp::snmp::pdu::{ErrorStatus, ObjectSyntax, Pdu, PduData, VarBind};
+
+mod common;
+use common::Values;
+
+const SYSTEM: [u64; 7] = [1, 3, 6, 1, 2, 1, 1];
+const SECRETS: [u64; 7] = [1, 3, 6, 1, 4, 1, 99];
+
+// "public" reads all but the secrets, "private" reads everything and
+// writes the system group
+fn vacm() -> Vacm {
+    Vacm::new()
+        .group(SecurityModel::V2c, "public", "readers")
+        .group(SecurityModel::V1, "public", "readers")
+        .group(SecurityModel::V2c, "private", "admins")
+        .include("all", "1")
+        .unwrap()
+        .include("public", "1.3.6.1")
+        .unwrap()
+        .exclude("public", "1.3.6.1.4.1.99")
+        .unwrap()
+        .include("system", "1.3.6.1.2.1.1")
+        .unwrap()
+        .access("readers", SecurityLevel::NoAuthNoPriv, "public", "")
+        .access("admins", SecurityLevel::NoAuthNoPriv, "all", "system")
+}
+
+// writable Integers at `subtree.arc.0`
+fn values(subtree: &[u64], arcs: &[u64]) -> Values {
+    let values: Vec<_> = arcs
+        .iter()
+        .map(|&arc| (arc, ObjectSyntax::Integer(1)))
+        .collect();
+    Values::new(subtree, &values).writable()
+}
+
+fn agent() -> Agent {
+    let agent = Agent::builder().vacm(vacm()).build();
+    agent
+        .register("1.3.6.1.2.1.1", values(&SYSTEM, &[1, 5]))
+        .unwrap();
+    agent
+        .register("1.3.6.1.4.1.99", values(&SECRETS, &[1, 2]))
+        .unwrap();
+    agent
+}
+
+fn request(agent: &Agent, version: i32, community: &str, tag: Asn1Tag, oid: &[u64]) -> Option<Pdu> {
+    let request = SnmpMessage {
+        version,
+        community: community.as_bytes().to_vec(),
+        pdu: Pdu {
+            tag,
+            request_id: 1,
+            data: PduData::Basic {
+                error_status: ErrorStatus::NoError,
+                error_index: 0,
+            },
+            varbinds: vec![VarBind {
+                oid: oid.to_vec(),
+                value: ObjectSyntax::Integer(2),
+            }],
+        },
+    };
+    let response = agent.handle(&request.to_bytes())?;
+    Some(parse_message(&response).unwrap().pdu)
+}
+
+fn error(pdu: &Pdu) -> (ErrorStatus, i32) {
+    match pdu.data {
+        PduData::Basic {
+            error_status,
+            error_index,
+        } => (error_status, error_index),
+        _ => panic!("not a response"),
+    }
+}
+
+#[test]
+fn test_view_families() {
+    let vacm = Vacm::new()
+        .group(SecurityModel::Usm, "ops", "ops")
+        .include("ifs", "1.3.6.1.2.1.2.2.1.*.3")
+        .unwrap()
+        .exclude("ifs", "1.3.6.1.2.1.2.2.1.7.3")
+        .unwrap()
+        .access("ops", SecurityLevel::AuthNoPriv, "ifs", "");
+    let allowed = |level, kind, oid: &[u64]| {
+        vacm.is_access_allowed(SecurityModel::Usm, b"ops", level, kind, oid)
+    };
+    let auth = SecurityLevel::AuthPriv;
+
+    // any column of interface 3, but not ifAdminStatus
+    assert_eq!(
+        allowed(auth, AccessKind::Read, &[1, 3, 6, 1, 2, 1, 2, 2, 1, 2, 3]),
+        Ok(())
+    );
+    assert_eq!(
+        allowed(auth, AccessKind::Read, &[1, 3, 6, 1, 2, 1, 2, 2, 1, 2, 4]),
+        Err(VacmError::NotInView)
+    );
+    assert_eq!(
+        allowed(auth, AccessKind::Read, &[1, 3, 6, 1, 2, 1, 2, 2, 1, 7, 3]),
+        Err(VacmError::NotInView)
+    );
+
+    // an empty view name, too low a level, an unknown user
+    let oid = [1, 3, 6, 1, 2, 1, 2, 2, 1, 2, 3];
+    assert_eq!(
+        allowed(auth, AccessKind::Write, &oid),
+        Err(VacmError::NoSuchView)
+    );
+    assert_eq!(
+        allowed(SecurityLevel::NoAuthNoPriv, AccessKind::Read, &oid),
+        Err(VacmError::NoAccessEntry)
+    );
+    assert_eq!(
+        vacm.is_access_allowed(SecurityModel::V2c, b"ops", auth, AccessKind::Read, &oid),
+        Err(VacmError::NoSuchGroupName)
+    );
+    assert!(Vacm::new().include("bad", "1.3.x").is_err());
+}
+
+#[test]
+fn test_excluded_subtree_is_hidden() {
+    let agent = agent();
+    let secret = [&SECRETS[..], &[1, 0]].concat();
+    let pdu = request(&agent, 1, "public", Asn1Tag::GetRequest, &secret).unwrap();
+    assert_eq!(pdu.varbinds[0].value, ObjectSyntax::NoSuchObject);
+    let pdu = request(&agent, 1, "private", Asn1Tag::GetRequest, &secret).unwrap();
+    assert_eq!(pdu.varbinds[0].value, ObjectSyntax::Integer(1));
+
+    // the walk steps over it
+    let last = [&SYSTEM[..], &[5, 0]].concat();
+    let pdu = request(&agent, 1, "public", Asn1Tag::GetNextRequest, &last).unwrap();
+    assert_eq!(pdu.varbinds[0].value, ObjectSyntax::EndOfMib);
+    let pdu = request(&agent, 1, "private", Asn1Tag::GetNextRequest, &last).unwrap();
+    assert_eq!(pdu.varbinds[0].oid, secret);
+
+    // and v1 knows only noSuchName
+    let pdu = request(&agent, 0, "public", Asn1Tag::GetRequest, &secret).unwrap();
+    assert_eq!(error(&pdu), (ErrorStatus::NoSuchName, 1));
+}
+
+#[test]
+fn test_write_access() {
+    let agent = agent();
+    let contact = [&SYSTEM[..], &[5, 0]].concat();
+    let pdu = request(&agent, 1, "private", Asn1Tag::SetRequest, &contact).unwrap();
+    assert_eq!(error(&pdu), (ErrorStatus::NoError, 0));
+    let secret = [&SECRETS[..], &[1, 0]].concat();
+    let pdu = request(&agent, 1, "private", Asn1Tag::SetRequest, &secret).unwrap();
+    assert_eq!(error(&pdu), (ErrorStatus::NoAccess, 1));
+
+    // "public" has no write view at all
+    let pdu = request(&agent, 1, "public", Asn1Tag::SetRequest, &contact).unwrap();
+    assert_eq!(error(&pdu), (ErrorStatus::AuthorizationError, 1));
+    let pdu = request(&agent, 0, "public", Asn1Tag::SetRequest, &contact).unwrap();
+    assert_eq!(error(&pdu), (ErrorStatus::NoSuchName, 1));
+}
+
+#[test]
+fn test_unknown_community_is_dropped() {
+    let agent = agent();
+    let oid = [&SYSTEM[..], &[1, 0]].concat();
+    assert!(request(&agent, 1, "secret", Asn1Tag::GetRequest, &oid).is_none());
+    // in a group only for v2c
+    assert!(request(&agent, 0, "private", Asn1Tag::GetRequest, &oid).is_none());
+}
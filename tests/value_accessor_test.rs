@@ -0,0 +1,45 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use rusnmp::snmp::pdu::ObjectSyntax;
+
+#[test]
+fn test_integer_accessors() {
+    assert_eq!(ObjectSyntax::Counter32(7).as_u64(), Some(7));
+    assert_eq!(ObjectSyntax::Gauge32(8).as_i64(), Some(8));
+    assert_eq!(ObjectSyntax::TimeTicks(9).as_u64(), Some(9));
+    assert_eq!(ObjectSyntax::Counter64(u64::MAX).as_u64(), Some(u64::MAX));
+    assert_eq!(ObjectSyntax::Counter64(u64::MAX).as_i64(), None);
+    assert_eq!(ObjectSyntax::Integer(-3).as_i64(), Some(-3));
+    assert_eq!(ObjectSyntax::Integer(-3).as_u64(), None);
+    assert_eq!(ObjectSyntax::Integer(3).as_u64(), Some(3));
+    assert_eq!(ObjectSyntax::OctetString(b"3".to_vec()).as_u64(), None);
+}
+
+#[test]
+fn test_string_accessors() {
+    let name = ObjectSyntax::OctetString(b"core-1".to_vec());
+    assert_eq!(name.as_str(), Some("core-1"));
+    assert_eq!(name.as_bytes(), Some(&b"core-1"[..]));
+    assert_eq!(ObjectSyntax::OctetString(vec![0xff]).as_str(), None);
+    assert_eq!(ObjectSyntax::Opaque(vec![1]).as_bytes(), Some(&[1][..]));
+    assert_eq!(ObjectSyntax::Null.as_bytes(), None);
+}
+
+#[test]
+fn test_address_and_oid_accessors() {
+    assert_eq!(
+        ObjectSyntax::IpAddress(vec![10, 0, 0, 1]).as_ipaddr(),
+        Some(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)))
+    );
+    let mut v6 = vec![0; 16];
+    v6[15] = 1;
+    assert_eq!(
+        ObjectSyntax::IpAddress(v6).as_ipaddr(),
+        Some(IpAddr::V6(Ipv6Addr::LOCALHOST))
+    );
+    assert_eq!(ObjectSyntax::IpAddress(vec![10, 0, 0]).as_ipaddr(), None);
+
+    let oid = ObjectSyntax::ObjectIdentifier(vec![1, 3, 6, 1]);
+    assert_eq!(oid.as_oid(), Some(&[1, 3, 6, 1][..]));
+    assert_eq!(ObjectSyntax::Integer(1).as_oid(), None);
+}
@@ -0,0 +1,154 @@
+use rusnmp::ber::{Asn1Tag, parse_ber_object};
+use rusnmp::snmp::message::{SnmpMessage, parse_message};
+use rusnmp::snmp::pdu::{
+    ErrorStatus, GenericTrap, ObjectSyntax, Pdu, PduData, VarBind, parse_varbind_list,
+};
+use rusnmp::snmp::varbind_ref::{ObjectSyntaxRef, parse_message_ref, parse_varbind_list_ref};
+
+fn varbind_list(varbinds: &[VarBind]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    rusnmp::ber::encoder::encode_sequence_with(&mut bytes, |content| {
+        for varbind in varbinds {
+            varbind.write_to_buf(content);
+        }
+    });
+    bytes
+}
+
+#[test]
+fn test_borrowed_varbinds_match_owned() {
+    let varbinds = vec![
+        VarBind {
+            oid: vec![1, 3, 6, 1, 2, 1, 1, 1, 0],
+            value: ObjectSyntax::OctetString(b"Linux box".to_vec()),
+        },
+        VarBind {
+            oid: vec![1, 3, 6, 1, 2, 1, 1, 2, 0],
+            value: ObjectSyntax::ObjectIdentifier(vec![1, 3, 6, 1, 4, 1, 8072, 3, 2, 10]),
+        },
+        VarBind {
+            oid: vec![1, 3, 6, 1, 2, 1, 31, 1, 1, 1, 6, 2],
+            value: ObjectSyntax::Counter64(1 << 40),
+        },
+        VarBind {
+            oid: vec![1, 3, 6, 1, 4, 1, 2021, 10, 1, 6, 1],
+            value: ObjectSyntax::Float(0.25),
+        },
+        VarBind {
+            oid: vec![1, 3, 6, 1, 2, 1, 1, 9, 0],
+            value: ObjectSyntax::EndOfMib,
+        },
+    ];
+    let bytes = varbind_list(&varbinds);
+
+    let (obj, _) = parse_ber_object(&bytes).unwrap();
+    let borrowed = parse_varbind_list_ref(obj).unwrap();
+    assert_eq!(
+        borrowed[0].value,
+        ObjectSyntaxRef::OctetString(b"Linux box")
+    );
+    // the string points into the packet rather than a copy
+    let ObjectSyntaxRef::OctetString(text) = borrowed[0].value else {
+        unreachable!()
+    };
+    assert!(bytes.as_ptr_range().contains(&text.as_ptr()));
+
+    let owned = borrowed
+        .into_iter()
+        .map(|varbind| varbind.into_owned().unwrap())
+        .collect::<Vec<_>>();
+    let (obj, _) = parse_ber_object(&bytes).unwrap();
+    assert_eq!(owned, parse_varbind_list(obj).unwrap());
+    assert_eq!(owned, varbinds);
+}
+
+#[test]
+fn test_borrowed_message() {
+    // linkDown as an SNMPv2-Trap, then as a v1 Trap
+    let varbinds = vec![
+        VarBind {
+            oid: vec![1, 3, 6, 1, 2, 1, 1, 3, 0],
+            value: ObjectSyntax::TimeTicks(4200),
+        },
+        VarBind {
+            oid: vec![1, 3, 6, 1, 6, 3, 1, 1, 4, 1, 0],
+            value: ObjectSyntax::ObjectIdentifier(vec![1, 3, 6, 1, 6, 3, 1, 1, 5, 3]),
+        },
+        VarBind {
+            oid: vec![1, 3, 6, 1, 2, 1, 2, 2, 1, 2, 3],
+            value: ObjectSyntax::OctetString(b"eth2".to_vec()),
+        },
+    ];
+    let v2c = SnmpMessage {
+        version: 1,
+        community: b"public".to_vec(),
+        pdu: Pdu {
+            tag: Asn1Tag::SnmpV2Trap,
+            request_id: 77,
+            data: PduData::Basic {
+                error_status: ErrorStatus::NoError,
+                error_index: 0,
+            },
+            varbinds: varbinds.clone(),
+        },
+    };
+    let v1 = SnmpMessage {
+        version: 0,
+        community: b"public".to_vec(),
+        pdu: Pdu {
+            tag: Asn1Tag::Trap,
+            request_id: 0,
+            data: PduData::Trap {
+                enterprise: vec![1, 3, 6, 1, 4, 1, 8072],
+                agent_addr: [192, 0, 2, 1],
+                generic_trap: GenericTrap::LinkDown,
+                specific_trap: 0,
+                time_stamp: 4200,
+            },
+            varbinds: varbinds[2..].to_vec(),
+        },
+    };
+
+    for message in [v2c, v1] {
+        let bytes = message.to_bytes();
+        let borrowed = parse_message_ref(&bytes).unwrap();
+        assert_eq!(borrowed.version, message.version);
+        assert_eq!(borrowed.community, b"public");
+        assert!(bytes.as_ptr_range().contains(&borrowed.community.as_ptr()));
+        assert_eq!(borrowed.pdu.tag, message.pdu.tag);
+        assert_eq!(borrowed.pdu.request_id, message.pdu.request_id);
+        assert_eq!(borrowed.pdu.data, message.pdu.data);
+
+        let ObjectSyntaxRef::OctetString(name) = borrowed.pdu.varbinds.last().unwrap().value else {
+            panic!("expected ifDescr");
+        };
+        assert_eq!(name, b"eth2");
+        assert!(bytes.as_ptr_range().contains(&name.as_ptr()));
+
+        let owned: Vec<VarBind> = borrowed
+            .pdu
+            .varbinds
+            .into_iter()
+            .map(|varbind| varbind.into_owned().unwrap())
+            .collect();
+        assert_eq!(owned, parse_message(&bytes).unwrap().pdu.varbinds);
+    }
+
+    // the same framing checks as the owned parser
+    let mut bytes = SnmpMessage {
+        version: 1,
+        community: b"public".to_vec(),
+        pdu: Pdu {
+            tag: Asn1Tag::GetResponse,
+            request_id: 1,
+            data: PduData::Basic {
+                error_status: ErrorStatus::NoError,
+                error_index: 0,
+            },
+            varbinds,
+        },
+    }
+    .to_bytes();
+    bytes.push(0);
+    assert!(parse_message_ref(&bytes).is_err());
+}
@@ -0,0 +1,76 @@
+use std::pin::pin;
+
+use common::FakeAgent;
+use futures::StreamExt;
+use rusnmp::manager::{Credentials, Manager, OidNotIncreasingError};
+use rusnmp::snmp::pdu::ObjectSyntax;
+
+mod common;
+
+const TABLE: [u64; 7] = [1, 3, 6, 1, 4, 1, 99];
+
+// a table of `rows` integers under TABLE; with `stuck` every GetNext is
+// answered with the OID it asked about
+async fn agent(rows: u64, stuck: bool) -> String {
+    let agent = FakeAgent::new().serve(move |message| {
+        let varbind = &mut message.pdu.varbinds[0];
+        let row = varbind.oid.get(TABLE.len()).map_or(1, |row| row + 1);
+        if !stuck {
+            varbind.oid = [&TABLE[..], &[row]].concat();
+        } else if varbind.oid.len() == TABLE.len() {
+            varbind.oid.push(1);
+        }
+        varbind.value = if row > rows {
+            ObjectSyntax::EndOfMib
+        } else {
+            ObjectSyntax::Integer(row as i32)
+        };
+        true
+    });
+    agent.await.target
+}
+
+#[tokio::test]
+async fn test_stream_stops_early() {
+    let target = agent(100, false).await;
+    let manager = Manager::new();
+    let community = Credentials::v2c("public");
+
+    let rows: Vec<_> = manager
+        .walk_stream(&target, &community, "1.3.6.1.4.1.99")
+        .take(3)
+        .collect()
+        .await;
+    assert_eq!(rows.len(), 3);
+    assert_eq!(rows[2].as_ref().unwrap().value, ObjectSyntax::Integer(3));
+    assert_eq!(manager.stats(&target).unwrap().packets_sent, 3);
+
+    let all = manager
+        .walk(&target, &community, "1.3.6.1.4.1.99")
+        .await
+        .unwrap();
+    assert_eq!(all.len(), 100);
+}
+
+#[tokio::test]
+async fn test_stream_errors_end_it() {
+    let manager = Manager::new();
+    let community = Credentials::v2c("public");
+    let mut bad_root = pin!(manager.walk_stream("127.0.0.1", &community, "1.3.x"));
+    assert!(bad_root.next().await.unwrap().is_err());
+    assert!(bad_root.next().await.is_none());
+
+    let target = agent(5, true).await;
+    let mut stuck = pin!(manager.walk_stream(&target, &community, "1.3.6.1.4.1.99"));
+    assert!(stuck.next().await.unwrap().is_ok());
+    let error = stuck.next().await.unwrap().unwrap_err();
+    assert!(error.downcast_ref::<OidNotIncreasingError>().is_some());
+    assert!(stuck.next().await.is_none());
+
+    let lenient = Manager::new().with_skip_non_increasing(true);
+    let rows = lenient
+        .walk(&target, &community, "1.3.6.1.4.1.99")
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 1);
+}
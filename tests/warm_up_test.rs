@@ -0,0 +1,72 @@
+use std::sync::Mutex;
+use std::time::Duration;
+
+use rusnmp::manager::Manager;
+
+#[tokio::test]
+async fn test_warm_up_reports_each_target() {
+    let targets = vec!["127.0.0.1:1161".to_string(), "127.0.0.1:99999".to_string()];
+    let progress = Mutex::new(Vec::new());
+    let report = Manager::new()
+        .warm_up(&targets, false, Duration::from_secs(5), |target, result| {
+            progress
+                .lock()
+                .unwrap()
+                .push((target.to_string(), result.is_ok()));
+        })
+        .await;
+
+    assert_eq!(report.ready, ["127.0.0.1:1161"]);
+    assert_eq!(report.failed.len(), 1);
+    assert_eq!(report.failed[0].0, "127.0.0.1:99999");
+    assert!(report.timed_out.is_empty());
+
+    let mut progress = progress.into_inner().unwrap();
+    progress.sort();
+    assert_eq!(
+        progress,
+        [
+            ("127.0.0.1:1161".to_string(), true),
+            ("127.0.0.1:99999".to_string(), false)
+        ]
+    );
+}
+
+#[cfg(not(feature = "v3"))]
+#[tokio::test]
+async fn test_discovery_needs_v3() {
+    let targets = vec!["127.0.0.1:1161".to_string()];
+    let report = Manager::new()
+        .warm_up(&targets, true, Duration::from_secs(5), |_, _| {})
+        .await;
+    assert_eq!(report.failed.len(), 1);
+}
+
+#[cfg(feature = "v3")]
+#[tokio::test]
+async fn test_warm_up_discovers_engines() {
+    use std::sync::Arc;
+
+    use rusnmp::agent::Agent;
+    use tokio::net::UdpSocket;
+
+    let agent = Arc::new(Agent::builder().build());
+    let engine_id = agent.engine_id().clone();
+    let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let answering = socket.local_addr().unwrap().to_string();
+    tokio::spawn(async move { agent.serve(socket).await });
+    // bound but never answering
+    let silent = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let silent_target = silent.local_addr().unwrap().to_string();
+
+    let manager = Manager::new();
+    let targets = vec![answering.clone(), silent_target.clone()];
+    let report = manager
+        .warm_up(&targets, true, Duration::from_millis(500), |_, _| {})
+        .await;
+
+    assert_eq!(report.ready, std::slice::from_ref(&answering));
+    assert!(report.failed.is_empty());
+    assert_eq!(report.timed_out, [silent_target]);
+    assert_eq!(manager.engine_id(&answering), Some(engine_id));
+}